@@ -167,6 +167,12 @@ mock! {
         async fn disconnect(&mut self) -> Result<(), AstarteError>;
 
         fn is_paired(&self) -> bool;
+
+        async fn pause(&mut self) -> Result<(), AstarteError>;
+
+        fn resume(&self);
+
+        fn is_paused(&self) -> bool;
     }
 }
 
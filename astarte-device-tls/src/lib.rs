@@ -19,16 +19,38 @@
 use std::sync::Arc;
 
 use rustls::client::WantsClientCert;
+use rustls::client::danger::ServerCertVerifier;
 use rustls::crypto::CryptoProvider;
+use rustls::pki_types::CertificateDer;
 use rustls::{ClientConfig, ConfigBuilder, Error, RootCertStore};
-use rustls_native_certs::load_native_certs;
-use tracing::instrument;
-use tracing::{error, info};
+use tracing::{info, instrument};
 
 // re-export
 pub use rustls;
 
 pub mod insecure;
+pub mod pinning;
+
+use self::pinning::{PinnedVerifier, SpkiPin};
+
+/// Additional trust for the server certificates.
+///
+/// The CA certificates are trusted together with the ones of the configured roots, the pins are
+/// checked after the certificate chain is verified.
+#[derive(Debug, Clone, Default)]
+pub struct TrustOptions {
+    /// Additional trusted CA certificates.
+    pub ca_certificates: Vec<CertificateDer<'static>>,
+    /// Public keys one of the server certificates must match.
+    pub pins: Vec<SpkiPin>,
+}
+
+impl TrustOptions {
+    /// Returns true if there are no CA certificates or pins.
+    pub fn is_empty(&self) -> bool {
+        self.ca_certificates.is_empty() && self.pins.is_empty()
+    }
+}
 
 /// Returns a configured TLS client.
 ///
@@ -40,6 +62,17 @@ pub fn config() -> Result<ClientConfig, Error> {
     builder().map(ConfigBuilder::<ClientConfig, WantsClientCert>::with_no_client_auth)
 }
 
+/// Returns a configured TLS client, trusting the additional CA certificates and pins.
+///
+/// # Errors
+///
+/// If we cannot configure the verifier or read the root CAs.
+#[instrument(skip(trust))]
+pub fn config_with_trust(trust: &TrustOptions) -> Result<ClientConfig, Error> {
+    builder_with_trust(trust)
+        .map(ConfigBuilder::<ClientConfig, WantsClientCert>::with_no_client_auth)
+}
+
 /// Returns a configured builder.
 ///
 /// This builder can be used for client authentication.
@@ -49,11 +82,7 @@ pub fn config() -> Result<ClientConfig, Error> {
 /// If we cannot configure the verifier or read the root CAs.
 #[instrument]
 pub fn builder() -> Result<ConfigBuilder<ClientConfig, WantsClientCert>, Error> {
-    // This step is to ensure a CryptoProvider is configured also in the tests.
-    let provider = CryptoProvider::get_default().map_or_else(
-        || Arc::new(rustls::crypto::aws_lc_rs::default_provider()),
-        Arc::clone,
-    );
+    let provider = default_provider();
 
     cfg_if::cfg_if! {
         if #[cfg(feature = "platform-verifier")] {
@@ -66,6 +95,86 @@ pub fn builder() -> Result<ConfigBuilder<ClientConfig, WantsClientCert>, Error>
     }
 }
 
+/// Returns a configured builder, trusting the additional CA certificates and pins.
+///
+/// With empty [`TrustOptions`] it's the same as [`builder`].
+///
+/// # Errors
+///
+/// If we cannot configure the verifier or read the root CAs.
+#[instrument(skip(trust))]
+pub fn builder_with_trust(
+    trust: &TrustOptions,
+) -> Result<ConfigBuilder<ClientConfig, WantsClientCert>, Error> {
+    if trust.is_empty() {
+        return builder();
+    }
+
+    let provider = default_provider();
+
+    let verifier = trusted_verifier(trust, Arc::clone(&provider))?;
+
+    let verifier: Arc<dyn ServerCertVerifier> = if trust.pins.is_empty() {
+        verifier
+    } else {
+        Arc::new(PinnedVerifier::new(verifier, trust.pins.clone()))
+    };
+
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(verifier);
+
+    info!(
+        ca_certificates = trust.ca_certificates.len(),
+        pins = trust.pins.len(),
+        "tls client configured with additional trust"
+    );
+
+    Ok(config)
+}
+
+// This step is to ensure a CryptoProvider is configured also in the tests.
+fn default_provider() -> Arc<CryptoProvider> {
+    CryptoProvider::get_default().map_or_else(
+        || Arc::new(rustls::crypto::aws_lc_rs::default_provider()),
+        Arc::clone,
+    )
+}
+
+/// Verifier of the configured roots, with the additional CA certificates.
+fn trusted_verifier(
+    trust: &TrustOptions,
+    provider: Arc<CryptoProvider>,
+) -> Result<Arc<dyn ServerCertVerifier>, Error> {
+    let extra = trust.ca_certificates.iter().cloned();
+
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "platform-verifier")] {
+            let verifier =
+                rustls_platform_verifier::Verifier::new_with_extra_roots(extra, provider)?;
+
+            Ok(Arc::new(verifier))
+        } else {
+            #[cfg(feature = "webpki-roots")]
+            let mut roots = RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            };
+            #[cfg(not(feature = "webpki-roots"))]
+            let mut roots = RootCertStore::clone(native_roots());
+
+            let (added, ignored) = roots.add_parsable_certificates(extra);
+
+            info!(added, ignored, "additional CA certificates loaded");
+
+            rustls::client::WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map(|verifier| verifier as Arc<dyn ServerCertVerifier>)
+                .map_err(|err| Error::General(err.to_string()))
+        }
+    }
+}
+
 #[cfg(feature = "platform-verifier")]
 #[instrument(skip(provider))]
 fn platform_verifier(
@@ -110,18 +219,18 @@ pub fn webpki(provider: Arc<CryptoProvider>) -> Result<ClientConfig, Error> {
     Ok(config)
 }
 
-// NOTE: the `_` in the name is to prevent a dead_code warning when the other features are enabled
-#[instrument(skip(provider))]
-fn _default(
-    provider: Arc<CryptoProvider>,
-) -> Result<ConfigBuilder<ClientConfig, WantsClientCert>, Error> {
+/// Loads the native root certificates once.
+// NOTE: only used by `_default` when the platform verifier is enabled
+#[cfg(not(feature = "webpki-roots"))]
+#[cfg_attr(feature = "platform-verifier", allow(dead_code))]
+fn native_roots() -> &'static Arc<RootCertStore> {
     static ROOTS: std::sync::OnceLock<Arc<RootCertStore>> = std::sync::OnceLock::new();
 
-    let roots = ROOTS.get_or_init(|| {
-        let res = load_native_certs();
+    ROOTS.get_or_init(|| {
+        let res = rustls_native_certs::load_native_certs();
 
         for error in res.errors {
-            error!(%error, "couldn't load native certificate");
+            tracing::error!(%error, "couldn't load native certificate");
         }
 
         let mut roots = RootCertStore::empty();
@@ -131,11 +240,18 @@ fn _default(
         info!(added, ignored, "native certificates loaded");
 
         Arc::new(roots)
-    });
+    })
+}
 
+// NOTE: the `_` in the name is to prevent a dead_code warning when the other features are enabled
+#[cfg(not(feature = "webpki-roots"))]
+#[instrument(skip(provider))]
+fn _default(
+    provider: Arc<CryptoProvider>,
+) -> Result<ConfigBuilder<ClientConfig, WantsClientCert>, Error> {
     let config = ClientConfig::builder_with_provider(provider)
         .with_safe_default_protocol_versions()?
-        .with_root_certificates(Arc::clone(roots));
+        .with_root_certificates(Arc::clone(native_roots()));
 
     info!("tls client configured with native roots");
 
@@ -152,12 +268,27 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "webpki-roots"))]
     fn configure_default() {
         let provider = rustls::crypto::aws_lc_rs::default_provider();
 
         _default(Arc::new(provider)).unwrap();
     }
 
+    #[test]
+    fn configure_with_trust() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert = cert.cert.der().clone();
+
+        let trust = TrustOptions {
+            pins: vec![SpkiPin::from_certificate(&cert).unwrap()],
+            ca_certificates: vec![cert],
+        };
+
+        config_with_trust(&trust).unwrap();
+        config_with_trust(&TrustOptions::default()).unwrap();
+    }
+
     #[test]
     #[cfg(feature = "platform-verifier")]
     fn configure_platform_verifier() {
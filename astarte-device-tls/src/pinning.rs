@@ -0,0 +1,295 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pinning of the server certificates by the hash of their public key.
+
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use aws_lc_rs::digest;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, Error, SignatureScheme};
+use tracing::{debug, warn};
+
+/// Prefix of the textual representation of the pin.
+const SHA256_PREFIX: &str = "sha256/";
+
+/// SHA-256 hash of the DER encoded `SubjectPublicKeyInfo` of a certificate.
+///
+/// The textual representation is the base64 encoded hash, optionally prefixed by `sha256/`. It's
+/// the same value that can be obtained with:
+///
+/// ```sh
+/// openssl x509 -in cert.pem -pubkey -noout \
+///     | openssl pkey -pubin -outform der \
+///     | openssl dgst -sha256 -binary \
+///     | base64
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpkiPin([u8; 32]);
+
+impl SpkiPin {
+    /// Creates the pin from the SHA-256 hash of the public key.
+    pub const fn from_sha256(hash: [u8; 32]) -> Self {
+        Self(hash)
+    }
+
+    /// Hashes the DER encoded `SubjectPublicKeyInfo`.
+    pub fn from_spki_der(spki: &[u8]) -> Self {
+        let digest = digest::digest(&digest::SHA256, spki);
+
+        let mut hash = [0; 32];
+        hash.copy_from_slice(digest.as_ref());
+
+        Self(hash)
+    }
+
+    /// Hashes the public key of the certificate.
+    ///
+    /// # Errors
+    ///
+    /// If the certificate cannot be parsed.
+    pub fn from_certificate(cert: &CertificateDer<'_>) -> Result<Self, Error> {
+        let (_, parsed) = x509_parser::parse_x509_certificate(cert)
+            .map_err(|_| Error::InvalidCertificate(CertificateError::BadEncoding))?;
+
+        Ok(Self::from_spki_der(parsed.public_key().raw))
+    }
+
+    /// Returns the SHA-256 hash of the public key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Debug for SpkiPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SpkiPin").field(&self.to_string()).finish()
+    }
+}
+
+impl Display for SpkiPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{SHA256_PREFIX}{}", BASE64_STANDARD.encode(self.0))
+    }
+}
+
+/// Error returned when parsing an invalid [`SpkiPin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SpkiPinError {
+    /// The pin is not valid base64.
+    #[error("the pin is not valid base64")]
+    Base64,
+    /// The decoded pin is not a SHA-256 hash.
+    #[error("expected a SHA-256 hash of 32 bytes, got {0} bytes")]
+    Length(usize),
+}
+
+impl FromStr for SpkiPin {
+    type Err = SpkiPinError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded = s.trim();
+        let encoded = encoded.strip_prefix(SHA256_PREFIX).unwrap_or(encoded);
+
+        let bytes = BASE64_STANDARD
+            .decode(encoded)
+            .map_err(|_| SpkiPinError::Base64)?;
+
+        let hash: [u8; 32] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| SpkiPinError::Length(bytes.len()))?;
+
+        Ok(Self(hash))
+    }
+}
+
+/// Verifier that checks the certificate public key against a set of pins.
+///
+/// The chain is first verified by the wrapped verifier, then one of the certificates, either the
+/// end entity or an intermediate, must have a public key matching one of the pins.
+#[derive(Debug)]
+pub struct PinnedVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    pins: Vec<SpkiPin>,
+}
+
+impl PinnedVerifier {
+    /// Wraps the verifier to check the pins.
+    pub fn new(inner: Arc<dyn ServerCertVerifier>, pins: Vec<SpkiPin>) -> Self {
+        Self { inner, pins }
+    }
+
+    fn is_pinned(&self, cert: &CertificateDer<'_>) -> bool {
+        match SpkiPin::from_certificate(cert) {
+            Ok(pin) => self.pins.contains(&pin),
+            Err(error) => {
+                debug!(%error, "couldn't hash the certificate public key");
+
+                false
+            }
+        }
+    }
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        let pinned = std::iter::once(end_entity)
+            .chain(intermediates)
+            .any(|cert| self.is_pinned(cert));
+
+        if !pinned {
+            warn!(
+                ?server_name,
+                "no certificate matches the pinned public keys"
+            );
+
+            return Err(Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ));
+        }
+
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustls::RootCertStore;
+    use rustls::client::WebPkiServerVerifier;
+
+    use super::*;
+
+    fn self_signed(name: &str) -> CertificateDer<'static> {
+        let cert = rcgen::generate_simple_self_signed(vec![name.to_string()]).unwrap();
+
+        cert.cert.der().clone()
+    }
+
+    fn pinned_verifier(roots: &[&CertificateDer<'static>], pins: Vec<SpkiPin>) -> PinnedVerifier {
+        let mut store = RootCertStore::empty();
+        for root in roots {
+            store.add((*root).clone()).unwrap();
+        }
+
+        let inner = WebPkiServerVerifier::builder_with_provider(
+            Arc::new(store),
+            Arc::new(rustls::crypto::aws_lc_rs::default_provider()),
+        )
+        .build()
+        .unwrap();
+
+        PinnedVerifier::new(inner, pins)
+    }
+
+    #[test]
+    fn should_parse_and_display_pin() {
+        let pin = SpkiPin::from_spki_der(b"public key");
+
+        let display = pin.to_string();
+        assert!(display.starts_with("sha256/"));
+        assert_eq!(display.parse::<SpkiPin>().unwrap(), pin);
+
+        let no_prefix = display.strip_prefix("sha256/").unwrap();
+        assert_eq!(no_prefix.parse::<SpkiPin>().unwrap(), pin);
+
+        assert_eq!("sha256/!".parse::<SpkiPin>(), Err(SpkiPinError::Base64));
+        assert_eq!("AAAA".parse::<SpkiPin>(), Err(SpkiPinError::Length(3)));
+    }
+
+    #[test]
+    fn should_check_the_pins() {
+        let cert = self_signed("broker.astarte.localhost");
+        let other = self_signed("other.astarte.localhost");
+
+        let server_name = ServerName::try_from("broker.astarte.localhost").unwrap();
+
+        let pin = SpkiPin::from_certificate(&cert).unwrap();
+        let verifier = pinned_verifier(&[&cert], vec![pin]);
+        verifier
+            .verify_server_cert(&cert, &[], &server_name, &[], UnixTime::now())
+            .unwrap();
+
+        let pin = SpkiPin::from_certificate(&other).unwrap();
+        let verifier = pinned_verifier(&[&cert], vec![pin]);
+        let err = verifier
+            .verify_server_cert(&cert, &[], &server_name, &[], UnixTime::now())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            Error::InvalidCertificate(CertificateError::ApplicationVerificationFailure)
+        );
+    }
+
+    #[test]
+    fn should_verify_the_chain_before_the_pins() {
+        let cert = self_signed("broker.astarte.localhost");
+        let other = self_signed("other.astarte.localhost");
+
+        let server_name = ServerName::try_from("broker.astarte.localhost").unwrap();
+
+        // Pinned but not trusted
+        let pin = SpkiPin::from_certificate(&cert).unwrap();
+        let verifier = pinned_verifier(&[&other], vec![pin]);
+        let err = verifier
+            .verify_server_cert(&cert, &[], &server_name, &[], UnixTime::now())
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidCertificate(_)));
+    }
+}
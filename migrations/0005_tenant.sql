@@ -0,0 +1,131 @@
+-- Namespace all the data by tenant, to share a single database between multiple devices.
+-- The empty tenant is used by a store for a single device.
+CREATE TABLE IF NOT EXISTS propcache_v3 (
+    tenant TEXT NOT NULL DEFAULT '',
+    interface TEXT NOT NULL,
+    path TEXT NOT NULL,
+    -- Nullable for unset
+    value BLOB,
+    type INTEGER NOT NULL,
+    interface_major INTEGER NOT NULL,
+    -- Ownership of the interface
+    -- 0: Server owned
+    -- 1: Device owned
+    ownership INTEGER NOT NULL,
+    state INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (tenant, interface, path)
+);
+
+INSERT OR REPLACE INTO propcache_v3 (
+    tenant, interface, path, value, type, interface_major, ownership, state
+)
+SELECT
+    '',
+    interface,
+    path,
+    value,
+    type,
+    interface_major,
+    ownership,
+    state
+FROM propcache;
+
+DROP TABLE propcache;
+
+ALTER TABLE propcache_v3 RENAME TO propcache;
+
+CREATE TABLE IF NOT EXISTS retention_mapping_v2 (
+    tenant TEXT NOT NULL DEFAULT '',
+    -- Interface name
+    interface TEXT NOT NULL,
+    -- Interface path where the data was published on.
+    path TEXT NOT NULL,
+    -- Version of the interface the data was published on.
+    major_version INTEGER NOT NULL,
+    -- Quality of service
+    reliability INTEGER NOT NULL,
+    -- Seconds after the entry will expire
+    expiry_sec INTEGER,
+    PRIMARY KEY (tenant, interface, path)
+);
+
+CREATE TABLE IF NOT EXISTS retention_publish_v2 (
+    tenant TEXT NOT NULL DEFAULT '',
+    -- Timestamp as u128 milliseconds since the Unix epoch, used for packet order
+    t_millis BLOB NOT NULL,
+    --- Counter for same milliseconds packets
+    counter INTEGER NOT NULL,
+    -- Interface name
+    interface TEXT NOT NULL,
+    --- interface path
+    path TEXT NOT NULL,
+    -- Timestamp as u64 milliseconds since the Unix epoch, when the publish expires
+    -- (t_millis as secs + expiry_sec).
+    expiry_t_secs BLOB,
+    --  Whether the publish was sent or stored when offline.
+    sent BOOLEAN NOT NULL,
+    -- Payload for the packet
+    payload BLOB NOT NULL,
+    -- Primary key for packet uniqueness and ordering the table ordering
+    PRIMARY KEY (tenant, t_millis, counter),
+    -- References to the retention information
+    FOREIGN KEY (tenant, interface, path) REFERENCES retention_mapping_v2 (
+        tenant, interface, path
+    )
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
+
+INSERT OR REPLACE INTO retention_mapping_v2 (
+    tenant, interface, path, major_version, reliability, expiry_sec
+)
+SELECT
+    '',
+    interface,
+    path,
+    major_version,
+    reliability,
+    expiry_sec
+FROM retention_mapping;
+
+INSERT OR REPLACE INTO retention_publish_v2 (
+    tenant, t_millis, counter, interface, path, expiry_t_secs, sent, payload
+)
+SELECT
+    '',
+    t_millis,
+    counter,
+    interface,
+    path,
+    expiry_t_secs,
+    sent,
+    payload
+FROM retention_publish;
+
+DROP TABLE retention_publish;
+
+DROP TABLE retention_mapping;
+
+ALTER TABLE retention_mapping_v2 RENAME TO retention_mapping;
+
+ALTER TABLE retention_publish_v2 RENAME TO retention_publish;
+
+CREATE TABLE IF NOT EXISTS introspection_v2 (
+    "tenant" TEXT NOT NULL DEFAULT '',
+    "name" TEXT NOT NULL,
+    "major" INTEGER NOT NULL,
+    "minor" INTEGER NOT NULL,
+    PRIMARY KEY ("tenant", "name")
+);
+
+INSERT OR REPLACE INTO introspection_v2 (tenant, name, major, minor)
+SELECT
+    '',
+    name,
+    major,
+    minor
+FROM introspection;
+
+DROP TABLE introspection;
+
+ALTER TABLE introspection_v2 RENAME TO introspection;
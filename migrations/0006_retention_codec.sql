@@ -0,0 +1 @@
+ALTER TABLE retention_publish ADD COLUMN codec INTEGER NOT NULL DEFAULT 0;
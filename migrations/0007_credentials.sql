@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS credentials (
+    tenant TEXT NOT NULL DEFAULT '',
+    -- Kind of the credential: secret, certificate or private key
+    kind TEXT NOT NULL,
+    data BLOB NOT NULL,
+    PRIMARY KEY (tenant, kind)
+);
@@ -0,0 +1,5 @@
+SELECT data
+FROM credentials
+WHERE
+    tenant = ?1
+    AND kind = ?2
@@ -0,0 +1,3 @@
+DELETE FROM credentials
+WHERE
+    tenant = ?1;
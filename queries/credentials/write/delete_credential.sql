@@ -0,0 +1,4 @@
+DELETE FROM credentials
+WHERE
+    tenant = ?1
+    AND kind = ?2
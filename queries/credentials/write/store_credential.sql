@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO credentials (tenant, kind, data)
+VALUES (?1, ?2, ?3)
@@ -7,5 +7,6 @@ SELECT
     ownership
 FROM propcache
 WHERE
-    interface = ?
+    tenant = ?1
+    AND interface = ?2
     AND value IS NOT NULL;
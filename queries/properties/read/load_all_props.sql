@@ -7,4 +7,5 @@ SELECT
     ownership
 FROM propcache
 WHERE
-    value IS NOT NULL;
+    tenant = ?1
+    AND value IS NOT NULL;
@@ -4,6 +4,7 @@ SELECT
     interface_major
 FROM propcache
 WHERE
-    interface = ?
-    AND path = ?
+    tenant = ?1
+    AND interface = ?2
+    AND path = ?3
     AND value IS NOT NULL;
@@ -7,5 +7,6 @@ SELECT
     ownership
 FROM propcache
 WHERE
-    ownership = ?
+    tenant = ?1
+    AND ownership = ?2
     AND value IS NOT NULL;
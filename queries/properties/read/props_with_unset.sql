@@ -7,7 +7,8 @@ SELECT
     ownership
 FROM propcache
 WHERE
-    ownership = ?
-    AND state = ?
+    tenant = ?1
+    AND ownership = ?2
+    AND state = ?3
 ORDER BY interface, path
-LIMIT ? OFFSET ?;
+LIMIT ?4 OFFSET ?5;
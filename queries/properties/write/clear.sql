@@ -1 +1,3 @@
-DELETE FROM propcache;
+DELETE FROM propcache
+WHERE
+    tenant = ?1;
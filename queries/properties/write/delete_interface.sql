@@ -1,3 +1,4 @@
 DELETE FROM propcache
 WHERE
-    interface = ?;
+    tenant = ?1
+    AND interface = ?2;
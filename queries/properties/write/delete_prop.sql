@@ -1,4 +1,5 @@
 DELETE FROM propcache
 WHERE
-    interface = ?
-    AND path = ?;
+    tenant = ?1
+    AND interface = ?2
+    AND path = ?3;
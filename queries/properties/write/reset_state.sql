@@ -1,4 +1,5 @@
 UPDATE propcache
-SET state = ?
+SET state = ?2
 WHERE
-    ownership = ?;
+    tenant = ?1
+    AND ownership = ?3;
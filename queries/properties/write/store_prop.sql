@@ -1,4 +1,5 @@
 INSERT OR REPLACE INTO propcache (
+    tenant,
     interface,
     path,
     value,
@@ -6,4 +7,4 @@ INSERT OR REPLACE INTO propcache (
     interface_major,
     ownership,
     state
-) VALUES (?, ?, ?, ?, ?, ?, ?);
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);
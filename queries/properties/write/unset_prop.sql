@@ -1,7 +1,8 @@
 UPDATE propcache
 SET
     value = NULL,
-    state = ?
+    state = ?2
 WHERE
-    interface = ?
-    AND path = ?;
+    tenant = ?1
+    AND interface = ?3
+    AND path = ?4;
@@ -1,5 +1,6 @@
 UPDATE propcache
-SET state = ?
+SET state = ?2
 WHERE
-    interface = ?
-    AND path = ?;
+    tenant = ?1
+    AND interface = ?3
+    AND path = ?4;
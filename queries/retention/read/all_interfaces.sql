@@ -1,4 +1,6 @@
 SELECT DISTINCT
     interface,
     major_version
-FROM retention_mapping;
+FROM retention_mapping
+WHERE
+    tenant = ?1;
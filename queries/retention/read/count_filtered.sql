@@ -0,0 +1,8 @@
+SELECT COUNT(*)
+FROM retention_publish
+WHERE
+    tenant = ?1
+    AND (?2 IS NULL OR interface = ?2)
+    AND (?3 IS NULL OR path = ?3)
+    AND (?4 IS NULL OR t_millis >= ?4)
+    AND (?5 IS NULL OR t_millis < ?5);
@@ -1,2 +1,4 @@
 SELECT COUNT(*)
-FROM retention_publish;
+FROM retention_publish
+WHERE
+    tenant = ?1;
@@ -0,0 +1,7 @@
+SELECT
+    COUNT(*),
+    COALESCE(SUM(LENGTH(payload)), 0)
+FROM retention_publish
+WHERE
+    tenant = ?1
+    AND interface = ?2;
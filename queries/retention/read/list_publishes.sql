@@ -0,0 +1,19 @@
+SELECT
+    retention_publish.t_millis,
+    retention_publish.counter,
+    retention_publish.interface,
+    retention_publish.path,
+    retention_publish.sent,
+    LENGTH(retention_publish.payload),
+    retention_mapping.major_version,
+    retention_mapping.expiry_sec
+FROM retention_publish
+INNER JOIN retention_mapping USING (tenant, interface, path)
+WHERE
+    retention_publish.tenant = ?1
+    AND (?2 IS NULL OR retention_publish.interface = ?2)
+    AND (?3 IS NULL OR retention_publish.path = ?3)
+    AND (?4 IS NULL OR retention_publish.t_millis >= ?4)
+    AND (?5 IS NULL OR retention_publish.t_millis < ?5)
+ORDER BY t_millis ASC, counter ASC
+LIMIT ?6;
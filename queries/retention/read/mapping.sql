@@ -6,5 +6,6 @@ SELECT
     expiry_sec
 FROM retention_mapping
 WHERE
-    interface = ?
-    AND path = ?
+    tenant = ?1
+    AND interface = ?2
+    AND path = ?3
@@ -5,8 +5,10 @@ SELECT
     path,
     expiry_t_secs,
     sent,
-    payload
+    payload,
+    codec
 FROM retention_publish
 WHERE
-    t_millis = ?
-    AND counter = ?;
+    tenant = ?1
+    AND t_millis = ?2
+    AND counter = ?3;
@@ -1,20 +1,57 @@
+WITH unsent AS (
+    SELECT
+        retention_publish.t_millis,
+        retention_publish.counter,
+        retention_publish.interface,
+        retention_publish.path,
+        retention_publish.sent,
+        retention_publish.payload,
+        retention_mapping.reliability,
+        retention_mapping.major_version,
+        retention_mapping.expiry_sec,
+        retention_publish.codec,
+        COALESCE(prio.value, ?5) AS priority
+    FROM retention_publish
+    INNER JOIN retention_mapping USING (tenant, interface, path)
+    LEFT JOIN json_each(?4) AS prio ON retention_publish.interface = prio.key
+    WHERE
+        retention_publish.tenant = ?1
+        AND retention_publish.sent = FALSE
+        AND (
+            retention_publish.expiry_t_secs IS NULL
+            OR retention_publish.expiry_t_secs >= ?2
+        )
+),
+ranked AS (
+    SELECT
+        *,
+        ROW_NUMBER() OVER (
+            PARTITION BY priority ORDER BY t_millis ASC, counter ASC
+        ) AS oldest_rank,
+        ROW_NUMBER() OVER (
+            PARTITION BY priority ORDER BY t_millis DESC, counter DESC
+        ) AS newest_rank
+    FROM unsent
+)
 SELECT
-    retention_publish.t_millis,
-    retention_publish.counter,
-    retention_publish.interface,
-    retention_publish.path,
-    retention_publish.sent,
-    retention_publish.payload,
-    retention_mapping.reliability,
-    retention_mapping.major_version,
-    retention_mapping.expiry_sec
-FROM retention_publish
-INNER JOIN retention_mapping USING (interface, path)
-WHERE
-    retention_publish.sent = FALSE
-    AND (
-        retention_publish.expiry_t_secs IS NULL
-        OR retention_publish.expiry_t_secs >= ?
-    )
-ORDER BY t_millis ASC, counter ASC
-LIMIT ?;
+    t_millis,
+    counter,
+    interface,
+    path,
+    sent,
+    payload,
+    reliability,
+    major_version,
+    expiry_sec,
+    codec
+FROM ranked
+ORDER BY
+    priority DESC,
+    CASE ?6
+        WHEN 0 THEN oldest_rank
+        WHEN 1 THEN newest_rank
+        -- Same interleave of the resend order in Rust
+        ELSE MIN(oldest_rank, newest_rank)
+    END ASC,
+    oldest_rank ASC
+LIMIT ?3;
@@ -0,0 +1,6 @@
+SELECT
+    COUNT(*),
+    COALESCE(SUM(LENGTH(payload)), 0)
+FROM retention_publish
+WHERE
+    tenant = ?1;
@@ -0,0 +1,4 @@
+-- The publishes are removed by the cascade on the mapping
+DELETE FROM retention_mapping
+WHERE
+    tenant = ?1;
@@ -1,3 +1,5 @@
 DELETE FROM retention_publish
 WHERE
-    expiry_t_secs < ?;
+    tenant = ?1
+    AND expiry_t_secs < ?2
+RETURNING t_millis, counter, interface, path;
@@ -0,0 +1,6 @@
+DELETE FROM retention_publish
+WHERE
+    tenant = ?1
+    AND interface = ?2
+    AND expiry_t_secs < ?3
+RETURNING t_millis, counter, interface, path;
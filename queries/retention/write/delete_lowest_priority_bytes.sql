@@ -0,0 +1,19 @@
+DELETE FROM retention_publish
+WHERE
+    tenant = ?1
+    AND (t_millis, counter) IN (
+        SELECT t_millis, counter
+        FROM (
+            SELECT
+                p.t_millis,
+                p.counter,
+                SUM(LENGTH(p.payload)) OVER (
+                    ORDER BY COALESCE(prio.value, ?4) ASC, p.t_millis ASC, p.counter ASC
+                ) - LENGTH(p.payload) AS freed
+            FROM retention_publish AS p
+            LEFT JOIN json_each(?3) AS prio ON p.interface = prio.key
+            WHERE p.tenant = ?1
+        )
+        WHERE freed < ?2
+    )
+RETURNING t_millis, counter, interface, path;
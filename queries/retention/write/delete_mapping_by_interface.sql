@@ -1,3 +1,4 @@
 DELETE FROM retention_mapping
 WHERE
-    interface = ?;
+    tenant = ?1
+    AND interface = ?2;
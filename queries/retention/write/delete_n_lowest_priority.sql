@@ -0,0 +1,12 @@
+DELETE FROM retention_publish
+WHERE
+    tenant = ?1
+    AND (t_millis, counter) IN (
+        SELECT p.t_millis, p.counter
+        FROM retention_publish AS p
+        LEFT JOIN json_each(?3) AS prio ON p.interface = prio.key
+        WHERE p.tenant = ?1
+        ORDER BY COALESCE(prio.value, ?4) ASC, p.t_millis ASC, p.counter ASC
+        LIMIT ?2
+    )
+RETURNING t_millis, counter, interface, path;
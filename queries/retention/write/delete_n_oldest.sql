@@ -1,7 +1,11 @@
 DELETE FROM retention_publish
-WHERE (t_millis, counter) IN (
-    SELECT t_millis, counter
-    FROM retention_publish
-    ORDER BY t_millis ASC, counter ASC
-    LIMIT ?
-);
+WHERE
+    tenant = ?1
+    AND (t_millis, counter) IN (
+        SELECT t_millis, counter
+        FROM retention_publish
+        WHERE tenant = ?1
+        ORDER BY t_millis ASC, counter ASC
+        LIMIT ?2
+    )
+RETURNING t_millis, counter, interface, path;
@@ -0,0 +1,11 @@
+DELETE FROM retention_publish
+WHERE
+    tenant = ?1
+    AND (t_millis, counter) IN (
+        SELECT t_millis, counter
+        FROM retention_publish
+        WHERE tenant = ?1 AND interface = ?2
+        ORDER BY t_millis ASC, counter ASC
+        LIMIT ?3
+    )
+RETURNING t_millis, counter, interface, path;
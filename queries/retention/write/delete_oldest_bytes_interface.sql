@@ -0,0 +1,18 @@
+DELETE FROM retention_publish
+WHERE
+    tenant = ?1
+    AND (t_millis, counter) IN (
+        SELECT t_millis, counter
+        FROM (
+            SELECT
+                t_millis,
+                counter,
+                SUM(LENGTH(payload)) OVER (
+                    ORDER BY t_millis ASC, counter ASC
+                ) - LENGTH(payload) AS freed
+            FROM retention_publish
+            WHERE tenant = ?1 AND interface = ?2
+        )
+        WHERE freed < ?3
+    )
+RETURNING t_millis, counter, interface, path;
@@ -1,4 +1,5 @@
 DELETE FROM retention_publish
 WHERE
-    t_millis = ?
-    AND counter = ?;
+    tenant = ?1
+    AND t_millis = ?2
+    AND counter = ?3;
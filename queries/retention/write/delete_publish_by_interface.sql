@@ -1,3 +1,5 @@
 DELETE FROM retention_publish
 WHERE
-    interface = ?;
+    tenant = ?1
+    AND interface = ?2
+RETURNING t_millis, counter, interface, path;
@@ -0,0 +1,10 @@
+UPDATE retention_publish
+SET
+    expiry_t_secs = ?6
+WHERE
+    tenant = ?1
+    AND (?2 IS NULL OR interface = ?2)
+    AND (?3 IS NULL OR path = ?3)
+    AND (?4 IS NULL OR t_millis >= ?4)
+    AND (?5 IS NULL OR t_millis < ?5)
+    AND (expiry_t_secs IS NULL OR expiry_t_secs > ?6);
@@ -1,3 +1,5 @@
 UPDATE retention_publish
 SET
-    sent = FALSE;
+    sent = FALSE
+WHERE
+    tenant = ?1;
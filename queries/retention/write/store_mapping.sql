@@ -1,7 +1,8 @@
 INSERT OR REPLACE INTO retention_mapping (
+    tenant,
     interface,
     path,
     major_version,
     reliability,
     expiry_sec
-) VALUES (?, ?, ?, ?, ?);
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6);
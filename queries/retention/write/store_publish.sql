@@ -1,9 +1,11 @@
 INSERT OR FAIL INTO retention_publish (
+    tenant,
     t_millis,
     counter,
     interface,
     path,
     expiry_t_secs,
     sent,
-    payload
-) VALUES (?, ?, ?, ?, ?, ?, ?);
+    payload,
+    codec
+) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9);
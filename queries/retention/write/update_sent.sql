@@ -1,5 +1,7 @@
 UPDATE retention_publish
 SET
-    sent = ?
+    sent = ?2
 WHERE
-    t_millis = ? AND counter = ?;
+    tenant = ?1
+    AND t_millis = ?3
+    AND counter = ?4;
@@ -3,3 +3,5 @@ SELECT
     major,
     minor
 FROM introspection
+WHERE
+    tenant = ?1
@@ -1 +1,3 @@
-DELETE FROM introspection;
+DELETE FROM introspection
+WHERE
+    tenant = ?1;
@@ -1,5 +1,6 @@
 DELETE FROM introspection
 WHERE
-    name = ?
-    AND major = ?
-    AND minor = ?
+    tenant = ?1
+    AND name = ?2
+    AND major = ?3
+    AND minor = ?4
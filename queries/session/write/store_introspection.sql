@@ -1,2 +1,2 @@
-INSERT OR REPLACE INTO introspection (name, major, minor)
-VALUES (?, ?, ?)
+INSERT OR REPLACE INTO introspection (tenant, name, major, minor)
+VALUES (?1, ?2, ?3, ?4)
@@ -37,13 +37,19 @@ use astarte_interfaces::Interface;
 use tracing::debug;
 
 use crate::client::DeviceClient;
-use crate::connection::DeviceConnection;
+use crate::connection::duty_cycle::PriorityWake;
+use crate::connection::{DeviceConnection, DutyCycle};
 use crate::error::AstarteError;
 use crate::error::ErrorKind;
 use crate::error::InterfaceError;
 use crate::interfaces::Interfaces;
-use crate::retention::StoredRetention;
+use crate::retention::events::RetentionEventSender;
 use crate::retention::memory::VolatileStore;
+use crate::retention::order::{ResendOrder, ResendPolicy};
+use crate::retention::quota::{RetentionLimit, RetentionQuotas};
+use crate::retention::spool::Spool;
+use crate::retention::{RetentionError, StoredRetention};
+use crate::retry::Backoff;
 use crate::retry::ExponentialIter;
 use crate::retry::RandomExponentialIter;
 use crate::state::SharedState;
@@ -109,6 +115,12 @@ pub struct Config {
     pub exponential_backoff_reset: Duration,
     /// Percentage jitter to add to the backoff.
     pub exponential_backoff_jitter: u8,
+    /// Order of the publishes with the same priority resent after a reconnection.
+    pub resend_order: ResendOrder,
+    /// Send the new publishes while the retention is being resent.
+    pub live_sends_first: bool,
+    /// Connect only in the windows of the duty cycle.
+    pub duty_cycle: Option<DutyCycle>,
 }
 
 impl Default for Config {
@@ -123,6 +135,9 @@ impl Default for Config {
             exponential_backoff_max: DEFAULT_BACKOFF_MAXIMUM_DELAY,
             exponential_backoff_reset: DEFAULT_BACKOFF_RESET_INTERVAL,
             exponential_backoff_jitter: RandomExponentialIter::DEFAULT_RANDOM_JITTER_RANGE,
+            resend_order: ResendOrder::default(),
+            live_sends_first: false,
+            duty_cycle: None,
         }
     }
 }
@@ -150,7 +165,12 @@ pub struct DeviceBuilder<C = NoConnect, S = NoStore> {
     config: Config,
     interfaces: Interfaces,
     stored_retention: NonZero<usize>,
+    stored_retention_bytes: Option<NonZero<u64>>,
+    retention_quotas: RetentionQuotas,
     volatile_retention: NonZero<usize>,
+    volatile_retention_bytes: Option<NonZero<u64>>,
+    volatile_spool: Option<NonZero<u64>>,
+    backoff: Option<Box<dyn Backoff>>,
     store: S,
     connection_config: C,
 }
@@ -173,7 +193,12 @@ impl DeviceBuilder<NoConnect, NoStore> {
     pub fn new() -> Self {
         Self {
             volatile_retention: DEFAULT_VOLATILE_CAPACITY,
+            volatile_retention_bytes: None,
+            volatile_spool: None,
+            backoff: None,
             stored_retention: DEFAULT_STORE_CAPACITY,
+            stored_retention_bytes: None,
+            retention_quotas: RetentionQuotas::default(),
             interfaces: Interfaces::new(),
             connection_config: NoConnect,
             store: NoStore,
@@ -278,6 +303,68 @@ impl<S, C> DeviceBuilder<S, C> {
         self
     }
 
+    /// Set the maximum size in bytes of the values that will be kept in memory
+    ///
+    /// The size is an estimate of the data, without the encoding overhead. The oldest values are
+    /// removed when either this or the [`DeviceBuilder::max_volatile_retention`] limit is reached.
+    pub fn max_volatile_retention_bytes(mut self, bytes: NonZero<u64>) -> Self {
+        self.volatile_retention_bytes = Some(bytes);
+
+        self
+    }
+
+    /// Spill the publishes evicted from the volatile retention to a file of at most `max_bytes`
+    ///
+    /// The file is created in the [`DeviceBuilder::writable_dir`], which is required. It's
+    /// truncated when the device is built, so the spooled publishes are lost on restart like the
+    /// ones in memory. When the file is full the oldest publishes are dropped.
+    pub fn volatile_spool(mut self, max_bytes: NonZero<u64>) -> Self {
+        self.volatile_spool = Some(max_bytes);
+
+        self
+    }
+
+    /// Set the priority of an interface, its publishes are resent first after a reconnection
+    ///
+    /// The priority is also used by the
+    /// [`EvictionPolicy::LowestPriority`](crate::retention::quota::EvictionPolicy::LowestPriority)
+    /// of the stored retention. The interfaces without a priority have the
+    /// [`DEFAULT_INTERFACE_PRIORITY`](crate::retention::quota::DEFAULT_INTERFACE_PRIORITY).
+    pub fn interface_priority(mut self, interface: impl Into<String>, priority: u8) -> Self {
+        self.retention_quotas.set_priority(interface, priority);
+
+        self
+    }
+
+    /// Set the order of the publishes with the same priority resent after a reconnection
+    ///
+    /// The default is [`ResendOrder::Oldest`].
+    pub fn resend_order(mut self, order: ResendOrder) -> Self {
+        self.config.resend_order = order;
+
+        self
+    }
+
+    /// Send the new publishes while the retention is being resent after a reconnection
+    ///
+    /// By default the new publishes are stored and sent after the retention. When enabled, the
+    /// device is considered connected as soon as the device owned properties are sent, so the new
+    /// publishes reach Astarte before the backlog.
+    pub fn live_sends_first(mut self, enabled: bool) -> Self {
+        self.config.live_sends_first = enabled;
+
+        self
+    }
+
+    /// Connect to Astarte only in periodic windows, see [`DutyCycle`].
+    ///
+    /// By default the device is always connected.
+    pub fn duty_cycle(mut self, duty_cycle: DutyCycle) -> Self {
+        self.config.duty_cycle = Some(duty_cycle);
+
+        self
+    }
+
     /// Set the timeout used while performing individual HTTP calls
     /// and used while waiting for a connection to the MQTT server.
     pub fn connection_timeout(mut self, timeout: Duration) -> Self {
@@ -334,6 +421,16 @@ impl<S, C> DeviceBuilder<S, C> {
 
         self
     }
+
+    /// Sets the strategy for the delay between the connection retries.
+    ///
+    /// It replaces the default exponential backoff, so the exponential backoff options are
+    /// ignored. See the [`retry`](crate::retry) module for the built-in strategies.
+    pub fn backoff(mut self, backoff: impl Backoff) -> Self {
+        self.backoff = Some(Box::new(backoff));
+
+        self
+    }
 }
 
 impl<C> DeviceBuilder<C, NoStore> {
@@ -346,8 +443,13 @@ impl<C> DeviceBuilder<C, NoStore> {
     {
         DeviceBuilder {
             volatile_retention: self.volatile_retention,
+            volatile_retention_bytes: self.volatile_retention_bytes,
+            volatile_spool: self.volatile_spool,
+            backoff: self.backoff,
             config: self.config,
             stored_retention: self.stored_retention,
+            stored_retention_bytes: self.stored_retention_bytes,
+            retention_quotas: self.retention_quotas,
             connection_config: self.connection_config,
             interfaces: self.interfaces,
             store,
@@ -365,6 +467,36 @@ where
 
         self
     }
+
+    /// Set the maximum size in bytes of the payloads that will be kept in the store
+    ///
+    /// The publishes are removed when either this or the [`DeviceBuilder::max_stored_retention`]
+    /// limit is reached.
+    pub fn max_stored_retention_bytes(mut self, bytes: NonZero<u64>) -> Self {
+        self.stored_retention_bytes = Some(bytes);
+
+        self
+    }
+
+    /// Set the quotas for each interface and the eviction policy of the stored retention.
+    ///
+    /// This replaces the quotas set with [`DeviceBuilder::interface_retention_quota`].
+    pub fn retention_quotas(mut self, quotas: RetentionQuotas) -> Self {
+        self.retention_quotas = quotas;
+
+        self
+    }
+
+    /// Set the stored retention quota of a single interface.
+    pub fn interface_retention_quota(
+        mut self,
+        interface: impl Into<String>,
+        limit: RetentionLimit,
+    ) -> Self {
+        self.retention_quotas.set_interface(interface, limit);
+
+        self
+    }
 }
 
 impl<S> DeviceBuilder<NoConnect, S>
@@ -383,7 +515,12 @@ where
             interfaces: self.interfaces,
             store: self.store,
             volatile_retention: self.volatile_retention,
+            volatile_retention_bytes: self.volatile_retention_bytes,
+            volatile_spool: self.volatile_spool,
+            backoff: self.backoff,
             stored_retention: self.stored_retention,
+            stored_retention_bytes: self.stored_retention_bytes,
+            retention_quotas: self.retention_quotas,
             config: self.config,
             connection_config,
         }
@@ -410,22 +547,54 @@ where
         let (events_tx, events_rx) = async_channel::bounded(self.config.channel_size.get());
         let (disconnect_tx, disconnect_rx) = async_channel::bounded(1);
 
-        let volatile_store = VolatileStore::with_capacity(self.volatile_retention.get());
-
-        let backoff = RandomExponentialIter::with_jitter(
-            ExponentialIter::new(
-                self.config.exponential_backoff_max,
-                self.config.exponential_backoff_reset,
-            ),
-            self.config.exponential_backoff_jitter,
+        // saturate on 32 bit targets
+        let volatile_bytes = self
+            .volatile_retention_bytes
+            .map(|bytes| NonZero::<usize>::try_from(bytes).unwrap_or(NonZero::<usize>::MAX));
+        let mut volatile_store = VolatileStore::with_limits(
+            self.volatile_retention.get(),
+            volatile_bytes,
+            RetentionEventSender::new(),
         );
 
-        let state = Arc::new(SharedState::new(
-            self.config,
-            self.interfaces,
-            volatile_store,
+        if let Some(max_bytes) = self.volatile_spool {
+            let dir = self.config.writable_dir.as_deref().ok_or(Error::with(
+                ErrorKind::Retention(RetentionError::Spool),
+                "the volatile spool requires a writable directory",
+            ))?;
+
+            let spool = Spool::create(dir, max_bytes)
+                .wrap_err(ErrorKind::Retention(RetentionError::Spool))?;
+
+            volatile_store = volatile_store.with_spool(spool);
+        }
+
+        let volatile_store = volatile_store.with_resend_policy(ResendPolicy::new(
+            self.config.resend_order,
+            &self.retention_quotas,
         ));
 
+        let backoff = self.backoff.unwrap_or_else(|| {
+            Box::new(RandomExponentialIter::with_jitter(
+                ExponentialIter::new(
+                    self.config.exponential_backoff_max,
+                    self.config.exponential_backoff_reset,
+                ),
+                self.config.exponential_backoff_jitter,
+            ))
+        });
+
+        let priority_wake = self
+            .config
+            .duty_cycle
+            .map(|duty_cycle| PriorityWake::new(duty_cycle.wake_interfaces(&self.retention_quotas)))
+            .unwrap_or_default();
+
+        let state = Arc::new(
+            SharedState::new(self.config, self.interfaces, volatile_store)
+                .with_priority_wake(priority_wake),
+        );
+
         let config = BuildConfig {
             store: self.store,
             state: Arc::clone(&state),
@@ -468,7 +637,13 @@ where
             backoff,
         );
 
-        connection.init_store(self.stored_retention).await?;
+        connection
+            .init_store(
+                self.stored_retention,
+                self.stored_retention_bytes,
+                &self.retention_quotas,
+            )
+            .await?;
 
         Ok((client, connection))
     }
@@ -635,6 +810,24 @@ mod test {
         .unwrap();
     }
 
+    #[tokio::test]
+    async fn volatile_spool_should_require_writable_dir() {
+        let config = MockConfig::<MemoryStore>::new();
+
+        let res = DeviceBuilder::new()
+            .volatile_spool(NonZero::new(1024).unwrap())
+            .store(MemoryStore::default())
+            .connection(config)
+            .build()
+            .await;
+
+        let err = res.err().expect("should fail without the writable dir");
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Retention(RetentionError::Spool)
+        ));
+    }
+
     #[test]
     fn test_get_introspection_string() {
         let options = DeviceBuilder::new()
@@ -31,13 +31,14 @@ use crate::error::{AstarteError, ErrorKind, InterfaceError};
 use crate::event::DeviceEvent;
 use crate::logging::security::{SecurityEvent, notify_security_event};
 use crate::pairing::Pairing;
+use crate::retention::inspect::RetentionHandle;
 use crate::retention::memory::{ItemValue, VolatileItemError};
 use crate::retention::{
     Id, RetentionId, StoredRetention, StoredRetentionExt, stored_mark_unsent, volatile_mark_unsent,
 };
 use crate::state::{ClientState, ConnStatus};
 use crate::store::StoreCapabilities;
-use crate::transport::mqtt::Mqtt;
+use crate::transport::mqtt::{Mqtt, MqttMetrics};
 use crate::transport::{Connection, Disconnect, Publish};
 use crate::types::AstarteData;
 use crate::validate::{ValidatedIndividual, ValidatedObject};
@@ -280,6 +281,40 @@ pub trait ClientConnection {
 
     /// Check if the client is already paired.
     fn is_paired(&self) -> bool;
+
+    /// Pauses the connection to Astarte, without closing the client.
+    ///
+    /// The device disconnects cleanly and doesn't reconnect until
+    /// [`resume`](ClientConnection::resume) is called. While paused, the data sent is handled like
+    /// when the device is offline: it's kept in the retention configured for the interface and
+    /// resent after the resume.
+    ///
+    /// This is useful on metered connections to go offline on purpose.
+    ///
+    /// The default implementation returns an [`Unsupported`](std::io::ErrorKind::Unsupported)
+    /// I/O error.
+    fn pause(&mut self) -> impl Future<Output = Result<(), AstarteError>> + Send {
+        async {
+            Err(AstarteError::with(
+                ErrorKind::Io(std::io::ErrorKind::Unsupported),
+                "pause is not supported by the connection",
+            ))
+        }
+    }
+
+    /// Resumes the connection paused with [`pause`](ClientConnection::pause).
+    ///
+    /// The device reconnects immediately and resends the data stored while paused.
+    ///
+    /// The default implementation does nothing.
+    fn resume(&self) {}
+
+    /// Returns true if the connection is paused, see [`pause`](ClientConnection::pause).
+    ///
+    /// The default implementation always returns false.
+    fn is_paused(&self) -> bool {
+        false
+    }
 }
 
 /// Client to send and receive message to and form Astarte or access the Device properties.
@@ -324,6 +359,40 @@ where
         }
     }
 
+    /// Returns a handle to inspect and purge the publishes waiting in the retention.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use astarte_device_sdk::builder::DeviceBuilder;
+    /// use astarte_device_sdk::retention::inspect::RetentionFilter;
+    /// use astarte_device_sdk::store::memory::MemoryStore;
+    /// use astarte_device_sdk::transport::mqtt::{MqttConfig, MqttArgs, Credential};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let args = MqttArgs{
+    ///         realm: "realm_id".to_string(),
+    ///         device_id: "device_id".to_string(),
+    ///         credential: Credential::secret("credential_secret"),
+    ///         pairing_url: "http://api.astarte.localhost/pairing".parse().expect("a valid URL")
+    ///     };
+    ///     let mqtt_config = MqttConfig::new(args);
+    ///
+    ///     let (client, connection) = DeviceBuilder::new().store(MemoryStore::new())
+    ///         .connection(mqtt_config).build().await.unwrap();
+    ///
+    ///     let filter = RetentionFilter::new()
+    ///         .with_interface("my.interface.name")
+    ///         .older_than(Duration::from_secs(3600));
+    ///
+    ///     let purged = client.retention().purge(&filter).await.unwrap();
+    /// }
+    /// ```
+    pub fn retention(&self) -> RetentionHandle<C::Store> {
+        RetentionHandle::new(self.store.clone(), self.state.clone())
+    }
+
     async fn send<T>(
         state: &ClientState,
         store: &C::Store,
@@ -336,6 +405,11 @@ where
         C::Sender: Publish,
     {
         match state.connection().await {
+            ConnStatus::Connected if state.is_paused() => {
+                trace!("publish while connection is paused");
+
+                return Self::offline_send(state, store, sender, data).await;
+            }
             ConnStatus::Connected => {
                 trace!("publish while connection is connected");
             }
@@ -376,9 +450,13 @@ where
         C::Store: StoreCapabilities,
         C::Sender: Publish,
     {
+        let priority = state.priority_wake().is_priority(data.interface());
+
         match data.get_retention() {
             Retention::Discard => {
                 debug!("drop publish with retention discard since disconnected");
+
+                return Ok(());
             }
             Retention::Volatile { .. } => {
                 let id = state.retention_ctx().next();
@@ -400,6 +478,10 @@ where
             }
         }
 
+        if priority {
+            state.priority_wake().wake();
+        }
+
         Ok(())
     }
 
@@ -472,6 +554,14 @@ where
         self.state.cert_expiry().await
     }
 
+    /// Returns a handle to the quality metrics of the MQTT connection.
+    ///
+    /// The handle is shared with the connection, take a
+    /// [`snapshot`](MqttMetrics::snapshot) to read the current values.
+    pub fn metrics(&self) -> MqttMetrics {
+        self.sender.metrics.clone()
+    }
+
     /// Retrieve the expiry (not_after) timestamp of the current certificate
     /// Note that this function will log a security event if the feature is enabled
     /// when the certificate will expire at the passed datetime
@@ -629,11 +719,52 @@ where
     fn is_paired(&self) -> bool {
         self.state.is_device_paired()
     }
+
+    async fn pause(&mut self) -> Result<(), AstarteError> {
+        if self.state.connection().await == ConnStatus::Closed {
+            return Err(AstarteError::with(
+                ErrorKind::Disconnected,
+                "cannot pause a closed connection",
+            ));
+        }
+
+        if self.state.set_paused(true) {
+            debug!("connection already paused");
+
+            return Ok(());
+        }
+
+        if self.state.connection().await == ConnStatus::Connected
+            && let Err(err) = self.sender.disconnect().await
+        {
+            self.state.set_paused(false);
+
+            return Err(err);
+        }
+
+        info!("connection paused");
+
+        Ok(())
+    }
+
+    fn resume(&self) {
+        if self.state.set_paused(false) {
+            info!("connection resumed");
+        } else {
+            debug!("connection not paused");
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state.is_paused()
+    }
 }
 
 trait ClientPacket {
     fn get_retention(&self) -> Retention;
 
+    fn interface(&self) -> &str;
+
     fn serialize<S>(&self, sender: &S) -> Result<Vec<u8>, AstarteError>
     where
         S: Publish;
@@ -667,6 +798,10 @@ impl ClientPacket for ValidatedIndividual {
         self.retention
     }
 
+    fn interface(&self) -> &str {
+        &self.interface
+    }
+
     fn serialize<S>(&self, sender: &S) -> Result<Vec<u8>, AstarteError>
     where
         S: Publish,
@@ -713,6 +848,10 @@ impl ClientPacket for ValidatedObject {
         self.retention
     }
 
+    fn interface(&self) -> &str {
+        &self.interface
+    }
+
     fn serialize<S>(&self, sender: &S) -> Result<Vec<u8>, AstarteError>
     where
         S: Publish,
@@ -901,4 +1040,34 @@ pub(crate) mod tests {
 
         client.disconnect.recv().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn client_pause_and_resume() {
+        let mut client = mock_client(&[], ConnStatus::Connected);
+
+        client
+            .sender
+            .expect_disconnect()
+            .once()
+            .returning(|| Ok(()));
+
+        client.pause().await.unwrap();
+        assert!(client.is_paused());
+
+        // already paused
+        client.pause().await.unwrap();
+
+        client.resume();
+        assert!(!client.is_paused());
+    }
+
+    #[tokio::test]
+    async fn client_pause_closed() {
+        let mut client = mock_client(&[], ConnStatus::Closed);
+
+        let err = client.pause().await.unwrap_err();
+
+        assert_eq!(*err.kind(), ErrorKind::Disconnected);
+        assert!(!client.is_paused());
+    }
 }
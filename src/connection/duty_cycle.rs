@@ -0,0 +1,367 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Duty-cycled connection, connecting to Astarte only in periodic windows.
+
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tracing::{debug, error, info, instrument, trace};
+
+use crate::error::{AstarteError, Report};
+use crate::retention::quota::RetentionQuotas;
+use crate::state::ConnStatus;
+use crate::transport::{Connection, Disconnect, Publish, Receive};
+
+use super::DeviceConnection;
+
+/// Default time without incoming data before closing the window.
+pub const DEFAULT_DUTY_CYCLE_IDLE: Duration = Duration::from_secs(5);
+
+/// Schedule of a duty-cycled connection.
+///
+/// The device connects once every period, sends the publishes stored in the retention, receives
+/// the server data and then disconnects until the next period. The window closes when the
+/// retention is sent and no data is received for the idle timeout, or at the maximum window.
+///
+/// While disconnected the publishes are stored in the retention like when the device is offline.
+/// The MQTT session is kept between the windows, so the subscriptions stay valid and the server
+/// data sent in the meantime is delivered in the next window. The period should be shorter than
+/// the session expiry configured on the broker, or the one requested with
+/// [`MqttProtocol::V5`](crate::transport::mqtt::MqttProtocol::V5).
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use astarte_device_sdk::connection::DutyCycle;
+///
+/// // Connect every 15 minutes for at most 1 minute
+/// let duty_cycle = DutyCycle::new(Duration::from_secs(15 * 60), Duration::from_secs(60))
+///     .wake_on_priority(10);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DutyCycle {
+    period: Duration,
+    max_window: Duration,
+    idle: Duration,
+    wake_priority: Option<u8>,
+}
+
+impl DutyCycle {
+    /// Creates the schedule with the period between the connections and the maximum duration of
+    /// a connection window.
+    pub fn new(period: Duration, max_window: Duration) -> Self {
+        Self {
+            period,
+            max_window,
+            idle: DEFAULT_DUTY_CYCLE_IDLE,
+            wake_priority: None,
+        }
+    }
+
+    /// Sets the time without incoming data before closing the window.
+    ///
+    /// The default is [`DEFAULT_DUTY_CYCLE_IDLE`].
+    pub fn idle_timeout(mut self, idle: Duration) -> Self {
+        self.idle = idle;
+
+        self
+    }
+
+    /// Connects before the next window when data is sent on an interface with at least the given
+    /// priority.
+    ///
+    /// The priority of the interfaces is configured with
+    /// [`DeviceBuilder::retention_quotas`](crate::builder::DeviceBuilder::retention_quotas).
+    pub fn wake_on_priority(mut self, min_priority: u8) -> Self {
+        self.wake_priority = Some(min_priority);
+
+        self
+    }
+
+    /// Returns the period between the connections.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns the maximum duration of a connection window.
+    pub fn max_window(&self) -> Duration {
+        self.max_window
+    }
+
+    /// Returns the interfaces that wake the connection before the next window.
+    pub(crate) fn wake_interfaces(&self, quotas: &RetentionQuotas) -> HashSet<String> {
+        let Some(min_priority) = self.wake_priority else {
+            return HashSet::new();
+        };
+
+        quotas
+            .priorities()
+            .filter(|(_, priority)| *priority >= min_priority)
+            .map(|(interface, _)| interface.to_string())
+            .collect()
+    }
+}
+
+/// Wakes the duty-cycled connection when sending on a priority interface.
+#[derive(Debug, Default)]
+pub(crate) struct PriorityWake {
+    interfaces: HashSet<String>,
+    notify: Notify,
+}
+
+impl PriorityWake {
+    pub(crate) fn new(interfaces: HashSet<String>) -> Self {
+        Self {
+            interfaces,
+            notify: Notify::new(),
+        }
+    }
+
+    /// Returns true if data on the interface wakes the connection.
+    pub(crate) fn is_priority(&self, interface: &str) -> bool {
+        self.interfaces.contains(interface)
+    }
+
+    /// Wakes the connection, the wake is kept if the connection is not waiting.
+    pub(crate) fn wake(&self) {
+        debug!("priority data stored, waking the connection");
+
+        self.notify.notify_one();
+    }
+
+    /// Waits for data sent on a priority interface.
+    pub(crate) async fn wait(&self) {
+        self.notify.notified().await
+    }
+}
+
+/// How a connection window ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Window {
+    /// The retention was sent and no data was received for the idle time, or the window elapsed.
+    Elapsed,
+    /// The connection was lost during the window.
+    Disconnected,
+    /// The connection was closed by the client.
+    Closed,
+}
+
+impl<C> DeviceConnection<C>
+where
+    C: Connection,
+{
+    /// Connects only in the windows of the duty cycle.
+    ///
+    /// It's called after the first connection succeeded.
+    #[instrument(skip_all)]
+    pub(super) async fn run_duty_cycle(
+        &mut self,
+        duty_cycle: &DutyCycle,
+    ) -> Result<(), AstarteError>
+    where
+        C: Receive,
+        C::Sender: Publish + Disconnect + 'static,
+    {
+        let mut started = Instant::now();
+
+        loop {
+            match self.poll_window(duty_cycle, started).await? {
+                Window::Elapsed => {}
+                Window::Disconnected => {
+                    if self.reconnect_and_resend().await?.is_break() {
+                        return Ok(());
+                    }
+
+                    // Restart the window to send the retention
+                    started = Instant::now();
+
+                    continue;
+                }
+                Window::Closed => return Ok(()),
+            }
+
+            if self.close_window().await.is_break() {
+                return Ok(());
+            }
+
+            if self
+                .wait_next_window(started + duty_cycle.period)
+                .await
+                .is_break()
+            {
+                return Ok(());
+            }
+
+            started = Instant::now();
+
+            if self.reconnect_window().await?.is_break() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Polls the connection until the end of the window.
+    async fn poll_window(
+        &mut self,
+        duty_cycle: &DutyCycle,
+        started: Instant,
+    ) -> Result<Window, AstarteError>
+    where
+        C: Receive,
+        C::Sender: Publish + 'static,
+    {
+        let deadline = started + duty_cycle.max_window;
+
+        loop {
+            let resent = self
+                .resend
+                .as_ref()
+                .is_none_or(|handle| handle.is_finished());
+
+            let timeout = if resent {
+                deadline.min(Instant::now() + duty_cycle.idle)
+            } else {
+                deadline
+            };
+
+            match tokio::time::timeout_at(timeout, self.poll()).await {
+                Ok(Ok(ConnStatus::Connected)) => {}
+                Ok(Ok(ConnStatus::Disconnected)) => return Ok(Window::Disconnected),
+                Ok(Ok(ConnStatus::Closed)) => return Ok(Window::Closed),
+                Ok(Err(err)) => return Err(err),
+                Err(_) if resent || Instant::now() >= deadline => {
+                    debug!(elapsed = ?started.elapsed(), "connection window elapsed");
+
+                    return Ok(Window::Elapsed);
+                }
+                // Still sending the retention
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// Disconnects cleanly at the end of the window, keeping the session.
+    async fn close_window(&mut self) -> ControlFlow<()>
+    where
+        C: Receive,
+        C::Sender: Publish + Disconnect + 'static,
+    {
+        info!("closing the connection window");
+
+        self.cancel_prev_resend().await;
+
+        if let Err(err) = self.sender.disconnect().await {
+            error!(error = %Report::new(&err), "couldn't disconnect at the end of the window");
+        }
+
+        // Poll to send the disconnect, until the connection is closed
+        let timeout = self.state.config().connection_timeout;
+        let drain = async {
+            loop {
+                match self.poll().await {
+                    Ok(ConnStatus::Connected) => trace!("event received while disconnecting"),
+                    Ok(status) => break status,
+                    Err(err) => {
+                        error!(error = %Report::new(&err), "couldn't disconnect");
+
+                        break ConnStatus::Disconnected;
+                    }
+                }
+            }
+        };
+
+        let status = tokio::time::timeout(timeout, drain)
+            .await
+            .unwrap_or(ConnStatus::Disconnected);
+
+        if status == ConnStatus::Closed {
+            return ControlFlow::Break(());
+        }
+
+        self.state.set_connection(ConnStatus::Disconnected).await;
+
+        ControlFlow::Continue(())
+    }
+
+    /// Waits for the next window or an early wake for priority data.
+    async fn wait_next_window(&self, next: Instant) -> ControlFlow<()> {
+        debug!(
+            delay = ?next.saturating_duration_since(Instant::now()),
+            "waiting for the next window"
+        );
+
+        let wait = async {
+            tokio::select! {
+                () = tokio::time::sleep_until(next) => {}
+                () = self.state.priority_wake().wait() => {
+                    info!("connecting before the next window for priority data");
+                }
+            }
+        };
+
+        match Self::run_until_disconnect(&self.disconnect, wait).await {
+            Some(()) => ControlFlow::Continue(()),
+            None => ControlFlow::Break(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_collect_wake_interfaces() {
+        let quotas = RetentionQuotas::new()
+            .with_priority("com.example.Alarms", 10)
+            .with_priority("com.example.Warnings", 5)
+            .with_priority("com.example.Logs", 1);
+
+        let duty_cycle = DutyCycle::new(Duration::from_secs(600), Duration::from_secs(60));
+        assert!(duty_cycle.wake_interfaces(&quotas).is_empty());
+
+        let interfaces = duty_cycle.wake_on_priority(5).wake_interfaces(&quotas);
+        let exp = HashSet::from([
+            "com.example.Alarms".to_string(),
+            "com.example.Warnings".to_string(),
+        ]);
+        assert_eq!(interfaces, exp);
+    }
+
+    #[tokio::test]
+    async fn should_wake_on_priority_interface() {
+        let wake = PriorityWake::new(HashSet::from(["com.example.Alarms".to_string()]));
+
+        assert!(!wake.is_priority("com.example.Logs"));
+        let res = tokio::time::timeout(Duration::from_millis(50), wake.wait()).await;
+        assert!(res.is_err());
+
+        // The wake is stored until someone waits
+        assert!(wake.is_priority("com.example.Alarms"));
+        wake.wake();
+        tokio::time::timeout(Duration::from_secs(1), wake.wait())
+            .await
+            .unwrap();
+    }
+}
@@ -35,11 +35,14 @@ use crate::error::ErrorKind;
 use crate::error::InterfaceError;
 use crate::error::Report;
 use crate::event::DeviceEvent;
-use crate::retry::RandomExponentialIter;
+use crate::retry::Backoff;
 use crate::state::{ConnStatus, ConnectionState};
 use crate::transport::ReceivedEvent;
-use crate::transport::{Connection, Publish, Receive};
+use crate::transport::{Connection, Disconnect, Publish, Receive};
 
+pub use self::duty_cycle::{DEFAULT_DUTY_CYCLE_IDLE, DutyCycle};
+
+pub(crate) mod duty_cycle;
 mod incoming;
 mod resend;
 
@@ -98,7 +101,7 @@ where
     sender: C::Sender,
     state: ConnectionState,
     resend: Option<JoinHandle<()>>,
-    backoff: RandomExponentialIter,
+    backoff: Box<dyn Backoff>,
 }
 
 impl<C> DeviceConnection<C>
@@ -112,7 +115,7 @@ where
         state: ConnectionState,
         connection: C,
         sender: C::Sender,
-        backoff: RandomExponentialIter,
+        backoff: Box<dyn Backoff>,
     ) -> Self {
         Self {
             events,
@@ -285,7 +288,7 @@ where
 impl<C> EventLoop for DeviceConnection<C>
 where
     C: Connection + Receive + 'static,
-    C::Sender: Publish + 'static,
+    C::Sender: Publish + Disconnect + 'static,
 {
     #[instrument(skip(self))]
     async fn handle_events(mut self) -> Result<(), AstarteError> {
@@ -310,6 +313,14 @@ where
             }
         }
 
+        if let Some(duty_cycle) = self.state.config().duty_cycle {
+            self.run_duty_cycle(&duty_cycle).await?;
+
+            info!("connection closed successfully");
+
+            return Ok(());
+        }
+
         loop {
             match self.poll().await? {
                 ConnStatus::Connected => {}
@@ -346,6 +357,7 @@ mod tests {
     use crate::builder::{Config, DEFAULT_CHANNEL_SIZE, DEFAULT_VOLATILE_CAPACITY};
     use crate::interfaces::Interfaces;
     use crate::retention::memory::VolatileStore;
+    use crate::retry::RandomExponentialIter;
     use crate::state::SharedState;
     use crate::store::StoreCapabilities;
     use crate::store::memory::MemoryStore;
@@ -421,7 +433,7 @@ mod tests {
             ConnectionState::new(Arc::new(state)),
             connection,
             sender,
-            RandomExponentialIter::default(),
+            Box::new(RandomExponentialIter::default()),
         );
 
         TestConnection {
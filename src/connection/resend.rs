@@ -16,9 +16,9 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::HashMap;
 use std::num::NonZero;
 use std::ops::ControlFlow;
-use std::time::Duration;
 
 use astarte_device_error::ResultExt;
 use astarte_interfaces::schema::Ownership;
@@ -26,8 +26,11 @@ use tracing::{debug, error, info, instrument, trace};
 
 use crate::error::{AstarteError, ErrorKind, Report};
 use crate::retention::memory::ItemValue;
+use crate::retention::order::{ResendCursor, ResendKey};
+use crate::retention::quota::RetentionQuotas;
 use crate::retention::{
-    RetentionId, StoredRetention, StoredRetentionExt, stored_mark_unsent, volatile_mark_unsent,
+    Id, PublishInfo, RetentionId, StoredRetention, StoredRetentionExt, stored_mark_unsent,
+    volatile_mark_unsent,
 };
 use crate::state::{ConnStatus, ConnectionState};
 use crate::store::{PropertyMapping, PropertyState, PropertyStore, StoreCapabilities};
@@ -35,6 +38,12 @@ use crate::transport::{AttemptStatus, Connection, Publish, Receive};
 
 use super::DeviceConnection;
 
+/// Publish loaded from the retention to be resent.
+enum Unsent {
+    Volatile(ItemValue),
+    Stored(PublishInfo<'static>),
+}
+
 impl<C> DeviceConnection<C>
 where
     C: Connection,
@@ -44,11 +53,18 @@ where
     pub(crate) async fn init_store(
         &self,
         stored_retention: NonZero<usize>,
+        stored_retention_bytes: Option<NonZero<u64>>,
+        quotas: &RetentionQuotas,
     ) -> Result<(), AstarteError> {
         trace!("initialize stored retention and properties");
 
         // set max retention items in the store
         if let Some(retention) = self.store.get_retention() {
+            retention
+                .set_retention_events(self.state.volatile_store().events().clone())
+                .await
+                .map_kind(ErrorKind::Retention)?;
+
             {
                 let interfaces = self.state.interfaces().read().await;
 
@@ -64,6 +80,23 @@ where
                 .await
                 .map_kind(ErrorKind::Retention)?;
 
+            if let Some(bytes) = stored_retention_bytes {
+                retention
+                    .set_max_retention_bytes(bytes)
+                    .await
+                    .map_kind(ErrorKind::Retention)?;
+            }
+
+            retention
+                .set_retention_quotas(quotas)
+                .await
+                .map_kind(ErrorKind::Retention)?;
+
+            retention
+                .set_resend_order(self.state.config().resend_order)
+                .await
+                .map_kind(ErrorKind::Retention)?;
+
             debug!("resetting all datastream sent flags");
             retention
                 .reset_all_publishes()
@@ -90,7 +123,28 @@ where
 
         self.cancel_prev_resend().await;
 
-        if self.reconnect().await?.is_break() {
+        if self.reconnect(true).await?.is_break() {
+            return Ok(ControlFlow::Break(()));
+        }
+
+        self.resend(true).await;
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Connects at the start of a duty cycle window and resends all retention publishes.
+    ///
+    /// The first attempt is not delayed by the backoff.
+    pub(crate) async fn reconnect_window(&mut self) -> Result<ControlFlow<()>, AstarteError>
+    where
+        C: Receive,
+        C::Sender: Publish + 'static,
+    {
+        trace!("connecting for the window");
+
+        self.cancel_prev_resend().await;
+
+        if self.reconnect(false).await?.is_break() {
             return Ok(ControlFlow::Break(()));
         }
 
@@ -121,31 +175,56 @@ where
         self.resend = Some(tokio::task::spawn(async move {
             let _interfaces = state.interfaces().read().await;
 
-            let mut remaining_data = true;
-
-            while remaining_data {
-                remaining_data = false;
+            let live_first = state.config().live_sends_first;
 
-                if volatile {
-                    match Self::resend_volatile_publishes(&mut sender, &state, limit).await {
-                        Ok(sent) => remaining_data |= sent >= limit.get(),
+            if live_first {
+                // The properties are sent before the new publishes, so an older value is not
+                // sent after a newer one.
+                loop {
+                    match Self::send_device_properties(&mut store, &mut sender, limit.get()).await {
+                        Ok(sent) if sent >= limit.get() => {}
+                        Ok(_) => break,
                         Err(err) => {
-                            error!(error = %Report::new(&err), "error sending volatile retention");
-                            // in case of errors while sending we still exit the loop
+                            error!(error = %Report::new(&err), "error sending device properties");
+
                             break;
                         }
                     }
                 }
 
-                match Self::resend_stored_publishes(&mut store, &mut sender, limit).await {
-                    Ok(sent) => remaining_data |= sent >= limit.get(),
+                debug!("connected, resending the retention after the new publishes");
+
+                state.set_connection(ConnStatus::Connected).await;
+            }
+
+            let mut remaining_data = true;
+            let mut cursor = ResendCursor::default();
+
+            while remaining_data {
+                remaining_data = false;
+
+                match Self::resend_publishes(
+                    &mut store,
+                    &mut sender,
+                    &state,
+                    limit,
+                    volatile,
+                    &mut cursor,
+                )
+                .await
+                {
+                    Ok(remaining) => remaining_data |= remaining,
                     Err(err) => {
-                        error!(error = %Report::new(&err), "error sending stored retention");
+                        error!(error = %Report::new(&err), "error sending retention");
                         // in case of errors while sending we still exit the loop
                         break;
                     }
                 }
 
+                if live_first {
+                    continue;
+                }
+
                 match Self::send_device_properties(&mut store, &mut sender, limit.get()).await {
                     Ok(sent) => remaining_data |= sent >= limit.get(),
                     Err(err) => {
@@ -156,8 +235,10 @@ where
                 }
             }
 
-            // after everything got resent we are connected
-            state.set_connection(ConnStatus::Connected).await;
+            if !live_first {
+                // after everything got resent we are connected
+                state.set_connection(ConnStatus::Connected).await;
+            }
         }));
     }
 
@@ -217,7 +298,7 @@ where
 
     /// Check if there is a previous task for the resend of stored publishes and cancels it.
     #[instrument(skip(self))]
-    async fn cancel_prev_resend(&mut self) {
+    pub(crate) async fn cancel_prev_resend(&mut self) {
         trace!("checking for previous resend");
 
         if let Some(resend) = self.resend.take() {
@@ -239,103 +320,161 @@ where
         }
     }
 
-    async fn resend_volatile_publishes(
+    /// Sends at most `limit` publishes of the volatile and stored retention, ordered by the
+    /// priority of their interfaces and the [`ResendOrder`](crate::retention::order::ResendOrder).
+    ///
+    /// The cursor keeps the order between the calls.
+    ///
+    /// Returns `true` if there could be other publishes to send.
+    async fn resend_publishes(
+        store: &mut C::Store,
         sender: &mut C::Sender,
         state: &ConnectionState,
         limit: NonZero<usize>,
-    ) -> Result<usize, AstarteError>
+        volatile: bool,
+        cursor: &mut ResendCursor,
+    ) -> Result<bool, AstarteError>
     where
         C::Sender: Publish,
     {
-        let mut buf = Vec::new();
+        let policy = state.volatile_store().resend_policy();
+
+        // One more to continue the interleave from the newest
+        let fetch = limit.get().saturating_add(1);
+
+        let mut volatile_buf = Vec::new();
+        let volatile_count = if volatile {
+            state
+                .volatile_store()
+                .get_unsent(&mut volatile_buf, fetch)
+                .await
+        } else {
+            0
+        };
+
+        trace!("loaded {volatile_count} volatile publishes");
+
+        let mut stored_buf = Vec::new();
+        let stored_count = match store.get_retention() {
+            Some(retention) => retention
+                .unsent_publishes(fetch, &mut stored_buf)
+                .await
+                .map_kind(ErrorKind::Retention)?,
+            None => 0,
+        };
 
-        let count = state
-            .volatile_store()
-            .get_unsent(&mut buf, limit.get())
-            .await;
+        trace!("loaded {stored_count} stored publishes");
 
-        trace!("loaded {count} volatile publishes");
+        let mut keys = Vec::with_capacity(volatile_count.saturating_add(stored_count));
+        let mut unsent = HashMap::with_capacity(keys.capacity());
 
-        for (id, value) in buf.drain(..) {
-            // mark as sent before so that no resend is tried while in flight
-            state.volatile_store().mark_sent(&id, true).await;
+        for (id, value) in volatile_buf {
+            keys.push(policy.key(id, value.interface()));
+            unsent.insert(id, Unsent::Volatile(value));
+        }
+
+        for (id, info) in stored_buf {
+            keys.push(policy.key(id, &info.interface));
+            unsent.insert(id, Unsent::Stored(info));
+        }
 
-            let result = match value {
-                ItemValue::Individual(individual) => {
-                    sender
-                        .send_individual_stored(RetentionId::Volatile(id), individual)
-                        .await
+        // Each retention returns its first publishes, the ones not sent are fetched again
+        policy.sort_batch(&mut keys, limit.get(), cursor);
+
+        let sent = keys.len();
+
+        for ResendKey { id, .. } in keys {
+            let Some(value) = unsent.remove(&id) else {
+                continue;
+            };
+
+            match value {
+                Unsent::Volatile(value) => {
+                    Self::resend_volatile_publish(sender, state, id, value).await?
                 }
-                ItemValue::Object(object) => {
-                    sender
-                        .send_object_stored(RetentionId::Volatile(id), object)
-                        .await
+                Unsent::Stored(info) => {
+                    Self::resend_stored_publish(store, sender, id, info).await?
                 }
-            };
+            }
+        }
 
-            if let Err(e) = result {
-                error!(error=%Report::new(&e), "error while sending volatile marking unsent");
-                volatile_mark_unsent(state.volatile_store(), &id).await;
-                return Err(e);
+        // A retention with more publishes returned more than the sent ones
+        Ok(volatile_count.saturating_add(stored_count) > sent)
+    }
+
+    async fn resend_volatile_publish(
+        sender: &mut C::Sender,
+        state: &ConnectionState,
+        id: Id,
+        value: ItemValue,
+    ) -> Result<(), AstarteError>
+    where
+        C::Sender: Publish,
+    {
+        // mark as sent before so that no resend is tried while in flight
+        state.volatile_store().mark_sent(&id, true).await;
+
+        let result = match value {
+            ItemValue::Individual(individual) => {
+                sender
+                    .send_individual_stored(RetentionId::Volatile(id), individual)
+                    .await
+            }
+            ItemValue::Object(object) => {
+                sender
+                    .send_object_stored(RetentionId::Volatile(id), object)
+                    .await
             }
+        };
+
+        if let Err(e) = result {
+            error!(error=%Report::new(&e), "error while sending volatile marking unsent");
+            volatile_mark_unsent(state.volatile_store(), &id).await;
+            return Err(e);
         }
 
-        Ok(count)
+        Ok(())
     }
 
-    async fn resend_stored_publishes(
+    async fn resend_stored_publish(
         store: &mut C::Store,
         sender: &mut C::Sender,
-        limit: NonZero<usize>,
-    ) -> Result<usize, AstarteError>
+        id: Id,
+        info: PublishInfo<'static>,
+    ) -> Result<(), AstarteError>
     where
         C::Sender: Publish,
     {
         let Some(retention) = store.get_retention() else {
-            return Ok(0);
+            return Ok(());
         };
 
-        let mut buf = Vec::new();
-
-        debug!("start sending store publishes");
-
-        let count = retention
-            .unsent_publishes(limit.get(), &mut buf)
+        // mark as sent before so that no resend is tried while in flight
+        retention
+            .update_sent_flag(&id, true)
             .await
             .map_kind(ErrorKind::Retention)?;
 
-        trace!("loaded {count} stored publishes");
+        let result = sender.resend_stored(RetentionId::Stored(id), info).await;
 
-        for (id, info) in buf.drain(..) {
-            // mark as sent before so that no resend is tried while in flight
-            retention
-                .update_sent_flag(&id, true)
-                .await
-                .map_kind(ErrorKind::Retention)?;
-
-            let result = sender.resend_stored(RetentionId::Stored(id), info).await;
+        if let Err(e) = result {
+            error!(error=%Report::new(&e), "error while sending stored marking unsent");
 
-            if let Err(e) = result {
-                error!(error=%Report::new(&e), "error while sending stored marking unsent");
+            stored_mark_unsent(store, &id).await;
 
-                stored_mark_unsent(store, &id).await;
-
-                return Err(e);
-            }
+            return Err(e);
         }
 
-        Ok(count)
+        Ok(())
     }
 
     #[instrument(skip(self))]
-    async fn reconnect(&mut self) -> Result<ControlFlow<()>, AstarteError>
+    async fn reconnect(&mut self, backoff: bool) -> Result<ControlFlow<()>, AstarteError>
     where
         C: Receive,
     {
         trace!("start reconnection");
 
-        let interfaces = self.state.interfaces().read().await;
-
         info!("reconnecting");
 
         // Wait before trying to reconnect the first time, this will prevent cases where the
@@ -355,16 +494,20 @@ where
         //
         // If we didn't keep track of the last disconnection, the error loop above would continue to
         // happen without timeouts, wasting device bandwidth and resources.
-        let timeout = self.backoff.next();
-
-        if self.wait_timeout(timeout).await.is_break() {
+        if self.wait_before_attempt(backoff).await.is_break() {
             return Ok(ControlFlow::Break(()));
         }
 
         let session;
 
         loop {
-            let attempt_status = self.connection.reconnect(&interfaces).await?;
+            // The lock is released before waiting, so the client can change the interfaces while
+            // the connection is paused
+            let attempt_status = {
+                let interfaces = self.state.interfaces().read().await;
+
+                self.connection.reconnect(&interfaces).await?
+            };
 
             match attempt_status {
                 AttemptStatus::Connected { session_present } => {
@@ -375,9 +518,7 @@ where
                     self.handle_and_send_to_client(event).await?;
                 }
                 AttemptStatus::Disconnected => {
-                    let timeout = self.backoff.next();
-
-                    if self.wait_timeout(timeout).await.is_break() {
+                    if self.wait_before_attempt(true).await.is_break() {
                         return Ok(ControlFlow::Break(()));
                     }
                 }
@@ -407,18 +548,51 @@ where
         Ok(ControlFlow::Continue(()))
     }
 
-    async fn wait_timeout(&self, timeout: Duration) -> ControlFlow<()> {
-        debug!(seconds = timeout.as_secs(), "waiting before retrying");
+    /// Waits the backoff before a connection attempt.
+    ///
+    /// While the connection is paused by the client, it waits for the resume instead and then
+    /// connects without delay.
+    async fn wait_before_attempt(&mut self, backoff: bool) -> ControlFlow<()> {
+        let mut paused = self.state.paused();
+
+        if !*paused.borrow_and_update() {
+            if !backoff {
+                return ControlFlow::Continue(());
+            }
+
+            let timeout = self.backoff.next_delay();
 
-        let is_disconnected =
-            Self::run_until_disconnect(&self.disconnect, tokio::time::sleep(timeout))
+            debug!(seconds = timeout.as_secs(), "waiting before retrying");
+
+            // The wait is interrupted if the connection gets paused
+            let wait = async {
+                tokio::select! {
+                    () = tokio::time::sleep(timeout) => {}
+                    _ = paused.wait_for(|paused| *paused) => {}
+                }
+            };
+
+            if Self::run_until_disconnect(&self.disconnect, wait)
                 .await
-                .is_none();
+                .is_none()
+            {
+                return ControlFlow::Break(());
+            }
 
-        if is_disconnected {
-            ControlFlow::Break(())
-        } else {
-            ControlFlow::Continue(())
+            if !*paused.borrow() {
+                return ControlFlow::Continue(());
+            }
+        }
+
+        info!("connection paused, waiting to be resumed");
+
+        // The watch guard is not Send, so it's dropped before returning
+        let resumed = async { paused.wait_for(|paused| !*paused).await.is_ok() };
+
+        match Self::run_until_disconnect(&self.disconnect, resumed).await {
+            Some(true) => ControlFlow::Continue(()),
+            // The sender is in the shared state, so it cannot be dropped
+            Some(false) | None => ControlFlow::Break(()),
         }
     }
 }
@@ -428,19 +602,29 @@ mod tests {
     use std::ops::ControlFlow;
     use std::time::Duration;
 
+    use std::num::NonZero;
+    use std::sync::{Arc, Mutex};
+
     use astarte_interfaces::MappingPath;
+    use astarte_interfaces::interface::Retention;
+    use astarte_interfaces::schema::Reliability;
     use futures::FutureExt;
     use mockall::{Sequence, predicate};
     use tempfile::TempDir;
 
     use crate::AstarteData;
-    use crate::builder::DEFAULT_STORE_CAPACITY;
+    use crate::DeviceConnection;
+    use crate::builder::{Config, DEFAULT_STORE_CAPACITY, DEFAULT_VOLATILE_CAPACITY};
     use crate::connection::tests::{mock_connection, mock_connection_with_store};
-    use crate::retention::{PublishInfo, RetentionId, StoredRetention, StoredRetentionExt};
-    use crate::state::ConnStatus;
+    use crate::interfaces::Interfaces;
+    use crate::retention::memory::VolatileStore;
+    use crate::retention::order::{ResendCursor, ResendOrder, ResendPolicy};
+    use crate::retention::quota::RetentionQuotas;
+    use crate::retention::{Id, PublishInfo, RetentionId, StoredRetention, StoredRetentionExt};
+    use crate::state::{ConnStatus, ConnectionState, SharedState};
     use crate::store::{SqliteStore, StoreCapabilities};
     use crate::test::{STORED_DEVICE_DATASTREAM, STORED_DEVICE_DATASTREAM_NAME};
-    use crate::transport::mock::MockSender;
+    use crate::transport::mock::{MockCon, MockSender};
     use crate::validate::ValidatedIndividual;
 
     #[tokio::test]
@@ -552,7 +736,10 @@ mod tests {
                 sender
             });
 
-        connection.init_store(DEFAULT_STORE_CAPACITY).await.unwrap();
+        connection
+            .init_store(DEFAULT_STORE_CAPACITY, None, &RetentionQuotas::default())
+            .await
+            .unwrap();
         connection.resend(false).await;
 
         tokio::time::timeout(Duration::from_secs(2), connection.resend.take().unwrap())
@@ -560,4 +747,141 @@ mod tests {
             .unwrap()
             .unwrap();
     }
+
+    #[tokio::test]
+    async fn should_keep_interleave_across_batches() {
+        let tmp = TempDir::new().unwrap();
+        let store = SqliteStore::options()
+            .with_writable_dir(tmp.path())
+            .await
+            .unwrap();
+
+        let policy = ResendPolicy::new(ResendOrder::Interleaved, &RetentionQuotas::new());
+        let state = ConnectionState::new(Arc::new(SharedState::new(
+            Config::default(),
+            Interfaces::new(),
+            VolatileStore::with_capacity(DEFAULT_VOLATILE_CAPACITY.get())
+                .with_resend_policy(policy),
+        )));
+
+        let retention = store.get_retention().unwrap();
+        retention
+            .set_resend_order(ResendOrder::Interleaved)
+            .await
+            .unwrap();
+
+        // alternate the volatile and the stored publishes
+        let ids: Vec<Id> = (0..7).map(|_| state.retention_ctx().next()).collect();
+        for (i, id) in ids.iter().enumerate() {
+            let path = format!("/{i}");
+
+            if i % 2 == 0 {
+                state
+                    .volatile_store()
+                    .push_unsent(
+                        *id,
+                        ValidatedIndividual {
+                            interface: "com.Foo".to_string(),
+                            path,
+                            version_major: 1,
+                            reliability: Reliability::Guaranteed,
+                            retention: Retention::Volatile { expiry: None },
+                            data: AstarteData::Integer(42),
+                            timestamp: None,
+                        },
+                    )
+                    .await;
+            } else {
+                retention
+                    .store_publish(
+                        id,
+                        PublishInfo::from_ref(
+                            "com.Foo",
+                            &path,
+                            1,
+                            Reliability::Guaranteed,
+                            Retention::Stored { expiry: None },
+                            false,
+                            &[],
+                        ),
+                    )
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let sent = Arc::new(Mutex::new(Vec::new()));
+
+        let mut sender = MockSender::new();
+        sender.expect_send_individual_stored().returning({
+            let sent = Arc::clone(&sent);
+
+            move |id, _| {
+                sent.lock().unwrap().push(id);
+
+                Ok(())
+            }
+        });
+        sender.expect_resend_stored().returning({
+            let sent = Arc::clone(&sent);
+
+            move |id, _| {
+                sent.lock().unwrap().push(id);
+
+                Ok(())
+            }
+        });
+
+        let mut store = store;
+        let mut cursor = ResendCursor::default();
+        let mut batches = 0;
+        // an odd limit stops the batches after the oldest
+        while DeviceConnection::<MockCon<SqliteStore>>::resend_publishes(
+            &mut store,
+            &mut sender,
+            &state,
+            NonZero::new(3).unwrap(),
+            true,
+            &mut cursor,
+        )
+        .await
+        .unwrap()
+        {
+            batches += 1;
+        }
+
+        assert_eq!(batches, 2);
+
+        let expected: Vec<RetentionId> = [0, 6, 1, 5, 2, 4, 3]
+            .into_iter()
+            .map(|i| {
+                if i % 2 == 0 {
+                    RetentionId::Volatile(ids[i])
+                } else {
+                    RetentionId::Stored(ids[i])
+                }
+            })
+            .collect();
+        assert_eq!(*sent.lock().unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn wait_resume_when_paused() {
+        let mut connection = mock_connection(&[], ConnStatus::Disconnected);
+
+        let state = connection.state.clone();
+        state.set_paused(true);
+
+        let mut wait = std::pin::pin!(connection.wait_before_attempt(true));
+
+        let res = tokio::time::timeout(Duration::from_millis(100), &mut wait).await;
+        assert!(res.is_err(), "should wait while paused");
+
+        state.set_paused(false);
+
+        let res = tokio::time::timeout(Duration::from_secs(2), wait)
+            .await
+            .unwrap();
+        assert_eq!(res, ControlFlow::Continue(()));
+    }
 }
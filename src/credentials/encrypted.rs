@@ -0,0 +1,217 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Credentials encrypted before being stored.
+
+use astarte_device_error::{Error, WrapError};
+use aws_lc_rs::aead::{AES_256_GCM, Aad, NONCE_LEN, Nonce, RandomizedNonceKey};
+
+use super::{CredentialKind, CredentialStore, CredentialStoreError};
+
+/// Length of the key used to encrypt the credentials.
+pub const ENCRYPTION_KEY_LEN: usize = 32;
+
+/// Encrypts the credentials with AES-256-GCM before passing them to the inner store.
+///
+/// The stored data is the random nonce followed by the ciphertext and the tag. The
+/// [`CredentialKind`] is authenticated with the data, so a credential can't be swapped with
+/// another one.
+///
+/// # Example
+///
+/// ```no_run
+/// use astarte_device_sdk::credentials::{
+///     EncryptedCredentialStore, FileCredentialStore,
+/// };
+///
+/// // The key should be read from a secure location, like a keyring
+/// let key = [0u8; 32];
+///
+/// let store = EncryptedCredentialStore::new(FileCredentialStore::new("/var/lib/astarte"), &key)
+///     .expect("should be a valid key");
+/// ```
+#[derive(Debug)]
+pub struct EncryptedCredentialStore<S> {
+    inner: S,
+    key: RandomizedNonceKey,
+}
+
+impl<S> EncryptedCredentialStore<S> {
+    /// Encrypts the credentials of the inner store with the given key.
+    pub fn new(
+        inner: S,
+        key: &[u8; ENCRYPTION_KEY_LEN],
+    ) -> Result<Self, Error<CredentialStoreError>> {
+        let key = RandomizedNonceKey::new(&AES_256_GCM, key).wrap_err_msg(
+            CredentialStoreError::Encryption,
+            "couldn't create the encryption key",
+        )?;
+
+        Ok(Self { inner, key })
+    }
+
+    fn encrypt(
+        &self,
+        kind: CredentialKind,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error<CredentialStoreError>> {
+        let mut in_out = data.to_vec();
+
+        let nonce = self
+            .key
+            .seal_in_place_append_tag(Aad::from(kind.as_str()), &mut in_out)
+            .wrap_err_with(|_| {
+                Error::with(
+                    CredentialStoreError::Encryption,
+                    "couldn't encrypt the credential",
+                )
+                .set_ctx(kind)
+            })?;
+
+        let mut encrypted = Vec::with_capacity(NONCE_LEN + in_out.len());
+        encrypted.extend_from_slice(nonce.as_ref());
+        encrypted.append(&mut in_out);
+
+        Ok(encrypted)
+    }
+
+    fn decrypt(
+        &self,
+        kind: CredentialKind,
+        mut data: Vec<u8>,
+    ) -> Result<Vec<u8>, Error<CredentialStoreError>> {
+        if data.len() < NONCE_LEN {
+            return Err(Error::with(
+                CredentialStoreError::Encryption,
+                "encrypted credential is too short",
+            )
+            .set_ctx(kind));
+        }
+
+        let mut in_out = data.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&data).wrap_err_with(|_| {
+            Error::with(CredentialStoreError::Encryption, "invalid nonce").set_ctx(kind)
+        })?;
+
+        let len = self
+            .key
+            .open_in_place(nonce, Aad::from(kind.as_str()), &mut in_out)
+            .wrap_err_with(|_| {
+                Error::with(
+                    CredentialStoreError::Encryption,
+                    "couldn't decrypt the credential",
+                )
+                .set_ctx(kind)
+            })?
+            .len();
+
+        in_out.truncate(len);
+
+        Ok(in_out)
+    }
+}
+
+impl<S> CredentialStore for EncryptedCredentialStore<S>
+where
+    S: CredentialStore,
+{
+    async fn load(
+        &self,
+        kind: CredentialKind,
+    ) -> Result<Option<Vec<u8>>, Error<CredentialStoreError>> {
+        self.inner
+            .load(kind)
+            .await?
+            .map(|data| self.decrypt(kind, data))
+            .transpose()
+    }
+
+    async fn store(
+        &self,
+        kind: CredentialKind,
+        data: &[u8],
+    ) -> Result<(), Error<CredentialStoreError>> {
+        let encrypted = self.encrypt(kind, data)?;
+
+        self.inner.store(kind, &encrypted).await
+    }
+
+    async fn delete(&self, kind: CredentialKind) -> Result<(), Error<CredentialStoreError>> {
+        self.inner.delete(kind).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::credentials::MemoryCredentialStore;
+    use crate::credentials::tests::check_credential_store;
+
+    use super::*;
+
+    const KEY: [u8; ENCRYPTION_KEY_LEN] = [42; ENCRYPTION_KEY_LEN];
+
+    #[tokio::test]
+    async fn should_encrypt_credentials() {
+        let inner = MemoryCredentialStore::new();
+        let store = EncryptedCredentialStore::new(inner.clone(), &KEY).unwrap();
+
+        check_credential_store(&store).await;
+
+        store
+            .store(CredentialKind::Secret, b"secret")
+            .await
+            .unwrap();
+
+        let encrypted = inner.load(CredentialKind::Secret).await.unwrap().unwrap();
+        assert_ne!(encrypted, b"secret");
+        assert!(!encrypted.windows(6).any(|w| w == b"secret"));
+
+        let secret = store.load(CredentialKind::Secret).await.unwrap();
+        assert_eq!(secret.as_deref(), Some(b"secret".as_slice()));
+    }
+
+    #[tokio::test]
+    async fn should_reject_tampered_credentials() {
+        let inner = MemoryCredentialStore::new();
+        let store = EncryptedCredentialStore::new(inner.clone(), &KEY).unwrap();
+
+        store
+            .store(CredentialKind::Secret, b"secret")
+            .await
+            .unwrap();
+        let encrypted = inner.load(CredentialKind::Secret).await.unwrap().unwrap();
+
+        // Swapped credential
+        inner
+            .store(CredentialKind::Certificate, &encrypted)
+            .await
+            .unwrap();
+        let err = store.load(CredentialKind::Certificate).await.unwrap_err();
+        assert_eq!(*err.kind(), CredentialStoreError::Encryption);
+
+        // Different key
+        let other = EncryptedCredentialStore::new(inner.clone(), &[1; ENCRYPTION_KEY_LEN]).unwrap();
+        let err = other.load(CredentialKind::Secret).await.unwrap_err();
+        assert_eq!(*err.kind(), CredentialStoreError::Encryption);
+
+        // Truncated
+        inner.store(CredentialKind::Secret, &[1, 2]).await.unwrap();
+        let err = store.load(CredentialKind::Secret).await.unwrap_err();
+        assert_eq!(*err.kind(), CredentialStoreError::Encryption);
+    }
+}
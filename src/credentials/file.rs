@@ -0,0 +1,184 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Credentials stored in files.
+
+use std::io;
+use std::path::PathBuf;
+
+use astarte_device_error::{Error, WrapError};
+use tracing::debug;
+
+use crate::pairing::api::{CERTIFICATE_FILE, CREDENTIAL_FILE, PRIVATE_KEY_FILE};
+
+use super::{CredentialKind, CredentialStore, CredentialStoreError};
+
+/// Stores each credential in a file in a directory.
+///
+/// This is the default store, using the writable directory with the [`CREDENTIAL_FILE`],
+/// [`CERTIFICATE_FILE`] and [`PRIVATE_KEY_FILE`] files.
+#[derive(Debug, Clone)]
+pub struct FileCredentialStore {
+    dir: PathBuf,
+}
+
+impl FileCredentialStore {
+    /// Stores the credentials in the given directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the path of the file for the credential.
+    pub(crate) fn path(&self, kind: CredentialKind) -> PathBuf {
+        let file = match kind {
+            CredentialKind::Secret => CREDENTIAL_FILE,
+            CredentialKind::Certificate => CERTIFICATE_FILE,
+            CredentialKind::PrivateKey => PRIVATE_KEY_FILE,
+        };
+
+        self.dir.join(file)
+    }
+}
+
+impl CredentialStore for FileCredentialStore {
+    async fn load(
+        &self,
+        kind: CredentialKind,
+    ) -> Result<Option<Vec<u8>>, Error<CredentialStoreError>> {
+        let path = self.path(kind);
+
+        match tokio::fs::read(&path).await {
+            // An empty file is left by a failed write
+            Ok(data) if data.is_empty() => {
+                debug!(%kind, "credential file is empty");
+
+                Ok(None)
+            }
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!(%kind, "credential file is missing");
+
+                Ok(None)
+            }
+            Err(err) => Err(Error::with(
+                CredentialStoreError::Load,
+                "couldn't read credential file",
+            )
+            .set_source(err)
+            .set_ctx(format!("file {}", path.display()))),
+        }
+    }
+
+    async fn store(
+        &self,
+        kind: CredentialKind,
+        data: &[u8],
+    ) -> Result<(), Error<CredentialStoreError>> {
+        let path = self.path(kind);
+
+        tokio::fs::write(&path, data).await.wrap_err_with(|_| {
+            Error::with(
+                CredentialStoreError::Store,
+                "couldn't write credential file",
+            )
+            .set_ctx(format!("file {}", path.display()))
+        })
+    }
+
+    async fn delete(&self, kind: CredentialKind) -> Result<(), Error<CredentialStoreError>> {
+        let path = self.path(kind);
+
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(Error::with(
+                CredentialStoreError::Delete,
+                "couldn't remove credential file",
+            )
+            .set_source(err)
+            .set_ctx(format!("file {}", path.display()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use crate::credentials::tests::check_credential_store;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn should_store_credentials_in_files() {
+        let dir = TempDir::new().unwrap();
+
+        let store = FileCredentialStore::new(dir.path());
+
+        check_credential_store(&store).await;
+
+        store
+            .store(CredentialKind::Secret, b"secret")
+            .await
+            .unwrap();
+        let secret = tokio::fs::read(dir.path().join(CREDENTIAL_FILE))
+            .await
+            .unwrap();
+        assert_eq!(secret, b"secret");
+
+        // Empty file
+        tokio::fs::write(dir.path().join(CREDENTIAL_FILE), b"")
+            .await
+            .unwrap();
+        assert!(store.load(CredentialKind::Secret).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn should_fail_to_store_in_missing_dir() {
+        let dir = TempDir::new().unwrap();
+
+        let store = FileCredentialStore::new(dir.path().join("missing"));
+
+        let err = store
+            .store(CredentialKind::Certificate, b"cert")
+            .await
+            .unwrap_err();
+
+        assert_eq!(*err.kind(), CredentialStoreError::Store);
+    }
+
+    #[test]
+    fn check_key_and_cert_file() {
+        let store = FileCredentialStore::new("/foo");
+
+        assert_eq!(
+            store.path(CredentialKind::PrivateKey),
+            Path::new("/foo/priv-key.der")
+        );
+        assert_eq!(
+            store.path(CredentialKind::Certificate),
+            Path::new("/foo/certificate.pem")
+        );
+        assert_eq!(
+            store.path(CredentialKind::Secret),
+            Path::new("/foo/credential")
+        );
+    }
+}
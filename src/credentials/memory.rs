@@ -0,0 +1,97 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Credentials kept in memory.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use astarte_device_error::Error;
+use tokio::sync::RwLock;
+
+use super::{CredentialKind, CredentialStore, CredentialStoreError};
+
+/// Keeps the credentials in memory.
+///
+/// The credentials are lost when the process exits, it's useful for ephemeral containers where the
+/// device is registered again on each start. The clones of the store share the credentials.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCredentialStore {
+    credentials: Arc<RwLock<HashMap<CredentialKind, Vec<u8>>>>,
+}
+
+impl MemoryCredentialStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CredentialStore for MemoryCredentialStore {
+    async fn load(
+        &self,
+        kind: CredentialKind,
+    ) -> Result<Option<Vec<u8>>, Error<CredentialStoreError>> {
+        Ok(self.credentials.read().await.get(&kind).cloned())
+    }
+
+    async fn store(
+        &self,
+        kind: CredentialKind,
+        data: &[u8],
+    ) -> Result<(), Error<CredentialStoreError>> {
+        self.credentials.write().await.insert(kind, data.to_vec());
+
+        Ok(())
+    }
+
+    async fn delete(&self, kind: CredentialKind) -> Result<(), Error<CredentialStoreError>> {
+        self.credentials.write().await.remove(&kind);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::credentials::tests::check_credential_store;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn should_store_credentials_in_memory() {
+        let store = MemoryCredentialStore::new();
+
+        check_credential_store(&store).await;
+
+        store
+            .store(CredentialKind::Secret, b"secret")
+            .await
+            .unwrap();
+
+        let cloned = store.clone();
+        assert_eq!(
+            cloned
+                .load(CredentialKind::Secret)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(b"secret".as_slice())
+        );
+    }
+}
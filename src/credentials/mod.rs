@@ -0,0 +1,241 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Storage for the credentials of the device.
+//!
+//! The credential secret returned by the registration, the client certificate and the private key
+//! are persisted by a [`CredentialStore`]. By default they are stored in files in the writable
+//! directory, see [`FileCredentialStore`].
+
+use std::fmt::{Debug, Display};
+use std::future::Future;
+
+use astarte_device_error::Error;
+use futures::FutureExt;
+use futures::future::BoxFuture;
+
+pub use self::encrypted::{ENCRYPTION_KEY_LEN, EncryptedCredentialStore};
+pub use self::file::FileCredentialStore;
+pub use self::memory::MemoryCredentialStore;
+
+mod encrypted;
+mod file;
+mod memory;
+mod sqlite;
+
+/// Error returned by a [`CredentialStore`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialStoreError {
+    /// Couldn't load the credential.
+    Load,
+    /// Couldn't store the credential.
+    Store,
+    /// Couldn't delete the credential.
+    Delete,
+    /// Couldn't encrypt or decrypt the credential.
+    Encryption,
+}
+
+impl Display for CredentialStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialStoreError::Load => write!(f, "couldn't load the credential"),
+            CredentialStoreError::Store => write!(f, "couldn't store the credential"),
+            CredentialStoreError::Delete => write!(f, "couldn't delete the credential"),
+            CredentialStoreError::Encryption => {
+                write!(f, "couldn't encrypt or decrypt the credential")
+            }
+        }
+    }
+}
+
+/// Credential persisted by a [`CredentialStore`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CredentialKind {
+    /// Credential secret returned by the registration with the pairing token.
+    Secret,
+    /// Client certificate in the PEM format.
+    Certificate,
+    /// Private key of the client certificate in the PKCS#8 DER format.
+    ///
+    /// It's stored only when the key is not managed by a custom
+    /// [`KeyProvider`](crate::transport::mqtt::KeyProvider).
+    PrivateKey,
+}
+
+impl CredentialKind {
+    /// Returns the name used to identify the credential.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CredentialKind::Secret => "secret",
+            CredentialKind::Certificate => "certificate",
+            CredentialKind::PrivateKey => "private_key",
+        }
+    }
+}
+
+impl Display for CredentialKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Persists the credentials of the device.
+///
+/// The [`load`](CredentialStore::load) of a credential that was never stored, or that was deleted,
+/// must return [`None`].
+pub trait CredentialStore: Debug + Send + Sync + 'static {
+    /// Loads a credential.
+    fn load(
+        &self,
+        kind: CredentialKind,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, Error<CredentialStoreError>>> + Send;
+
+    /// Stores a credential, replacing the previous one.
+    fn store(
+        &self,
+        kind: CredentialKind,
+        data: &[u8],
+    ) -> impl Future<Output = Result<(), Error<CredentialStoreError>>> + Send;
+
+    /// Deletes a credential.
+    ///
+    /// Deleting a missing credential is not an error.
+    fn delete(
+        &self,
+        kind: CredentialKind,
+    ) -> impl Future<Output = Result<(), Error<CredentialStoreError>>> + Send;
+}
+
+/// Object safe version of the [`CredentialStore`], to store it in the configuration.
+pub(crate) trait DynCredentialStore: Debug + Send + Sync + 'static {
+    fn load_boxed(
+        &self,
+        kind: CredentialKind,
+    ) -> BoxFuture<'_, Result<Option<Vec<u8>>, Error<CredentialStoreError>>>;
+
+    fn store_boxed<'a>(
+        &'a self,
+        kind: CredentialKind,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), Error<CredentialStoreError>>>;
+
+    fn delete_boxed(
+        &self,
+        kind: CredentialKind,
+    ) -> BoxFuture<'_, Result<(), Error<CredentialStoreError>>>;
+}
+
+impl<T> DynCredentialStore for T
+where
+    T: CredentialStore,
+{
+    fn load_boxed(
+        &self,
+        kind: CredentialKind,
+    ) -> BoxFuture<'_, Result<Option<Vec<u8>>, Error<CredentialStoreError>>> {
+        self.load(kind).boxed()
+    }
+
+    fn store_boxed<'a>(
+        &'a self,
+        kind: CredentialKind,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<(), Error<CredentialStoreError>>> {
+        self.store(kind, data).boxed()
+    }
+
+    fn delete_boxed(
+        &self,
+        kind: CredentialKind,
+    ) -> BoxFuture<'_, Result<(), Error<CredentialStoreError>>> {
+        self.delete(kind).boxed()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Checks the load, store and delete of all the credentials.
+    pub(crate) async fn check_credential_store<S>(store: &S)
+    where
+        S: CredentialStore,
+    {
+        let kinds = [
+            CredentialKind::Secret,
+            CredentialKind::Certificate,
+            CredentialKind::PrivateKey,
+        ];
+
+        for kind in kinds {
+            assert!(store.load(kind).await.unwrap().is_none());
+            // Missing credential
+            store.delete(kind).await.unwrap();
+        }
+
+        for kind in kinds {
+            store.store(kind, kind.as_str().as_bytes()).await.unwrap();
+        }
+
+        for kind in kinds {
+            let data = store.load(kind).await.unwrap();
+            assert_eq!(data.as_deref(), Some(kind.as_str().as_bytes()));
+        }
+
+        store
+            .store(CredentialKind::Secret, b"replaced")
+            .await
+            .unwrap();
+        let data = store.load(CredentialKind::Secret).await.unwrap();
+        assert_eq!(data.as_deref(), Some(b"replaced".as_slice()));
+
+        for kind in kinds {
+            store.delete(kind).await.unwrap();
+            assert!(store.load(kind).await.unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn should_store_through_dyn_store() {
+        let store: std::sync::Arc<dyn DynCredentialStore> =
+            std::sync::Arc::new(MemoryCredentialStore::new());
+
+        store
+            .store_boxed(CredentialKind::Certificate, b"cert")
+            .await
+            .unwrap();
+
+        let cert = store.load_boxed(CredentialKind::Certificate).await.unwrap();
+        assert_eq!(cert.as_deref(), Some(b"cert".as_slice()));
+
+        store
+            .delete_boxed(CredentialKind::Certificate)
+            .await
+            .unwrap();
+        assert!(
+            store
+                .load_boxed(CredentialKind::Certificate)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Credentials stored in the SQLite database, in the tenant of the device.
+
+use std::sync::Arc;
+
+use astarte_device_error::{Error, WrapError};
+
+use crate::store::SqliteStore;
+
+use super::{CredentialKind, CredentialStore, CredentialStoreError};
+
+mod statement;
+
+impl CredentialStore for SqliteStore {
+    async fn load(
+        &self,
+        kind: CredentialKind,
+    ) -> Result<Option<Vec<u8>>, Error<CredentialStoreError>> {
+        let tenant = Arc::clone(&self.tenant);
+
+        self.pool
+            .acquire_reader(move |reader| reader.load_credential(&tenant, kind))
+            .await
+            .wrap_err_with(|_| Error::new(CredentialStoreError::Load).set_ctx(kind))
+    }
+
+    async fn store(
+        &self,
+        kind: CredentialKind,
+        data: &[u8],
+    ) -> Result<(), Error<CredentialStoreError>> {
+        let tenant = Arc::clone(&self.tenant);
+        let data = data.to_vec();
+
+        self.pool
+            .acquire_writer(move |writer| writer.store_credential(&tenant, kind, &data))
+            .await
+            .wrap_err_with(|_| Error::new(CredentialStoreError::Store).set_ctx(kind))
+    }
+
+    async fn delete(&self, kind: CredentialKind) -> Result<(), Error<CredentialStoreError>> {
+        let tenant = Arc::clone(&self.tenant);
+
+        self.pool
+            .acquire_writer(move |writer| writer.delete_credential(&tenant, kind))
+            .await
+            .wrap_err_with(|_| Error::new(CredentialStoreError::Delete).set_ctx(kind))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::credentials::tests::check_credential_store;
+    use crate::store::sqlite::SqliteSharedStore;
+    use crate::store::sqlite::options::SqliteOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn should_store_credentials_in_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        check_credential_store(&store).await;
+    }
+
+    #[tokio::test]
+    async fn should_separate_credentials_by_tenant() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let shared = SqliteSharedStore::with_writable_dir(dir.path(), SqliteOptions::default())
+            .await
+            .unwrap();
+
+        let first = shared.device("realm", "first");
+        let second = shared.device("realm", "second");
+
+        first.store(CredentialKind::Secret, b"first").await.unwrap();
+
+        assert!(second.load(CredentialKind::Secret).await.unwrap().is_none());
+        assert_eq!(
+            first.load(CredentialKind::Secret).await.unwrap().as_deref(),
+            Some(b"first".as_slice())
+        );
+    }
+}
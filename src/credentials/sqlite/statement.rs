@@ -0,0 +1,100 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use astarte_device_error::{Error, WrapError};
+use rusqlite::OptionalExtension;
+use tracing::instrument;
+
+use crate::credentials::CredentialKind;
+use crate::store::sqlite::connection::{ReadConnection, WriteConnection};
+use crate::store::sqlite::error::SqliteError;
+use crate::store::sqlite::statements::include_query;
+
+impl WriteConnection {
+    #[instrument(skip(self, data))]
+    pub(crate) fn store_credential(
+        &self,
+        tenant: &str,
+        kind: CredentialKind,
+        data: &[u8],
+    ) -> Result<(), Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/credentials/write/store_credential.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        statement
+            .execute((tenant, kind.as_str(), data))
+            .wrap_err(SqliteError::Query)?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub(crate) fn delete_credential(
+        &self,
+        tenant: &str,
+        kind: CredentialKind,
+    ) -> Result<(), Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/credentials/write/delete_credential.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        statement
+            .execute((tenant, kind.as_str()))
+            .wrap_err(SqliteError::Query)?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub(crate) fn clear_credentials(&self, tenant: &str) -> Result<(), Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/credentials/write/clear_credentials.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        statement.execute([tenant]).wrap_err(SqliteError::Query)?;
+
+        Ok(())
+    }
+}
+
+impl ReadConnection {
+    #[instrument(skip(self))]
+    pub(crate) fn load_credential(
+        &self,
+        tenant: &str,
+        kind: CredentialKind,
+    ) -> Result<Option<Vec<u8>>, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/credentials/read/load_credential.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        statement
+            .query_row((tenant, kind.as_str()), |row| row.get(0))
+            .optional()
+            .wrap_err(SqliteError::Query)
+    }
+}
@@ -38,6 +38,7 @@ pub mod aggregate;
 pub mod builder;
 pub mod client;
 pub mod connection;
+pub mod credentials;
 pub mod error;
 pub mod event;
 mod interfaces;
@@ -47,7 +48,7 @@ pub mod pairing;
 pub mod prelude;
 pub mod properties;
 pub mod retention;
-mod retry;
+pub mod retry;
 pub mod session;
 pub(crate) mod state;
 pub mod store;
@@ -69,6 +70,7 @@ pub use astarte_device_fdo;
 pub use astarte_interfaces;
 pub use chrono;
 pub use rumqttc;
+pub use rustls;
 
 /// Timestamp returned in the astarte payload
 pub(crate) type Timestamp = chrono::DateTime<chrono::Utc>;
@@ -29,7 +29,9 @@ use tracing::{error, instrument, trace};
 
 use crate::builder::Config;
 use crate::logging::security::{SecurityEvent, notify_security_event};
+use crate::pairing::PairingConfig;
 
+use crate::transport::mqtt::config::proxy::ProxyConfig;
 use crate::transport::mqtt::config::transport::TransportProvider;
 
 use super::PairingApiError;
@@ -42,6 +44,17 @@ pub(crate) struct ClientArgs<'a> {
     pub(crate) token: &'a str,
 }
 
+impl<'a> From<&'a PairingConfig> for ClientArgs<'a> {
+    fn from(value: &'a PairingConfig) -> Self {
+        ClientArgs {
+            realm: &value.client_id.realm,
+            device_id: &value.client_id.device_id,
+            pairing_url: &value.pairing_url,
+            token: &value.secret,
+        }
+    }
+}
+
 /// Struct with the information for the pairing
 pub(crate) struct ApiClient<'a> {
     pub(crate) realm: &'a str,
@@ -55,6 +68,7 @@ impl<'a> ApiClient<'a> {
         args: ClientArgs<'a>,
         config: &Config,
         tls: rustls::ClientConfig,
+        proxy: &ProxyConfig,
     ) -> Result<Self, Error<PairingApiError>> {
         let ClientArgs {
             realm,
@@ -70,16 +84,16 @@ impl<'a> ApiClient<'a> {
         value.set_sensitive(true);
         headers.insert(reqwest::header::AUTHORIZATION, value);
 
-        let client = reqwest::Client::builder()
+        let builder = reqwest::Client::builder()
             .use_preconfigured_tls(tls)
             .default_headers(headers)
             .connect_timeout(config.connection_timeout)
-            .read_timeout(config.recv_timeout)
-            .build()
-            .wrap_err_msg(
-                PairingApiError::InvalidArgument,
-                "while building the request client",
-            )?;
+            .read_timeout(config.recv_timeout);
+
+        let client = proxy.config_http(builder)?.build().wrap_err_msg(
+            PairingApiError::InvalidArgument,
+            "while building the request client",
+        )?;
 
         Ok(Self {
             realm,
@@ -96,7 +110,7 @@ impl<'a> ApiClient<'a> {
     ) -> Result<Self, Error<PairingApiError>> {
         let tls = provider.api_tls_config()?;
 
-        Self::create(args, config, tls)
+        Self::create(args, config, tls, provider.proxy())
     }
 
     fn url<'i, I>(&self, segments: I) -> Result<Url, Error<PairingApiError>>
@@ -393,7 +407,7 @@ struct MqttV1Credential {
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use crate::transport::mqtt::crypto::Bundle;
+    use crate::transport::mqtt::crypto::{Bundle, FileKeyProvider, KeyAlgorithm, KeyProvider};
 
     use super::*;
 
@@ -504,7 +518,10 @@ pub(crate) mod tests {
         let client = ApiClient::from_transport(&Config::default(), &provider, args)
             .expect("couldn't create api client");
 
-        let bundle = Bundle::generate_key("test", "device_id").unwrap();
+        let key = FileKeyProvider::in_memory()
+            .generate(KeyAlgorithm::default())
+            .unwrap();
+        let bundle = Bundle::new("test", "device_id", key).unwrap();
 
         let res = client.create_certificate(&bundle.csr).await.unwrap();
 
@@ -648,7 +665,10 @@ pub(crate) mod tests {
         let client = ApiClient::from_transport(&Config::default(), &provider, args)
             .expect("couldn't create api client");
 
-        let bundle = Bundle::generate_key("test", "device_id").unwrap();
+        let key = FileKeyProvider::in_memory()
+            .generate(KeyAlgorithm::default())
+            .unwrap();
+        let bundle = Bundle::new("test", "device_id", key).unwrap();
 
         let cert = client.create_certificate(&bundle.csr).await.unwrap();
 
@@ -23,11 +23,12 @@ use std::io;
 use std::path::Path;
 
 use astarte_device_error::{Error, WrapError};
-use tokio::fs;
 use tracing::{debug, error, info, instrument};
 
 use self::client::{ApiClient, ClientArgs};
 
+use crate::credentials::{CredentialKind, CredentialStoreError};
+use crate::error::Report;
 use crate::transport::mqtt::components::ClientId;
 use crate::transport::mqtt::connection::context::ConnCtx;
 use crate::transport::mqtt::crypto::CryptoError;
@@ -56,6 +57,8 @@ pub enum PairingApiError {
     Io(std::io::ErrorKind),
     /// Crypto operation failed
     Crypto(CryptoError),
+    /// Couldn't access the credential store
+    CredentialStore(CredentialStoreError),
 }
 
 impl Display for PairingApiError {
@@ -68,6 +71,7 @@ impl Display for PairingApiError {
             PairingApiError::Join => write!(f, "couldn't join task"),
             PairingApiError::Io(error) => write!(f, "io error {error}"),
             PairingApiError::Crypto(error) => write!(f, "crypto error {error}"),
+            PairingApiError::CredentialStore(error) => write!(f, "credential store error {error}"),
         }
     }
 }
@@ -78,13 +82,16 @@ pub const CREDENTIAL_FILE: &str = "credential";
 pub const CERTIFICATE_FILE: &str = "certificate.pem";
 /// File where the private key is stored in PEM format
 pub const PRIVATE_KEY_FILE: &str = "priv-key.der";
+/// File where the broker url and the certificate verification are cached
+pub const PAIRING_CACHE_FILE: &str = "pairing-cache.json";
 
 /// Uses the legacy pairing API to register the device.
 ///
 /// To the API you either have to:
 ///
 /// - Provide a credential secret
-/// - Provide a pairing token and a store directory
+/// - Provide a pairing token and a store directory or a
+///   [`CredentialStore`](crate::credentials::CredentialStore)
 #[derive(Debug)]
 pub struct PairingApi {
     mqtt_config: MqttConfig,
@@ -113,39 +120,34 @@ impl PairingApi {
         }
     }
 
-    /// Register the device and stores the credentials secret in the given directory
+    /// Register the device and stores the credentials secret in the credential store
     async fn read_secret_or_register<S>(
         &self,
         ctx: &mut ConnCtx<'_, S>,
         pairing_token: &str,
     ) -> Result<String, Error<PairingApiError>> {
-        let credential_file = ctx
-            .state
-            .config
-            .writable_dir
-            .as_ref()
-            .map(|dir| dir.join(CERTIFICATE_FILE))
-            .ok_or(Error::with(
-                PairingApiError::InvalidArgument,
-                "missing writable dir to store credentials",
-            ))?;
-
-        match fs::read_to_string(&credential_file).await {
-            Ok(secret) => {
-                info!("secret read from file");
+        let store = ctx.provider.credential_store().ok_or(Error::with(
+            PairingApiError::InvalidArgument,
+            "missing writable dir or credential store to store credentials",
+        ))?;
+
+        let stored = store
+            .load_boxed(CredentialKind::Secret)
+            .await
+            .wrap_err_msg(
+                PairingApiError::CredentialStore(CredentialStoreError::Load),
+                "while reading credential secret",
+            )?;
+
+        match stored.map(String::from_utf8).transpose() {
+            Ok(Some(secret)) => {
+                info!("secret read from the credential store");
 
                 return Ok(secret);
             }
-            Err(err) if err.kind() == io::ErrorKind::NotFound => {
-                info!("no credential file {}", credential_file.display())
-            }
+            Ok(None) => info!("no credential secret stored"),
             Err(err) => {
-                return Err(Error::with(
-                    PairingApiError::Io(err.kind()),
-                    "while reading credential file",
-                )
-                .set_source(err)
-                .set_ctx(format!("from {}", credential_file.display())));
+                error!(error = %Report::new(err), "invalid credential secret, registering the device again");
             }
         }
 
@@ -161,17 +163,15 @@ impl PairingApi {
         let secret = client.register_device().await?;
 
         // We can register the device multiple times with the same pairing token if the device
-        // hasn't connected. If the call to write the file fails, we will just re-register the
+        // hasn't connected. If the call to store the secret fails, we will just re-register the
         // device.
-        fs::write(&credential_file, &secret)
+        store
+            .store_boxed(CredentialKind::Secret, secret.as_bytes())
             .await
-            .wrap_err_with(|err| {
-                Error::with(
-                    PairingApiError::Io(err.kind()),
-                    "while writing credential secret",
-                )
-                .set_ctx(format!("to {}", credential_file.display()))
-            })?;
+            .wrap_err_msg(
+                PairingApiError::CredentialStore(CredentialStoreError::Store),
+                "while writing credential secret",
+            )?;
 
         Ok(secret)
     }
@@ -203,20 +203,56 @@ impl Pairing for PairingApi {
             return Ok(true);
         }
 
-        let credential_file = store_dir.map(|p| p.join(CREDENTIAL_FILE)).ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "store directory not configured for pairing with token",
-            )
-        })?;
+        let store = self
+            .mqtt_config
+            .resolve_credential_store(store_dir)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "store directory not configured for pairing with token",
+                )
+            })?;
 
-        tokio::fs::try_exists(&credential_file)
+        store
+            .load_boxed(CredentialKind::Secret)
             .await
-            .inspect_err(|_| {
-                error!(
-                    path = %credential_file.display(),
-                    "couldn't read credentials file"
-                )
+            .map(|secret| secret.is_some())
+            .map_err(|err| {
+                error!(error = %Report::new(&err), "couldn't read credential secret");
+
+                io::Error::other(err)
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::credentials::{CredentialStore, MemoryCredentialStore};
+    use crate::transport::mqtt::MqttArgs;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn should_check_paired_in_credential_store() {
+        let store = MemoryCredentialStore::new();
+
+        let config = MqttConfig::new(MqttArgs {
+            realm: "realm".to_string(),
+            device_id: "device_id".to_string(),
+            credential: Credential::paring_token("token"),
+            pairing_url: "http://api.astarte.localhost/pairing".parse().unwrap(),
+        });
+
+        let api = PairingApi::new(config.clone());
+        assert!(api.is_paired(None).await.is_err());
+
+        let api = PairingApi::new(config.credential_store(store.clone()));
+        assert!(!api.is_paired(None).await.unwrap());
+
+        store
+            .store(CredentialKind::Secret, b"secret")
+            .await
+            .unwrap();
+        assert!(api.is_paired(None).await.unwrap());
+    }
+}
@@ -26,6 +26,7 @@ use uuid::Uuid;
 use crate::builder::Config;
 
 use crate::pairing::api::client::{ApiClient, ClientArgs};
+use crate::transport::mqtt::ProxyConfig;
 
 use super::PairingApiError;
 
@@ -67,14 +68,21 @@ impl<'a> From<&RegisterDevice<'a>> for ClientArgs<'a> {
 }
 
 /// Obtain a credentials secret from the astarte API
+///
+/// The proxy is read from the environment, see [`ProxyConfig::Environment`].
 pub async fn register_device<'a>(
     args: RegisterDevice<'a>,
 ) -> Result<String, Error<PairingApiError>> {
     let config = Config::default();
 
-    ApiClient::create(ClientArgs::from(&args), &config, args.tls)?
-        .register_device()
-        .await
+    ApiClient::create(
+        ClientArgs::from(&args),
+        &config,
+        args.tls,
+        &ProxyConfig::Environment,
+    )?
+    .register_device()
+    .await
 }
 
 /// Generate a random device Id with UUIDv4.
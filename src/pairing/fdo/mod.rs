@@ -307,6 +307,7 @@ where
             retention,
             store: store.clone(),
             state,
+            metrics: client.metrics.clone(),
         };
 
         Ok(DeviceTransport {
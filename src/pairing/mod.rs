@@ -71,14 +71,7 @@ impl PairingConfig {
         broker_url: &Url,
         timeout: Duration,
     ) -> Result<(MqttOptions, NetworkOptions), Error<PairingApiError>> {
-        let host = broker_url.host_str().ok_or_else(|| {
-            Error::with(PairingApiError::InvalidArgument, "missing host in url")
-                .set_ctx(format!("url {broker_url}"))
-        })?;
-        let port = broker_url.port().ok_or_else(|| {
-            Error::with(PairingApiError::InvalidArgument, "missing port in url")
-                .set_ctx(format!("url {broker_url}"))
-        })?;
+        let (host, port) = broker_addr(&transport, broker_url)?;
 
         let mut mqtt_opts = MqttOptions::new(self.client_id.to_string(), host, port);
 
@@ -107,3 +100,33 @@ impl PairingConfig {
         Ok((mqtt_opts, net_opts))
     }
 }
+
+/// Returns the host and port of the broker for the transport.
+fn broker_addr<'a>(
+    transport: &Transport,
+    broker_url: &'a Url,
+) -> Result<(&'a str, u16), Error<PairingApiError>> {
+    // The WebSocket transport connects to the whole url, the port is read from it.
+    #[cfg(feature = "websocket")]
+    if let Transport::Wss(_) = transport {
+        let port = broker_url.port_or_known_default().ok_or_else(|| {
+            Error::with(PairingApiError::InvalidArgument, "missing port in url")
+                .set_ctx(format!("url {broker_url}"))
+        })?;
+
+        return Ok((broker_url.as_str(), port));
+    }
+    #[cfg(not(feature = "websocket"))]
+    let _ = transport;
+
+    let host = broker_url.host_str().ok_or_else(|| {
+        Error::with(PairingApiError::InvalidArgument, "missing host in url")
+            .set_ctx(format!("url {broker_url}"))
+    })?;
+    let port = broker_url.port().ok_or_else(|| {
+        Error::with(PairingApiError::InvalidArgument, "missing port in url")
+            .set_ctx(format!("url {broker_url}"))
+    })?;
+
+    Ok((host, port))
+}
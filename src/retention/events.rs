@@ -0,0 +1,184 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Notifications for the publishes removed from the retention before being delivered.
+//!
+//! The events are received with [`RetentionHandle::events`](super::inspect::RetentionHandle::events),
+//! only the ones emitted after the subscription are received.
+
+use tokio::sync::broadcast;
+use tracing::{trace, warn};
+
+use super::Id;
+use super::inspect::RetentionKind;
+
+/// Number of events buffered for each subscriber before the oldest are lost.
+pub(crate) const RETENTION_EVENTS_CAPACITY: usize = 256;
+
+/// Reason a publish was removed from the retention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DropReason {
+    /// Removed to make space for newer publishes.
+    Evicted,
+    /// Removed since the expiry of the publish passed.
+    Expired,
+    /// Removed since the interface was removed or its major version changed.
+    InterfaceRemoved,
+}
+
+/// A publish was removed from the retention before being delivered.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct RetentionEvent {
+    /// Id of the removed publish.
+    pub id: Id,
+    /// Retention the publish was kept in.
+    pub kind: RetentionKind,
+    /// Interface of the publish.
+    pub interface: String,
+    /// Path of the publish.
+    pub path: String,
+    /// Reason of the removal.
+    pub reason: DropReason,
+}
+
+impl RetentionEvent {
+    /// Creates a new event.
+    pub fn new(
+        id: Id,
+        kind: RetentionKind,
+        interface: impl Into<String>,
+        path: impl Into<String>,
+        reason: DropReason,
+    ) -> Self {
+        Self {
+            id,
+            kind,
+            interface: interface.into(),
+            path: path.into(),
+            reason,
+        }
+    }
+}
+
+/// Sender for the [`RetentionEvent`].
+///
+/// Passed to the store with
+/// [`StoredRetention::set_retention_events`](super::StoredRetention::set_retention_events).
+#[derive(Debug, Clone)]
+pub struct RetentionEventSender {
+    tx: broadcast::Sender<RetentionEvent>,
+}
+
+impl RetentionEventSender {
+    pub(crate) fn new() -> Self {
+        let (tx, _rx) = broadcast::channel(RETENTION_EVENTS_CAPACITY);
+
+        Self { tx }
+    }
+
+    /// Notifies the event to the subscribers.
+    ///
+    /// The event is discarded if there are no subscribers.
+    pub fn send(&self, event: RetentionEvent) {
+        trace!(id = %event.id, reason = ?event.reason, "publish removed from the retention");
+
+        // Error only if there are no receivers
+        let _ = self.tx.send(event);
+    }
+
+    pub(crate) fn subscribe(&self) -> RetentionEvents {
+        RetentionEvents {
+            rx: self.tx.subscribe(),
+        }
+    }
+}
+
+impl Default for RetentionEventSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Stream of [`RetentionEvent`].
+///
+/// A slow subscriber loses the oldest events, a warning is logged with the number of lost ones.
+#[derive(Debug)]
+pub struct RetentionEvents {
+    rx: broadcast::Receiver<RetentionEvent>,
+}
+
+impl RetentionEvents {
+    /// Receives the next event.
+    ///
+    /// Returns [`None`] only when all the senders are dropped, this includes the device and the
+    /// stores the sender was passed to.
+    pub async fn recv(&mut self) -> Option<RetentionEvent> {
+        loop {
+            match self.rx.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(lost)) => {
+                    warn!(lost, "retention events lost, the subscriber is too slow");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::retention::Context;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn should_receive_after_subscribe() {
+        let sender = RetentionEventSender::new();
+        let ctx = Context::new();
+
+        // no subscribers
+        sender.send(RetentionEvent::new(
+            ctx.next(),
+            RetentionKind::Volatile,
+            "com.Foo",
+            "/foo",
+            DropReason::Evicted,
+        ));
+
+        let mut events = sender.subscribe();
+
+        let event = RetentionEvent::new(
+            ctx.next(),
+            RetentionKind::Stored,
+            "com.Foo",
+            "/bar",
+            DropReason::Expired,
+        );
+        sender.send(event.clone());
+
+        assert_eq!(events.recv().await, Some(event));
+
+        drop(sender);
+
+        assert_eq!(events.recv().await, None);
+    }
+}
@@ -0,0 +1,351 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inspect and purge the publishes waiting in the retention.
+//!
+//! The [`RetentionHandle`] is returned by [`DeviceClient::retention`](crate::DeviceClient::retention)
+//! and works on both the stored and the volatile retention.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use astarte_device_error::WrapError;
+use tracing::debug;
+
+use crate::error::{AstarteError, ErrorKind};
+use crate::state::ClientState;
+use crate::store::StoreCapabilities;
+
+use super::events::RetentionEvents;
+use super::{Id, RetentionError, StoredRetention, TimestampMillis};
+
+/// Selects the publishes in the retention.
+///
+/// An empty filter matches all the publishes. The time range is checked against the time the
+/// publish was stored.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use astarte_device_sdk::retention::inspect::RetentionFilter;
+///
+/// // Telemetry stored more than an hour ago
+/// let filter = RetentionFilter::new()
+///     .with_interface("com.example.Telemetry")
+///     .older_than(Duration::from_secs(3600));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RetentionFilter {
+    interface: Option<String>,
+    path: Option<String>,
+    since: Option<TimestampMillis>,
+    until: Option<TimestampMillis>,
+}
+
+impl RetentionFilter {
+    /// Creates a filter matching all the publishes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches only the publishes on the interface.
+    #[must_use]
+    pub fn with_interface(mut self, interface: impl Into<String>) -> Self {
+        self.interface = Some(interface.into());
+
+        self
+    }
+
+    /// Matches only the publishes on the path.
+    #[must_use]
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+
+        self
+    }
+
+    /// Matches only the publishes stored at or after the given time.
+    #[must_use]
+    pub fn stored_since(mut self, time: SystemTime) -> Self {
+        self.since = Some(timestamp_from(time));
+
+        self
+    }
+
+    /// Matches only the publishes stored before the given time.
+    #[must_use]
+    pub fn stored_until(mut self, time: SystemTime) -> Self {
+        self.until = Some(timestamp_from(time));
+
+        self
+    }
+
+    /// Matches only the publishes stored more than `age` ago.
+    #[must_use]
+    pub fn older_than(self, age: Duration) -> Self {
+        let time = SystemTime::now().checked_sub(age).unwrap_or(UNIX_EPOCH);
+
+        self.stored_until(time)
+    }
+
+    /// Matches only the publishes stored in the last `age`.
+    #[must_use]
+    pub fn newer_than(self, age: Duration) -> Self {
+        let time = SystemTime::now().checked_sub(age).unwrap_or(UNIX_EPOCH);
+
+        self.stored_since(time)
+    }
+
+    /// Returns the interface to match.
+    pub fn interface(&self) -> Option<&str> {
+        self.interface.as_deref()
+    }
+
+    /// Returns the path to match.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Returns the start of the time range, inclusive.
+    pub fn since(&self) -> Option<TimestampMillis> {
+        self.since
+    }
+
+    /// Returns the end of the time range, exclusive.
+    pub fn until(&self) -> Option<TimestampMillis> {
+        self.until
+    }
+
+    /// Checks if a publish matches the filter.
+    pub(crate) fn matches(&self, id: &Id, interface: &str, path: &str) -> bool {
+        self.interface.as_deref().is_none_or(|i| i == interface)
+            && self.path.as_deref().is_none_or(|p| p == path)
+            && self.since.is_none_or(|since| id.timestamp >= since)
+            && self.until.is_none_or(|until| id.timestamp < until)
+    }
+}
+
+fn timestamp_from(time: SystemTime) -> TimestampMillis {
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+
+    TimestampMillis::from_millis(millis)
+}
+
+/// Kind of retention a publish is kept in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RetentionKind {
+    /// Kept in memory, lost on restart.
+    Volatile,
+    /// Kept in the store.
+    Stored,
+}
+
+/// Publish waiting in the retention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PendingPublish {
+    /// Unique id of the publish.
+    pub id: Id,
+    /// Retention the publish is kept in.
+    pub kind: RetentionKind,
+    /// Interface of the publish.
+    pub interface: String,
+    /// Path of the publish.
+    pub path: String,
+    /// Major version of the interface.
+    pub version_major: i32,
+    /// Whether the publish was sent and is waiting for the acknowledgment.
+    pub sent: bool,
+    /// Size in bytes of the payload.
+    ///
+    /// For the stored retention it's the size in the store, for the volatile one it's an estimate.
+    pub size: usize,
+    /// Expiry of the publish, from the time it was stored.
+    pub expiry: Option<Duration>,
+}
+
+impl PendingPublish {
+    /// Returns the time the publish was stored.
+    pub fn stored_at(&self) -> SystemTime {
+        Duration::try_from(self.id.timestamp)
+            .ok()
+            .and_then(|d| UNIX_EPOCH.checked_add(d))
+            .unwrap_or(UNIX_EPOCH)
+    }
+}
+
+/// Handle to inspect and purge the retention of a device.
+///
+/// The operations apply to both the stored and the volatile retention. If the store doesn't
+/// support the retention, only the volatile one is used.
+#[derive(Debug, Clone)]
+pub struct RetentionHandle<S> {
+    store: S,
+    state: ClientState,
+}
+
+impl<S> RetentionHandle<S>
+where
+    S: StoreCapabilities,
+{
+    pub(crate) fn new(store: S, state: ClientState) -> Self {
+        Self { store, state }
+    }
+
+    /// Subscribes to the publishes removed from the retention before being delivered.
+    ///
+    /// Only the events emitted after the subscription are received.
+    pub fn events(&self) -> RetentionEvents {
+        self.state.volatile_store().events().subscribe()
+    }
+
+    /// Counts the pending publishes matching the filter.
+    pub async fn count(&self, filter: &RetentionFilter) -> Result<usize, AstarteError> {
+        let stored = match self.store.get_retention() {
+            Some(retention) => retention
+                .count_publishes(filter)
+                .await
+                .wrap_err(ErrorKind::Retention(RetentionError::Inspect))?,
+            None => 0,
+        };
+
+        let volatile = self.state.volatile_store().count(filter).await;
+
+        Ok(stored.saturating_add(volatile))
+    }
+
+    /// Lists at most `limit` pending publishes matching the filter, ordered by the time they were
+    /// stored.
+    pub async fn list(
+        &self,
+        filter: &RetentionFilter,
+        limit: usize,
+    ) -> Result<Vec<PendingPublish>, AstarteError> {
+        let mut publishes = match self.store.get_retention() {
+            Some(retention) => retention
+                .list_publishes(filter, limit)
+                .await
+                .wrap_err(ErrorKind::Retention(RetentionError::Inspect))?,
+            None => Vec::new(),
+        };
+
+        publishes.extend(self.state.volatile_store().list(filter, limit).await);
+
+        publishes.sort_by_key(|publish| publish.id);
+        publishes.truncate(limit);
+
+        Ok(publishes)
+    }
+
+    /// Removes the publishes matching the filter, returning the number of removed publishes.
+    pub async fn purge(&self, filter: &RetentionFilter) -> Result<usize, AstarteError> {
+        let stored = match self.store.get_retention() {
+            Some(retention) => retention
+                .purge_publishes(filter)
+                .await
+                .wrap_err(ErrorKind::Retention(RetentionError::Purge))?,
+            None => 0,
+        };
+
+        let volatile = self.state.volatile_store().purge(filter).await;
+
+        debug!(stored, volatile, "purged publishes");
+
+        Ok(stored.saturating_add(volatile))
+    }
+
+    /// Marks the publishes matching the filter as expired, returning the number of publishes.
+    ///
+    /// The expired publishes are not sent anymore and are removed like the ones past their
+    /// expiry.
+    pub async fn expire(&self, filter: &RetentionFilter) -> Result<usize, AstarteError> {
+        let stored = match self.store.get_retention() {
+            Some(retention) => retention
+                .expire_publishes(filter)
+                .await
+                .wrap_err(ErrorKind::Retention(RetentionError::Purge))?,
+            None => 0,
+        };
+
+        let volatile = self.state.volatile_store().expire(filter).await;
+
+        debug!(stored, volatile, "expired publishes");
+
+        Ok(stored.saturating_add(volatile))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::retention::Context;
+
+    use super::*;
+
+    #[test]
+    fn filter_should_match() {
+        let id = Context::new().next();
+
+        assert!(RetentionFilter::new().matches(&id, "com.Foo", "/foo"));
+
+        let filter = RetentionFilter::new()
+            .with_interface("com.Foo")
+            .with_path("/foo");
+        assert!(filter.matches(&id, "com.Foo", "/foo"));
+        assert!(!filter.matches(&id, "com.Foo", "/bar"));
+        assert!(!filter.matches(&id, "com.Bar", "/foo"));
+
+        let old = id.before(Duration::from_secs(120));
+        let filter = RetentionFilter::new().older_than(Duration::from_secs(60));
+        assert!(filter.matches(&old, "com.Foo", "/foo"));
+        assert!(!filter.matches(&id, "com.Foo", "/foo"));
+
+        let filter = RetentionFilter::new().newer_than(Duration::from_secs(60));
+        assert!(!filter.matches(&old, "com.Foo", "/foo"));
+        assert!(filter.matches(&id, "com.Foo", "/foo"));
+    }
+
+    #[test]
+    fn pending_should_return_stored_time() {
+        let id = Id {
+            timestamp: TimestampMillis::from_millis(1_500),
+            counter: 0,
+        };
+
+        let publish = PendingPublish {
+            id,
+            kind: RetentionKind::Stored,
+            interface: "com.Foo".to_string(),
+            path: "/foo".to_string(),
+            version_major: 1,
+            sent: false,
+            size: 0,
+            expiry: None,
+        };
+
+        assert_eq!(
+            publish.stored_at(),
+            UNIX_EPOCH + Duration::from_millis(1_500)
+        );
+    }
+}
@@ -21,49 +21,102 @@
 //! It's a configurable size FIFO cache for the volatile packets.
 
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
+    num::NonZeroUsize,
+    sync::Arc,
     time::{Duration, SystemTime},
 };
 
 use astarte_interfaces::interface::Retention;
 use tokio::sync::Mutex;
-use tracing::{error, trace};
+use tracing::{error, trace, warn};
 
 use crate::{
+    AstarteData,
     builder::DEFAULT_VOLATILE_CAPACITY,
     validate::{ValidatedIndividual, ValidatedObject},
 };
 
 use super::Id;
+use super::events::{DropReason, RetentionEvent, RetentionEventSender};
+use super::inspect::{PendingPublish, RetentionFilter, RetentionKind};
+use super::order::{ResendKey, ResendPolicy};
+use super::spool::Spool;
 
 /// Struct for the volatile retention.
 ///
 /// The methods will only require a `&self` and handle the locking internally to prevent problems
 /// in the critical sections.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(crate) struct VolatileStore {
     store: Mutex<State>,
+    events: RetentionEventSender,
+    policy: Arc<ResendPolicy>,
 }
 
 impl VolatileStore {
+    #[cfg(test)]
     pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self::with_limits(capacity, None, RetentionEventSender::new())
+    }
+
+    /// Creates the store with a maximum number of items and, optionally, of bytes.
+    ///
+    /// The bytes are an estimate of the size of the values, see [`ItemValue::size`]. The evicted
+    /// and expired publishes are notified to the `events`.
+    pub(crate) fn with_limits(
+        capacity: usize,
+        max_bytes: Option<NonZeroUsize>,
+        events: RetentionEventSender,
+    ) -> Self {
+        let mut state = State::with_capacity(capacity);
+        state.max_bytes = max_bytes;
+        state.events = events.clone();
+
         Self {
-            store: Mutex::new(State::with_capacity(capacity)),
+            store: Mutex::new(state),
+            events,
+            policy: Arc::default(),
         }
     }
 
+    /// Sets the priorities and the order of the unsent publishes.
+    pub(crate) fn with_resend_policy(mut self, policy: ResendPolicy) -> Self {
+        self.policy = Arc::new(policy);
+        self.store.get_mut().policy = Arc::clone(&self.policy);
+
+        self
+    }
+
+    /// Returns the priorities and the order of the unsent publishes.
+    pub(crate) fn resend_policy(&self) -> &ResendPolicy {
+        &self.policy
+    }
+
+    /// Moves the evicted publishes to the disk spool instead of dropping them.
+    pub(crate) fn with_spool(mut self, spool: Spool) -> Self {
+        self.store.get_mut().spool = Some(spool);
+
+        self
+    }
+
+    /// Returns the sender of the removed publishes.
+    pub(crate) fn events(&self) -> &RetentionEventSender {
+        &self.events
+    }
+
     pub(crate) async fn push_sent<T>(&self, id: Id, value: T)
     where
         T: TryInto<ItemValue, Error = VolatileItemError>,
     {
-        self.store.lock().await.push(id, value, true);
+        self.store.lock().await.push(id, value, true).await;
     }
 
     pub(crate) async fn push_unsent<T>(&self, id: Id, value: T)
     where
         T: TryInto<ItemValue, Error = VolatileItemError>,
     {
-        self.store.lock().await.push(id, value, false);
+        self.store.lock().await.push(id, value, false).await;
     }
 
     pub(crate) async fn mark_sent(&self, id: &Id, sent: bool) -> Option<bool> {
@@ -71,7 +124,7 @@ impl VolatileStore {
     }
 
     pub(crate) async fn get_unsent(&self, buf: &mut Vec<(Id, ItemValue)>, limit: usize) -> usize {
-        self.store.lock().await.get_unsent(buf, limit)
+        self.store.lock().await.get_unsent(buf, limit).await
     }
 
     pub(crate) async fn reset_sent(&self) {
@@ -79,43 +132,75 @@ impl VolatileStore {
     }
 
     pub(crate) async fn mark_received(&self, id: &Id) -> Option<ItemValue> {
-        self.store.lock().await.mark_received(id)
+        self.store.lock().await.mark_received(id).await
     }
 
     pub(crate) async fn delete_interface(&self, interface_name: &str) -> usize {
-        self.store.lock().await.delete_interface(interface_name)
+        self.store
+            .lock()
+            .await
+            .delete_interface(interface_name)
+            .await
+    }
+
+    pub(crate) async fn count(&self, filter: &RetentionFilter) -> usize {
+        self.store.lock().await.count(filter)
+    }
+
+    pub(crate) async fn list(&self, filter: &RetentionFilter, limit: usize) -> Vec<PendingPublish> {
+        self.store.lock().await.list(filter, limit)
+    }
+
+    pub(crate) async fn purge(&self, filter: &RetentionFilter) -> usize {
+        self.store.lock().await.purge(filter).await
+    }
+
+    pub(crate) async fn expire(&self, filter: &RetentionFilter) -> usize {
+        self.store.lock().await.expire(filter)
+    }
+}
+
+impl Default for VolatileStore {
+    fn default() -> Self {
+        Self::with_limits(
+            DEFAULT_VOLATILE_CAPACITY.get(),
+            None,
+            RetentionEventSender::new(),
+        )
     }
 }
 
 #[derive(Debug)]
 struct State {
     store: VecDeque<VolatileItem>,
+    /// Estimated size of the stored values.
+    bytes: usize,
+    max_bytes: Option<NonZeroUsize>,
+    events: RetentionEventSender,
+    /// Spool for the evicted publishes.
+    spool: Option<Spool>,
+    policy: Arc<ResendPolicy>,
 }
 
 impl State {
     fn with_capacity(capacity: usize) -> Self {
         Self {
             store: VecDeque::with_capacity(capacity),
+            bytes: 0,
+            max_bytes: None,
+            events: RetentionEventSender::new(),
+            spool: None,
+            policy: Arc::default(),
         }
     }
 
-    fn push<T>(&mut self, id: Id, value: T, sent: bool)
+    async fn push<T>(&mut self, id: Id, value: T, sent: bool)
     where
         T: TryInto<ItemValue, Error = VolatileItemError>,
     {
-        if self.is_full() {
-            if self.store.capacity() == 0 {
-                // we shouldn't store anything
-                return;
-            }
-
-            // remote the expired only if its full, it will be done while iterating
-            self.remove_expired();
-
-            // If still full, remove the oldest one
-            if self.is_full() {
-                self.store.pop_front();
-            }
+        if self.store.capacity() == 0 {
+            // we shouldn't store anything
+            return;
         }
 
         let res = value.try_into();
@@ -130,71 +215,266 @@ impl State {
             }
         };
 
-        self.store.push_back(VolatileItem::new(id, item, sent));
+        let item = VolatileItem::new(id, item, sent);
+
+        // It would evict all the other items and still not fit
+        if self.max_bytes.is_some_and(|max| item.size > max.get()) {
+            warn!(%id, size = item.size, "item bigger than the volatile retention max bytes");
+
+            self.evict(item).await;
+
+            return;
+        }
+
+        if self.is_full(item.size) {
+            // remote the expired only if its full, it will be done while iterating
+            self.remove_expired().await;
+
+            // If still full, remove the oldest ones
+            while self.is_full(item.size) {
+                let Some(evicted) = self.pop_front() else {
+                    break;
+                };
+
+                self.evict(evicted).await;
+            }
+        }
+
+        self.bytes = self.bytes.saturating_add(item.size);
+        self.store.push_back(item);
+    }
+
+    /// Moves the item to the spool, or drops it if there isn't one.
+    async fn evict(&mut self, item: VolatileItem) {
+        let priority = self.policy.priority(item.value.interface());
+
+        match &mut self.spool {
+            Some(spool) => spool.push(item, priority, &self.events).await,
+            None => self.events.send(item.event(DropReason::Evicted)),
+        }
+    }
+
+    fn pop_front(&mut self) -> Option<VolatileItem> {
+        let item = self.store.pop_front()?;
+
+        self.bytes = self.bytes.saturating_sub(item.size);
+
+        Some(item)
     }
 
     fn reset_sent(&mut self) {
         self.store.iter_mut().for_each(|item| {
             item.sent = false;
-        })
+        });
+
+        if let Some(spool) = &mut self.spool {
+            spool.reset_sent();
+        }
     }
 
-    fn get_unsent(&mut self, unsent: &mut Vec<(Id, ItemValue)>, limit: usize) -> usize {
-        self.remove_expired();
+    async fn get_unsent(&mut self, unsent: &mut Vec<(Id, ItemValue)>, limit: usize) -> usize {
+        self.remove_expired().await;
 
         let before = unsent.len();
 
-        let unsent_iter = self
+        let indexes: HashMap<Id, usize> = self
             .store
             .iter()
-            .filter(|item| !item.sent)
-            .map(|item| (item.id, item.value.clone()))
-            .take(limit);
+            .enumerate()
+            .filter(|(_, item)| !item.sent)
+            .map(|(idx, item)| (item.id, idx))
+            .collect();
 
-        unsent.extend(unsent_iter);
+        let mut keys: Vec<ResendKey> = indexes
+            .iter()
+            .map(|(id, idx)| self.policy.key(*id, self.store[*idx].value.interface()))
+            .collect();
+
+        if let Some(spool) = &self.spool {
+            keys.extend(spool.unsent_keys());
+        }
+
+        self.policy.sort(&mut keys);
+        keys.truncate(limit);
+
+        for ResendKey { id, .. } in keys {
+            let value = match indexes.get(&id) {
+                Some(idx) => Some(self.store[*idx].value.clone()),
+                None => match &mut self.spool {
+                    Some(spool) => spool.read_unsent(&id).await,
+                    None => None,
+                },
+            };
+
+            if let Some(value) = value {
+                unsent.push((id, value));
+            }
+        }
 
         unsent.len() - before
     }
 
     fn mark_sent(&mut self, id: &Id, sent: bool) -> Option<bool> {
-        self.store
+        let item = self
+            .store
             .iter_mut()
             .find(|item| item.id == *id)
-            .map(|item| std::mem::replace(&mut item.sent, sent))
+            .map(|item| std::mem::replace(&mut item.sent, sent));
+
+        item.or_else(|| self.spool.as_mut()?.mark_sent(id, sent))
     }
 
-    fn mark_received(&mut self, id: &Id) -> Option<ItemValue> {
-        let idx = self.store.iter().position(|item| item.id == *id)?;
+    async fn mark_received(&mut self, id: &Id) -> Option<ItemValue> {
+        let Some(idx) = self.store.iter().position(|item| item.id == *id) else {
+            return self.spool.as_mut()?.mark_received(id).await;
+        };
 
-        self.store.remove(idx).map(|item| item.value)
+        let item = self.store.remove(idx)?;
+
+        self.bytes = self.bytes.saturating_sub(item.size);
+
+        Some(item.value)
     }
 
-    fn remove_expired(&mut self) {
+    async fn remove_expired(&mut self) {
         let now = SystemTime::now();
 
-        self.store.retain(|item| !item.is_expired(now));
+        let bytes = &mut self.bytes;
+        let events = &self.events;
+        self.store.retain(|item| {
+            let expired = item.is_expired(now);
+
+            if expired {
+                *bytes = bytes.saturating_sub(item.size);
+                events.send(item.event(DropReason::Expired));
+            }
+
+            !expired
+        });
+
+        if let Some(spool) = &mut self.spool {
+            spool.remove_expired(now, &self.events).await;
+        }
     }
 
-    fn is_full(&mut self) -> bool {
-        self.store.len() == self.store.capacity()
+    /// Checks if there is no space for a new item of the given size.
+    fn is_full(&self, size: usize) -> bool {
+        let bytes_full = self
+            .max_bytes
+            .is_some_and(|max| self.bytes.saturating_add(size) > max.get());
+
+        self.store.len() == self.store.capacity() || bytes_full
     }
 
-    fn delete_interface(&mut self, interface_name: &str) -> usize {
+    async fn delete_interface(&mut self, interface_name: &str) -> usize {
         let now = SystemTime::now();
 
         let mut count = 0;
 
+        let bytes = &mut self.bytes;
+        let events = &self.events;
         self.store.retain(|v| {
-            let expired_or_interface = v.is_expired(now) || v.is_interface(interface_name);
+            let reason = if v.is_expired(now) {
+                DropReason::Expired
+            } else if v.is_interface(interface_name) {
+                DropReason::InterfaceRemoved
+            } else {
+                return true;
+            };
+
+            count += 1;
+            *bytes = bytes.saturating_sub(v.size);
+            events.send(v.event(reason));
+
+            false
+        });
+
+        if let Some(spool) = &mut self.spool {
+            count += spool.delete_interface(interface_name, &self.events).await;
+        }
+
+        trace!(count, "interface removed");
+
+        count
+    }
+
+    fn count(&self, filter: &RetentionFilter) -> usize {
+        let now = SystemTime::now();
 
-            if expired_or_interface {
+        let count = self
+            .store
+            .iter()
+            .filter(|item| !item.is_expired(now) && item.matches(filter))
+            .count();
+
+        let spooled = self.spool.as_ref().map_or(0, |spool| spool.count(filter));
+
+        count.saturating_add(spooled)
+    }
+
+    fn list(&self, filter: &RetentionFilter, limit: usize) -> Vec<PendingPublish> {
+        let now = SystemTime::now();
+
+        let mut publishes = self
+            .spool
+            .as_ref()
+            .map(|spool| spool.list(filter, limit))
+            .unwrap_or_default();
+
+        let remaining = limit.saturating_sub(publishes.len());
+        publishes.extend(
+            self.store
+                .iter()
+                .filter(|item| !item.is_expired(now) && item.matches(filter))
+                .map(VolatileItem::pending)
+                .take(remaining),
+        );
+
+        publishes
+    }
+
+    async fn purge(&mut self, filter: &RetentionFilter) -> usize {
+        let mut count = 0;
+
+        let bytes = &mut self.bytes;
+        self.store.retain(|item| {
+            let matches = item.matches(filter);
+
+            if matches {
                 count += 1;
+                *bytes = bytes.saturating_sub(item.size);
             }
 
-            !expired_or_interface
+            !matches
         });
 
-        trace!(count, "interface removed");
+        if let Some(spool) = &mut self.spool {
+            count += spool.purge(filter).await;
+        }
+
+        trace!(count, "publishes purged");
+
+        count
+    }
+
+    fn expire(&mut self, filter: &RetentionFilter) -> usize {
+        let now = SystemTime::now();
+
+        let mut count = 0;
+        for item in self
+            .store
+            .iter_mut()
+            .filter(|item| !item.is_expired(now) && item.matches(filter))
+        {
+            item.force_expired = true;
+            count += 1;
+        }
+
+        if let Some(spool) = &mut self.spool {
+            count += spool.expire(filter);
+        }
+
+        trace!(count, "publishes expired");
 
         count
     }
@@ -207,24 +487,35 @@ impl Default for State {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-struct VolatileItem {
-    id: Id,
-    store_time: SystemTime,
-    sent: bool,
-    value: ItemValue,
+pub(super) struct VolatileItem {
+    pub(super) id: Id,
+    pub(super) store_time: SystemTime,
+    pub(super) sent: bool,
+    size: usize,
+    /// Expired with [`RetentionHandle::expire`](crate::retention::inspect::RetentionHandle::expire).
+    pub(super) force_expired: bool,
+    pub(super) value: ItemValue,
 }
 
 impl VolatileItem {
-    fn new(id: Id, value: ItemValue, sent: bool) -> Self {
+    pub(super) fn new(id: Id, value: ItemValue, sent: bool) -> Self {
         Self {
             id,
             sent,
             store_time: SystemTime::now(),
+            size: value.size(),
+            force_expired: false,
             value,
         }
     }
 
     fn is_expired(&self, now: SystemTime) -> bool {
+        if self.force_expired {
+            trace!(%self.id, "forced expired");
+
+            return true;
+        }
+
         let Some(expiry) = self.value.expiry() else {
             return false;
         };
@@ -239,12 +530,57 @@ impl VolatileItem {
     }
 
     fn is_interface(&self, interface_name: &str) -> bool {
-        let name = match &self.value {
-            ItemValue::Individual(validated_individual) => &validated_individual.interface,
-            ItemValue::Object(validated_object) => &validated_object.interface,
-        };
+        self.value.interface() == interface_name
+    }
 
-        name == interface_name
+    fn matches(&self, filter: &RetentionFilter) -> bool {
+        filter.matches(&self.id, self.value.interface(), self.value.path())
+    }
+
+    pub(super) fn event(&self, reason: DropReason) -> RetentionEvent {
+        RetentionEvent::new(
+            self.id,
+            RetentionKind::Volatile,
+            self.value.interface(),
+            self.value.path(),
+            reason,
+        )
+    }
+
+    fn pending(&self) -> PendingPublish {
+        PendingPublish {
+            id: self.id,
+            kind: RetentionKind::Volatile,
+            interface: self.value.interface().to_string(),
+            path: self.value.path().to_string(),
+            version_major: self.value.version_major(),
+            sent: self.sent,
+            size: self.size,
+            expiry: self.value.expiry(),
+        }
+    }
+}
+
+fn data_size(data: &AstarteData) -> usize {
+    fn sum<T>(values: &[T], f: impl Fn(&T) -> usize) -> usize {
+        values.iter().map(f).fold(0, usize::saturating_add)
+    }
+
+    match data {
+        AstarteData::Boolean(_) => size_of::<bool>(),
+        AstarteData::Integer(_) => size_of::<i32>(),
+        AstarteData::Double(_) | AstarteData::LongInteger(_) | AstarteData::DateTime(_) => {
+            size_of::<i64>()
+        }
+        AstarteData::String(value) => value.len(),
+        AstarteData::BinaryBlob(value) => value.len(),
+        AstarteData::BooleanArray(values) => values.len().saturating_mul(size_of::<bool>()),
+        AstarteData::IntegerArray(values) => values.len().saturating_mul(size_of::<i32>()),
+        AstarteData::DoubleArray(values) => values.len().saturating_mul(size_of::<i64>()),
+        AstarteData::LongIntegerArray(values) => values.len().saturating_mul(size_of::<i64>()),
+        AstarteData::DateTimeArray(values) => values.len().saturating_mul(size_of::<i64>()),
+        AstarteData::StringArray(values) => sum(values, String::len),
+        AstarteData::BinaryBlobArray(values) => sum(values, Vec::len),
     }
 }
 
@@ -264,7 +600,50 @@ pub(crate) enum ItemValue {
 }
 
 impl ItemValue {
-    fn expiry(&self) -> Option<Duration> {
+    /// Estimated size of the value in bytes.
+    ///
+    /// It's the size of the data without the encoding overhead, the strings and the binaries are
+    /// counted by their length.
+    fn size(&self) -> usize {
+        match self {
+            ItemValue::Individual(individual) => data_size(&individual.data),
+            ItemValue::Object(object) => object
+                .data
+                .iter()
+                .map(|(key, value)| key.len().saturating_add(data_size(value)))
+                .fold(0, usize::saturating_add),
+        }
+    }
+
+    pub(crate) fn interface(&self) -> &str {
+        match self {
+            ItemValue::Individual(individual) => &individual.interface,
+            ItemValue::Object(object) => &object.interface,
+        }
+    }
+
+    pub(super) fn path(&self) -> &str {
+        match self {
+            ItemValue::Individual(individual) => &individual.path,
+            ItemValue::Object(object) => &object.path,
+        }
+    }
+
+    pub(super) fn version_major(&self) -> i32 {
+        match self {
+            ItemValue::Individual(individual) => individual.version_major,
+            ItemValue::Object(object) => object.version_major,
+        }
+    }
+
+    pub(super) fn retention(&self) -> Retention {
+        match self {
+            ItemValue::Individual(i) => i.retention,
+            ItemValue::Object(o) => o.retention,
+        }
+    }
+
+    pub(super) fn expiry(&self) -> Option<Duration> {
         match self {
             ItemValue::Individual(i) => i.retention.as_expiry().copied(),
             ItemValue::Object(o) => o.retention.as_expiry().copied(),
@@ -312,12 +691,15 @@ impl TryFrom<ValidatedObject> for ItemValue {
 
 #[cfg(test)]
 mod tests {
+    use std::num::NonZero;
     use std::time::Duration;
 
     use astarte_interfaces::interface::Retention;
     use astarte_interfaces::schema::Reliability;
     use pretty_assertions::assert_eq;
 
+    use crate::retention::order::ResendOrder;
+    use crate::retention::quota::RetentionQuotas;
     use crate::{AstarteData, aggregate::AstarteObject, retention::Context};
 
     use super::*;
@@ -332,14 +714,14 @@ mod tests {
         fn pop_next(&mut self) -> Option<ItemValue> {
             let now = SystemTime::now();
 
-            std::iter::from_fn(|| self.store.pop_front())
+            std::iter::from_fn(|| self.pop_front())
                 .find(|item| !item.is_expired(now))
                 .map(|item| item.value)
         }
     }
 
-    #[test]
-    fn should_be_full() {
+    #[tokio::test]
+    async fn should_be_full() {
         let info = ValidatedIndividual {
             interface: "interface".to_string(),
             path: "path".to_string(),
@@ -354,13 +736,304 @@ mod tests {
 
         let ctx = Context::new();
 
-        store.push(ctx.next(), info, false);
+        store.push(ctx.next(), info, false).await;
+
+        assert!(store.is_full(0));
+    }
+
+    #[tokio::test]
+    async fn should_evict_over_max_bytes() {
+        let info = |data: &str| ValidatedIndividual {
+            interface: "interface".to_string(),
+            path: "path".to_string(),
+            version_major: 0,
+            reliability: Reliability::Unique,
+            retention: Retention::Volatile { expiry: None },
+            data: AstarteData::String(data.to_string()),
+            timestamp: None,
+        };
+
+        let mut store = State::with_capacity(10);
+        store.max_bytes = NonZeroUsize::new(8);
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+
+        store.push(ids[0], info("abc"), false).await;
+        store.push(ids[1], info("def"), false).await;
+        assert_eq!(store.bytes, 6);
+
+        // needs to remove only the first
+        store.push(ids[2], info("ghijk"), false).await;
+        assert_eq!(store.bytes, 8);
+
+        let stored: Vec<Id> = store.store.iter().map(|item| item.id).collect();
+        assert_eq!(stored, [ids[1], ids[2]]);
+
+        store.mark_received(&ids[1]).await;
+        assert_eq!(store.bytes, 5);
+
+        store.delete_interface("interface").await;
+        assert_eq!(store.bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn should_reject_item_over_max_bytes() {
+        let info = |data: &str| ValidatedIndividual {
+            interface: "interface".to_string(),
+            path: "path".to_string(),
+            version_major: 0,
+            reliability: Reliability::Unique,
+            retention: Retention::Volatile { expiry: None },
+            data: AstarteData::String(data.to_string()),
+            timestamp: None,
+        };
+
+        let mut store = State::with_capacity(10);
+        store.max_bytes = NonZeroUsize::new(8);
+        let mut events = store.events.subscribe();
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+
+        store.push(ids[0], info("abc"), false).await;
+        store.push(ids[1], info("def"), false).await;
+        store.push(ids[2], info("too big to fit"), false).await;
+        assert_eq!(store.bytes, 6);
+
+        let stored: Vec<Id> = store.store.iter().map(|item| item.id).collect();
+        assert_eq!(stored, [ids[0], ids[1]]);
+
+        let event = events.recv().await.unwrap();
+        assert_eq!((event.id, event.reason), (ids[2], DropReason::Evicted));
+    }
+
+    #[tokio::test]
+    async fn should_spill_evicted_to_spool() {
+        let info = |path: &str| ValidatedIndividual {
+            interface: "com.Foo".to_string(),
+            path: path.to_string(),
+            version_major: 1,
+            reliability: Reliability::Unique,
+            retention: Retention::Volatile { expiry: None },
+            data: AstarteData::Integer(42),
+            timestamp: None,
+        };
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let spool = Spool::create(dir.path(), NonZero::new(4096).unwrap()).unwrap();
+
+        let store = VolatileStore::with_capacity(1).with_spool(spool);
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+
+        store.push_unsent(ids[0], info("/0")).await;
+        store.push_sent(ids[1], info("/1")).await;
+        store.push_unsent(ids[2], info("/2")).await;
+
+        assert_eq!(store.count(&RetentionFilter::new()).await, 3);
 
-        assert!(store.is_full());
+        let mut unsent = Vec::new();
+        assert_eq!(store.get_unsent(&mut unsent, 10).await, 2);
+        assert_eq!(
+            unsent,
+            [
+                (ids[0], ItemValue::Individual(info("/0"))),
+                (ids[2], ItemValue::Individual(info("/2")))
+            ]
+        );
+
+        assert_eq!(store.mark_sent(&ids[1], false).await, Some(true));
+        assert_eq!(
+            store.mark_received(&ids[0]).await,
+            Some(ItemValue::Individual(info("/0")))
+        );
+
+        let listed: Vec<Id> = store
+            .list(&RetentionFilter::new(), 10)
+            .await
+            .into_iter()
+            .map(|publish| publish.id)
+            .collect();
+        assert_eq!(listed, [ids[1], ids[2]]);
+
+        assert_eq!(store.delete_interface("com.Foo").await, 2);
+        assert_eq!(store.count(&RetentionFilter::new()).await, 0);
+    }
+
+    #[tokio::test]
+    async fn should_order_unsent_by_priority() {
+        let info = |interface: &str| ValidatedIndividual {
+            interface: interface.to_string(),
+            path: "/path".to_string(),
+            version_major: 1,
+            reliability: Reliability::Unique,
+            retention: Retention::Volatile { expiry: None },
+            data: AstarteData::Integer(42),
+            timestamp: None,
+        };
+
+        let quotas = RetentionQuotas::new().with_priority("com.Alarm", 10);
+        let store = VolatileStore::with_capacity(10)
+            .with_resend_policy(ResendPolicy::new(ResendOrder::Newest, &quotas));
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..4).map(|_| ctx.next()).collect();
+
+        store.push_unsent(ids[0], info("com.Foo")).await;
+        store.push_unsent(ids[1], info("com.Alarm")).await;
+        store.push_unsent(ids[2], info("com.Foo")).await;
+        store.push_unsent(ids[3], info("com.Foo")).await;
+
+        let mut unsent = Vec::new();
+        assert_eq!(store.get_unsent(&mut unsent, 3).await, 3);
+
+        let unsent: Vec<Id> = unsent.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(unsent, [ids[1], ids[3], ids[2]]);
     }
 
     #[test]
-    fn should_remove_last() {
+    fn should_estimate_object_size() {
+        let data = AstarteObject::from_iter([
+            ("long".to_string(), AstarteData::LongInteger(1)),
+            (
+                "blobs".to_string(),
+                AstarteData::BinaryBlobArray(vec![vec![1, 2], vec![3]]),
+            ),
+        ]);
+        let value = ItemValue::Object(ValidatedObject {
+            interface: "interface".to_string(),
+            path: "path".to_string(),
+            version_major: 0,
+            reliability: Reliability::Unique,
+            retention: Retention::Volatile { expiry: None },
+            data,
+            timestamp: None,
+        });
+
+        assert_eq!(value.size(), 4 + 8 + 5 + 3);
+    }
+
+    #[tokio::test]
+    async fn should_notify_removed() {
+        let info = |interface: &str, expiry: Option<Duration>| ValidatedIndividual {
+            interface: interface.to_string(),
+            path: "/path".to_string(),
+            version_major: 1,
+            reliability: Reliability::Unique,
+            retention: Retention::Volatile { expiry },
+            data: AstarteData::Integer(42),
+            timestamp: None,
+        };
+
+        let mut store = State::with_capacity(2);
+        let mut events = store.events.subscribe();
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..4).map(|_| ctx.next()).collect();
+
+        store.push(ids[0], info("com.Foo", None), false).await;
+        store.push(ids[1], info("com.Bar", None), false).await;
+        store
+            .push(
+                ids[2],
+                info("com.Foo", Some(Duration::from_nanos(1))),
+                false,
+            )
+            .await;
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(
+            event,
+            RetentionEvent::new(
+                ids[0],
+                RetentionKind::Volatile,
+                "com.Foo",
+                "/path",
+                DropReason::Evicted
+            )
+        );
+
+        store.store[1].store_time -= Duration::from_secs(1);
+        store.remove_expired().await;
+        let event = events.recv().await.unwrap();
+        assert_eq!((event.id, event.reason), (ids[2], DropReason::Expired));
+
+        store.delete_interface("com.Bar").await;
+        let event = events.recv().await.unwrap();
+        assert_eq!(
+            (event.id, event.reason),
+            (ids[1], DropReason::InterfaceRemoved)
+        );
+    }
+
+    #[tokio::test]
+    async fn should_inspect_and_purge() {
+        let info = |interface: &str| ValidatedIndividual {
+            interface: interface.to_string(),
+            path: "/path".to_string(),
+            version_major: 1,
+            reliability: Reliability::Unique,
+            retention: Retention::Volatile { expiry: None },
+            data: AstarteData::Integer(42),
+            timestamp: None,
+        };
+
+        let mut store = State::with_capacity(10);
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+
+        store.push(ids[0], info("com.Foo"), false).await;
+        store.push(ids[1], info("com.Bar"), true).await;
+        store.push(ids[2], info("com.Foo"), false).await;
+
+        let foo = RetentionFilter::new().with_interface("com.Foo");
+        assert_eq!(store.count(&RetentionFilter::new()), 3);
+        assert_eq!(store.count(&foo), 2);
+
+        let listed = store.list(&RetentionFilter::new(), 2);
+        assert_eq!(
+            listed,
+            [
+                PendingPublish {
+                    id: ids[0],
+                    kind: RetentionKind::Volatile,
+                    interface: "com.Foo".to_string(),
+                    path: "/path".to_string(),
+                    version_major: 1,
+                    sent: false,
+                    size: 4,
+                    expiry: None,
+                },
+                PendingPublish {
+                    id: ids[1],
+                    kind: RetentionKind::Volatile,
+                    interface: "com.Bar".to_string(),
+                    path: "/path".to_string(),
+                    version_major: 1,
+                    sent: true,
+                    size: 4,
+                    expiry: None,
+                }
+            ]
+        );
+
+        let bar = RetentionFilter::new().with_interface("com.Bar");
+        assert_eq!(store.expire(&bar), 1);
+        assert_eq!(store.count(&bar), 0);
+
+        assert_eq!(store.purge(&foo).await, 2);
+        assert_eq!(store.bytes, 4);
+
+        store.remove_expired().await;
+        assert!(store.store.is_empty());
+        assert_eq!(store.bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn should_remove_last() {
         let info1 = ValidatedIndividual {
             interface: "interface1".to_string(),
             path: "path".to_string(),
@@ -384,17 +1057,17 @@ mod tests {
         let mut store = State::with_capacity(1);
         let ctx = Context::new();
 
-        store.push(ctx.next(), info1, false);
+        store.push(ctx.next(), info1, false).await;
 
-        assert!(store.is_full());
+        assert!(store.is_full(0));
 
-        store.push(ctx.next(), info2.clone(), false);
+        store.push(ctx.next(), info2.clone(), false).await;
 
         assert_eq!(store.store[0].value, ItemValue::Individual(info2));
     }
 
-    #[test]
-    fn should_remove_expired() {
+    #[tokio::test]
+    async fn should_remove_expired() {
         let info1 = ValidatedIndividual {
             interface: "interface1".to_string(),
             path: "path".to_string(),
@@ -431,20 +1104,20 @@ mod tests {
 
         let ctx = Context::new();
 
-        store.push(ctx.next(), info1, false);
-        store.push(ctx.next(), info2, false);
+        store.push(ctx.next(), info1, false).await;
+        store.push(ctx.next(), info2, false).await;
 
         store.store[0].store_time -= Duration::from_secs(1);
 
-        assert!(store.is_full());
+        assert!(store.is_full(0));
 
-        store.push(ctx.next(), info3.clone(), false);
+        store.push(ctx.next(), info3.clone(), false).await;
 
         assert_eq!(store.store[0].value, ItemValue::Individual(info3));
     }
 
-    #[test]
-    fn should_queue_non_expired() {
+    #[tokio::test]
+    async fn should_queue_non_expired() {
         let info1 = ValidatedIndividual {
             interface: "interface1".to_string(),
             path: "path".to_string(),
@@ -481,16 +1154,16 @@ mod tests {
 
         let ctx = Context::new();
 
-        store.push(ctx.next(), info1, false);
-        store.push(ctx.next(), info2, false);
-        store.push(ctx.next(), info3.clone(), false);
+        store.push(ctx.next(), info1, false).await;
+        store.push(ctx.next(), info2, false).await;
+        store.push(ctx.next(), info3.clone(), false).await;
 
         store.store[0].store_time -= Duration::from_secs(1);
         store.store[1].store_time -= Duration::from_secs(1);
 
         let mut buf = Vec::with_capacity(1);
         // does not remove the non expired elements
-        store.get_unsent(&mut buf, 1);
+        store.get_unsent(&mut buf, 1).await;
 
         assert_eq!(buf.pop().map(|e| e.1), Some(ItemValue::Individual(info3)));
         assert_eq!(store.store.len(), 1);
@@ -527,8 +1200,8 @@ mod tests {
         assert!(res.is_err());
     }
 
-    #[test]
-    fn should_mark_sent() {
+    #[tokio::test]
+    async fn should_mark_sent() {
         let info1 = ValidatedIndividual {
             interface: "interface1".to_string(),
             path: "path".to_string(),
@@ -565,18 +1238,18 @@ mod tests {
 
         let ctx = Context::new();
 
-        store.push(ctx.next(), info1, false);
+        store.push(ctx.next(), info1, false).await;
         let id = ctx.next();
-        store.push(id, info2, false);
-        store.push(ctx.next(), info3.clone(), false);
+        store.push(id, info2, false).await;
+        store.push(ctx.next(), info3.clone(), false).await;
 
         assert!(!store.mark_sent(&id, true).unwrap());
 
         assert!(store.store[1].sent)
     }
 
-    #[test]
-    fn should_mark_received() {
+    #[tokio::test]
+    async fn should_mark_received() {
         let info1 = ValidatedIndividual {
             interface: "interface1".to_string(),
             path: "path".to_string(),
@@ -613,19 +1286,19 @@ mod tests {
 
         let ctx = Context::new();
 
-        store.push(ctx.next(), info1, false);
+        store.push(ctx.next(), info1, false).await;
         let id = ctx.next();
-        store.push(id, info2, false);
-        store.push(ctx.next(), info3.clone(), false);
+        store.push(id, info2, false).await;
+        store.push(ctx.next(), info3.clone(), false).await;
 
-        assert!(store.mark_received(&id).is_some());
+        assert!(store.mark_received(&id).await.is_some());
 
         assert_eq!(store.store.len(), 2);
         assert_eq!(store.store[1].value, ItemValue::Individual(info3));
     }
 
-    #[test]
-    fn capacity_0_volatile_store_should_not_store() {
+    #[tokio::test]
+    async fn capacity_0_volatile_store_should_not_store() {
         let mut store = State::with_capacity(0);
         let ctx = Context::new();
 
@@ -639,13 +1312,13 @@ mod tests {
             timestamp: None,
         };
 
-        store.push(ctx.next(), info.clone(), false);
+        store.push(ctx.next(), info.clone(), false).await;
 
         assert_eq!(None, store.store.pop_front());
     }
 
-    #[test]
-    fn should_accept_stored_retention_items() {
+    #[tokio::test]
+    async fn should_accept_stored_retention_items() {
         let mut store = State::with_capacity(1);
         let ctx = Context::new();
 
@@ -659,7 +1332,7 @@ mod tests {
             timestamp: None,
         };
 
-        store.push(ctx.next(), info.clone(), false);
+        store.push(ctx.next(), info.clone(), false).await;
 
         assert_eq!(
             store.store.pop_front().map(|e| e.value).unwrap(),
@@ -676,7 +1349,7 @@ mod tests {
             timestamp: None,
         };
 
-        store.push(ctx.next(), info.clone(), false);
+        store.push(ctx.next(), info.clone(), false).await;
 
         assert_eq!(
             store.store.pop_front().map(|e| e.value).unwrap(),
@@ -684,8 +1357,8 @@ mod tests {
         );
     }
 
-    #[test]
-    fn should_delete_interfaces() {
+    #[tokio::test]
+    async fn should_delete_interfaces() {
         let mut store = State::default();
         let ctx = Context::new();
 
@@ -710,11 +1383,11 @@ mod tests {
             timestamp: None,
         };
 
-        store.push(ctx.next(), individual.clone(), false);
-        store.push(ctx.next(), individual.clone(), false);
-        store.push(ctx.next(), object.clone(), false);
+        store.push(ctx.next(), individual.clone(), false).await;
+        store.push(ctx.next(), individual.clone(), false).await;
+        store.push(ctx.next(), object.clone(), false).await;
 
-        assert_eq!(store.delete_interface(interface), 2);
+        assert_eq!(store.delete_interface(interface).await, 2);
 
         assert_eq!(
             store.store.pop_front().map(|e| e.value).unwrap(),
@@ -722,8 +1395,8 @@ mod tests {
         );
     }
 
-    #[test]
-    fn should_mark_all_unsent() {
+    #[tokio::test]
+    async fn should_mark_all_unsent() {
         let info1 = ValidatedIndividual {
             interface: "interface1".to_string(),
             path: "path".to_string(),
@@ -761,13 +1434,13 @@ mod tests {
         let ctx = Context::new();
 
         let id = ctx.next();
-        store.push(id, info1, true);
+        store.push(id, info1, true).await;
         store.mark_sent(&id, true);
         let id = ctx.next();
-        store.push(id, info2, true);
+        store.push(id, info2, true).await;
         store.mark_sent(&id, true);
         let id = ctx.next();
-        store.push(id, info3.clone(), true);
+        store.push(id, info3.clone(), true).await;
         store.mark_sent(&id, true);
 
         store.reset_sent();
@@ -777,8 +1450,8 @@ mod tests {
         assert!(!store.store[2].sent);
     }
 
-    #[test]
-    fn should_return_unsent_only() {
+    #[tokio::test]
+    async fn should_return_unsent_only() {
         let info = ValidatedIndividual {
             interface: "interface1".to_string(),
             path: "path".to_string(),
@@ -813,13 +1486,13 @@ mod tests {
 
             // 4 elements marked as not sent
             if (i + 1) % 25 == 0 {
-                store.push(id, info_unsent_check.clone(), true);
+                store.push(id, info_unsent_check.clone(), true).await;
                 store.mark_sent(&id, false);
                 let id = ctx.next();
-                store.push(id, info_unsent_check.clone(), true);
+                store.push(id, info_unsent_check.clone(), true).await;
                 store.mark_sent(&id, false);
             } else {
-                store.push(id, info.clone(), true);
+                store.push(id, info.clone(), true).await;
                 store.mark_sent(&id, true);
             }
         }
@@ -831,7 +1504,7 @@ mod tests {
 
         let mut buf = Vec::with_capacity(4);
 
-        store.get_unsent(&mut buf, 50);
+        store.get_unsent(&mut buf, 50).await;
 
         assert_eq!(buf.len(), 4);
         assert!(check_is_unsent_element(&buf[0].1));
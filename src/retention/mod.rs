@@ -23,7 +23,7 @@ use std::{
     collections::HashSet,
     fmt::Display,
     future::Future,
-    num::{NonZeroUsize, TryFromIntError},
+    num::{NonZeroU64, NonZeroUsize, TryFromIntError},
     sync::atomic::{AtomicU32, Ordering},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
@@ -36,12 +36,23 @@ use tracing::warn;
 use crate::{
     error::Report,
     interfaces::Interfaces,
-    retention::memory::VolatileStore,
+    retention::{
+        events::RetentionEventSender,
+        inspect::{PendingPublish, RetentionFilter},
+        memory::VolatileStore,
+        order::ResendOrder,
+        quota::RetentionQuotas,
+    },
     store::StoreCapabilities,
     validate::{ValidatedIndividual, ValidatedObject},
 };
 
+pub mod events;
+pub mod inspect;
 pub(crate) mod memory;
+pub mod order;
+pub mod quota;
+pub(crate) mod spool;
 pub(crate) mod sqlite;
 
 /// Error returned by the retention.
@@ -66,8 +77,20 @@ pub enum RetentionError {
     FetchInterfaces,
     /// Couldn't set the maximum capacity of items.
     SetCapacity,
+    /// Couldn't set the retention quotas.
+    SetQuotas,
+    /// The publish was rejected since the retention is full.
+    QuotaExceeded,
+    /// Couldn't read the pending publishes.
+    Inspect,
+    /// Couldn't purge or expire the publishes.
+    Purge,
+    /// Couldn't create the disk spool of the volatile retention.
+    Spool,
     /// Couldn't acquire the store connection
     Connection,
+    /// The operation is not supported by the retention.
+    Unsupported,
 }
 
 impl Display for RetentionError {
@@ -82,7 +105,13 @@ impl Display for RetentionError {
             RetentionError::DeleteInterfaceMany => write!(f, "couldn't delete multiple interfaces"),
             RetentionError::FetchInterfaces => write!(f, "couldn't fetch interfaces"),
             RetentionError::SetCapacity => write!(f, "couldn't set capacity"),
+            RetentionError::SetQuotas => write!(f, "couldn't set retention quotas"),
+            RetentionError::QuotaExceeded => write!(f, "retention quota exceeded"),
+            RetentionError::Inspect => write!(f, "couldn't read pending publishes"),
+            RetentionError::Purge => write!(f, "couldn't purge publishes"),
+            RetentionError::Spool => write!(f, "couldn't create the volatile spool"),
             RetentionError::Connection => write!(f, "store operation error"),
+            RetentionError::Unsupported => write!(f, "operation not supported by the retention"),
         }
     }
 }
@@ -235,6 +264,118 @@ pub trait StoredRetention: Clone + Send + Sync {
         &self,
         size: NonZeroUsize,
     ) -> impl Future<Output = Result<(), Error<RetentionError>>> + Send;
+
+    /// Set the store size limit in bytes of the stored payloads.
+    ///
+    /// The default implementation returns [`RetentionError::Unsupported`].
+    fn set_max_retention_bytes(
+        &self,
+        size: NonZeroU64,
+    ) -> impl Future<Output = Result<(), Error<RetentionError>>> + Send {
+        async move { Err(Error::with(RetentionError::Unsupported, "max retention bytes").set_ctx(size)) }
+    }
+
+    /// Set the quotas for each interface and the eviction policy.
+    ///
+    /// The stored publishes exceeding the new quotas are removed.
+    ///
+    /// The default implementation only accepts quotas without limits and priorities, and returns
+    /// [`RetentionError::Unsupported`] otherwise.
+    fn set_retention_quotas(
+        &self,
+        quotas: &RetentionQuotas,
+    ) -> impl Future<Output = Result<(), Error<RetentionError>>> + Send {
+        let unlimited = *quotas == RetentionQuotas::default();
+
+        async move {
+            if unlimited {
+                Ok(())
+            } else {
+                Err(Error::with(RetentionError::Unsupported, "retention quotas"))
+            }
+        }
+    }
+
+    /// Counts the stored publishes matching the filter.
+    ///
+    /// The default implementation returns [`RetentionError::Unsupported`].
+    fn count_publishes(
+        &self,
+        filter: &RetentionFilter,
+    ) -> impl Future<Output = Result<usize, Error<RetentionError>>> + Send {
+        let _ = filter;
+
+        async { Err(Error::with(RetentionError::Unsupported, "count publishes")) }
+    }
+
+    /// Lists at most `limit` stored publishes matching the filter, ordered by [`Id`].
+    ///
+    /// The default implementation returns [`RetentionError::Unsupported`].
+    fn list_publishes(
+        &self,
+        filter: &RetentionFilter,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<PendingPublish>, Error<RetentionError>>> + Send {
+        let _ = (filter, limit);
+
+        async { Err(Error::with(RetentionError::Unsupported, "list publishes")) }
+    }
+
+    /// Deletes the stored publishes matching the filter, returning the number of deleted ones.
+    ///
+    /// The default implementation returns [`RetentionError::Unsupported`].
+    fn purge_publishes(
+        &self,
+        filter: &RetentionFilter,
+    ) -> impl Future<Output = Result<usize, Error<RetentionError>>> + Send {
+        let _ = filter;
+
+        async { Err(Error::with(RetentionError::Unsupported, "purge publishes")) }
+    }
+
+    /// Marks the stored publishes matching the filter as expired, returning their number.
+    ///
+    /// The publishes must not be returned by [`unsent_publishes`](StoredRetention::unsent_publishes)
+    /// anymore.
+    ///
+    /// The default implementation returns [`RetentionError::Unsupported`].
+    fn expire_publishes(
+        &self,
+        filter: &RetentionFilter,
+    ) -> impl Future<Output = Result<usize, Error<RetentionError>>> + Send {
+        let _ = filter;
+
+        async { Err(Error::with(RetentionError::Unsupported, "expire publishes")) }
+    }
+
+    /// Sets the order of the publishes returned by
+    /// [`unsent_publishes`](StoredRetention::unsent_publishes).
+    ///
+    /// The publishes of the interfaces with the highest priority in the [`RetentionQuotas`] are
+    /// returned first, then the ones with the same priority are sorted by the [`ResendOrder`].
+    ///
+    /// The default implementation ignores the order, the publishes are returned ordered by
+    /// [`Id`].
+    fn set_resend_order(
+        &self,
+        order: ResendOrder,
+    ) -> impl Future<Output = Result<(), Error<RetentionError>>> + Send {
+        let _ = order;
+
+        async { Ok(()) }
+    }
+
+    /// Sets the sender to notify the publishes evicted, expired or removed with their interface.
+    ///
+    /// The default implementation ignores the sender, so no event is sent.
+    fn set_retention_events(
+        &self,
+        events: RetentionEventSender,
+    ) -> impl Future<Output = Result<(), Error<RetentionError>>> + Send {
+        drop(events);
+
+        async { Ok(()) }
+    }
 }
 
 /// Interface and major version of a [`PublishInfo`] stored in the retention.
@@ -338,6 +479,16 @@ pub struct Id {
     counter: u32,
 }
 
+impl Id {
+    /// Returns the same id with the timestamp moved back by the given duration.
+    #[cfg(any(test, feature = "store-conformance"))]
+    pub(crate) fn before(mut self, duration: Duration) -> Self {
+        self.timestamp = TimestampMillis(self.timestamp.0.saturating_sub(duration.as_millis()));
+
+        self
+    }
+}
+
 impl Display for Id {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}-{}", self.timestamp, self.counter)
@@ -451,11 +602,171 @@ pub(crate) async fn volatile_mark_unsent(volatile: &VolatileStore, id: &Id) {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
-    use std::sync::Arc;
+    use std::collections::{BTreeMap, HashSet};
+    use std::sync::{Arc, Mutex};
+
+    use crate::store::conformance;
 
     use super::*;
 
+    /// Retention implementing only the required methods.
+    #[derive(Debug, Clone, Default)]
+    struct MinimalRetention {
+        state: Arc<Mutex<MinimalState>>,
+    }
+
+    impl MinimalRetention {
+        fn state(&self) -> std::sync::MutexGuard<'_, MinimalState> {
+            self.state.lock().unwrap()
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct MinimalState {
+        capacity: Option<NonZeroUsize>,
+        publishes: BTreeMap<Id, PublishInfo<'static>>,
+    }
+
+    impl MinimalState {
+        fn remove_expired(&mut self) {
+            let now = duration_from_epoch().as_millis();
+
+            self.publishes.retain(|id, info| {
+                info.expiry
+                    .is_none_or(|expiry| id.timestamp.0 + expiry.as_millis() >= now)
+            });
+        }
+
+        /// Removes the expired and then the oldest publishes to store new ones.
+        fn free(&mut self, to_store: usize) {
+            let Some(capacity) = self.capacity else {
+                return;
+            };
+
+            if self.publishes.len() + to_store <= capacity.get() {
+                return;
+            }
+
+            self.remove_expired();
+
+            while self.publishes.len() + to_store > capacity.get() {
+                self.publishes.pop_first();
+            }
+        }
+    }
+
+    impl StoredRetention for MinimalRetention {
+        async fn store_publish(
+            &self,
+            id: &Id,
+            publish: PublishInfo<'_>,
+        ) -> Result<(), Error<RetentionError>> {
+            let mut state = self.state();
+
+            state.free(1);
+            state.publishes.insert(*id, publish.into_owned());
+
+            Ok(())
+        }
+
+        async fn update_sent_flag(&self, id: &Id, sent: bool) -> Result<(), Error<RetentionError>> {
+            if let Some(publish) = self.state().publishes.get_mut(id) {
+                publish.sent = sent;
+            }
+
+            Ok(())
+        }
+
+        async fn mark_received(&self, id: &Id) -> Result<(), Error<RetentionError>> {
+            self.state().publishes.remove(id);
+
+            Ok(())
+        }
+
+        async fn delete_interface(&self, interface: &str) -> Result<(), Error<RetentionError>> {
+            self.state()
+                .publishes
+                .retain(|_, publish| publish.interface != interface);
+
+            Ok(())
+        }
+
+        async fn unsent_publishes(
+            &self,
+            limit: usize,
+            buf: &mut Vec<(Id, PublishInfo<'static>)>,
+        ) -> Result<usize, Error<RetentionError>> {
+            let mut state = self.state();
+
+            state.remove_expired();
+
+            let start = buf.len();
+            buf.extend(
+                state
+                    .publishes
+                    .iter()
+                    .filter(|(_, publish)| !publish.sent)
+                    .take(limit)
+                    .map(|(id, publish)| (*id, publish.clone())),
+            );
+
+            Ok(buf.len() - start)
+        }
+
+        async fn reset_all_publishes(&self) -> Result<(), Error<RetentionError>> {
+            let mut state = self.state();
+
+            state.remove_expired();
+            state
+                .publishes
+                .values_mut()
+                .for_each(|publish| publish.sent = false);
+
+            Ok(())
+        }
+
+        async fn fetch_all_interfaces(
+            &self,
+        ) -> Result<HashSet<StoredInterface>, Error<RetentionError>> {
+            let interfaces = self
+                .state()
+                .publishes
+                .values()
+                .map(|publish| StoredInterface {
+                    name: publish.interface.to_string(),
+                    version_major: publish.version_major,
+                })
+                .collect();
+
+            Ok(interfaces)
+        }
+
+        async fn set_max_retention_items(
+            &self,
+            size: NonZeroUsize,
+        ) -> Result<(), Error<RetentionError>> {
+            let mut state = self.state();
+
+            state.capacity = Some(size);
+            state.free(0);
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn default_methods_should_pass_conformance() {
+        let retention = MinimalRetention::default();
+
+        conformance::stored_retention(&retention).await;
+
+        let err = retention
+            .set_retention_quotas(&RetentionQuotas::new().with_priority("com.Foo", 10))
+            .await
+            .unwrap_err();
+        assert_eq!(*err.kind(), RetentionError::Unsupported);
+    }
+
     #[test]
     fn id_should_be_unique() {
         const NUM: usize = 5;
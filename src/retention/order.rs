@@ -0,0 +1,269 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Order of the publishes resent after a reconnection.
+//!
+//! The publishes of the interfaces with the highest priority, set with
+//! [`RetentionQuotas::with_priority`](super::quota::RetentionQuotas::with_priority), are always
+//! sent first. The [`ResendOrder`] chooses the order of the publishes with the same priority.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::Id;
+use super::quota::{DEFAULT_INTERFACE_PRIORITY, RetentionQuotas};
+
+/// Order of the publishes with the same priority in the resend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ResendOrder {
+    /// Send the oldest publishes first.
+    #[default]
+    Oldest,
+    /// Send the newest publishes first.
+    Newest,
+    /// Alternate the newest and the oldest publishes, so the current data is sent while the
+    /// backlog is drained.
+    Interleaved,
+}
+
+impl ResendOrder {
+    /// Sorts the publishes by priority, and then by this order.
+    pub(crate) fn sort(&self, keys: &mut [ResendKey]) {
+        self.sort_from(keys, &ResendCursor::default());
+    }
+
+    /// Sorts the publishes like [`ResendOrder::sort`], continuing the interleave of the cursor.
+    fn sort_from(&self, keys: &mut [ResendKey], cursor: &ResendCursor) {
+        keys.sort_unstable_by_key(|key| (Reverse(key.priority), key.id));
+
+        match self {
+            ResendOrder::Oldest => {}
+            ResendOrder::Newest => {
+                keys.chunk_by_mut(|a, b| a.priority == b.priority)
+                    .for_each(|chunk| chunk.reverse());
+            }
+            ResendOrder::Interleaved => {
+                keys.chunk_by_mut(|a, b| a.priority == b.priority)
+                    .for_each(|chunk| {
+                        let newest_first = chunk
+                            .first()
+                            .is_some_and(|key| cursor.newest_first == Some(key.priority));
+
+                        interleave(chunk, newest_first)
+                    });
+            }
+        }
+    }
+
+    /// Value of the order in the SQL queries.
+    pub(crate) fn to_sql(self) -> u8 {
+        match self {
+            ResendOrder::Oldest => 0,
+            ResendOrder::Newest => 1,
+            ResendOrder::Interleaved => 2,
+        }
+    }
+}
+
+/// Priorities of the interfaces and order of the resend.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ResendPolicy {
+    order: ResendOrder,
+    priorities: HashMap<String, u8>,
+}
+
+impl ResendPolicy {
+    /// Creates the policy with the priorities of the interfaces in the quotas.
+    pub(crate) fn new(order: ResendOrder, quotas: &RetentionQuotas) -> Self {
+        let priorities = quotas
+            .priorities()
+            .map(|(interface, priority)| (interface.to_string(), priority))
+            .collect();
+
+        Self { order, priorities }
+    }
+
+    pub(crate) fn priority(&self, interface: &str) -> u8 {
+        self.priorities
+            .get(interface)
+            .copied()
+            .unwrap_or(DEFAULT_INTERFACE_PRIORITY)
+    }
+
+    pub(crate) fn key(&self, id: Id, interface: &str) -> ResendKey {
+        ResendKey {
+            id,
+            priority: self.priority(interface),
+        }
+    }
+
+    pub(crate) fn sort(&self, keys: &mut [ResendKey]) {
+        self.order.sort(keys);
+    }
+
+    /// Sorts the publishes of a resend batch and keeps the first `limit`.
+    ///
+    /// The publishes still unsent are sorted again in the next batch, the cursor is updated so
+    /// the interleave continues where this batch stopped. To be able to continue from the newest,
+    /// each retention should return at least `limit + 1` publishes, if it has them.
+    pub(crate) fn sort_batch(
+        &self,
+        keys: &mut Vec<ResendKey>,
+        limit: usize,
+        cursor: &mut ResendCursor,
+    ) {
+        self.order.sort_from(keys, cursor);
+        keys.truncate(limit);
+
+        if self.order != ResendOrder::Interleaved {
+            return;
+        }
+
+        let Some(last) = keys.last().map(|key| key.priority) else {
+            return;
+        };
+
+        let sent = keys
+            .iter()
+            .rev()
+            .take_while(|key| key.priority == last)
+            .count();
+        let newest_first = cursor.newest_first == Some(last);
+
+        // An odd number of publishes leaves the next one on the other side
+        cursor.newest_first = (newest_first != (sent % 2 == 1)).then_some(last);
+    }
+}
+
+/// Position in the [`ResendOrder::Interleaved`] order between the resend batches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ResendCursor {
+    /// Priority of the publishes to continue from the newest.
+    newest_first: Option<u8>,
+}
+
+/// Reorders the keys sorted from the oldest as the oldest, the newest, the second oldest and so
+/// on, or starting from the newest.
+///
+/// Each publish is ranked by its distance from the nearest end, with the ties broken by the side
+/// to start from. It's the same order of the `unsent_publishes.sql` query, which always starts
+/// from the oldest.
+fn interleave(keys: &mut [ResendKey], newest_first: bool) {
+    let len = keys.len();
+
+    let mut ranked: Vec<((usize, usize), ResendKey)> = keys
+        .iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let oldest_rank = i + 1;
+            let newest_rank = len - i;
+            let tie = if newest_first {
+                newest_rank
+            } else {
+                oldest_rank
+            };
+
+            ((oldest_rank.min(newest_rank), tie), *key)
+        })
+        .collect();
+
+    ranked.sort_unstable_by_key(|(rank, _)| *rank);
+
+    for (key, (_, ranked)) in keys.iter_mut().zip(ranked) {
+        *key = ranked;
+    }
+}
+
+/// Sorting key of a publish to resend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ResendKey {
+    pub(crate) id: Id,
+    pub(crate) priority: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::retention::Context;
+
+    use super::*;
+
+    fn sorted(order: ResendOrder, keys: &[ResendKey]) -> Vec<Id> {
+        let mut keys = keys.to_vec();
+
+        order.sort(&mut keys);
+
+        keys.into_iter().map(|key| key.id).collect()
+    }
+
+    #[test]
+    fn should_sort_by_priority_and_order() {
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..6).map(|_| ctx.next()).collect();
+
+        // the alarm has the highest priority
+        let keys: Vec<ResendKey> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| ResendKey {
+                id: *id,
+                priority: if i == 2 { 10 } else { 0 },
+            })
+            .collect();
+
+        assert_eq!(
+            sorted(ResendOrder::Oldest, &keys),
+            [ids[2], ids[0], ids[1], ids[3], ids[4], ids[5]]
+        );
+        assert_eq!(
+            sorted(ResendOrder::Newest, &keys),
+            [ids[2], ids[5], ids[4], ids[3], ids[1], ids[0]]
+        );
+        assert_eq!(
+            sorted(ResendOrder::Interleaved, &keys),
+            [ids[2], ids[0], ids[5], ids[1], ids[4], ids[3]]
+        );
+    }
+
+    #[test]
+    fn should_continue_interleave_across_batches() {
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..7).map(|_| ctx.next()).collect();
+        let policy = ResendPolicy::new(ResendOrder::Interleaved, &RetentionQuotas::new());
+
+        let all: Vec<ResendKey> = ids.iter().map(|id| policy.key(*id, "com.Foo")).collect();
+        let expected = sorted(ResendOrder::Interleaved, &all);
+
+        for limit in 1..=all.len() {
+            let mut unsent = all.clone();
+            let mut cursor = ResendCursor::default();
+            let mut sent = Vec::new();
+
+            while !unsent.is_empty() {
+                let mut batch = unsent.clone();
+                policy.sort_batch(&mut batch, limit, &mut cursor);
+
+                unsent.retain(|key| !batch.contains(key));
+                sent.extend(batch.into_iter().map(|key| key.id));
+            }
+
+            assert_eq!(sent, expected, "with limit {limit}");
+        }
+    }
+}
@@ -0,0 +1,216 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Quotas for the stored retention of each interface.
+//!
+//! The quotas limit how many publishes of an interface are kept in the store, so a chatty
+//! interface cannot evict all the entries of the others.
+
+use std::collections::HashMap;
+use std::num::{NonZeroU64, NonZeroUsize};
+
+/// Priority of the interfaces without an explicit one.
+pub const DEFAULT_INTERFACE_PRIORITY: u8 = 0;
+
+/// Maximum space an interface can use in the stored retention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetentionLimit {
+    /// Maximum number of publishes.
+    Items(NonZeroUsize),
+    /// Maximum size in bytes of the stored payloads.
+    Bytes(NonZeroU64),
+}
+
+impl RetentionLimit {
+    /// Checks if the used space, plus a new publish of the given size, fits in the limit.
+    pub(crate) fn fits(&self, items: usize, bytes: u64, payload: u64) -> bool {
+        match self {
+            RetentionLimit::Items(max) => items.saturating_add(1) <= max.get(),
+            RetentionLimit::Bytes(max) => bytes.saturating_add(payload) <= max.get(),
+        }
+    }
+}
+
+/// What to do when the retention is full and a new publish needs to be stored.
+///
+/// Expired publishes are always removed first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EvictionPolicy {
+    /// Remove the oldest publishes.
+    #[default]
+    Oldest,
+    /// Remove the oldest publishes of the interfaces with the lowest priority.
+    ///
+    /// The quota of a single interface always evicts the oldest publishes of the interface.
+    LowestPriority,
+    /// Reject the new publish, returning an error.
+    Reject,
+}
+
+/// Configuration of a single interface.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct InterfaceQuota {
+    limit: Option<RetentionLimit>,
+    priority: Option<u8>,
+}
+
+/// Quotas of the stored retention.
+///
+/// Each interface can have its own [`RetentionLimit`], or use the default one. The global capacity
+/// set with [`DeviceBuilder::max_stored_retention`](crate::builder::DeviceBuilder::max_stored_retention)
+/// still applies to all the interfaces.
+///
+/// # Example
+///
+/// ```
+/// use std::num::NonZero;
+///
+/// use astarte_device_sdk::retention::quota::{EvictionPolicy, RetentionLimit, RetentionQuotas};
+///
+/// let quotas = RetentionQuotas::new()
+///     .with_default_limit(RetentionLimit::Items(NonZero::new(1000).unwrap()))
+///     .with_interface("com.example.Telemetry", RetentionLimit::Bytes(NonZero::new(1 << 20).unwrap()))
+///     .with_priority("com.example.Alarms", 10)
+///     .with_policy(EvictionPolicy::LowestPriority);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionQuotas {
+    default_limit: Option<RetentionLimit>,
+    interfaces: HashMap<String, InterfaceQuota>,
+    policy: EvictionPolicy,
+}
+
+impl RetentionQuotas {
+    /// Creates quotas without any limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the limit for the interfaces without one.
+    #[must_use]
+    pub fn with_default_limit(mut self, limit: RetentionLimit) -> Self {
+        self.default_limit = Some(limit);
+
+        self
+    }
+
+    /// Sets the limit of the interface, overriding the default one.
+    #[must_use]
+    pub fn with_interface(mut self, interface: impl Into<String>, limit: RetentionLimit) -> Self {
+        self.set_interface(interface, limit);
+
+        self
+    }
+
+    /// Sets the priority of the interface.
+    ///
+    /// It's used by [`EvictionPolicy::LowestPriority`] and to resend the publishes of the
+    /// interfaces with the highest priority first. The interfaces without a priority have the
+    /// [`DEFAULT_INTERFACE_PRIORITY`].
+    #[must_use]
+    pub fn with_priority(mut self, interface: impl Into<String>, priority: u8) -> Self {
+        self.set_priority(interface, priority);
+
+        self
+    }
+
+    /// Sets the eviction policy.
+    #[must_use]
+    pub fn with_policy(mut self, policy: EvictionPolicy) -> Self {
+        self.policy = policy;
+
+        self
+    }
+
+    /// Sets the limit of the interface, overriding the default one.
+    pub fn set_interface(&mut self, interface: impl Into<String>, limit: RetentionLimit) {
+        self.interfaces.entry(interface.into()).or_default().limit = Some(limit);
+    }
+
+    /// Sets the priority of the interface, see [`RetentionQuotas::with_priority`].
+    pub fn set_priority(&mut self, interface: impl Into<String>, priority: u8) {
+        self.interfaces
+            .entry(interface.into())
+            .or_default()
+            .priority = Some(priority);
+    }
+
+    /// Returns the limit of the interface, or the default one.
+    pub fn limit(&self, interface: &str) -> Option<RetentionLimit> {
+        self.interfaces
+            .get(interface)
+            .and_then(|quota| quota.limit)
+            .or(self.default_limit)
+    }
+
+    /// Returns the priority of the interface.
+    pub fn priority(&self, interface: &str) -> u8 {
+        self.interfaces
+            .get(interface)
+            .and_then(|quota| quota.priority)
+            .unwrap_or(DEFAULT_INTERFACE_PRIORITY)
+    }
+
+    /// Returns the eviction policy.
+    pub fn policy(&self) -> EvictionPolicy {
+        self.policy
+    }
+
+    /// Returns the interfaces with an explicit priority.
+    pub(crate) fn priorities(&self) -> impl Iterator<Item = (&str, u8)> {
+        self.interfaces
+            .iter()
+            .filter_map(|(name, quota)| quota.priority.map(|p| (name.as_str(), p)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn interface_should_override_default() {
+        let default = RetentionLimit::Items(NonZeroUsize::new(10).unwrap());
+        let bytes = RetentionLimit::Bytes(NonZeroU64::new(42).unwrap());
+
+        let quotas = RetentionQuotas::new()
+            .with_default_limit(default)
+            .with_interface("com.Foo", bytes)
+            .with_priority("com.Bar", 3);
+
+        assert_eq!(quotas.limit("com.Foo"), Some(bytes));
+        assert_eq!(quotas.limit("com.Bar"), Some(default));
+        assert_eq!(quotas.limit("com.Baz"), Some(default));
+        assert_eq!(quotas.priority("com.Bar"), 3);
+        assert_eq!(quotas.priority("com.Foo"), DEFAULT_INTERFACE_PRIORITY);
+        assert_eq!(quotas.policy(), EvictionPolicy::Oldest);
+    }
+
+    #[test]
+    fn limit_should_check_fit() {
+        let items = RetentionLimit::Items(NonZeroUsize::new(2).unwrap());
+        assert!(items.fits(1, 100, 100));
+        assert!(!items.fits(2, 0, 0));
+
+        let bytes = RetentionLimit::Bytes(NonZeroU64::new(10).unwrap());
+        assert!(bytes.fits(100, 5, 5));
+        assert!(!bytes.fits(0, 5, 6));
+    }
+}
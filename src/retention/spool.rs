@@ -0,0 +1,870 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Disk spool for the publishes evicted from the [`VolatileStore`](super::memory::VolatileStore).
+//!
+//! The publishes that don't fit in memory are written to a file with a maximum size, while only a
+//! small index is kept in memory. The file is truncated when it's opened, so the spooled publishes
+//! are lost on restart like the ones in memory.
+
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::num::NonZero;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use astarte_device_error::{Error, WrapError};
+use astarte_interfaces::interface::Retention;
+use astarte_interfaces::schema::{self, MappingType, Reliability};
+use bson::Bson;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, trace, warn};
+
+use crate::Timestamp;
+use crate::aggregate::AstarteObject;
+use crate::error::Report;
+use crate::types::AstarteData;
+use crate::types::de::BsonConverter;
+use crate::validate::{ValidatedIndividual, ValidatedObject};
+
+use super::Id;
+use super::events::{DropReason, RetentionEvent, RetentionEventSender};
+use super::inspect::{PendingPublish, RetentionFilter, RetentionKind};
+use super::memory::{ItemValue, VolatileItem};
+use super::order::ResendKey;
+
+/// Name of the spool file in the writable directory.
+pub(crate) const SPOOL_FILE_NAME: &str = "volatile-spool.bin";
+
+/// Errors while reading or writing the spool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SpoolError {
+    /// Couldn't open the spool file.
+    Open,
+    /// Couldn't read or write the spool file.
+    Io,
+    /// Couldn't encode the publish.
+    Encode,
+    /// Couldn't decode the publish.
+    Decode,
+    /// Couldn't join the task accessing the file.
+    Join,
+}
+
+impl Display for SpoolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpoolError::Open => write!(f, "couldn't open the volatile spool"),
+            SpoolError::Io => write!(f, "couldn't access the volatile spool"),
+            SpoolError::Encode => write!(f, "couldn't encode the spooled publish"),
+            SpoolError::Decode => write!(f, "couldn't decode the spooled publish"),
+            SpoolError::Join => write!(f, "couldn't join the volatile spool task"),
+        }
+    }
+}
+
+/// Bounded file of publishes.
+///
+/// The records are appended to the file and removed only from the index, the file is compacted
+/// when a new record doesn't fit at the end.
+///
+/// The file is read and written in a [`tokio::task::spawn_blocking`] to not block the runtime.
+#[derive(Debug)]
+pub(crate) struct Spool {
+    file: Arc<File>,
+    max_bytes: u64,
+    /// End of the last record in the file.
+    end: u64,
+    /// Size of the records in the index.
+    bytes: u64,
+    entries: VecDeque<SpoolEntry>,
+}
+
+impl Spool {
+    /// Creates the spool file in the directory, truncating the previous one.
+    pub(crate) fn create(dir: &Path, max_bytes: NonZero<u64>) -> Result<Self, Error<SpoolError>> {
+        let path = dir.join(SPOOL_FILE_NAME);
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .wrap_err_with(|_| Error::new(SpoolError::Open).set_ctx(path.display().to_string()))?;
+
+        debug!(path = %path.display(), max_bytes, "volatile spool created");
+
+        Ok(Self {
+            file: Arc::new(file),
+            max_bytes: max_bytes.get(),
+            end: 0,
+            bytes: 0,
+            entries: VecDeque::new(),
+        })
+    }
+
+    /// Size of the spooled records.
+    #[cfg(test)]
+    pub(crate) fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Writes an item evicted from memory.
+    ///
+    /// The oldest records are evicted to make space for it. If the item doesn't fit in the spool
+    /// or it cannot be written, the item is evicted too. The priority is used to order the resend.
+    pub(super) async fn push(
+        &mut self,
+        item: VolatileItem,
+        priority: u8,
+        events: &RetentionEventSender,
+    ) {
+        let buf = match SpoolRecord::from_value(&item.value).encode() {
+            Ok(buf) => buf,
+            Err(err) => {
+                error!(error = %Report::new(err), id = %item.id, "couldn't spool publish");
+
+                events.send(item.event(DropReason::Evicted));
+
+                return;
+            }
+        };
+
+        let len = u64::try_from(buf.len()).unwrap_or(u64::MAX);
+
+        if len > self.max_bytes {
+            warn!(id = %item.id, len, "publish bigger than the volatile spool, evicting it");
+
+            events.send(item.event(DropReason::Evicted));
+
+            return;
+        }
+
+        while self.bytes.saturating_add(len) > self.max_bytes {
+            let Some(entry) = self.entries.front() else {
+                break;
+            };
+
+            events.send(entry.event(DropReason::Evicted));
+            self.remove(0).await;
+        }
+
+        if let Err(err) = self.write(buf).await {
+            error!(error = %Report::new(err), id = %item.id, "couldn't spool publish");
+
+            events.send(item.event(DropReason::Evicted));
+
+            return;
+        }
+
+        let offset = self.end;
+        self.end = self.end.saturating_add(len);
+        self.bytes = self.bytes.saturating_add(len);
+
+        self.entries.push_back(SpoolEntry {
+            id: item.id,
+            interface: item.value.interface().to_string(),
+            path: item.value.path().to_string(),
+            version_major: item.value.version_major(),
+            offset,
+            len,
+            sent: item.sent,
+            store_time: item.store_time,
+            expiry: item.value.expiry(),
+            force_expired: item.force_expired,
+            priority,
+        });
+
+        trace!(id = %item.id, len, "publish spooled");
+    }
+
+    /// Returns the keys of the unsent publishes, oldest first.
+    ///
+    /// The expired publishes should be removed before.
+    pub(crate) fn unsent_keys(&self) -> impl Iterator<Item = ResendKey> + '_ {
+        self.entries
+            .iter()
+            .filter(|entry| !entry.sent)
+            .map(|entry| ResendKey {
+                id: entry.id,
+                priority: entry.priority,
+            })
+    }
+
+    /// Reads the value of a publish, removing it if it cannot be read.
+    pub(crate) async fn read_unsent(&mut self, id: &Id) -> Option<ItemValue> {
+        let idx = self.entries.iter().position(|entry| entry.id == *id)?;
+
+        match self.read_value(&self.entries[idx]).await {
+            Ok(value) => Some(value),
+            Err(err) => {
+                error!(error = %Report::new(err), %id, "couldn't read spooled publish, removing it");
+
+                self.remove(idx).await;
+
+                None
+            }
+        }
+    }
+
+    pub(crate) fn mark_sent(&mut self, id: &Id, sent: bool) -> Option<bool> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.id == *id)
+            .map(|entry| std::mem::replace(&mut entry.sent, sent))
+    }
+
+    pub(crate) fn reset_sent(&mut self) {
+        self.entries.iter_mut().for_each(|entry| entry.sent = false);
+    }
+
+    /// Removes the publish, returning its value.
+    pub(crate) async fn mark_received(&mut self, id: &Id) -> Option<ItemValue> {
+        let idx = self.entries.iter().position(|entry| entry.id == *id)?;
+
+        let value = self
+            .read_value(&self.entries[idx])
+            .await
+            .inspect_err(
+                |err| error!(error = %Report::new(err), %id, "couldn't read spooled publish"),
+            )
+            .ok();
+
+        self.remove(idx).await;
+
+        value
+    }
+
+    pub(crate) async fn remove_expired(&mut self, now: SystemTime, events: &RetentionEventSender) {
+        self.retain(|entry| {
+            if !entry.is_expired(now) {
+                return true;
+            }
+
+            events.send(entry.event(DropReason::Expired));
+
+            false
+        })
+        .await;
+    }
+
+    pub(crate) async fn delete_interface(
+        &mut self,
+        interface_name: &str,
+        events: &RetentionEventSender,
+    ) -> usize {
+        let now = SystemTime::now();
+        let mut count = 0;
+
+        self.retain(|entry| {
+            let reason = if entry.is_expired(now) {
+                DropReason::Expired
+            } else if entry.interface == interface_name {
+                DropReason::InterfaceRemoved
+            } else {
+                return true;
+            };
+
+            count += 1;
+            events.send(entry.event(reason));
+
+            false
+        })
+        .await;
+
+        count
+    }
+
+    pub(crate) fn count(&self, filter: &RetentionFilter) -> usize {
+        let now = SystemTime::now();
+
+        self.entries
+            .iter()
+            .filter(|entry| !entry.is_expired(now) && entry.matches(filter))
+            .count()
+    }
+
+    pub(crate) fn list(&self, filter: &RetentionFilter, limit: usize) -> Vec<PendingPublish> {
+        let now = SystemTime::now();
+
+        self.entries
+            .iter()
+            .filter(|entry| !entry.is_expired(now) && entry.matches(filter))
+            .map(SpoolEntry::pending)
+            .take(limit)
+            .collect()
+    }
+
+    pub(crate) async fn purge(&mut self, filter: &RetentionFilter) -> usize {
+        let mut count = 0;
+
+        self.retain(|entry| {
+            let matches = entry.matches(filter);
+
+            if matches {
+                count += 1;
+            }
+
+            !matches
+        })
+        .await;
+
+        count
+    }
+
+    pub(crate) fn expire(&mut self, filter: &RetentionFilter) -> usize {
+        let now = SystemTime::now();
+
+        let mut count = 0;
+
+        self.entries
+            .iter_mut()
+            .filter(|entry| !entry.is_expired(now) && entry.matches(filter))
+            .for_each(|entry| {
+                entry.force_expired = true;
+                count += 1;
+            });
+
+        count
+    }
+
+    /// Keeps only the entries for which the function returns `true`.
+    async fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&SpoolEntry) -> bool,
+    {
+        let before = self.entries.len();
+
+        let bytes = &mut self.bytes;
+        self.entries.retain(|entry| {
+            let keep = keep(entry);
+
+            if !keep {
+                *bytes = bytes.saturating_sub(entry.len);
+            }
+
+            keep
+        });
+
+        if self.entries.is_empty() && before > 0 {
+            self.truncate().await;
+        }
+    }
+
+    async fn remove(&mut self, idx: usize) {
+        let Some(entry) = self.entries.remove(idx) else {
+            return;
+        };
+
+        self.bytes = self.bytes.saturating_sub(entry.len);
+
+        if self.entries.is_empty() {
+            self.truncate().await;
+        }
+    }
+
+    /// Frees the file when the spool is empty.
+    async fn truncate(&mut self) {
+        self.end = 0;
+        self.bytes = 0;
+
+        if let Err(err) = self.with_file(|file| file.set_len(0)).await {
+            error!(error = %Report::new(err), "couldn't truncate the volatile spool");
+        }
+    }
+
+    /// Appends the record, compacting the file if it doesn't fit at the end.
+    async fn write(&mut self, buf: Vec<u8>) -> Result<(), Error<SpoolError>> {
+        let len = u64::try_from(buf.len()).unwrap_or(u64::MAX);
+
+        if self.end.saturating_add(len) > self.max_bytes {
+            self.compact().await?;
+        }
+
+        let offset = self.end;
+
+        self.with_file(move |file| write_at(file, offset, &buf))
+            .await
+    }
+
+    /// Moves the records to the start of the file, removing the holes.
+    async fn compact(&mut self) -> Result<(), Error<SpoolError>> {
+        let mut cursor = 0u64;
+        let mut moves = Vec::new();
+
+        // The index is updated before the file, since the task completes even if this future is
+        // dropped
+        for entry in &mut self.entries {
+            if entry.offset != cursor {
+                moves.push((entry.offset, cursor, entry.len));
+
+                entry.offset = cursor;
+            }
+
+            cursor = cursor.saturating_add(entry.len);
+        }
+
+        self.end = cursor;
+
+        self.with_file(move |file| {
+            for &(from, to, len) in &moves {
+                let buf = read_at(file, from, len)?;
+                write_at(file, to, &buf)?;
+            }
+
+            file.set_len(cursor)
+        })
+        .await?;
+
+        trace!(end = self.end, "volatile spool compacted");
+
+        Ok(())
+    }
+
+    async fn read_value(&self, entry: &SpoolEntry) -> Result<ItemValue, Error<SpoolError>> {
+        let (offset, len) = (entry.offset, entry.len);
+
+        let buf = self
+            .with_file(move |file| read_at(file, offset, len))
+            .await?;
+
+        SpoolRecord::decode(&buf)?.try_into_value()
+    }
+
+    /// Runs the operation on the file in a blocking task.
+    async fn with_file<F, O>(&self, f: F) -> Result<O, Error<SpoolError>>
+    where
+        F: FnOnce(&File) -> std::io::Result<O> + Send + 'static,
+        O: Send + 'static,
+    {
+        let file = Arc::clone(&self.file);
+
+        tokio::task::spawn_blocking(move || f(&file))
+            .await
+            .wrap_err(SpoolError::Join)?
+            .wrap_err(SpoolError::Io)
+    }
+}
+
+fn write_at(mut file: &File, offset: u64, buf: &[u8]) -> std::io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(buf)
+}
+
+fn read_at(mut file: &File, offset: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    let len = usize::try_from(len).map_err(std::io::Error::other)?;
+    let mut buf = vec![0; len];
+
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+
+    Ok(buf)
+}
+
+/// Index of a record in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SpoolEntry {
+    id: Id,
+    interface: String,
+    path: String,
+    version_major: i32,
+    offset: u64,
+    len: u64,
+    sent: bool,
+    store_time: SystemTime,
+    expiry: Option<Duration>,
+    force_expired: bool,
+    priority: u8,
+}
+
+impl SpoolEntry {
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.force_expired
+            || self
+                .expiry
+                .is_some_and(|expiry| (self.store_time + expiry) < now)
+    }
+
+    fn matches(&self, filter: &RetentionFilter) -> bool {
+        filter.matches(&self.id, &self.interface, &self.path)
+    }
+
+    fn event(&self, reason: DropReason) -> RetentionEvent {
+        RetentionEvent::new(
+            self.id,
+            RetentionKind::Volatile,
+            self.interface.as_str(),
+            self.path.as_str(),
+            reason,
+        )
+    }
+
+    fn pending(&self) -> PendingPublish {
+        PendingPublish {
+            id: self.id,
+            kind: RetentionKind::Volatile,
+            interface: self.interface.clone(),
+            path: self.path.clone(),
+            version_major: self.version_major,
+            sent: self.sent,
+            size: usize::try_from(self.len).unwrap_or(usize::MAX),
+            expiry: self.expiry,
+        }
+    }
+}
+
+/// Publish encoded in the spool file.
+#[derive(Debug, Serialize, Deserialize)]
+struct SpoolRecord {
+    interface: String,
+    path: String,
+    version_major: i32,
+    reliability: Reliability,
+    retention: schema::Retention,
+    expiry_millis: Option<i64>,
+    timestamp: Option<Timestamp>,
+    /// Values of an object, `None` for an individual.
+    keys: Option<Vec<String>>,
+    types: Vec<MappingType>,
+    values: Vec<Bson>,
+}
+
+impl SpoolRecord {
+    fn from_value(value: &ItemValue) -> Self {
+        let retention = match value.retention() {
+            Retention::Stored { .. } => schema::Retention::Stored,
+            Retention::Discard | Retention::Volatile { .. } => schema::Retention::Volatile,
+        };
+        let expiry_millis = value
+            .expiry()
+            .map(|expiry| i64::try_from(expiry.as_millis()).unwrap_or(i64::MAX));
+
+        match value {
+            ItemValue::Individual(individual) => Self {
+                interface: individual.interface.clone(),
+                path: individual.path.clone(),
+                version_major: individual.version_major,
+                reliability: individual.reliability,
+                retention,
+                expiry_millis,
+                timestamp: individual.timestamp,
+                keys: None,
+                types: vec![individual.data.mapping_type()],
+                values: vec![Bson::from(individual.data.clone())],
+            },
+            ItemValue::Object(object) => {
+                let (keys, (types, values)) = object
+                    .data
+                    .iter()
+                    .map(|(key, value)| {
+                        (
+                            key.clone(),
+                            (value.mapping_type(), Bson::from(value.clone())),
+                        )
+                    })
+                    .unzip();
+
+                Self {
+                    interface: object.interface.clone(),
+                    path: object.path.clone(),
+                    version_major: object.version_major,
+                    reliability: object.reliability,
+                    retention,
+                    expiry_millis,
+                    timestamp: object.timestamp,
+                    keys: Some(keys),
+                    types,
+                    values,
+                }
+            }
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, Error<SpoolError>> {
+        bson::serialize_to_vec(self).wrap_err(SpoolError::Encode)
+    }
+
+    fn decode(buf: &[u8]) -> Result<Self, Error<SpoolError>> {
+        bson::deserialize_from_slice(buf).wrap_err(SpoolError::Decode)
+    }
+
+    fn try_into_value(self) -> Result<ItemValue, Error<SpoolError>> {
+        let expiry = self
+            .expiry_millis
+            .and_then(|millis| u64::try_from(millis).ok())
+            .map(Duration::from_millis);
+        let retention = match self.retention {
+            schema::Retention::Stored => Retention::Stored { expiry },
+            _ => Retention::Volatile { expiry },
+        };
+
+        let mut data = self
+            .types
+            .into_iter()
+            .zip(self.values)
+            .map(|(mapping_type, value)| {
+                AstarteData::try_from(BsonConverter::new(mapping_type, value))
+                    .wrap_err(SpoolError::Decode)
+            });
+
+        match self.keys {
+            None => {
+                let data = data
+                    .next()
+                    .ok_or(Error::with(SpoolError::Decode, "missing individual value"))??;
+
+                Ok(ItemValue::Individual(ValidatedIndividual {
+                    interface: self.interface,
+                    path: self.path,
+                    version_major: self.version_major,
+                    reliability: self.reliability,
+                    retention,
+                    data,
+                    timestamp: self.timestamp,
+                }))
+            }
+            Some(keys) => {
+                let data = keys
+                    .into_iter()
+                    .zip(data)
+                    .map(|(key, value)| value.map(|value| (key, value)))
+                    .collect::<Result<AstarteObject, _>>()?;
+
+                Ok(ItemValue::Object(ValidatedObject {
+                    interface: self.interface,
+                    path: self.path,
+                    version_major: self.version_major,
+                    reliability: self.reliability,
+                    retention,
+                    data,
+                    timestamp: self.timestamp,
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use crate::retention::Context;
+
+    use super::*;
+
+    fn individual(path: &str, data: AstarteData) -> ItemValue {
+        ItemValue::Individual(ValidatedIndividual {
+            interface: "com.Foo".to_string(),
+            path: path.to_string(),
+            version_major: 1,
+            reliability: Reliability::Guaranteed,
+            retention: Retention::Volatile { expiry: None },
+            data,
+            timestamp: None,
+        })
+    }
+
+    async fn unsent(spool: &mut Spool) -> Vec<(Id, ItemValue)> {
+        let ids: Vec<Id> = spool.unsent_keys().map(|key| key.id).collect();
+
+        let mut unsent = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(value) = spool.read_unsent(&id).await {
+                unsent.push((id, value));
+            }
+        }
+
+        unsent
+    }
+
+    fn spool(dir: &TempDir, max_bytes: u64) -> Spool {
+        Spool::create(dir.path(), NonZero::new(max_bytes).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn should_encode_and_decode_records() {
+        let timestamp = Utc.timestamp_opt(1_700_000_000, 123_456_789).unwrap();
+
+        let values = [
+            ItemValue::Individual(ValidatedIndividual {
+                interface: "com.Foo".to_string(),
+                path: "/foo".to_string(),
+                version_major: 2,
+                reliability: Reliability::Unique,
+                retention: Retention::Stored {
+                    expiry: Some(Duration::from_secs(30)),
+                },
+                data: AstarteData::StringArray(vec!["a".to_string(), "b".to_string()]),
+                timestamp: Some(timestamp),
+            }),
+            ItemValue::Object(ValidatedObject {
+                interface: "com.Bar".to_string(),
+                path: "/bar".to_string(),
+                version_major: 1,
+                reliability: Reliability::Guaranteed,
+                retention: Retention::Volatile { expiry: None },
+                data: AstarteObject::from_iter([
+                    ("long".to_string(), AstarteData::LongInteger(1 << 40)),
+                    ("blob".to_string(), AstarteData::BinaryBlob(vec![1, 2, 3])),
+                    ("bool".to_string(), AstarteData::Boolean(true)),
+                ]),
+                timestamp: None,
+            }),
+        ];
+
+        for value in values {
+            let buf = SpoolRecord::from_value(&value).encode().unwrap();
+            let res = SpoolRecord::decode(&buf).unwrap().try_into_value().unwrap();
+
+            assert_eq!(res, value);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_evict_oldest_and_compact() {
+        let dir = TempDir::new().unwrap();
+        let events = RetentionEventSender::new();
+        let mut rx = events.subscribe();
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..4).map(|_| ctx.next()).collect();
+        let value = |i: usize| individual(&format!("/{i}"), AstarteData::Integer(42));
+
+        let len = SpoolRecord::from_value(&value(0)).encode().unwrap().len() as u64;
+
+        // space for three records
+        let mut spool = spool(&dir, len * 3);
+
+        for (i, id) in ids.iter().take(3).enumerate() {
+            spool
+                .push(VolatileItem::new(*id, value(i), false), 0, &events)
+                .await;
+        }
+        assert_eq!(spool.len(), 3);
+        assert_eq!(spool.bytes(), len * 3);
+
+        // leaves a hole in the middle
+        assert_eq!(spool.mark_received(&ids[1]).await, Some(value(1)));
+        assert_eq!(spool.len(), 2);
+
+        // doesn't fit at the end, compacts the file
+        spool
+            .push(VolatileItem::new(ids[3], value(3), false), 0, &events)
+            .await;
+        assert_eq!(spool.len(), 3);
+        assert_eq!(spool.end, len * 3);
+        assert_eq!(
+            std::fs::metadata(dir.path().join(SPOOL_FILE_NAME))
+                .unwrap()
+                .len(),
+            len * 3
+        );
+
+        // evicts the oldest
+        let id = ctx.next();
+        spool
+            .push(VolatileItem::new(id, value(4), false), 0, &events)
+            .await;
+
+        let event = rx.recv().await.unwrap();
+        assert_eq!(
+            event,
+            RetentionEvent::new(
+                ids[0],
+                RetentionKind::Volatile,
+                "com.Foo",
+                "/0",
+                DropReason::Evicted
+            )
+        );
+
+        assert_eq!(
+            unsent(&mut spool).await,
+            [(ids[2], value(2)), (ids[3], value(3)), (id, value(4))]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_drop_bigger_than_spool() {
+        let dir = TempDir::new().unwrap();
+        let events = RetentionEventSender::new();
+        let mut rx = events.subscribe();
+
+        let mut spool = spool(&dir, 16);
+
+        let id = Context::new().next();
+        spool
+            .push(
+                VolatileItem::new(id, individual("/foo", AstarteData::Integer(1)), false),
+                0,
+                &events,
+            )
+            .await;
+
+        assert_eq!(spool.len(), 0);
+        assert_eq!(rx.recv().await.unwrap().reason, DropReason::Evicted);
+    }
+
+    #[tokio::test]
+    async fn should_truncate_when_empty() {
+        let dir = TempDir::new().unwrap();
+        let events = RetentionEventSender::new();
+
+        let mut spool = spool(&dir, 4096);
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..2).map(|_| ctx.next()).collect();
+        for id in &ids {
+            spool
+                .push(
+                    VolatileItem::new(*id, individual("/foo", AstarteData::Integer(1)), true),
+                    0,
+                    &events,
+                )
+                .await;
+        }
+
+        let filter = RetentionFilter::new().with_path("/foo");
+        assert_eq!(spool.count(&filter), 2);
+        assert_eq!(spool.list(&filter, 1).len(), 1);
+
+        assert_eq!(unsent(&mut spool).await.len(), 0);
+        spool.reset_sent();
+        assert_eq!(unsent(&mut spool).await.len(), 2);
+
+        assert_eq!(spool.purge(&filter).await, 2);
+        assert_eq!(spool.end, 0);
+        assert_eq!(
+            std::fs::metadata(dir.path().join(SPOOL_FILE_NAME))
+                .unwrap()
+                .len(),
+            0
+        );
+    }
+}
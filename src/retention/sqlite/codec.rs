@@ -0,0 +1,138 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compression of the stored retention payloads.
+
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+use flate2::{Compression, bufread::ZlibDecoder, write::ZlibEncoder};
+use rusqlite::{
+    ToSql,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef},
+};
+use tracing::warn;
+
+use crate::error::Report;
+use crate::store::sqlite::options::RetentionCompression;
+
+/// Marker of the encoding of a stored payload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum PayloadCodec {
+    /// The payload is stored as is.
+    #[default]
+    Raw,
+    /// The payload is compressed with zlib.
+    Zlib,
+}
+
+impl PayloadCodec {
+    /// Encodes the payload with the configured compression.
+    ///
+    /// The payload is kept as is if the compressed one is not smaller.
+    pub(crate) fn encode(
+        compression: RetentionCompression,
+        payload: &[u8],
+    ) -> (Self, Cow<'_, [u8]>) {
+        match compression {
+            RetentionCompression::None => (Self::Raw, Cow::Borrowed(payload)),
+            RetentionCompression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+
+                match encoder.write_all(payload).and_then(|()| encoder.finish()) {
+                    Ok(compressed) if compressed.len() < payload.len() => {
+                        (Self::Zlib, Cow::Owned(compressed))
+                    }
+                    Ok(_) => (Self::Raw, Cow::Borrowed(payload)),
+                    Err(err) => {
+                        warn!(error = %Report::new(err), "couldn't compress the payload, storing it uncompressed");
+
+                        (Self::Raw, Cow::Borrowed(payload))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decodes a stored payload.
+    pub(crate) fn decode(self, payload: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        match self {
+            PayloadCodec::Raw => Ok(payload),
+            PayloadCodec::Zlib => {
+                let mut decoder = ZlibDecoder::new(payload.as_slice());
+                let mut buf = Vec::with_capacity(payload.len().saturating_mul(2));
+
+                decoder.read_to_end(&mut buf)?;
+
+                Ok(buf)
+            }
+        }
+    }
+}
+
+impl ToSql for PayloadCodec {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let value: u8 = match self {
+            PayloadCodec::Raw => 0,
+            PayloadCodec::Zlib => 1,
+        };
+
+        Ok(ToSqlOutput::from(value))
+    }
+}
+
+impl FromSql for PayloadCodec {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_i64()? {
+            0 => Ok(PayloadCodec::Raw),
+            1 => Ok(PayloadCodec::Zlib),
+            value => Err(FromSqlError::OutOfRange(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn should_compress_and_decompress() {
+        let payload = b"some very repetitive payload payload payload payload payload".to_vec();
+
+        let (codec, encoded) = PayloadCodec::encode(RetentionCompression::Zlib, &payload);
+        assert_eq!(codec, PayloadCodec::Zlib);
+        assert!(encoded.len() < payload.len());
+
+        let decoded = codec.decode(encoded.into_owned()).unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn should_keep_small_payload_raw() {
+        let payload = [1, 2, 3];
+
+        let (codec, encoded) = PayloadCodec::encode(RetentionCompression::Zlib, &payload);
+        assert_eq!(codec, PayloadCodec::Raw);
+        assert_eq!(*encoded, payload);
+
+        let (codec, _) = PayloadCodec::encode(RetentionCompression::None, &payload);
+        assert_eq!(codec, PayloadCodec::Raw);
+    }
+}
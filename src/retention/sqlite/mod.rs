@@ -18,7 +18,13 @@
 
 //! Retention implemented using an SQLite database.
 
-use std::{borrow::Cow, collections::HashSet, num::TryFromIntError, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    num::{NonZeroU64, TryFromIntError},
+    sync::Arc,
+    time::Duration,
+};
 
 use astarte_device_error::{Error, WrapError};
 use astarte_interfaces::schema::Reliability;
@@ -32,12 +38,21 @@ use crate::error::Report;
 use crate::store::SqliteStore;
 use crate::store::sqlite::connection::WriteConnection;
 use crate::store::sqlite::error::SqliteError;
+use crate::store::sqlite::options::RetentionCompression;
+
+use self::codec::PayloadCodec;
+use self::statements::UnsentOrder;
 
+use super::events::RetentionEventSender;
+use super::inspect::{PendingPublish, RetentionFilter};
+use super::order::ResendOrder;
+use super::quota::{EvictionPolicy, RetentionLimit, RetentionQuotas};
 use super::{
     Id, PublishInfo, RetentionError, StoredInterface, StoredRetention, TimestampMillis,
     duration_from_epoch,
 };
 
+mod codec;
 mod statements;
 
 impl FromSql for TimestampMillis {
@@ -109,6 +124,8 @@ pub(crate) struct RetentionPublish<'a> {
     expiry_time: Option<TimestampSecs>,
     /// Flag to check if the publish was sent.
     sent: bool,
+    /// Encoding of the stored payload.
+    codec: PayloadCodec,
     /// The serialized payload of the publish
     payload: Cow<'a, [u8]>,
 }
@@ -126,10 +143,29 @@ impl<'a> RetentionPublish<'a> {
             path: Cow::Borrowed(&info.path),
             expiry_time,
             sent: info.sent,
+            codec: PayloadCodec::Raw,
             payload: Cow::Borrowed(&info.value),
         })
     }
 
+    /// Returns the publish with the payload encoded with the given compression.
+    fn encode(&self, compression: RetentionCompression) -> RetentionPublish<'_> {
+        let (codec, payload) = match self.codec {
+            PayloadCodec::Raw => PayloadCodec::encode(compression, &self.payload),
+            codec => (codec, Cow::Borrowed(self.payload.as_ref())),
+        };
+
+        RetentionPublish {
+            id: self.id,
+            interface: Cow::Borrowed(&self.interface),
+            path: Cow::Borrowed(&self.path),
+            expiry_time: self.expiry_time,
+            sent: self.sent,
+            codec,
+            payload,
+        }
+    }
+
     /// Returns an owned version of the value
     pub(crate) fn into_owned(self) -> RetentionPublish<'static> {
         RetentionPublish {
@@ -138,6 +174,7 @@ impl<'a> RetentionPublish<'a> {
             path: self.path.into_owned().into(),
             expiry_time: self.expiry_time,
             sent: self.sent,
+            codec: self.codec,
             payload: self.payload.into_owned().into(),
         }
     }
@@ -227,34 +264,49 @@ impl StoredRetention for SqliteStore {
     ) -> Result<(), Error<RetentionError>> {
         let id = *id;
         let info = info.into_owned();
+        let compression = self.pool.options().await.retention_compression();
+        // Compress the payload before queueing it, so the other writes in the batch don't wait
         let publish = RetentionPublish::from_info(id, &info)
             .wrap_err_with(|_error| RetentionError::store("converting publish info", &info))?
+            .encode(compression)
             .into_owned();
 
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
-            .acquire_writer(move |writer| {
+            .queue_writer(move |writer| {
                 let mapping = RetentionMapping::from(&info);
 
-                writer.store(&mapping, &publish)
+                writer.store(&tenant, &mapping, &publish)
             })
             .await
-            .wrap_err_msg(RetentionError::Connection, "while storing publish")
+            .map_err(|err| {
+                let kind = if *err.kind() == SqliteError::QuotaExceeded {
+                    RetentionError::QuotaExceeded
+                } else {
+                    RetentionError::Connection
+                };
+
+                Error::with(kind, "while storing publish").set_source(err)
+            })
     }
 
     async fn update_sent_flag(&self, id: &Id, sent: bool) -> Result<(), Error<RetentionError>> {
         let id = *id;
+        let tenant = Arc::clone(&self.tenant);
 
         self.pool
-            .acquire_writer(move |writer| writer.update_publish_sent_flag(&id, sent))
+            .queue_writer(move |writer| writer.update_publish_sent_flag(&tenant, &id, sent))
             .await
             .wrap_err_with(|_err| RetentionError::update_sent(id, sent))
     }
 
     async fn mark_received(&self, id: &Id) -> Result<(), Error<RetentionError>> {
         let id = *id;
+        let tenant = Arc::clone(&self.tenant);
 
         self.pool
-            .acquire_writer(move |writer| writer.delete_publish_by_id(&id))
+            .queue_writer(move |writer| writer.delete_publish_by_id(&tenant, &id))
             .await
             .wrap_err_with(|_err| Error::new(RetentionError::Received).set_ctx(id))
     }
@@ -262,8 +314,9 @@ impl StoredRetention for SqliteStore {
     async fn delete_interface(&self, interface: &str) -> Result<(), Error<RetentionError>> {
         self.pool
             .acquire_writer({
+                let tenant = Arc::clone(&self.tenant);
                 let interface = interface.to_string();
-                move |writer| writer.delete_interface(&interface)
+                move |writer| writer.delete_interface(&tenant, &interface)
             })
             .await
             .wrap_err_with(|_err| {
@@ -281,15 +334,27 @@ impl StoredRetention for SqliteStore {
         // This is to move the vec to another thread
         let mut buf_take = std::mem::take(buf);
 
-        self.pool
-            .acquire_writer(move |writer| writer.delete_expired(&now))
+        let order = self
+            .pool
+            .acquire_writer({
+                let tenant = Arc::clone(&self.tenant);
+                move |writer| {
+                    writer.delete_expired(&tenant, &now)?;
+
+                    UnsentOrder::new(
+                        writer.retention_quotas(&tenant).as_deref(),
+                        writer.resend_order(&tenant),
+                    )
+                }
+            })
             .await
             .wrap_err_msg(RetentionError::Unsent, "while deleting expired")?;
 
+        let tenant = Arc::clone(&self.tenant);
         let (buf_ret, count) = self
             .pool
             .acquire_reader(move |reader| -> Result<_, Error<SqliteError>> {
-                let count = reader.unsent_publishes(&mut buf_take, &now, limit)?;
+                let count = reader.unsent_publishes(&tenant, &mut buf_take, &now, limit, &order)?;
 
                 Ok((buf_take, count))
             })
@@ -305,12 +370,16 @@ impl StoredRetention for SqliteStore {
         let now = TimestampSecs::now();
 
         self.pool
-            .acquire_writer(move |writer| writer.delete_expired(&now))
+            .acquire_writer({
+                let tenant = Arc::clone(&self.tenant);
+                move |writer| writer.delete_expired(&tenant, &now)
+            })
             .await
             .wrap_err_msg(RetentionError::Reset, "while deleting expired")?;
 
+        let tenant = Arc::clone(&self.tenant);
         self.pool
-            .acquire_writer(|writer| writer.reset_all_sent())
+            .acquire_writer(move |writer| writer.reset_all_sent(&tenant))
             .await
             .wrap_err(RetentionError::Reset)?;
 
@@ -320,8 +389,10 @@ impl StoredRetention for SqliteStore {
     async fn fetch_all_interfaces(
         &self,
     ) -> Result<HashSet<StoredInterface>, Error<RetentionError>> {
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
-            .acquire_reader(|reader| reader.all_interfaces())
+            .acquire_reader(move |reader| reader.all_interfaces(&tenant))
             .await
             .wrap_err(RetentionError::FetchInterfaces)
     }
@@ -330,64 +401,348 @@ impl StoredRetention for SqliteStore {
         &self,
         size: std::num::NonZeroUsize,
     ) -> Result<(), Error<RetentionError>> {
+        let tenant = Arc::clone(&self.tenant);
+
+        self.pool
+            .acquire_writer(move |writer| writer.set_max_retention_items(&tenant, size))
+            .await
+            .wrap_err_with(|_err| Error::new(RetentionError::SetCapacity).set_ctx(size))
+    }
+
+    async fn set_max_retention_bytes(&self, size: NonZeroU64) -> Result<(), Error<RetentionError>> {
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
-            .acquire_writer(move |writer| writer.set_max_retention_items(size))
+            .acquire_writer(move |writer| writer.set_max_retention_bytes(&tenant, size))
             .await
             .wrap_err_with(|_err| Error::new(RetentionError::SetCapacity).set_ctx(size))
     }
+
+    async fn set_retention_quotas(
+        &self,
+        quotas: &RetentionQuotas,
+    ) -> Result<(), Error<RetentionError>> {
+        let tenant = Arc::clone(&self.tenant);
+        let quotas = quotas.clone();
+
+        self.pool
+            .acquire_writer(move |writer| writer.apply_retention_quotas(&tenant, quotas))
+            .await
+            .wrap_err(RetentionError::SetQuotas)
+    }
+
+    async fn count_publishes(
+        &self,
+        filter: &RetentionFilter,
+    ) -> Result<usize, Error<RetentionError>> {
+        let tenant = Arc::clone(&self.tenant);
+        let filter = filter.clone();
+
+        self.pool
+            .acquire_reader(move |reader| reader.count_filtered(&tenant, &filter))
+            .await
+            .wrap_err(RetentionError::Inspect)
+    }
+
+    async fn list_publishes(
+        &self,
+        filter: &RetentionFilter,
+        limit: usize,
+    ) -> Result<Vec<PendingPublish>, Error<RetentionError>> {
+        let tenant = Arc::clone(&self.tenant);
+        let filter = filter.clone();
+
+        self.pool
+            .acquire_reader(move |reader| reader.list_publishes(&tenant, &filter, limit))
+            .await
+            .wrap_err(RetentionError::Inspect)
+    }
+
+    async fn purge_publishes(
+        &self,
+        filter: &RetentionFilter,
+    ) -> Result<usize, Error<RetentionError>> {
+        let tenant = Arc::clone(&self.tenant);
+        let filter = filter.clone();
+
+        self.pool
+            .acquire_writer(move |writer| writer.delete_filtered(&tenant, &filter))
+            .await
+            .wrap_err(RetentionError::Purge)
+    }
+
+    async fn expire_publishes(
+        &self,
+        filter: &RetentionFilter,
+    ) -> Result<usize, Error<RetentionError>> {
+        // In the past, so the publishes are already expired
+        let expiry = TimestampSecs(TimestampSecs::now().0.saturating_sub(1));
+
+        let tenant = Arc::clone(&self.tenant);
+        let filter = filter.clone();
+
+        self.pool
+            .acquire_writer(move |writer| writer.expire_filtered(&tenant, &filter, &expiry))
+            .await
+            .wrap_err(RetentionError::Purge)
+    }
+
+    async fn set_resend_order(&self, order: ResendOrder) -> Result<(), Error<RetentionError>> {
+        let tenant = Arc::clone(&self.tenant);
+
+        self.pool
+            .acquire_writer(move |writer| {
+                writer.set_resend_order(&tenant, order);
+
+                Ok(())
+            })
+            .await
+            .wrap_err(RetentionError::Connection)
+    }
+
+    async fn set_retention_events(
+        &self,
+        events: RetentionEventSender,
+    ) -> Result<(), Error<RetentionError>> {
+        let tenant = Arc::clone(&self.tenant);
+
+        self.pool
+            .acquire_writer(move |writer| {
+                writer.set_retention_events(&tenant, events);
+
+                Ok(())
+            })
+            .await
+            .wrap_err(RetentionError::Connection)
+    }
 }
 
 impl WriteConnection {
     /// Empty space when the database is full to allow storing newer elements
     ///
     /// In sequence
-    /// - first we check the occupied space is greater than the capacity
+    /// - first we check the occupied space is greater than the capacity, in items or bytes
     /// - if so, remove the expired elements from the store
-    /// - if the available space is still insufficient, remove the oldest elements
+    /// - if the available space is still insufficient, apply the [`EvictionPolicy`]
+    ///
+    /// The `payload` is the size of the publish to store, or [`None`] if the capacity is shrinking.
     ///
     /// Return the number of removed elements.
     #[instrument(skip(self))]
     pub(crate) fn free_retention_items(
         &mut self,
-        to_store: usize,
+        tenant: &str,
+        payload: Option<u64>,
     ) -> Result<usize, Error<SqliteError>> {
-        let max_items = self.retention_capacity.get();
+        let max_items = self.retention_capacity(tenant).get();
+        let max_bytes = self.retention_max_bytes(tenant).map(NonZeroU64::get);
+
+        let to_store = payload.map_or(0, |_| 1);
+        let to_store_bytes = payload.unwrap_or(0);
+
+        // space to free in items and bytes
+        let to_free = |(count, bytes): (usize, u64)| {
+            let items = count.saturating_add(to_store).saturating_sub(max_items);
+            let bytes = max_bytes.map_or(0, |max| {
+                bytes.saturating_add(to_store_bytes).saturating_sub(max)
+            });
+
+            (items, bytes)
+        };
+
+        let usage = self.usage_for_limits(tenant, max_bytes.is_some())?;
+
+        trace!(?usage, "initial usage");
+
+        if to_free(usage) == (0, 0) {
+            return Ok(0);
+        }
+
+        let expired = self.delete_expired(tenant, &TimestampSecs::now())?;
+        trace!(expired, "removed expired items");
+
+        let (items, bytes) = to_free(self.usage_for_limits(tenant, max_bytes.is_some())?);
+
+        if (items, bytes) == (0, 0) {
+            return Ok(expired);
+        }
+
+        let quotas = self.retention_quotas(tenant);
+        let policy = quotas
+            .as_deref()
+            .map(RetentionQuotas::policy)
+            .unwrap_or_default();
+
+        let mut removed = 0usize;
+        match (policy, quotas) {
+            // Shrinking the capacity always removes the oldest
+            (EvictionPolicy::Reject, _) if payload.is_some() => {
+                return Err(Error::with(
+                    SqliteError::QuotaExceeded,
+                    "the retention is full",
+                ));
+            }
+            (EvictionPolicy::LowestPriority, Some(quotas)) => {
+                if items > 0 {
+                    removed = self.remove_lowest_priority(tenant, items, &quotas)?;
+                }
+
+                let bytes = match max_bytes {
+                    Some(_) => to_free(self.retention_usage(tenant)?).1,
+                    None => 0,
+                };
+                if bytes > 0 {
+                    removed = removed
+                        .saturating_add(self.remove_lowest_priority_bytes(tenant, bytes, &quotas)?);
+                }
+            }
+            _ => {
+                if items > 0 {
+                    removed = self.remove_oldest(tenant, items)?;
+                }
+
+                let bytes = match max_bytes {
+                    Some(_) => to_free(self.retention_usage(tenant)?).1,
+                    None => 0,
+                };
+                if bytes > 0 {
+                    removed = removed.saturating_add(self.remove_oldest_bytes(tenant, bytes)?);
+                }
+            }
+        };
+        debug!(removed, ?policy, "removed elements");
+
+        Ok(removed.saturating_add(expired))
+    }
+
+    /// Returns the stored items and, only if needed, the size of their payloads.
+    fn usage_for_limits(
+        &self,
+        tenant: &str,
+        with_bytes: bool,
+    ) -> Result<(usize, u64), Error<SqliteError>> {
+        if with_bytes {
+            self.retention_usage(tenant)
+        } else {
+            self.count_stored(tenant).map(|count| (count, 0))
+        }
+    }
 
-        let count_stored = self.count_stored()?;
+    /// Empty space in the quota of the interface to allow storing a new publish of the given size.
+    ///
+    /// The expired publishes of the interface are removed first, then the oldest ones or the
+    /// publish is rejected if the policy is [`EvictionPolicy::Reject`].
+    ///
+    /// Return the number of removed elements.
+    #[instrument(skip(self))]
+    pub(crate) fn free_interface_quota(
+        &mut self,
+        tenant: &str,
+        interface: &str,
+        payload: Option<u64>,
+    ) -> Result<usize, Error<SqliteError>> {
+        let Some(quotas) = self.retention_quotas(tenant) else {
+            return Ok(0);
+        };
 
-        trace!(count_stored, "initial count");
+        let Some(limit) = quotas.limit(interface) else {
+            return Ok(0);
+        };
 
-        let stored = count_stored.saturating_add(to_store);
+        // Without a payload we only need to fit the current items
+        let fits = |(items, bytes): (usize, u64)| match payload {
+            Some(payload) => limit.fits(items, bytes, payload),
+            None => limit.fits(items.saturating_sub(1), bytes, 0),
+        };
 
-        if stored <= max_items {
+        if fits(self.interface_usage(tenant, interface)?) {
             return Ok(0);
         }
 
-        let expired = self.delete_expired(&TimestampSecs::now())?;
+        let expired = self.delete_expired_interface(tenant, interface, &TimestampSecs::now())?;
         trace!(expired, "removed expired items");
 
-        let stored = stored.saturating_sub(expired);
+        let (items, bytes) = self.interface_usage(tenant, interface)?;
 
-        if stored <= max_items {
+        if fits((items, bytes)) {
             return Ok(expired);
         }
 
-        let to_remove = stored.saturating_sub(max_items);
+        if payload.is_some() && quotas.policy() == EvictionPolicy::Reject {
+            return Err(
+                Error::with(SqliteError::QuotaExceeded, "the interface quota is full")
+                    .set_ctx(format!("for {interface}")),
+            );
+        }
+
+        let to_store = payload.map_or(0, |_| 1);
+
+        let removed = match limit {
+            RetentionLimit::Items(max) => {
+                let to_remove = items.saturating_add(to_store).saturating_sub(max.get());
 
-        let removed = self.remove_oldest(to_remove)?;
-        debug!(removed, "removed oldest elements");
+                self.remove_oldest_interface(tenant, interface, to_remove)?
+            }
+            RetentionLimit::Bytes(max) => {
+                let to_free = bytes
+                    .saturating_add(payload.unwrap_or(0))
+                    .saturating_sub(max.get());
+
+                self.remove_oldest_bytes_interface(tenant, interface, to_free)?
+            }
+        };
+        debug!(removed, "removed oldest elements of the interface");
 
         Ok(removed.saturating_add(expired))
     }
 
+    /// Sets the retention quotas and removes the publishes exceeding them.
+    fn apply_retention_quotas(
+        &mut self,
+        tenant: &str,
+        quotas: RetentionQuotas,
+    ) -> Result<(), Error<SqliteError>> {
+        self.set_retention_quotas(tenant, quotas);
+
+        let mut removed = 0usize;
+        for interface in statements::all_interfaces(self, tenant)? {
+            removed =
+                removed.saturating_add(self.free_interface_quota(tenant, &interface.name, None)?);
+        }
+
+        if removed > 0 {
+            self.vacuum();
+        }
+
+        Ok(())
+    }
+
     /// Sets max retention items
     fn set_max_retention_items(
         &mut self,
+        tenant: &str,
         size: std::num::NonZeroUsize,
     ) -> Result<(), Error<SqliteError>> {
-        self.retention_capacity = size;
+        self.set_retention_capacity(tenant, size);
+
+        let removed = self.free_retention_items(tenant, None)?;
+
+        if removed > 0 {
+            self.vacuum();
+        }
+
+        Ok(())
+    }
+
+    /// Sets max retention size in bytes
+    fn set_max_retention_bytes(
+        &mut self,
+        tenant: &str,
+        size: NonZeroU64,
+    ) -> Result<(), Error<SqliteError>> {
+        self.set_retention_max_bytes(tenant, size);
 
-        let removed = self.free_retention_items(0)?;
+        let removed = self.free_retention_items(tenant, None)?;
 
         if removed > 0 {
             self.vacuum();
@@ -405,13 +760,16 @@ impl WriteConnection {
 
 #[cfg(test)]
 mod tests {
-    use std::num::NonZeroUsize;
+    use std::num::{NonZeroU64, NonZeroUsize};
 
     use astarte_interfaces::interface::Retention;
+    use futures::FutureExt;
     use pretty_assertions::assert_eq;
     use statements::tests::{fetch_mapping, fetch_publish};
 
     use crate::retention::Context;
+    use crate::retention::events::{DropReason, RetentionEvent};
+    use crate::retention::inspect::RetentionKind;
 
     use super::*;
 
@@ -455,6 +813,7 @@ mod tests {
         let id = Context::new().next();
 
         let publish = RetentionPublish {
+            codec: PayloadCodec::Raw,
             id,
             interface: interface.into(),
             path: path.into(),
@@ -501,7 +860,7 @@ mod tests {
 
         let count = store
             .pool
-            .acquire_writer(|writer| writer.count_stored())
+            .acquire_writer(|writer| writer.count_stored(""))
             .await
             .unwrap();
         assert_eq!(count, 2);
@@ -511,7 +870,7 @@ mod tests {
 
         let count = store
             .pool
-            .acquire_writer(|writer| writer.count_stored())
+            .acquire_writer(|writer| writer.count_stored(""))
             .await
             .unwrap();
         assert_eq!(count, 1);
@@ -560,6 +919,7 @@ mod tests {
         let res = fetch_publish(&store, &id1).await.unwrap();
 
         let publish1 = RetentionPublish {
+            codec: PayloadCodec::Raw,
             id: id1,
             interface: interface.into(),
             path: "/path1".into(),
@@ -576,6 +936,7 @@ mod tests {
         let res = fetch_publish(&store, &id3).await.unwrap();
 
         let publish3 = RetentionPublish {
+            codec: PayloadCodec::Raw,
             id: id3,
             interface: interface.into(),
             path: "/path3".into(),
@@ -589,7 +950,7 @@ mod tests {
         //check that the store count is still 2
         let count = store
             .pool
-            .acquire_writer(|writer| writer.count_stored())
+            .acquire_writer(|writer| writer.count_stored(""))
             .await
             .unwrap();
         assert_eq!(count, 2);
@@ -639,6 +1000,7 @@ mod tests {
         let res = fetch_publish(&store, &id2).await.unwrap();
 
         let publish2 = RetentionPublish {
+            codec: PayloadCodec::Raw,
             id: id2,
             interface: interface.into(),
             path: "/path2".into(),
@@ -652,6 +1014,7 @@ mod tests {
         let res = fetch_publish(&store, &id3).await.unwrap();
 
         let publish3 = RetentionPublish {
+            codec: PayloadCodec::Raw,
             id: id3,
             interface: interface.into(),
             path: "/path3".into(),
@@ -665,7 +1028,7 @@ mod tests {
         //check that the store count is still 2
         let count = store
             .pool
-            .acquire_writer(|writer| writer.count_stored())
+            .acquire_writer(|writer| writer.count_stored(""))
             .await
             .unwrap();
         assert_eq!(count, 2);
@@ -759,4 +1122,504 @@ mod tests {
         let res = fetch_publish(&store, &id).await.unwrap();
         assert!(!res.sent);
     }
+
+    fn publish_for(interface: &'static str, value: &'static [u8]) -> PublishInfo<'static> {
+        PublishInfo::from_ref(
+            interface,
+            "/path",
+            1,
+            Reliability::Unique,
+            Retention::Stored { expiry: None },
+            false,
+            value,
+        )
+    }
+
+    async fn stored_ids(store: &SqliteStore) -> Vec<Id> {
+        let mut buf = Vec::new();
+        store.unsent_publishes(100, &mut buf).await.unwrap();
+
+        buf.into_iter().map(|(id, _)| id).collect()
+    }
+
+    #[tokio::test]
+    async fn should_evict_oldest_over_interface_quota() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        let quotas = RetentionQuotas::new().with_interface(
+            "com.Foo",
+            RetentionLimit::Items(NonZeroUsize::new(1).unwrap()),
+        );
+        store.set_retention_quotas(&quotas).await.unwrap();
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+
+        store
+            .store_publish(&ids[0], publish_for("com.Foo", &[]))
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[1], publish_for("com.Bar", &[]))
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[2], publish_for("com.Foo", &[]))
+            .await
+            .unwrap();
+
+        assert_eq!(stored_ids(&store).await, [ids[1], ids[2]]);
+    }
+
+    #[tokio::test]
+    async fn should_evict_bytes_over_interface_quota() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        let quotas = RetentionQuotas::new()
+            .with_default_limit(RetentionLimit::Bytes(NonZeroU64::new(5).unwrap()));
+        store.set_retention_quotas(&quotas).await.unwrap();
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+
+        store
+            .store_publish(&ids[0], publish_for("com.Foo", &[1, 2]))
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[1], publish_for("com.Foo", &[3, 4]))
+            .await
+            .unwrap();
+        // needs to free 2 bytes
+        store
+            .store_publish(&ids[2], publish_for("com.Foo", &[5, 6, 7]))
+            .await
+            .unwrap();
+
+        assert_eq!(stored_ids(&store).await, [ids[1], ids[2]]);
+    }
+
+    #[tokio::test]
+    async fn should_reject_over_interface_quota() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        let quotas = RetentionQuotas::new()
+            .with_interface(
+                "com.Foo",
+                RetentionLimit::Items(NonZeroUsize::new(1).unwrap()),
+            )
+            .with_policy(EvictionPolicy::Reject);
+        store.set_retention_quotas(&quotas).await.unwrap();
+
+        let ctx = Context::new();
+        let first = ctx.next();
+
+        store
+            .store_publish(&first, publish_for("com.Foo", &[]))
+            .await
+            .unwrap();
+        let err = store
+            .store_publish(&ctx.next(), publish_for("com.Foo", &[]))
+            .await
+            .unwrap_err();
+        assert_eq!(*err.kind(), RetentionError::QuotaExceeded);
+
+        assert_eq!(stored_ids(&store).await, [first]);
+    }
+
+    #[tokio::test]
+    async fn should_not_notify_rolled_back_expired() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        let sender = RetentionEventSender::new();
+        let mut events = sender.subscribe();
+        store.set_retention_events(sender).await.unwrap();
+
+        let quotas = RetentionQuotas::new()
+            .with_interface(
+                "com.Foo",
+                RetentionLimit::Bytes(NonZeroU64::new(5).unwrap()),
+            )
+            .with_policy(EvictionPolicy::Reject);
+        store.set_retention_quotas(&quotas).await.unwrap();
+
+        let ctx = Context::new();
+        let expired = ctx.next().before(Duration::from_secs(10));
+
+        store
+            .store_publish(&ctx.next(), publish_for("com.Foo", &[1, 2, 3, 4]))
+            .await
+            .unwrap();
+        store
+            .store_publish(
+                &expired,
+                PublishInfo::from_ref(
+                    "com.Foo",
+                    "/expired",
+                    1,
+                    Reliability::Unique,
+                    Retention::Stored {
+                        expiry: Some(Duration::from_secs(1)),
+                    },
+                    false,
+                    &[5],
+                ),
+            )
+            .await
+            .unwrap();
+
+        // removing the expired one isn't enough, the store is rolled back
+        let err = store
+            .store_publish(&ctx.next(), publish_for("com.Foo", &[6, 7, 8]))
+            .await
+            .unwrap_err();
+        assert_eq!(*err.kind(), RetentionError::QuotaExceeded);
+
+        assert_eq!(events.recv().now_or_never(), None);
+
+        let count = store
+            .pool
+            .acquire_writer(|writer| writer.count_stored(""))
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn should_evict_lowest_priority() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        store
+            .set_max_retention_items(NonZeroUsize::new(2).unwrap())
+            .await
+            .unwrap();
+        let quotas = RetentionQuotas::new()
+            .with_priority("com.Alarm", 10)
+            .with_policy(EvictionPolicy::LowestPriority);
+        store.set_retention_quotas(&quotas).await.unwrap();
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+
+        store
+            .store_publish(&ids[0], publish_for("com.Alarm", &[]))
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[1], publish_for("com.Foo", &[]))
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[2], publish_for("com.Foo", &[]))
+            .await
+            .unwrap();
+
+        assert_eq!(stored_ids(&store).await, [ids[0], ids[2]]);
+    }
+
+    #[tokio::test]
+    async fn should_order_unsent_by_priority() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        let quotas = RetentionQuotas::new().with_priority("com.Alarm", 10);
+        store.set_retention_quotas(&quotas).await.unwrap();
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..5).map(|_| ctx.next()).collect();
+
+        for (i, id) in ids.iter().enumerate() {
+            let interface = if i == 3 { "com.Alarm" } else { "com.Foo" };
+
+            store
+                .store_publish(id, publish_for(interface, &[]))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            stored_ids(&store).await,
+            [ids[3], ids[0], ids[1], ids[2], ids[4]]
+        );
+
+        store.set_resend_order(ResendOrder::Newest).await.unwrap();
+        assert_eq!(
+            stored_ids(&store).await,
+            [ids[3], ids[4], ids[2], ids[1], ids[0]]
+        );
+
+        store
+            .set_resend_order(ResendOrder::Interleaved)
+            .await
+            .unwrap();
+        assert_eq!(
+            stored_ids(&store).await,
+            [ids[3], ids[0], ids[4], ids[1], ids[2]]
+        );
+
+        // the limit keeps the order
+        let mut buf = Vec::new();
+        store.unsent_publishes(2, &mut buf).await.unwrap();
+        let limited: Vec<Id> = buf.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(limited, [ids[3], ids[0]]);
+    }
+
+    #[tokio::test]
+    async fn should_apply_quotas_to_stored() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+
+        for id in &ids {
+            store
+                .store_publish(id, publish_for("com.Foo", &[]))
+                .await
+                .unwrap();
+        }
+
+        let quotas = RetentionQuotas::new()
+            .with_default_limit(RetentionLimit::Items(NonZeroUsize::new(1).unwrap()));
+        store.set_retention_quotas(&quotas).await.unwrap();
+
+        assert_eq!(stored_ids(&store).await, [ids[2]]);
+    }
+
+    #[tokio::test]
+    async fn should_evict_over_max_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        store
+            .set_max_retention_bytes(NonZeroU64::new(6).unwrap())
+            .await
+            .unwrap();
+
+        let ctx = Context::new();
+        let ids: Vec<Id> = (0..4).map(|_| ctx.next()).collect();
+
+        store
+            .store_publish(&ids[0], publish_for("com.Foo", &[1, 2]))
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[1], publish_for("com.Bar", &[3, 4]))
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[2], publish_for("com.Foo", &[5, 6]))
+            .await
+            .unwrap();
+        // needs to free 3 bytes, so the two oldest
+        store
+            .store_publish(&ids[3], publish_for("com.Foo", &[7, 8, 9]))
+            .await
+            .unwrap();
+
+        assert_eq!(stored_ids(&store).await, [ids[2], ids[3]]);
+
+        // shrinking
+        store
+            .set_max_retention_bytes(NonZeroU64::new(3).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(stored_ids(&store).await, [ids[3]]);
+    }
+
+    #[tokio::test]
+    async fn should_compress_stored_payloads() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .set_retention_compression(RetentionCompression::Zlib)
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        let payload: &'static [u8] = &[42; 256];
+        let id = Context::new().next();
+
+        store
+            .store_publish(&id, publish_for("com.Foo", payload))
+            .await
+            .unwrap();
+
+        let stored = fetch_publish(&store, &id).await.unwrap();
+        assert_eq!(stored.codec, PayloadCodec::Zlib);
+        assert!(stored.payload.len() < payload.len());
+
+        let mut buf = Vec::new();
+        store.unsent_publishes(10, &mut buf).await.unwrap();
+
+        assert_eq!(buf.len(), 1);
+        assert_eq!(buf[0].0, id);
+        assert_eq!(*buf[0].1.value, *payload);
+    }
+
+    #[tokio::test]
+    async fn should_inspect_and_purge() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        let ctx = Context::new();
+        let old = ctx.next().before(Duration::from_secs(120));
+        let ids: Vec<Id> = (0..2).map(|_| ctx.next()).collect();
+
+        store
+            .store_publish(&old, publish_for("com.Foo", &[1, 2]))
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[0], publish_for("com.Bar", &[3]))
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[1], publish_for("com.Foo", &[4, 5, 6]))
+            .await
+            .unwrap();
+
+        let all = RetentionFilter::new();
+        assert_eq!(store.count_publishes(&all).await.unwrap(), 3);
+
+        let foo = RetentionFilter::new().with_interface("com.Foo");
+        let listed = store.list_publishes(&foo, 10).await.unwrap();
+        let listed: Vec<_> = listed.iter().map(|p| (p.id, p.size)).collect();
+        assert_eq!(listed, [(old, 2), (ids[1], 3)]);
+
+        let first = store.list_publishes(&all, 1).await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].id, old);
+        assert_eq!(first[0].interface, "com.Foo");
+        assert_eq!(first[0].path, "/path");
+
+        let old_filter = RetentionFilter::new().older_than(Duration::from_secs(60));
+        assert_eq!(store.count_publishes(&old_filter).await.unwrap(), 1);
+        assert_eq!(store.purge_publishes(&old_filter).await.unwrap(), 1);
+        assert_eq!(stored_ids(&store).await, ids);
+
+        let bar = RetentionFilter::new().with_interface("com.Bar");
+        assert_eq!(store.expire_publishes(&bar).await.unwrap(), 1);
+        assert_eq!(stored_ids(&store).await, [ids[1]]);
+        // already removed by the expiry
+        assert_eq!(store.count_publishes(&bar).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn should_notify_removed() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let store = SqliteStore::options()
+            .with_writable_dir(dir.path())
+            .await
+            .unwrap();
+
+        let sender = RetentionEventSender::new();
+        let mut events = sender.subscribe();
+        store.set_retention_events(sender).await.unwrap();
+
+        store
+            .set_max_retention_items(NonZeroUsize::new(2).unwrap())
+            .await
+            .unwrap();
+
+        let ctx = Context::new();
+        let expired = ctx.next().before(Duration::from_secs(10));
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+
+        store
+            .store_publish(
+                &expired,
+                PublishInfo::from_ref(
+                    "com.Bar",
+                    "/expired",
+                    1,
+                    Reliability::Unique,
+                    Retention::Stored {
+                        expiry: Some(Duration::from_secs(1)),
+                    },
+                    false,
+                    &[1],
+                ),
+            )
+            .await
+            .unwrap();
+        store
+            .store_publish(&ids[0], publish_for("com.Foo", &[2]))
+            .await
+            .unwrap();
+        // the expired is removed first
+        store
+            .store_publish(&ids[1], publish_for("com.Foo", &[3]))
+            .await
+            .unwrap();
+        // then the oldest
+        store
+            .store_publish(&ids[2], publish_for("com.Bar", &[4]))
+            .await
+            .unwrap();
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(
+            event,
+            RetentionEvent::new(
+                expired,
+                RetentionKind::Stored,
+                "com.Bar",
+                "/expired",
+                DropReason::Expired
+            )
+        );
+        let event = events.recv().await.unwrap();
+        assert_eq!((event.id, event.reason), (ids[0], DropReason::Evicted));
+
+        store.delete_interface("com.Foo").await.unwrap();
+        let event = events.recv().await.unwrap();
+        assert_eq!(
+            (event.id, event.reason),
+            (ids[1], DropReason::InterfaceRemoved)
+        );
+    }
 }
@@ -16,48 +16,62 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{borrow::Cow, collections::HashSet, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use astarte_device_error::{Error, WrapError};
-use rusqlite::{Connection, OptionalExtension, Transaction};
+use rusqlite::{Connection, OptionalExtension, Params, Row, Statement, Transaction, types::Type};
 use tracing::{debug, instrument, trace, warn};
 
+use crate::retention::events::{DropReason, RetentionEvent};
+use crate::retention::inspect::{PendingPublish, RetentionFilter, RetentionKind};
+use crate::retention::order::ResendOrder;
+use crate::retention::quota::{DEFAULT_INTERFACE_PRIORITY, RetentionQuotas};
 use crate::retention::{Id, PublishInfo, StoredInterface};
-use crate::store::sqlite::connection::{ReadConnection, WriteConnection};
+use crate::store::sqlite::connection::{ReadConnection, TenantEvents, WriteConnection};
 use crate::store::sqlite::error::SqliteError;
 use crate::store::sqlite::statements::include_query;
 
+use super::codec::PayloadCodec;
 use super::{RetentionMapping, RetentionPublish, RetentionReliability, TimestampSecs};
 
 impl WriteConnection {
     #[instrument(skip_all)]
     pub(super) fn store(
         &mut self,
+        tenant: &str,
         mapping: &RetentionMapping<'_>,
         publish: &RetentionPublish<'_>,
     ) -> Result<(), Error<SqliteError>> {
-        let exists = read_mapping(self, &mapping.interface, &mapping.path)?.is_some_and(|stored| {
-            if stored != *mapping {
-                warn!("mappings differ, replacing");
+        let exists =
+            read_mapping(self, tenant, &mapping.interface, &mapping.path)?.is_some_and(|stored| {
+                if stored != *mapping {
+                    warn!("mappings differ, replacing");
 
-                false
-            } else {
-                trace!("mapping already exists");
+                    false
+                } else {
+                    trace!("mapping already exists");
 
-                true
-            }
-        });
+                    true
+                }
+            });
 
-        self.free_retention_items(1)?;
+        let size = u64::try_from(publish.payload.len()).unwrap_or(u64::MAX);
+        self.free_interface_quota(tenant, &mapping.interface, Some(size))?;
+        self.free_retention_items(tenant, Some(size))?;
 
-        let transaction = self.transaction().wrap_err(SqliteError::Transaction)?;
+        // use a savepoint since the store could be part of a batch transaction
+        let transaction = self.savepoint().wrap_err(SqliteError::Transaction)?;
 
         if !exists {
-            Self::store_mapping(&transaction, mapping)?;
+            Self::store_mapping(&transaction, tenant, mapping)?;
             trace!("mapping stored");
         }
 
-        Self::store_publish(&transaction, publish)?;
+        Self::store_publish(&transaction, tenant, publish)?;
         trace!("publish stored");
 
         transaction.commit().wrap_err(SqliteError::Transaction)?;
@@ -67,7 +81,8 @@ impl WriteConnection {
 
     #[instrument(skip_all)]
     pub(super) fn store_mapping(
-        transaction: &Transaction<'_>,
+        transaction: &Connection,
+        tenant: &str,
         mapping: &RetentionMapping<'_>,
     ) -> Result<(), Error<SqliteError>> {
         let mut statement = transaction
@@ -78,6 +93,7 @@ impl WriteConnection {
 
         statement
             .execute((
+                tenant,
                 &mapping.interface,
                 &mapping.path,
                 mapping.version_major,
@@ -91,7 +107,8 @@ impl WriteConnection {
 
     #[instrument(skip_all)]
     pub(super) fn store_publish(
-        transaction: &Transaction<'_>,
+        transaction: &Connection,
+        tenant: &str,
         publish: &RetentionPublish<'_>,
     ) -> Result<(), Error<SqliteError>> {
         let mut statement = transaction
@@ -107,6 +124,7 @@ impl WriteConnection {
 
         statement
             .execute((
+                tenant,
                 timestamp,
                 counter,
                 &publish.interface,
@@ -114,6 +132,7 @@ impl WriteConnection {
                 expiry,
                 publish.sent,
                 &publish.payload,
+                publish.codec,
             ))
             .wrap_err(SqliteError::Query)?;
 
@@ -121,20 +140,24 @@ impl WriteConnection {
     }
 
     /// Retrieve the number of stored properties
-    pub(crate) fn count_stored(&self) -> Result<usize, Error<SqliteError>> {
+    pub(crate) fn count_stored(&self, tenant: &str) -> Result<usize, Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!("queries/retention/read/count_stored.sql"))
             .wrap_err(SqliteError::Prepare)?;
 
         statement
-            .query_row((), |row| row.get::<_, i64>(0))
+            .query_row([tenant], |row| row.get::<_, i64>(0))
             .wrap_err(SqliteError::Query)
             // count is positive
             .map(|value| value as usize)
     }
 
     /// Remove the N oldest elements from the store
-    pub(crate) fn remove_oldest(&self, to_remove: usize) -> Result<usize, Error<SqliteError>> {
+    pub(crate) fn remove_oldest(
+        &self,
+        tenant: &str,
+        to_remove: usize,
+    ) -> Result<usize, Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!(
                 "queries/retention/write/delete_n_oldest.sql"
@@ -147,11 +170,211 @@ impl WriteConnection {
             i64::MAX
         });
 
-        statement.execute([to_remove]).wrap_err(SqliteError::Query)
+        let events = self.retention_events(tenant);
+
+        execute_removed(
+            &mut statement,
+            (tenant, to_remove),
+            events.as_ref(),
+            DropReason::Evicted,
+        )
+    }
+
+    /// Remove the N oldest elements, starting from the interfaces with the lowest priority.
+    pub(super) fn remove_lowest_priority(
+        &self,
+        tenant: &str,
+        to_remove: usize,
+        quotas: &RetentionQuotas,
+    ) -> Result<usize, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/retention/write/delete_n_lowest_priority.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        let to_remove = i64::try_from(to_remove).unwrap_or_else(|_| {
+            warn!("removing only the last i64::MAX elements");
+
+            i64::MAX
+        });
+
+        let priorities = priorities_json(quotas)?;
+
+        let events = self.retention_events(tenant);
+
+        execute_removed(
+            &mut statement,
+            (tenant, to_remove, priorities, DEFAULT_INTERFACE_PRIORITY),
+            events.as_ref(),
+            DropReason::Evicted,
+        )
+    }
+
+    /// Remove the oldest publishes, starting from the interfaces with the lowest priority, until
+    /// at least the given bytes are freed.
+    pub(super) fn remove_lowest_priority_bytes(
+        &self,
+        tenant: &str,
+        to_free: u64,
+        quotas: &RetentionQuotas,
+    ) -> Result<usize, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/retention/write/delete_lowest_priority_bytes.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        let to_free = i64::try_from(to_free).unwrap_or(i64::MAX);
+        let priorities = priorities_json(quotas)?;
+
+        let events = self.retention_events(tenant);
+
+        execute_removed(
+            &mut statement,
+            (tenant, to_free, priorities, DEFAULT_INTERFACE_PRIORITY),
+            events.as_ref(),
+            DropReason::Evicted,
+        )
+    }
+
+    /// Remove the oldest publishes until at least the given bytes are freed.
+    pub(super) fn remove_oldest_bytes(
+        &self,
+        tenant: &str,
+        to_free: u64,
+    ) -> Result<usize, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/retention/write/delete_oldest_bytes.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        let to_free = i64::try_from(to_free).unwrap_or(i64::MAX);
+
+        let events = self.retention_events(tenant);
+
+        execute_removed(
+            &mut statement,
+            (tenant, to_free),
+            events.as_ref(),
+            DropReason::Evicted,
+        )
+    }
+
+    /// Retrieve the number of stored publishes and the size of their payloads.
+    pub(super) fn retention_usage(&self, tenant: &str) -> Result<(usize, u64), Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!("queries/retention/read/usage.sql"))
+            .wrap_err(SqliteError::Prepare)?;
+
+        statement
+            .query_row([tenant], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+            })
+            .wrap_err(SqliteError::Query)
+            // count and size are positive
+            .map(|(count, size)| (count as usize, size as u64))
+    }
+
+    /// Retrieve the number of stored publishes and the size of their payloads for an interface.
+    pub(super) fn interface_usage(
+        &self,
+        tenant: &str,
+        interface: &str,
+    ) -> Result<(usize, u64), Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!("queries/retention/read/interface_usage.sql"))
+            .wrap_err(SqliteError::Prepare)?;
+
+        statement
+            .query_row([tenant, interface], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+            })
+            .wrap_err(SqliteError::Query)
+            // count and size are positive
+            .map(|(count, size)| (count as usize, size as u64))
+    }
+
+    /// Deletes the expired publishes of an interface.
+    pub(super) fn delete_expired_interface(
+        &self,
+        tenant: &str,
+        interface: &str,
+        now: &TimestampSecs,
+    ) -> Result<usize, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/retention/write/delete_expired_interface.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        let timestamp = now.to_bytes();
+        let timestamp = timestamp.as_slice();
+
+        let events = self.retention_events(tenant);
+
+        execute_removed(
+            &mut statement,
+            (tenant, interface, timestamp),
+            events.as_ref(),
+            DropReason::Expired,
+        )
+    }
+
+    /// Remove the N oldest publishes of an interface.
+    pub(super) fn remove_oldest_interface(
+        &self,
+        tenant: &str,
+        interface: &str,
+        to_remove: usize,
+    ) -> Result<usize, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/retention/write/delete_n_oldest_interface.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        let to_remove = i64::try_from(to_remove).unwrap_or(i64::MAX);
+
+        let events = self.retention_events(tenant);
+
+        execute_removed(
+            &mut statement,
+            (tenant, interface, to_remove),
+            events.as_ref(),
+            DropReason::Evicted,
+        )
+    }
+
+    /// Remove the oldest publishes of an interface, until at least the given bytes are freed.
+    pub(super) fn remove_oldest_bytes_interface(
+        &self,
+        tenant: &str,
+        interface: &str,
+        to_free: u64,
+    ) -> Result<usize, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/retention/write/delete_oldest_bytes_interface.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        let to_free = i64::try_from(to_free).unwrap_or(i64::MAX);
+
+        let events = self.retention_events(tenant);
+
+        execute_removed(
+            &mut statement,
+            (tenant, interface, to_free),
+            events.as_ref(),
+            DropReason::Evicted,
+        )
     }
 
     pub(super) fn update_publish_sent_flag(
         &self,
+        tenant: &str,
         id: &Id,
         sent: bool,
     ) -> Result<(), Error<SqliteError>> {
@@ -163,7 +386,7 @@ impl WriteConnection {
         let timestamp = timestamp.as_slice();
 
         let changed = statement
-            .execute((sent, timestamp, id.counter))
+            .execute((tenant, sent, timestamp, id.counter))
             .wrap_err(SqliteError::Query)?;
 
         // If we remove an interface before the ACK is received the publish will also be deleted
@@ -172,7 +395,11 @@ impl WriteConnection {
         Ok(())
     }
 
-    pub(super) fn delete_publish_by_id(&self, id: &Id) -> Result<(), Error<SqliteError>> {
+    pub(super) fn delete_publish_by_id(
+        &self,
+        tenant: &str,
+        id: &Id,
+    ) -> Result<(), Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!("queries/retention/write/delete_publish.sql"))
             .wrap_err(SqliteError::Prepare)?;
@@ -181,7 +408,7 @@ impl WriteConnection {
         let timestamp = timestamp.as_slice();
 
         let changed = statement
-            .execute((timestamp, id.counter))
+            .execute((tenant, timestamp, id.counter))
             .wrap_err(SqliteError::Query)?;
 
         if changed == 0 {
@@ -194,19 +421,42 @@ impl WriteConnection {
         Ok(())
     }
 
-    pub(super) fn delete_interface(&mut self, interface: &str) -> Result<(), Error<SqliteError>> {
-        let transaction = self.transaction().wrap_err(SqliteError::Transaction)?;
-
-        Self::delete_interface_transaction(&transaction, interface)?;
+    pub(super) fn delete_interface(
+        &mut self,
+        tenant: &str,
+        interface: &str,
+    ) -> Result<(), Error<SqliteError>> {
+        let events = self.retention_events(tenant);
+        let pending = self.pending_events().clone();
+        let queued = pending.len();
+
+        let res = self
+            .transaction()
+            .wrap_err(SqliteError::Transaction)
+            .and_then(|transaction| {
+                Self::delete_interface_transaction(
+                    &transaction,
+                    tenant,
+                    interface,
+                    events.as_ref(),
+                )?;
+
+                transaction.commit().wrap_err(SqliteError::Transaction)
+            });
 
-        transaction.commit().wrap_err(SqliteError::Transaction)?;
+        // The transaction was rolled back
+        if res.is_err() {
+            pending.truncate(queued);
+        }
 
-        Ok(())
+        res
     }
 
     fn delete_interface_transaction(
         transaction: &Transaction,
+        tenant: &str,
         interface: &str,
+        events: Option<&TenantEvents>,
     ) -> Result<(), Error<SqliteError>> {
         // Delete publishes
         let mut statement = transaction
@@ -215,9 +465,12 @@ impl WriteConnection {
             ))
             .wrap_err(SqliteError::Prepare)?;
 
-        statement
-            .execute([interface])
-            .wrap_err(SqliteError::Query)?;
+        execute_removed(
+            &mut statement,
+            [tenant, interface],
+            events,
+            DropReason::InterfaceRemoved,
+        )?;
 
         // Delete mappings
         let mut statement = transaction
@@ -227,13 +480,17 @@ impl WriteConnection {
             .wrap_err(SqliteError::Prepare)?;
 
         statement
-            .execute([interface])
+            .execute([tenant, interface])
             .wrap_err(SqliteError::Query)?;
 
         Ok(())
     }
 
-    pub(super) fn delete_expired(&self, now: &TimestampSecs) -> Result<usize, Error<SqliteError>> {
+    pub(super) fn delete_expired(
+        &self,
+        tenant: &str,
+        now: &TimestampSecs,
+    ) -> Result<usize, Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!("queries/retention/write/delete_expired.sql"))
             .wrap_err(SqliteError::Prepare)?;
@@ -241,51 +498,192 @@ impl WriteConnection {
         let timestamp = now.to_bytes();
         let timestamp = timestamp.as_slice();
 
-        let deleted = statement
-            .execute([timestamp])
-            .wrap_err(SqliteError::Query)?;
+        let events = self.retention_events(tenant);
+
+        let deleted = execute_removed(
+            &mut statement,
+            (tenant, timestamp),
+            events.as_ref(),
+            DropReason::Expired,
+        )?;
 
         debug!(deleted, "deleted expired records");
 
         Ok(deleted)
     }
 
-    pub(super) fn reset_all_sent(&self) -> Result<(), Error<SqliteError>> {
+    pub(super) fn reset_all_sent(&self, tenant: &str) -> Result<(), Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!("queries/retention/write/reset_all_sent.sql"))
             .wrap_err(SqliteError::Prepare)?;
 
-        statement.execute([]).wrap_err(SqliteError::Query)?;
+        statement.execute([tenant]).wrap_err(SqliteError::Query)?;
+
+        Ok(())
+    }
+
+    /// Deletes the publishes matching the filter.
+    pub(super) fn delete_filtered(
+        &self,
+        tenant: &str,
+        filter: &RetentionFilter,
+    ) -> Result<usize, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/retention/write/delete_filtered.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        let filter = FilterParams::new(filter);
+
+        let deleted = statement
+            .execute((
+                tenant,
+                filter.interface,
+                filter.path,
+                filter.since(),
+                filter.until(),
+            ))
+            .wrap_err(SqliteError::Query)?;
+
+        debug!(deleted, "deleted filtered records");
+
+        Ok(deleted)
+    }
+
+    /// Sets the expiry of the publishes matching the filter, if they expire later.
+    pub(super) fn expire_filtered(
+        &self,
+        tenant: &str,
+        filter: &RetentionFilter,
+        expiry: &TimestampSecs,
+    ) -> Result<usize, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!(
+                "queries/retention/write/expire_filtered.sql"
+            ))
+            .wrap_err(SqliteError::Prepare)?;
+
+        let filter = FilterParams::new(filter);
+        let expiry = expiry.to_bytes();
+
+        let expired = statement
+            .execute((
+                tenant,
+                filter.interface,
+                filter.path,
+                filter.since(),
+                filter.until(),
+                expiry.as_slice(),
+            ))
+            .wrap_err(SqliteError::Query)?;
+
+        debug!(expired, "expired filtered records");
+
+        Ok(expired)
+    }
+
+    /// Deletes all the publishes and mappings of the tenant.
+    pub(crate) fn clear_retention(&self, tenant: &str) -> Result<(), Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!("queries/retention/write/clear.sql"))
+            .wrap_err(SqliteError::Prepare)?;
+
+        statement.execute([tenant]).wrap_err(SqliteError::Query)?;
 
         Ok(())
     }
 }
 
 impl ReadConnection {
-    pub(super) fn all_interfaces(&self) -> Result<HashSet<StoredInterface>, Error<SqliteError>> {
+    pub(super) fn count_filtered(
+        &self,
+        tenant: &str,
+        filter: &RetentionFilter,
+    ) -> Result<usize, Error<SqliteError>> {
         let mut statement = self
-            .prepare_cached(include_query!("queries/retention/read/all_interfaces.sql"))
+            .prepare_cached(include_query!("queries/retention/read/count_filtered.sql"))
             .wrap_err(SqliteError::Prepare)?;
 
-        let interfaces = statement
-            .query_map([], |row| {
-                Ok(StoredInterface {
-                    name: row.get(0)?,
-                    version_major: row.get(1)?,
-                })
-            })
+        let filter = FilterParams::new(filter);
+
+        statement
+            .query_row(
+                (
+                    tenant,
+                    filter.interface,
+                    filter.path,
+                    filter.since(),
+                    filter.until(),
+                ),
+                |row| row.get::<_, i64>(0),
+            )
+            .wrap_err(SqliteError::Query)
+            // count is positive
+            .map(|value| value as usize)
+    }
+
+    pub(super) fn list_publishes(
+        &self,
+        tenant: &str,
+        filter: &RetentionFilter,
+        limit: usize,
+    ) -> Result<Vec<PendingPublish>, Error<SqliteError>> {
+        let mut statement = self
+            .prepare_cached(include_query!("queries/retention/read/list_publishes.sql"))
+            .wrap_err(SqliteError::Prepare)?;
+
+        let filter = FilterParams::new(filter);
+
+        // Cap to max
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+
+        statement
+            .query_map(
+                (
+                    tenant,
+                    filter.interface,
+                    filter.path,
+                    filter.since(),
+                    filter.until(),
+                    limit,
+                ),
+                |row| {
+                    Ok(PendingPublish {
+                        id: Id {
+                            timestamp: row.get(0)?,
+                            counter: row.get(1)?,
+                        },
+                        kind: RetentionKind::Stored,
+                        interface: row.get(2)?,
+                        path: row.get(3)?,
+                        sent: row.get(4)?,
+                        // length is positive
+                        size: row.get::<_, i64>(5)? as usize,
+                        version_major: row.get(6)?,
+                        expiry: expiry_from_sql(row.get(7)?),
+                    })
+                },
+            )
             .wrap_err(SqliteError::Query)?
-            .collect::<Result<HashSet<StoredInterface>, rusqlite::Error>>()
-            .wrap_err(SqliteError::Query)?;
+            .collect::<Result<Vec<_>, rusqlite::Error>>()
+            .wrap_err(SqliteError::Query)
+    }
 
-        Ok(interfaces)
+    pub(super) fn all_interfaces(
+        &self,
+        tenant: &str,
+    ) -> Result<HashSet<StoredInterface>, Error<SqliteError>> {
+        all_interfaces(self, tenant)
     }
 
     pub(super) fn unsent_publishes(
         &self,
+        tenant: &str,
         buf: &mut Vec<(Id, PublishInfo<'static>)>,
         now: &TimestampSecs,
         limit: usize,
+        order: &UnsentOrder,
     ) -> Result<usize, Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!(
@@ -299,20 +697,34 @@ impl ReadConnection {
         // Cap to max
         let limit = i64::try_from(limit).unwrap_or(i64::MAX);
 
+        let params = (
+            tenant,
+            now,
+            limit,
+            order.priorities.as_str(),
+            DEFAULT_INTERFACE_PRIORITY,
+            order.order.to_sql(),
+        );
+
         let (count, _) = statement
-            .query_map((now, limit), |row| {
+            .query_map(params, |row| {
                 let id = Id {
                     timestamp: row.get(0)?,
                     counter: row.get(1)?,
                 };
 
+                let codec: PayloadCodec = row.get(9)?;
+                let value = codec.decode(row.get(5)?).map_err(|err| {
+                    rusqlite::Error::FromSqlConversionFailure(5, Type::Blob, Box::new(err))
+                })?;
+
                 Ok((
                     id,
                     PublishInfo {
                         interface: Cow::Owned(row.get(2)?),
                         path: Cow::Owned(row.get(3)?),
                         sent: row.get(4)?,
-                        value: Cow::Owned(row.get(5)?),
+                        value: Cow::Owned(value),
                         reliability: row.get::<_, RetentionReliability>(6)?.into(),
                         version_major: row.get(7)?,
                         expiry: expiry_from_sql(row.get(8)?),
@@ -332,8 +744,132 @@ impl ReadConnection {
     }
 }
 
+/// Executes a statement deleting publishes and returning them, notifying each one with the reason.
+///
+/// Returns the number of deleted publishes.
+fn execute_removed<P>(
+    statement: &mut Statement<'_>,
+    params: P,
+    events: Option<&TenantEvents>,
+    reason: DropReason,
+) -> Result<usize, Error<SqliteError>>
+where
+    P: Params,
+{
+    let mut rows = statement.query(params).wrap_err(SqliteError::Query)?;
+
+    let mut count = 0usize;
+    while let Some(row) = rows.next().wrap_err(SqliteError::Query)? {
+        count = count.saturating_add(1);
+
+        let Some(events) = events else {
+            continue;
+        };
+
+        let event = removed_event(row, reason).wrap_err(SqliteError::Query)?;
+
+        events.send(event);
+    }
+
+    Ok(count)
+}
+
+/// Reads a publish returned by a delete statement.
+fn removed_event(row: &Row<'_>, reason: DropReason) -> rusqlite::Result<RetentionEvent> {
+    let id = Id {
+        timestamp: row.get(0)?,
+        counter: row.get(1)?,
+    };
+
+    Ok(RetentionEvent::new(
+        id,
+        RetentionKind::Stored,
+        row.get::<_, String>(2)?,
+        row.get::<_, String>(3)?,
+        reason,
+    ))
+}
+
+/// Parameters of a [`RetentionFilter`] bound to the queries.
+struct FilterParams<'a> {
+    interface: Option<&'a str>,
+    path: Option<&'a str>,
+    since: Option<[u8; 16]>,
+    until: Option<[u8; 16]>,
+}
+
+impl<'a> FilterParams<'a> {
+    fn new(filter: &'a RetentionFilter) -> Self {
+        Self {
+            interface: filter.interface(),
+            path: filter.path(),
+            since: filter.since().map(|t| t.to_bytes()),
+            until: filter.until().map(|t| t.to_bytes()),
+        }
+    }
+
+    fn since(&self) -> Option<&[u8]> {
+        self.since.as_ref().map(|t| t.as_slice())
+    }
+
+    fn until(&self) -> Option<&[u8]> {
+        self.until.as_ref().map(|t| t.as_slice())
+    }
+}
+
+/// Order of the unsent publishes of a tenant.
+#[derive(Debug, Clone, Default)]
+pub(super) struct UnsentOrder {
+    /// Priorities of the interfaces, see [`priorities_json`].
+    pub(super) priorities: String,
+    pub(super) order: ResendOrder,
+}
+
+impl UnsentOrder {
+    pub(super) fn new(
+        quotas: Option<&RetentionQuotas>,
+        order: ResendOrder,
+    ) -> Result<Self, Error<SqliteError>> {
+        let priorities = match quotas {
+            Some(quotas) => priorities_json(quotas)?,
+            None => "{}".to_string(),
+        };
+
+        Ok(Self { priorities, order })
+    }
+}
+
+/// Serializes the interfaces priorities to a JSON object, used with `json_each` in the queries.
+fn priorities_json(quotas: &RetentionQuotas) -> Result<String, Error<SqliteError>> {
+    serde_json::to_string(&quotas.priorities().collect::<HashMap<_, _>>())
+        .wrap_err(SqliteError::Conversion)
+}
+
+pub(super) fn all_interfaces(
+    connection: &Connection,
+    tenant: &str,
+) -> Result<HashSet<StoredInterface>, Error<SqliteError>> {
+    let mut statement = connection
+        .prepare_cached(include_query!("queries/retention/read/all_interfaces.sql"))
+        .wrap_err(SqliteError::Prepare)?;
+
+    let interfaces = statement
+        .query_map([tenant], |row| {
+            Ok(StoredInterface {
+                name: row.get(0)?,
+                version_major: row.get(1)?,
+            })
+        })
+        .wrap_err(SqliteError::Query)?
+        .collect::<Result<HashSet<StoredInterface>, rusqlite::Error>>()
+        .wrap_err(SqliteError::Query)?;
+
+    Ok(interfaces)
+}
+
 fn read_mapping(
     connection: &Connection,
+    tenant: &str,
     interface: &str,
     path: &str,
 ) -> Result<Option<RetentionMapping<'static>>, Error<SqliteError>> {
@@ -342,7 +878,7 @@ fn read_mapping(
         .wrap_err(SqliteError::Prepare)?;
 
     statement
-        .query_row([interface, path], |row| {
+        .query_row([tenant, interface, path], |row| {
             let expiry: Option<i64> = row.get(4)?;
             let expiry = expiry.and_then(|exp| {
                 // If the conversion fails, let's keep the packet forever.
@@ -398,7 +934,7 @@ pub(crate) mod tests {
                 .wrap_err(SqliteError::Prepare)?;
 
             statement
-                .query_row((timestamp, id.counter), |row| {
+                .query_row(("", timestamp, id.counter), |row| {
                     let id = Id {
                         timestamp: row.get(0)?,
                         counter: row.get(1)?,
@@ -411,6 +947,7 @@ pub(crate) mod tests {
                         expiry_time: row.get(4)?,
                         sent: row.get(5)?,
                         payload: Cow::Owned(row.get(6)?),
+                        codec: row.get(7)?,
                     })
                 })
                 .optional()
@@ -425,7 +962,7 @@ pub(crate) mod tests {
             .acquire_writer(move |writer| -> Result<_, Error<SqliteError>> {
                 let t = writer.transaction().wrap_err(SqliteError::Transaction)?;
 
-                WriteConnection::store_mapping(&t, &mapping)?;
+                WriteConnection::store_mapping(&t, "", &mapping)?;
 
                 t.commit().wrap_err(SqliteError::Transaction)?;
 
@@ -442,7 +979,7 @@ pub(crate) mod tests {
             .acquire_writer(move |writer| -> Result<_, Error<SqliteError>> {
                 let t = writer.transaction().wrap_err(SqliteError::Transaction)?;
 
-                WriteConnection::store_publish(&t, &publish).unwrap();
+                WriteConnection::store_publish(&t, "", &publish).unwrap();
 
                 t.commit().wrap_err(SqliteError::Transaction)?;
 
@@ -473,7 +1010,7 @@ pub(crate) mod tests {
         let path = path.to_string();
         store
             .pool
-            .acquire_reader(move |reader| read_mapping(reader, &interface, &path))
+            .acquire_reader(move |reader| read_mapping(reader, "", &interface, &path))
             .await
             .unwrap()
     }
@@ -527,7 +1064,7 @@ pub(crate) mod tests {
             .pool
             .acquire_reader({
                 let mapping = mapping.clone();
-                move |reader| read_mapping(reader, &mapping.interface, &mapping.path)
+                move |reader| read_mapping(reader, "", &mapping.interface, &mapping.path)
             })
             .await
             .unwrap()
@@ -543,7 +1080,7 @@ pub(crate) mod tests {
             .pool
             .acquire_reader({
                 let mapping = mapping.clone();
-                move |reader| read_mapping(reader, &mapping.interface, &mapping.path)
+                move |reader| read_mapping(reader, "", &mapping.interface, &mapping.path)
             })
             .await
             .unwrap()
@@ -576,7 +1113,7 @@ pub(crate) mod tests {
             .acquire_reader({
                 let mapping = mapping.clone();
 
-                move |reader| read_mapping(reader, &mapping.interface, &mapping.path)
+                move |reader| read_mapping(reader, "", &mapping.interface, &mapping.path)
             })
             .await
             .unwrap()
@@ -619,6 +1156,7 @@ pub(crate) mod tests {
             interface: interface.into(),
             path: path.into(),
             payload: [].as_slice().into(),
+            codec: PayloadCodec::Raw,
             sent: false,
             expiry_time: Some(expiry_time),
         };
@@ -656,7 +1194,7 @@ pub(crate) mod tests {
             .acquire_reader({
                 let mapping = mapping.clone();
 
-                move |reader| read_mapping(reader, &mapping.interface, &mapping.path)
+                move |reader| read_mapping(reader, "", &mapping.interface, &mapping.path)
             })
             .await
             .unwrap()
@@ -674,6 +1212,7 @@ pub(crate) mod tests {
             interface: interface.into(),
             path: path.into(),
             payload: [].as_slice().into(),
+            codec: PayloadCodec::Raw,
             sent: false,
             expiry_time: None,
         };
@@ -684,7 +1223,7 @@ pub(crate) mod tests {
                 let mapping = mapping.clone();
                 let publish = publish.clone();
 
-                move |writer| writer.store(&mapping, &publish)
+                move |writer| writer.store("", &mapping, &publish)
             })
             .await
             .unwrap();
@@ -698,7 +1237,7 @@ pub(crate) mod tests {
             .acquire_reader({
                 let mapping = mapping.clone();
 
-                move |reader| read_mapping(reader, &mapping.interface, &mapping.path)
+                move |reader| read_mapping(reader, "", &mapping.interface, &mapping.path)
             })
             .await
             .unwrap()
@@ -737,6 +1276,7 @@ pub(crate) mod tests {
             interface: interface.into(),
             path: path.into(),
             payload: [].as_slice().into(),
+            codec: PayloadCodec::Raw,
             sent: false,
             expiry_time: None,
         };
@@ -791,6 +1331,7 @@ pub(crate) mod tests {
             expiry_time: None,
             sent: false,
             payload: [].as_slice().into(),
+            codec: PayloadCodec::Raw,
         };
 
         store_publish(&store, &exp).await;
@@ -834,6 +1375,7 @@ pub(crate) mod tests {
             expiry_time: None,
             sent: false,
             payload: [].as_slice().into(),
+            codec: PayloadCodec::Raw,
         };
 
         store_publish(&store, &exp).await;
@@ -849,7 +1391,7 @@ pub(crate) mod tests {
             .acquire_reader({
                 let mapping = mapping.clone();
 
-                move |reader| read_mapping(reader, &mapping.interface, &mapping.path)
+                move |reader| read_mapping(reader, "", &mapping.interface, &mapping.path)
             })
             .await
             .unwrap();
@@ -890,6 +1432,7 @@ pub(crate) mod tests {
                 expiry_time: None,
                 sent: false,
                 payload: [].as_slice().into(),
+                codec: PayloadCodec::Raw,
             },
             RetentionPublish {
                 id: Id {
@@ -901,6 +1444,7 @@ pub(crate) mod tests {
                 expiry_time: None,
                 sent: false,
                 payload: [].as_slice().into(),
+                codec: PayloadCodec::Raw,
             },
             RetentionPublish {
                 id: Id {
@@ -912,6 +1456,7 @@ pub(crate) mod tests {
                 expiry_time: None,
                 sent: false,
                 payload: [].as_slice().into(),
+                codec: PayloadCodec::Raw,
             },
         ];
 
@@ -976,6 +1521,7 @@ pub(crate) mod tests {
             expiry_time: None,
             sent: false,
             payload: [].as_slice().into(),
+            codec: PayloadCodec::Raw,
         };
         store_publish(&store, &publish).await;
 
@@ -1205,7 +1751,7 @@ pub(crate) mod tests {
 
         let res = store
             .pool
-            .acquire_reader(|reader| reader.all_interfaces())
+            .acquire_reader(|reader| reader.all_interfaces(""))
             .await
             .unwrap();
 
@@ -1239,7 +1785,7 @@ pub(crate) mod tests {
 
         let count = store
             .pool
-            .acquire_writer(|writer| writer.count_stored())
+            .acquire_writer(|writer| writer.count_stored(""))
             .await
             .unwrap();
 
@@ -1279,10 +1825,10 @@ pub(crate) mod tests {
         store
             .pool
             .acquire_writer(|writer| -> Result<_, Error<SqliteError>> {
-                let removed = writer.remove_oldest(0).unwrap();
+                let removed = writer.remove_oldest("", 0).unwrap();
                 assert_eq!(removed, 0);
 
-                let removed = writer.remove_oldest(2).unwrap();
+                let removed = writer.remove_oldest("", 2).unwrap();
                 assert_eq!(removed, 2);
 
                 Ok(())
@@ -1298,6 +1844,7 @@ pub(crate) mod tests {
             interface: "com.Foo".into(),
             path: "/path3".into(),
             payload: [].as_slice().into(),
+            codec: PayloadCodec::Raw,
             sent: false,
             expiry_time: None,
         };
@@ -1307,7 +1854,7 @@ pub(crate) mod tests {
         // try removing more elements than available
         let removed = store
             .pool
-            .acquire_writer(|writer| writer.remove_oldest(2))
+            .acquire_writer(|writer| writer.remove_oldest("", 2))
             .await
             .unwrap();
 
@@ -1359,13 +1906,16 @@ pub(crate) mod tests {
         store
             .pool
             .acquire_writer(|w| {
-                w.delete_expired(&TimestampSecs(
-                    SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs()
-                        + 101,
-                ))
+                w.delete_expired(
+                    "",
+                    &TimestampSecs(
+                        SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap()
+                            .as_secs()
+                            + 101,
+                    ),
+                )
             })
             .await
             .unwrap();
@@ -16,8 +16,15 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-//! Module to handle the retry of the MQTT connection
-
+//! Backoff strategies for the retry of the connection.
+//!
+//! The [`DeviceConnection`](crate::DeviceConnection) waits the delay returned by the [`Backoff`]
+//! before every reconnection attempt. The default strategy is a [`RandomExponentialIter`]
+//! configured with the [`DeviceBuilder`](crate::builder::DeviceBuilder) exponential backoff
+//! options, a different one can be set with
+//! [`DeviceBuilder::backoff`](crate::builder::DeviceBuilder::backoff).
+
+use std::fmt::Debug;
 use std::time::{Duration, Instant};
 
 use rand::{RngExt, rngs::SmallRng};
@@ -25,38 +32,116 @@ use tracing::trace;
 
 use crate::builder::{DEFAULT_BACKOFF_MAXIMUM_DELAY, DEFAULT_BACKOFF_RESET_INTERVAL};
 
+/// Strategy for the delay between the connection attempts.
+///
+/// The delay is requested before each reconnection, including the first one after a disconnection.
+/// The strategy should reset itself after the connection has been stable for a while, since a
+/// device can be disconnected right after connecting, for example when publishing on an interface
+/// not installed on Astarte.
+///
+/// # Example
+///
+/// Offset the delay by the device id, to spread the reconnections of a fleet after an outage.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use astarte_device_sdk::retry::{Backoff, ExponentialIter};
+///
+/// #[derive(Debug)]
+/// struct FleetBackoff {
+///     inner: ExponentialIter,
+///     offset: Duration,
+/// }
+///
+/// impl FleetBackoff {
+///     fn new(device_id: &str) -> Self {
+///         let hash = device_id
+///             .bytes()
+///             .fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b.into()));
+///
+///         Self {
+///             inner: ExponentialIter::default(),
+///             offset: Duration::from_millis(hash % 30_000),
+///         }
+///     }
+/// }
+///
+/// impl Backoff for FleetBackoff {
+///     fn next_delay(&mut self) -> Duration {
+///         self.inner.next_delay() + self.offset
+///     }
+/// }
+/// ```
+pub trait Backoff: Debug + Send + Sync + 'static {
+    /// Returns the time to wait before the next connection attempt.
+    fn next_delay(&mut self) -> Duration;
+}
+
+impl Backoff for Box<dyn Backoff> {
+    fn next_delay(&mut self) -> Duration {
+        (**self).next_delay()
+    }
+}
+
+/// Resets a strategy if it wasn't used for an interval.
+#[derive(Debug, Clone, Copy)]
+struct ResetAfter {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl ResetAfter {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+        }
+    }
+
+    /// Returns true if the strategy should start from the beginning.
+    fn check(&mut self) -> bool {
+        let reset = self
+            .last
+            .is_some_and(|instant| instant.elapsed() > self.interval);
+
+        if reset {
+            trace!("resetting timeout");
+        }
+
+        self.last = Some(Instant::now());
+
+        reset
+    }
+}
+
 /// Iterator that yields a delay that will increase exponentially till the max,
+///
+/// The delays are `0s, 1s, 2s, 4s, ...` and are reset to `0s` if no delay is requested for the
+/// reset interval.
 #[derive(Debug, Clone, Copy)]
-pub(crate) struct ExponentialIter {
+pub struct ExponentialIter {
     n: u32,
     max: Duration,
-    reset_after: Duration,
-    last: Option<Instant>,
+    reset: ResetAfter,
 }
 
 impl ExponentialIter {
-    pub(crate) fn new(max: Duration, reset_after: Duration) -> Self {
+    /// Creates the iterator with the maximum delay and the reset interval.
+    pub fn new(max: Duration, reset_after: Duration) -> Self {
         Self {
             n: 0,
             max,
-            reset_after,
-            last: None,
+            reset: ResetAfter::new(reset_after),
         }
     }
 
     pub(crate) fn next(&mut self) -> Duration {
-        if self
-            .last
-            .is_some_and(|instant| instant.elapsed() > self.reset_after)
-        {
-            trace!("resetting timeout");
-
+        if self.reset.check() {
             // Start from the beginning
             self.n = 0;
         }
 
-        self.last = Some(Instant::now());
-
         let v = ((self.n > 0) as u64).wrapping_shl(self.n.saturating_sub(1));
 
         let next = Duration::from_secs(v);
@@ -73,12 +158,16 @@ impl ExponentialIter {
 
 impl Default for ExponentialIter {
     fn default() -> Self {
-        Self {
-            n: 0,
-            max: DEFAULT_BACKOFF_MAXIMUM_DELAY,
-            reset_after: DEFAULT_BACKOFF_RESET_INTERVAL,
-            last: None,
-        }
+        Self::new(
+            DEFAULT_BACKOFF_MAXIMUM_DELAY,
+            DEFAULT_BACKOFF_RESET_INTERVAL,
+        )
+    }
+}
+
+impl Backoff for ExponentialIter {
+    fn next_delay(&mut self) -> Duration {
+        self.next()
     }
 }
 
@@ -90,15 +179,18 @@ impl Iterator for ExponentialIter {
     }
 }
 
-/// Iterator that yields a delay
+/// Iterator that yields an exponential delay with a random jitter.
+///
+/// The jitter is a percentage of the delay added or subtracted from it.
 #[derive(Debug, Clone)]
-pub(crate) struct RandomExponentialIter {
+pub struct RandomExponentialIter {
     iter: ExponentialIter,
     rng: SmallRng,
     jitter: u8,
 }
 
 impl RandomExponentialIter {
+    /// Default percentage of jitter.
     pub const DEFAULT_RANDOM_JITTER_RANGE: u8 = 50;
 
     fn new(iter: ExponentialIter, rng: SmallRng, jitter: u8) -> Self {
@@ -107,7 +199,8 @@ impl RandomExponentialIter {
         Self { iter, rng, jitter }
     }
 
-    pub(crate) fn with_jitter(iter: ExponentialIter, jitter: u8) -> Self {
+    /// Adds the jitter percentage, from 0 to 100, to the exponential delay.
+    pub fn with_jitter(iter: ExponentialIter, jitter: u8) -> Self {
         Self {
             iter,
             jitter,
@@ -152,6 +245,137 @@ impl Iterator for RandomExponentialIter {
     }
 }
 
+impl Backoff for RandomExponentialIter {
+    fn next_delay(&mut self) -> Duration {
+        self.next()
+    }
+}
+
+/// Decorrelated jitter backoff.
+///
+/// Each delay is random between the base and three times the previous delay, up to the maximum.
+/// It spreads the reconnections of many devices better than the exponential backoff. The delay
+/// starts again from the base if no delay is requested for the reset interval.
+#[derive(Debug, Clone)]
+pub struct DecorrelatedJitter {
+    base: Duration,
+    max: Duration,
+    prev: Duration,
+    reset: ResetAfter,
+    rng: SmallRng,
+}
+
+impl DecorrelatedJitter {
+    /// Creates the backoff with the base and maximum delay and the reset interval.
+    pub fn new(base: Duration, max: Duration, reset_after: Duration) -> Self {
+        Self::with_rng(base, max, reset_after, rand::make_rng())
+    }
+
+    fn with_rng(base: Duration, max: Duration, reset_after: Duration, rng: SmallRng) -> Self {
+        let max = max.max(base);
+
+        Self {
+            base,
+            max,
+            prev: base,
+            reset: ResetAfter::new(reset_after),
+            rng,
+        }
+    }
+}
+
+impl Default for DecorrelatedJitter {
+    fn default() -> Self {
+        Self::new(
+            Duration::from_secs(1),
+            DEFAULT_BACKOFF_MAXIMUM_DELAY,
+            DEFAULT_BACKOFF_RESET_INTERVAL,
+        )
+    }
+}
+
+impl Backoff for DecorrelatedJitter {
+    fn next_delay(&mut self) -> Duration {
+        if self.reset.check() {
+            self.prev = self.base;
+        }
+
+        let base = u64::try_from(self.base.as_millis()).unwrap_or(u64::MAX);
+        let upper = u64::try_from(self.prev.as_millis())
+            .unwrap_or(u64::MAX)
+            .saturating_mul(3)
+            .max(base);
+
+        let millis = self.rng.random_range(base..=upper);
+
+        self.prev = Duration::from_millis(millis).min(self.max);
+
+        self.prev
+    }
+}
+
+/// Constant delay between the attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedBackoff {
+    delay: Duration,
+}
+
+impl FixedBackoff {
+    /// Creates the backoff with the delay.
+    pub fn new(delay: Duration) -> Self {
+        Self { delay }
+    }
+}
+
+impl Backoff for FixedBackoff {
+    fn next_delay(&mut self) -> Duration {
+        self.delay
+    }
+}
+
+/// Delays from a schedule.
+///
+/// The delays are returned in order, the last one is repeated once the schedule is over. The
+/// schedule starts again if no delay is requested for the reset interval.
+#[derive(Debug, Clone)]
+pub struct ScheduleBackoff {
+    delays: Vec<Duration>,
+    n: usize,
+    reset: ResetAfter,
+}
+
+impl ScheduleBackoff {
+    /// Creates the backoff with the schedule and the reset interval.
+    ///
+    /// An empty schedule doesn't wait between the attempts.
+    pub fn new(delays: impl IntoIterator<Item = Duration>, reset_after: Duration) -> Self {
+        Self {
+            delays: delays.into_iter().collect(),
+            n: 0,
+            reset: ResetAfter::new(reset_after),
+        }
+    }
+}
+
+impl Backoff for ScheduleBackoff {
+    fn next_delay(&mut self) -> Duration {
+        if self.reset.check() {
+            self.n = 0;
+        }
+
+        let delay = self
+            .delays
+            .get(self.n)
+            .or(self.delays.last())
+            .copied()
+            .unwrap_or_default();
+
+        self.n = self.n.saturating_add(1).min(self.delays.len());
+
+        delay
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -161,7 +385,7 @@ mod tests {
 
     use crate::retry::RandomExponentialIter;
 
-    use super::ExponentialIter;
+    use super::*;
 
     const EXPONENTIAL_EXPECTED: [u64; 13] = [0, 1, 2, 4, 8, 16, 32, 64, 128, 256, 256, 256, 256];
 
@@ -223,4 +447,48 @@ mod tests {
             println!("{i:?}");
         }
     }
+
+    #[test]
+    fn decorrelated_jitter_delays() {
+        const SEED: u64 = 1;
+        const BASE: Duration = Duration::from_secs(1);
+        const MAX: Duration = Duration::from_secs(60);
+
+        let mut backoff =
+            DecorrelatedJitter::with_rng(BASE, MAX, Duration::MAX, SmallRng::seed_from_u64(SEED));
+
+        let mut prev = BASE;
+        for _ in 0..64 {
+            let delay = backoff.next_delay();
+
+            assert!(delay >= BASE, "{delay:?}");
+            assert!(delay <= MAX, "{delay:?}");
+            assert!(delay <= prev * 3, "{delay:?} {prev:?}");
+
+            prev = delay;
+        }
+    }
+
+    #[test]
+    fn fixed_delays() {
+        let mut backoff = FixedBackoff::new(Duration::from_secs(5));
+
+        for _ in 0..4 {
+            assert_eq!(backoff.next_delay(), Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    fn schedule_delays() {
+        let mut backoff = ScheduleBackoff::new([1, 5, 30].map(Duration::from_secs), Duration::MAX);
+
+        let delays: Vec<Duration> = std::iter::repeat_with(|| backoff.next_delay())
+            .take(5)
+            .collect();
+        let expected: Vec<Duration> = [1, 5, 30, 30, 30].map(Duration::from_secs).to_vec();
+        assert_eq!(delays, expected);
+
+        let mut empty = ScheduleBackoff::new([], Duration::MAX);
+        assert_eq!(empty.next_delay(), Duration::ZERO);
+    }
 }
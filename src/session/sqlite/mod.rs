@@ -16,6 +16,8 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use std::sync::Arc;
+
 use astarte_device_error::{Error, WrapError};
 use tracing::error;
 
@@ -27,11 +29,12 @@ mod statement;
 
 impl StoredSession for SqliteStore {
     async fn store_introspection(&self, interfaces: &[IntrospectionInterface]) {
+        let tenant = Arc::clone(&self.tenant);
         let interfaces = interfaces.to_vec();
 
         let res = self
             .pool
-            .acquire_writer(move |writer| writer.add_interfaces(&interfaces))
+            .acquire_writer(move |writer| writer.add_interfaces(&tenant, &interfaces))
             .await;
 
         if let Err(err) = res {
@@ -40,9 +43,11 @@ impl StoredSession for SqliteStore {
     }
 
     async fn clear_introspection(&self) {
+        let tenant = Arc::clone(&self.tenant);
+
         let res = self
             .pool
-            .acquire_writer(|writer| writer.clear_introspection())
+            .acquire_writer(move |writer| writer.clear_introspection(&tenant))
             .await;
 
         if let Err(err) = res {
@@ -58,16 +63,19 @@ impl StoredSession for SqliteStore {
             .iter()
             .map(|i| IntrospectionInterface::from(*i))
             .collect();
+        let tenant = Arc::clone(&self.tenant);
 
         self.pool
-            .acquire_writer(move |writer| writer.add_interfaces(&interfaces))
+            .acquire_writer(move |writer| writer.add_interfaces(&tenant, &interfaces))
             .await
             .wrap_err(SessionError::AddInterfaces)
     }
 
     async fn load_introspection(&self) -> Result<Vec<IntrospectionInterface>, Error<SessionError>> {
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
-            .acquire_reader(|reader| reader.load_introspection())
+            .acquire_reader(move |reader| reader.load_introspection(&tenant))
             .await
             .wrap_err(SessionError::LoadIntrospection)
     }
@@ -80,9 +88,10 @@ impl StoredSession for SqliteStore {
             .iter()
             .map(|i| IntrospectionInterface::from(*i))
             .collect();
+        let tenant = Arc::clone(&self.tenant);
 
         self.pool
-            .acquire_writer(move |writer| writer.remove_interfaces(&interfaces))
+            .acquire_writer(move |writer| writer.remove_interfaces(&tenant, &interfaces))
             .await
             .wrap_err(SessionError::RemoveInterfaces)
     }
@@ -29,6 +29,7 @@ impl WriteConnection {
     #[instrument(skip_all)]
     pub(crate) fn add_interfaces<S>(
         &mut self,
+        tenant: &str,
         interfaces: &[IntrospectionInterface<S>],
     ) -> Result<(), Error<SqliteError>>
     where
@@ -45,7 +46,7 @@ impl WriteConnection {
 
             for i in interfaces {
                 statement
-                    .execute((i.name(), i.version_major(), i.version_minor()))
+                    .execute((tenant, i.name(), i.version_major(), i.version_minor()))
                     .wrap_err(SqliteError::Query)?;
             }
         }
@@ -56,14 +57,14 @@ impl WriteConnection {
     }
 
     #[instrument(skip_all)]
-    pub(crate) fn clear_introspection(&self) -> Result<(), Error<SqliteError>> {
+    pub(crate) fn clear_introspection(&self, tenant: &str) -> Result<(), Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!(
                 "queries/session/write/clear_introspection.sql"
             ))
             .wrap_err(SqliteError::Prepare)?;
 
-        statement.execute(()).wrap_err(SqliteError::Query)?;
+        statement.execute([tenant]).wrap_err(SqliteError::Query)?;
 
         Ok(())
     }
@@ -71,6 +72,7 @@ impl WriteConnection {
     #[instrument(skip_all)]
     pub(crate) fn remove_interfaces(
         &mut self,
+        tenant: &str,
         interfaces: &[IntrospectionInterface],
     ) -> Result<(), Error<SqliteError>> {
         let trn = self.transaction().wrap_err(SqliteError::Transaction)?;
@@ -82,7 +84,7 @@ impl WriteConnection {
 
             for i in interfaces {
                 statement
-                    .execute((i.name(), i.version_major(), i.version_minor()))
+                    .execute((tenant, i.name(), i.version_major(), i.version_minor()))
                     .wrap_err(SqliteError::Query)?;
             }
         }
@@ -97,6 +99,7 @@ impl ReadConnection {
     #[instrument(skip(self))]
     pub(crate) fn load_introspection(
         &self,
+        tenant: &str,
     ) -> Result<Vec<IntrospectionInterface>, Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!(
@@ -105,7 +108,7 @@ impl ReadConnection {
             .wrap_err(SqliteError::Prepare)?;
 
         let interfaces = statement
-            .query_map([], |row| {
+            .query_map([tenant], |row| {
                 Ok(IntrospectionInterface {
                     name: row.get(0)?,
                     version_major: row.get(1)?,
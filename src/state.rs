@@ -23,9 +23,10 @@ use std::sync::atomic::Ordering;
 
 use chrono::DateTime;
 use chrono::Utc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, watch};
 
 use crate::builder::Config;
+use crate::connection::duty_cycle::PriorityWake;
 use crate::interfaces::Interfaces;
 use crate::retention;
 use crate::retention::memory::VolatileStore;
@@ -43,6 +44,10 @@ pub struct SharedState {
     pub(crate) cert_expiry: RwLock<Option<DateTime<Utc>>>,
     /// Status of the device, whether it's paired to Astarte
     pub(crate) device_status: AtomicU8,
+    /// The connection was paused by the client
+    pub(crate) paused: watch::Sender<bool>,
+    /// Wakes the duty-cycled connection for priority data
+    pub(crate) priority_wake: PriorityWake,
 }
 
 impl SharedState {
@@ -59,9 +64,18 @@ impl SharedState {
             status: RwLock::new(ConnStatus::default()),
             cert_expiry: RwLock::new(None),
             device_status: AtomicU8::new(DeviceStatus::Unknown.into()),
+            paused: watch::Sender::new(false),
+            priority_wake: PriorityWake::default(),
         }
     }
 
+    /// Sets the interfaces that wake the duty-cycled connection.
+    pub(crate) fn with_priority_wake(mut self, priority_wake: PriorityWake) -> Self {
+        self.priority_wake = priority_wake;
+
+        self
+    }
+
     pub(crate) fn split(self: Arc<Self>) -> (ClientState, ConnectionState) {
         (
             ClientState::new(Arc::clone(&self)),
@@ -114,6 +128,19 @@ impl ClientState {
         *self.0.cert_expiry.read().await
     }
 
+    pub(crate) fn is_paused(&self) -> bool {
+        *self.0.paused.borrow()
+    }
+
+    /// Sets the paused flag, returns the previous value.
+    pub(crate) fn set_paused(&self, paused: bool) -> bool {
+        self.0.paused.send_replace(paused)
+    }
+
+    pub(crate) fn priority_wake(&self) -> &PriorityWake {
+        &self.0.priority_wake
+    }
+
     pub(crate) fn is_device_paired(&self) -> bool {
         let value = DeviceStatus::from(self.0.device_status.load(Ordering::Acquire));
 
@@ -158,6 +185,15 @@ impl ConnectionState {
     pub(crate) fn config(&self) -> &Config {
         &self.0.config
     }
+
+    /// Receiver for the paused flag set by the client.
+    pub(crate) fn paused(&self) -> watch::Receiver<bool> {
+        self.0.paused.subscribe()
+    }
+
+    pub(crate) fn priority_wake(&self) -> &PriorityWake {
+        &self.0.priority_wake
+    }
 }
 
 /// Shared state of the connection
@@ -227,6 +263,10 @@ mod tests {
         pub(crate) fn retention_ctx(&self) -> &retention::Context {
             &self.0.retention_ctx
         }
+
+        pub(crate) fn set_paused(&self, paused: bool) {
+            self.0.paused.send_replace(paused);
+        }
     }
 
     #[test]
@@ -0,0 +1,773 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conformance test suite for the store traits.
+//!
+//! These are the same checks the SDK runs against the [`SqliteStore`](super::SqliteStore) and
+//! [`MemoryStore`](super::memory::MemoryStore), exposed so they can be used to validate external
+//! store implementations. Each function panics on the first failed assertion, so they are meant to
+//! be called from a test.
+//!
+//! ```no_run
+//! use astarte_device_sdk::store::SqliteStore;
+//! use astarte_device_sdk::store::conformance;
+//!
+//! async fn sqlite_conformance() {
+//!     let dir = tempfile::tempdir().unwrap();
+//!     let store = SqliteStore::options()
+//!         .with_writable_dir(dir.path())
+//!         .await
+//!         .unwrap();
+//!
+//!     conformance::property_store(&store).await;
+//!     conformance::stored_retention(&store).await;
+//!     conformance::stored_session(&store).await;
+//! }
+//! ```
+
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::str::FromStr;
+use std::time::Duration;
+
+use astarte_device_error::Error;
+use astarte_interfaces::Properties;
+use astarte_interfaces::interface::Retention;
+use astarte_interfaces::schema::{Ownership, Reliability};
+use chrono::{TimeZone, Utc};
+use futures::FutureExt;
+
+use super::{PropertyMapping, PropertyState, PropertyStore, StoredProp};
+use crate::retention::events::{RetentionEvent, RetentionEventSender, RetentionEvents};
+use crate::retention::inspect::{RetentionFilter, RetentionKind};
+use crate::retention::order::ResendOrder;
+use crate::retention::quota::{RetentionLimit, RetentionQuotas};
+use crate::retention::{
+    Context, Id, PublishInfo, RetentionError, StoredInterface, StoredRetention,
+};
+use crate::session::{IntrospectionInterface, StoredSession};
+use crate::types::AstarteData;
+
+const DEVICE_PROPERTY_NAME: &str = "org.astarte-platform.rust.conformance.DeviceProperty";
+const DEVICE_PROPERTY: &str = r#"{
+    "interface_name": "org.astarte-platform.rust.conformance.DeviceProperty",
+    "version_major": 1,
+    "version_minor": 0,
+    "type": "properties",
+    "ownership": "device",
+    "allow_unset": true,
+    "mappings": [
+        {
+            "endpoint": "/%{sensor_id}/integer_endpoint",
+            "type": "integer"
+        }
+    ]
+}"#;
+const SERVER_PROPERTY_NAME: &str = "org.astarte-platform.rust.conformance.ServerProperty";
+const SERVER_PROPERTY: &str = r#"{
+    "interface_name": "org.astarte-platform.rust.conformance.ServerProperty",
+    "version_major": 1,
+    "version_minor": 0,
+    "type": "properties",
+    "ownership": "server",
+    "allow_unset": true,
+    "mappings": [
+        {
+            "endpoint": "/%{sensor_id}/integer_endpoint",
+            "type": "integer"
+        }
+    ]
+}"#;
+
+/// Checks the behaviour of a [`PropertyStore`].
+///
+/// The store is cleared before and during the test.
+pub async fn property_store<S>(store: &S)
+where
+    S: PropertyStore,
+{
+    let ty = AstarteData::Integer(23);
+    let prop = StoredProp {
+        interface: "com.test",
+        path: "/test",
+        value: &ty,
+        interface_major: 1,
+        ownership: Ownership::Device,
+    };
+    let property_mapping = PropertyMapping::from(&prop);
+
+    store.clear().await.unwrap();
+
+    // non existing
+    assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
+
+    store.store_prop(prop).await.unwrap();
+    assert_eq!(
+        store.load_prop(&property_mapping).await.unwrap().unwrap(),
+        ty
+    );
+
+    let updated = store
+        .update_state(
+            &property_mapping,
+            PropertyState::Completed,
+            Some(ty.clone()),
+        )
+        .await
+        .unwrap();
+    assert!(updated);
+
+    // the state is not updated if the value doesn't match
+    let updated = store
+        .update_state(
+            &property_mapping,
+            PropertyState::Changed,
+            Some(AstarteData::Integer(42)),
+        )
+        .await
+        .unwrap();
+    assert!(!updated);
+
+    let mut property_mapping_next = property_mapping;
+    property_mapping_next.version_major = 2;
+
+    //major version mismatch
+    assert_eq!(store.load_prop(&property_mapping_next).await.unwrap(), None);
+
+    // after mismatch the path should be deleted
+    assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
+
+    // unset
+    store.store_prop(prop).await.unwrap();
+    assert_eq!(
+        store.load_prop(&property_mapping).await.unwrap().unwrap(),
+        ty
+    );
+    store.unset_prop(&property_mapping).await.unwrap();
+    assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
+    // with unset
+    assert!(store.device_props().await.unwrap().is_empty());
+    assert!(store.load_all_props().await.unwrap().is_empty());
+    assert!(store.server_props().await.unwrap().is_empty());
+    assert_eq!(
+        &[StoredProp {
+            interface: "com.test",
+            path: "/test",
+            value: None,
+            interface_major: 1,
+            ownership: Ownership::Device,
+        }],
+        store
+            .device_props_with_unset(PropertyState::Changed, 1, 0)
+            .await
+            .unwrap()
+            .as_slice()
+    );
+    // delete property if a value is expected, but it was unset
+    let updated = store
+        .delete_expected_prop(&property_mapping, Some(ty.clone()))
+        .await
+        .unwrap();
+    assert!(!updated);
+    // delete property if matches value, should delete the property
+    let updated = store
+        .delete_expected_prop(&property_mapping, None)
+        .await
+        .unwrap();
+    assert!(updated);
+    store.delete_prop(&property_mapping).await.unwrap();
+    // should now be empty
+    let props = store
+        .device_props_with_unset(PropertyState::Changed, 1, 0)
+        .await
+        .unwrap();
+    assert!(props.is_empty());
+
+    // delete
+    store.store_prop(prop).await.unwrap();
+    assert_eq!(
+        store.load_prop(&property_mapping).await.unwrap().unwrap(),
+        ty
+    );
+    store.delete_prop(&property_mapping).await.unwrap();
+    assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
+
+    // clear
+    store.store_prop(prop).await.unwrap();
+    assert_eq!(
+        store.load_prop(&property_mapping).await.unwrap().unwrap(),
+        ty
+    );
+    store.clear().await.unwrap();
+    assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
+
+    // load all props
+    let device_prop_interface = Properties::from_str(DEVICE_PROPERTY).unwrap();
+    let device_prop = StoredProp {
+        interface: DEVICE_PROPERTY_NAME.to_string(),
+        path: "/sensor2/integer_endpoint".to_string(),
+        value: ty.clone(),
+        interface_major: 1,
+        ownership: Ownership::Device,
+    };
+    let server_prop_interface = Properties::from_str(SERVER_PROPERTY).unwrap();
+    let server_prop = StoredProp {
+        interface: SERVER_PROPERTY_NAME.to_string(),
+        path: "/sensor2/integer_endpoint".to_string(),
+        value: ty.clone(),
+        interface_major: 1,
+        ownership: Ownership::Server,
+    };
+
+    store.store_prop(device_prop.as_prop_ref()).await.unwrap();
+    store.store_prop(server_prop.as_prop_ref()).await.unwrap();
+
+    let expected = [device_prop.clone(), server_prop.clone()];
+
+    let mut props = store.load_all_props().await.unwrap();
+    props.sort_unstable_by(|a, b| a.interface.cmp(&b.interface));
+    assert_eq!(props, expected);
+
+    // check that device properties are in the changed state
+    let device_props_changed = store
+        .device_props_with_unset(PropertyState::Changed, 1, 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        device_props_changed[0].value,
+        Some(device_prop.value.clone())
+    );
+    assert_eq!(device_props_changed[0].interface, device_prop.interface);
+    assert_eq!(device_props_changed[0].path, device_prop.path);
+    // update the state of the device property to completed
+    assert!(
+        store
+            .update_state(
+                &PropertyMapping::from(&device_prop),
+                PropertyState::Completed,
+                Some(device_prop.value.clone())
+            )
+            .await
+            .unwrap()
+    );
+    // check that no properties are in the changed state
+    let device_props_changed = store
+        .device_props_with_unset(PropertyState::Changed, 1, 0)
+        .await
+        .unwrap();
+    assert!(device_props_changed.is_empty());
+    // the completed property is still returned with its state
+    let device_props_completed = store
+        .device_props_with_unset(PropertyState::Completed, 1, 0)
+        .await
+        .unwrap();
+    assert_eq!(device_props_completed.len(), 1);
+    // reset the state of the properties to bring changes back
+    store.reset_state(Ownership::Device).await.unwrap();
+    // ensure state is now changed
+    let device_props_changed = store
+        .device_props_with_unset(PropertyState::Changed, 1, 0)
+        .await
+        .unwrap();
+    assert_eq!(device_props_changed.len(), 1);
+
+    let dev_props = store.device_props().await.unwrap();
+    assert_eq!(dev_props, std::slice::from_ref(&device_prop));
+
+    let serv_props = store.server_props().await.unwrap();
+    assert_eq!(serv_props, std::slice::from_ref(&server_prop));
+
+    // props from interface
+    let props = store.interface_props(&device_prop_interface).await.unwrap();
+    assert_eq!(props, std::slice::from_ref(&device_prop));
+    let props = store.interface_props(&server_prop_interface).await.unwrap();
+    assert_eq!(props, std::slice::from_ref(&server_prop));
+
+    // delete interface properties
+    store
+        .delete_interface(&device_prop_interface)
+        .await
+        .unwrap();
+    let prop = store.interface_props(&device_prop_interface).await.unwrap();
+    assert!(prop.is_empty());
+
+    // test all types
+    let all_types = [
+        AstarteData::Double(4.5.try_into().unwrap()),
+        AstarteData::Integer(-4),
+        AstarteData::Boolean(true),
+        AstarteData::LongInteger(45543543534_i64),
+        AstarteData::String("hello".into()),
+        AstarteData::BinaryBlob(b"hello".to_vec()),
+        AstarteData::DateTime(TimeZone::timestamp_opt(&Utc, 1627580808, 0).unwrap()),
+        AstarteData::DoubleArray([1.2, 3.4, 5.6, 7.8].map(|v| v.try_into().unwrap()).to_vec()),
+        AstarteData::IntegerArray(vec![1, 3, 5, 7]),
+        AstarteData::BooleanArray(vec![true, false, true, true]),
+        AstarteData::LongIntegerArray(vec![45543543534_i64, 45543543535_i64, 45543543536_i64]),
+        AstarteData::StringArray(vec!["hello".to_owned(), "world".to_owned()]),
+        AstarteData::BinaryBlobArray(vec![b"hello".to_vec(), b"world".to_vec()]),
+        AstarteData::DateTimeArray(vec![
+            TimeZone::timestamp_opt(&Utc, 1627580808, 0).unwrap(),
+            TimeZone::timestamp_opt(&Utc, 1627580809, 0).unwrap(),
+            TimeZone::timestamp_opt(&Utc, 1627580810, 0).unwrap(),
+        ]),
+    ];
+
+    for ty in all_types {
+        let path = format!("/test/{}", ty.display_type());
+
+        let prop = StoredProp {
+            interface: "com.test",
+            path: &path,
+            value: &ty,
+            interface_major: 1,
+            ownership: Ownership::Server,
+        };
+        let prop_mapping = PropertyMapping::from(&prop);
+
+        store.store_prop(prop).await.unwrap();
+
+        let res = store.load_prop(&prop_mapping).await.unwrap();
+
+        assert_eq!(res, Some(ty));
+    }
+
+    store.clear().await.unwrap();
+}
+
+fn publish(path: &'static str, expiry: Option<Duration>) -> PublishInfo<'static> {
+    PublishInfo::from_ref(
+        "com.Foo",
+        path,
+        1,
+        Reliability::Unique,
+        Retention::Stored { expiry },
+        false,
+        path.as_bytes(),
+    )
+}
+
+async fn unsent<S>(retention: &S) -> Vec<(Id, PublishInfo<'static>)>
+where
+    S: StoredRetention,
+{
+    let mut buf = Vec::new();
+    let count = retention
+        .unsent_publishes(usize::MAX, &mut buf)
+        .await
+        .unwrap();
+    assert_eq!(count, buf.len());
+
+    buf
+}
+
+/// Returns [`None`] if the optional operation is not supported by the retention.
+fn optional<T>(res: Result<T, Error<RetentionError>>) -> Option<T> {
+    match res {
+        Ok(value) => Some(value),
+        Err(err) if *err.kind() == RetentionError::Unsupported => None,
+        Err(err) => panic!("optional operation failed: {err:?}"),
+    }
+}
+
+/// Returns the events already sent.
+fn sent_events(events: &mut RetentionEvents) -> Vec<RetentionEvent> {
+    std::iter::from_fn(|| events.recv().now_or_never().flatten()).collect()
+}
+
+/// Checks the behaviour of a [`StoredRetention`].
+///
+/// The retention must be empty when the test starts. The capacity is changed during the test and
+/// all the stored publishes are removed at the end.
+///
+/// The operations with a default implementation are optional: they are checked only if the
+/// retention doesn't return [`RetentionError::Unsupported`], or if the default ignores them.
+pub async fn stored_retention<S>(retention: &S)
+where
+    S: StoredRetention,
+{
+    let ctx = Context::new();
+
+    // always set when connecting
+    let sender = RetentionEventSender::new();
+    retention
+        .set_retention_events(sender.clone())
+        .await
+        .unwrap();
+    retention
+        .set_retention_quotas(&RetentionQuotas::new())
+        .await
+        .unwrap();
+    retention
+        .set_resend_order(ResendOrder::Oldest)
+        .await
+        .unwrap();
+
+    assert!(unsent(retention).await.is_empty());
+    assert!(retention.fetch_all_interfaces().await.unwrap().is_empty());
+
+    // store and ordering by id
+    let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+    let paths = ["/path1", "/path2", "/path3"];
+    // store them in reverse to check the returned order is the id one
+    for (id, path) in ids.iter().zip(paths).rev() {
+        retention
+            .store_publish(id, publish(path, None))
+            .await
+            .unwrap();
+    }
+
+    let res = unsent(retention).await;
+    let expected: Vec<_> = ids
+        .iter()
+        .zip(paths)
+        .map(|(id, path)| (*id, publish(path, None)))
+        .collect();
+    assert_eq!(res, expected);
+
+    // limit and buffer reuse
+    let mut buf = Vec::new();
+    let count = retention.unsent_publishes(2, &mut buf).await.unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(buf, expected[..2]);
+
+    // interfaces
+    let interfaces = retention.fetch_all_interfaces().await.unwrap();
+    assert_eq!(interfaces.len(), 1);
+    assert!(interfaces.contains(&StoredInterface {
+        name: "com.Foo".to_string(),
+        version_major: 1,
+    }));
+
+    // sent flag and reset
+    retention.update_sent_flag(&ids[0], true).await.unwrap();
+    let res = unsent(retention).await;
+    assert_eq!(res, expected[1..]);
+    retention.reset_all_publishes().await.unwrap();
+    let res = unsent(retention).await;
+    assert_eq!(res, expected);
+
+    // received
+    retention.mark_received(&ids[1]).await.unwrap();
+    let res = unsent(retention).await;
+    assert_eq!(res, [expected[0].clone(), expected[2].clone()]);
+
+    // delete interface
+    retention.delete_interface("com.Foo").await.unwrap();
+    assert!(unsent(retention).await.is_empty());
+    assert!(retention.fetch_all_interfaces().await.unwrap().is_empty());
+
+    // the resend order is either applied or ignored
+    let mut events = sender.subscribe();
+    let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+    for (id, path) in ids.iter().zip(paths) {
+        retention
+            .store_publish(id, publish(path, None))
+            .await
+            .unwrap();
+    }
+    let oldest: Vec<_> = ids
+        .iter()
+        .zip(paths)
+        .map(|(id, path)| (*id, publish(path, None)))
+        .collect();
+    let newest: Vec<_> = oldest.iter().rev().cloned().collect();
+    retention
+        .set_resend_order(ResendOrder::Newest)
+        .await
+        .unwrap();
+    let res = unsent(retention).await;
+    assert!(res == newest || res == oldest, "unexpected order {res:?}");
+    retention
+        .set_resend_order(ResendOrder::Oldest)
+        .await
+        .unwrap();
+    retention.delete_interface("com.Foo").await.unwrap();
+
+    // events are sent only for the removed publishes
+    let removed = sent_events(&mut events);
+    assert!(
+        removed
+            .iter()
+            .all(|event| ids.contains(&event.id) && event.kind == RetentionKind::Stored),
+        "unexpected events {removed:?}"
+    );
+
+    // inspect, purge and expire
+    let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+    for (id, path) in ids.iter().zip(paths) {
+        retention
+            .store_publish(id, publish(path, None))
+            .await
+            .unwrap();
+    }
+    let mut pending: Vec<_> = ids
+        .iter()
+        .zip(paths)
+        .map(|(id, path)| (*id, publish(path, None)))
+        .collect();
+    let all = RetentionFilter::new();
+    if let Some(count) = optional(retention.count_publishes(&all).await) {
+        assert_eq!(count, 3);
+        let path1 = RetentionFilter::new().with_path(paths[0]);
+        assert_eq!(retention.count_publishes(&path1).await.unwrap(), 1);
+    }
+    if let Some(listed) = optional(retention.list_publishes(&all, 2).await) {
+        let listed: Vec<Id> = listed.into_iter().map(|publish| publish.id).collect();
+        assert_eq!(listed, ids[..2]);
+    }
+    let path1 = RetentionFilter::new().with_path(paths[0]);
+    if let Some(purged) = optional(retention.purge_publishes(&path1).await) {
+        assert_eq!(purged, 1);
+        pending.retain(|(id, _)| *id != ids[0]);
+    }
+    let path2 = RetentionFilter::new().with_path(paths[1]);
+    if let Some(expired) = optional(retention.expire_publishes(&path2).await) {
+        assert_eq!(expired, 1);
+        pending.retain(|(id, _)| *id != ids[1]);
+    }
+    let res = unsent(retention).await;
+    assert_eq!(res, pending);
+    let removed = sent_events(&mut events);
+    assert!(
+        removed
+            .iter()
+            .all(|event| ids.contains(&event.id) && !pending.iter().any(|(id, _)| *id == event.id)),
+        "unexpected events {removed:?}"
+    );
+    retention.delete_interface("com.Foo").await.unwrap();
+
+    // expiry, the publish must be expired by at least a second since some stores check the expiry
+    // in seconds
+    let expired = ctx.next().before(Duration::from_secs(2));
+    retention
+        .store_publish(&expired, publish("/expired", Some(Duration::ZERO)))
+        .await
+        .unwrap();
+    let valid = ctx.next();
+    retention
+        .store_publish(&valid, publish("/valid", Some(Duration::from_secs(3600))))
+        .await
+        .unwrap();
+    let res = unsent(retention).await;
+    assert_eq!(
+        res,
+        [(valid, publish("/valid", Some(Duration::from_secs(3600))))]
+    );
+    retention.mark_received(&valid).await.unwrap();
+
+    // quotas are optional, the oldest publishes of the interface are evicted
+    let quotas = RetentionQuotas::new().with_interface(
+        "com.Foo",
+        RetentionLimit::Items(NonZeroUsize::new(1).unwrap()),
+    );
+    if optional(retention.set_retention_quotas(&quotas).await).is_some() {
+        let ids: Vec<Id> = (0..2).map(|_| ctx.next()).collect();
+        for (id, path) in ids.iter().zip(paths) {
+            retention
+                .store_publish(id, publish(path, None))
+                .await
+                .unwrap();
+        }
+        let res = unsent(retention).await;
+        assert_eq!(res, [(ids[1], publish(paths[1], None))]);
+
+        retention
+            .set_retention_quotas(&RetentionQuotas::new())
+            .await
+            .unwrap();
+        retention.delete_interface("com.Foo").await.unwrap();
+    }
+
+    // the bytes limit is optional, the payloads are the paths of 6 bytes
+    if optional(
+        retention
+            .set_max_retention_bytes(NonZeroU64::new(12).unwrap())
+            .await,
+    )
+    .is_some()
+    {
+        let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+        for (id, path) in ids.iter().zip(paths) {
+            retention
+                .store_publish(id, publish(path, None))
+                .await
+                .unwrap();
+        }
+        let res = unsent(retention).await;
+        assert_eq!(
+            res,
+            [
+                (ids[1], publish(paths[1], None)),
+                (ids[2], publish(paths[2], None))
+            ]
+        );
+
+        retention
+            .set_max_retention_bytes(NonZeroU64::MAX)
+            .await
+            .unwrap();
+        retention.delete_interface("com.Foo").await.unwrap();
+    }
+
+    // eviction of the oldest at max items
+    retention
+        .set_max_retention_items(NonZeroUsize::new(2).unwrap())
+        .await
+        .unwrap();
+    let ids: Vec<Id> = (0..3).map(|_| ctx.next()).collect();
+    for (id, path) in ids.iter().zip(paths) {
+        retention
+            .store_publish(id, publish(path, None))
+            .await
+            .unwrap();
+    }
+    let res = unsent(retention).await;
+    assert_eq!(
+        res,
+        [
+            (ids[1], publish(paths[1], None)),
+            (ids[2], publish(paths[2], None))
+        ]
+    );
+
+    // expired are evicted before the oldest
+    let expired = ctx.next().before(Duration::from_secs(2));
+    retention.delete_interface("com.Foo").await.unwrap();
+    retention
+        .store_publish(&ids[0], publish(paths[0], None))
+        .await
+        .unwrap();
+    retention
+        .store_publish(&expired, publish("/expired", Some(Duration::ZERO)))
+        .await
+        .unwrap();
+    retention
+        .store_publish(&ids[2], publish(paths[2], None))
+        .await
+        .unwrap();
+    let res = unsent(retention).await;
+    assert_eq!(
+        res,
+        [
+            (ids[0], publish(paths[0], None)),
+            (ids[2], publish(paths[2], None))
+        ]
+    );
+
+    // shrinking the capacity removes the oldest
+    retention
+        .set_max_retention_items(NonZeroUsize::new(1).unwrap())
+        .await
+        .unwrap();
+    let res = unsent(retention).await;
+    assert_eq!(res, [(ids[2], publish(paths[2], None))]);
+
+    retention.delete_interface("com.Foo").await.unwrap();
+}
+
+/// Checks the behaviour of a [`StoredSession`].
+///
+/// The introspection is cleared before and after the test.
+pub async fn stored_session<S>(session: &S)
+where
+    S: StoredSession,
+{
+    const NAME: &str = "com.test.Test1";
+    const NAME_1: &str = "com.test.TestDifferent";
+    const NAME_2: &str = "com.test.TestOtherDifferent";
+
+    fn sorted(mut interfaces: Vec<IntrospectionInterface>) -> Vec<IntrospectionInterface> {
+        interfaces.sort_unstable();
+        interfaces
+    }
+
+    session.clear_introspection().await;
+    assert!(session.load_introspection().await.unwrap().is_empty());
+
+    // simple add
+    let interface = IntrospectionInterface::new(NAME.to_string(), 0, 1);
+    session
+        .add_interfaces(&[IntrospectionInterface::new(NAME, 0, 1)])
+        .await
+        .unwrap();
+    let stored = session.load_introspection().await.unwrap();
+    assert_eq!(stored, [interface]);
+
+    // replace or insert, even with an earlier version
+    let interfaces = [
+        IntrospectionInterface::new(NAME, 1, 0),
+        IntrospectionInterface::new(NAME_1, 1, 0),
+        IntrospectionInterface::new(NAME_2, 1, 1),
+    ];
+    session.add_interfaces(&interfaces).await.unwrap();
+    let stored = sorted(session.load_introspection().await.unwrap());
+    let expected = sorted(interfaces.map(IntrospectionInterface::from).to_vec());
+    assert_eq!(stored, expected);
+
+    let interfaces = [
+        IntrospectionInterface::new(NAME, 0, 1),
+        IntrospectionInterface::new(NAME_1, 0, 1),
+    ];
+    session.add_interfaces(&interfaces).await.unwrap();
+    let stored = sorted(session.load_introspection().await.unwrap());
+    let expected = sorted(vec![
+        IntrospectionInterface::new(NAME.to_string(), 0, 1),
+        IntrospectionInterface::new(NAME_1.to_string(), 0, 1),
+        IntrospectionInterface::new(NAME_2.to_string(), 1, 1),
+    ]);
+    assert_eq!(stored, expected);
+
+    // remove only if all the fields match
+    session
+        .remove_interfaces(&[IntrospectionInterface::new(NAME, 1, 0)])
+        .await
+        .unwrap();
+    let stored = sorted(session.load_introspection().await.unwrap());
+    assert_eq!(stored, expected);
+
+    session
+        .remove_interfaces(&[
+            IntrospectionInterface::new(NAME, 0, 1),
+            IntrospectionInterface::new(NAME_2, 1, 1),
+        ])
+        .await
+        .unwrap();
+    let stored = session.load_introspection().await.unwrap();
+    assert_eq!(
+        stored,
+        [IntrospectionInterface::new(NAME_1.to_string(), 0, 1)]
+    );
+
+    // store adds or replaces like add
+    let interfaces = [
+        IntrospectionInterface::new(NAME.to_string(), 1, 0),
+        IntrospectionInterface::new(NAME_2.to_string(), 1, 0),
+    ];
+    session.store_introspection(&interfaces).await;
+    let stored = sorted(session.load_introspection().await.unwrap());
+    let expected = sorted(vec![
+        IntrospectionInterface::new(NAME.to_string(), 1, 0),
+        IntrospectionInterface::new(NAME_1.to_string(), 0, 1),
+        IntrospectionInterface::new(NAME_2.to_string(), 1, 0),
+    ]);
+    assert_eq!(stored, expected);
+
+    // clear
+    session.clear_introspection().await;
+    assert!(session.load_introspection().await.unwrap().is_empty());
+}
@@ -354,12 +354,12 @@ impl From<(&Key, &Value)> for OptStoredProp {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::store::tests::test_property_store;
+    use crate::store::conformance;
 
     #[tokio::test]
     async fn test_memory_store() {
         let db = MemoryStore::new();
 
-        test_property_store(db).await;
+        conformance::property_store(&db).await;
     }
 }
@@ -21,7 +21,7 @@
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::future::Future;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU64, NonZeroUsize};
 
 use astarte_device_error::Error;
 use astarte_interfaces::schema::Ownership;
@@ -31,10 +31,17 @@ use self::error::StoreError;
 pub use self::sqlite::SqliteStore;
 use crate::interfaces::MappingRef;
 use crate::retention::StoredRetention;
+use crate::retention::events::RetentionEventSender;
+use crate::retention::inspect::{PendingPublish, RetentionFilter};
+use crate::retention::order::ResendOrder;
+use crate::retention::quota::RetentionQuotas;
 use crate::retention::{Id, PublishInfo, RetentionError, StoredInterface};
 use crate::session::{IntrospectionInterface, SessionError, StoredSession};
 use crate::types::AstarteData;
 
+#[cfg(any(test, feature = "store-conformance"))]
+#[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(feature = "store-conformance")))]
+pub mod conformance;
 pub mod error;
 pub mod memory;
 #[cfg(test)]
@@ -117,6 +124,60 @@ impl StoredRetention for MissingCapability {
     ) -> Result<(), Error<RetentionError>> {
         unreachable!("the type is Un-constructable");
     }
+
+    async fn set_max_retention_bytes(
+        &self,
+        _size: NonZeroU64,
+    ) -> Result<(), Error<RetentionError>> {
+        unreachable!("the type is Un-constructable");
+    }
+
+    async fn set_retention_quotas(
+        &self,
+        _quotas: &RetentionQuotas,
+    ) -> Result<(), Error<RetentionError>> {
+        unreachable!("the type is Un-constructable");
+    }
+
+    async fn count_publishes(
+        &self,
+        _filter: &RetentionFilter,
+    ) -> Result<usize, Error<RetentionError>> {
+        unreachable!("the type is Un-constructable");
+    }
+
+    async fn list_publishes(
+        &self,
+        _filter: &RetentionFilter,
+        _limit: usize,
+    ) -> Result<Vec<PendingPublish>, Error<RetentionError>> {
+        unreachable!("the type is Un-constructable");
+    }
+
+    async fn purge_publishes(
+        &self,
+        _filter: &RetentionFilter,
+    ) -> Result<usize, Error<RetentionError>> {
+        unreachable!("the type is Un-constructable");
+    }
+
+    async fn expire_publishes(
+        &self,
+        _filter: &RetentionFilter,
+    ) -> Result<usize, Error<RetentionError>> {
+        unreachable!("the type is Un-constructable");
+    }
+
+    async fn set_resend_order(&self, _order: ResendOrder) -> Result<(), Error<RetentionError>> {
+        unreachable!("the type is Un-constructable");
+    }
+
+    async fn set_retention_events(
+        &self,
+        _events: RetentionEventSender,
+    ) -> Result<(), Error<RetentionError>> {
+        unreachable!("the type is Un-constructable");
+    }
 }
 
 #[cfg_attr(__coverage, coverage(off))]
@@ -397,247 +458,10 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
-
     use crate::store::memory::MemoryStore;
-    use crate::test::{
-        E2E_DEVICE_PROPERTY, E2E_DEVICE_PROPERTY_NAME, E2E_SERVER_PROPERTY,
-        E2E_SERVER_PROPERTY_NAME,
-    };
-
-    use chrono::{TimeZone, Utc};
 
     use super::*;
 
-    pub(crate) async fn test_property_store<S>(store: S)
-    where
-        S: PropertyStore,
-    {
-        let ty = AstarteData::Integer(23);
-        let prop = StoredProp {
-            interface: "com.test",
-            path: "/test",
-            value: &ty,
-            interface_major: 1,
-            ownership: Ownership::Device,
-        };
-        let property_mapping = PropertyMapping::from(&prop);
-
-        store.clear().await.unwrap();
-
-        // non existing
-        assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
-
-        store.store_prop(prop).await.unwrap();
-        assert_eq!(
-            store.load_prop(&property_mapping).await.unwrap().unwrap(),
-            ty
-        );
-
-        let updated = store
-            .update_state(
-                &property_mapping,
-                PropertyState::Completed,
-                Some(ty.clone()),
-            )
-            .await
-            .unwrap();
-        assert!(updated);
-
-        let mut property_mapping_next = property_mapping;
-        property_mapping_next.version_major = 2;
-
-        //major version mismatch
-        assert_eq!(store.load_prop(&property_mapping_next).await.unwrap(), None);
-
-        // after mismatch the path should be deleted
-        assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
-
-        // unset
-        store.store_prop(prop).await.unwrap();
-        assert_eq!(
-            store.load_prop(&property_mapping).await.unwrap().unwrap(),
-            ty
-        );
-        store.unset_prop(&property_mapping).await.unwrap();
-        assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
-        // with unset
-        assert!(store.device_props().await.unwrap().is_empty());
-        assert!(store.load_all_props().await.unwrap().is_empty());
-        assert!(store.server_props().await.unwrap().is_empty());
-        assert_eq!(
-            &[StoredProp {
-                interface: "com.test",
-                path: "/test",
-                value: None,
-                interface_major: 1,
-                ownership: Ownership::Device,
-            }],
-            store
-                .device_props_with_unset(PropertyState::Changed, 1, 0)
-                .await
-                .unwrap()
-                .as_slice()
-        );
-        // delete property if mateches value, should delete the property
-        let updated = store
-            .delete_expected_prop(&property_mapping, None)
-            .await
-            .unwrap();
-        assert!(updated);
-        store.delete_prop(&property_mapping).await.unwrap();
-        // should now be empty
-        let props = store
-            .device_props_with_unset(PropertyState::Changed, 1, 0)
-            .await
-            .unwrap();
-        println!("{:?}", props);
-        assert!(props.is_empty());
-
-        // delete
-        store.store_prop(prop).await.unwrap();
-        assert_eq!(
-            store.load_prop(&property_mapping).await.unwrap().unwrap(),
-            ty
-        );
-        store.delete_prop(&property_mapping).await.unwrap();
-        assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
-
-        // clear
-        store.store_prop(prop).await.unwrap();
-        assert_eq!(
-            store.load_prop(&property_mapping).await.unwrap().unwrap(),
-            ty
-        );
-        store.clear().await.unwrap();
-        assert_eq!(store.load_prop(&property_mapping).await.unwrap(), None);
-
-        // load all props
-        let device_prop_interface = Properties::from_str(E2E_DEVICE_PROPERTY).unwrap();
-        let device_prop = StoredProp {
-            interface: E2E_DEVICE_PROPERTY_NAME.to_string(),
-            path: "/sensor2/integer_endpoint".to_string(),
-            value: ty.clone(),
-            interface_major: 1,
-            ownership: Ownership::Device,
-        };
-        let server_prop_interface = Properties::from_str(E2E_SERVER_PROPERTY).unwrap();
-        let server_prop = StoredProp {
-            interface: E2E_SERVER_PROPERTY_NAME.to_string(),
-            path: "/sensor2/integer_endpoint".to_string(),
-            value: ty.clone(),
-            interface_major: 1,
-            ownership: Ownership::Server,
-        };
-
-        store.store_prop(device_prop.as_prop_ref()).await.unwrap();
-        store.store_prop(server_prop.as_prop_ref()).await.unwrap();
-
-        let expected = [device_prop.clone(), server_prop.clone()];
-
-        let mut props = store.load_all_props().await.unwrap();
-        props.sort_unstable_by(|a, b| a.interface.cmp(&b.interface));
-        assert_eq!(props, expected);
-
-        // check that device properties are in the changed state
-        let device_props_changed = store
-            .device_props_with_unset(PropertyState::Changed, 1, 0)
-            .await
-            .unwrap();
-        assert_eq!(
-            device_props_changed[0].value,
-            Some(device_prop.value.clone())
-        );
-        assert_eq!(device_props_changed[0].interface, device_prop.interface);
-        assert_eq!(device_props_changed[0].path, device_prop.path);
-        // update the state of the device property to completed
-        assert!(
-            store
-                .update_state(
-                    &PropertyMapping::from(&device_prop),
-                    PropertyState::Completed,
-                    Some(device_prop.value.clone())
-                )
-                .await
-                .unwrap()
-        );
-        // check that no properties are in the changed state
-        let device_props_changed = store
-            .device_props_with_unset(PropertyState::Changed, 1, 0)
-            .await
-            .unwrap();
-        assert!(device_props_changed.is_empty());
-        // reset the state of the properties to bring changes back
-        store.reset_state(Ownership::Device).await.unwrap();
-        // ensure state is now changed
-        let device_props_changed = store
-            .device_props_with_unset(PropertyState::Changed, 1, 0)
-            .await
-            .unwrap();
-        assert_eq!(device_props_changed.len(), 1);
-
-        let dev_props = store.device_props().await.unwrap();
-        assert_eq!(dev_props, std::slice::from_ref(&device_prop));
-
-        let serv_props = store.server_props().await.unwrap();
-        assert_eq!(serv_props, std::slice::from_ref(&server_prop));
-
-        // props from interface
-        let props = store.interface_props(&device_prop_interface).await.unwrap();
-        assert_eq!(props, std::slice::from_ref(&device_prop));
-        let props = store.interface_props(&server_prop_interface).await.unwrap();
-        assert_eq!(props, std::slice::from_ref(&server_prop));
-
-        // delete interface properties
-        store
-            .delete_interface(&device_prop_interface)
-            .await
-            .unwrap();
-        let prop = store.interface_props(&device_prop_interface).await.unwrap();
-        assert!(prop.is_empty());
-
-        // test all types
-        let all_types = [
-            AstarteData::Double(4.5.try_into().unwrap()),
-            AstarteData::Integer(-4),
-            AstarteData::Boolean(true),
-            AstarteData::LongInteger(45543543534_i64),
-            AstarteData::String("hello".into()),
-            AstarteData::BinaryBlob(b"hello".to_vec()),
-            AstarteData::DateTime(TimeZone::timestamp_opt(&Utc, 1627580808, 0).unwrap()),
-            AstarteData::DoubleArray([1.2, 3.4, 5.6, 7.8].map(|v| v.try_into().unwrap()).to_vec()),
-            AstarteData::IntegerArray(vec![1, 3, 5, 7]),
-            AstarteData::BooleanArray(vec![true, false, true, true]),
-            AstarteData::LongIntegerArray(vec![45543543534_i64, 45543543535_i64, 45543543536_i64]),
-            AstarteData::StringArray(vec!["hello".to_owned(), "world".to_owned()]),
-            AstarteData::BinaryBlobArray(vec![b"hello".to_vec(), b"world".to_vec()]),
-            AstarteData::DateTimeArray(vec![
-                TimeZone::timestamp_opt(&Utc, 1627580808, 0).unwrap(),
-                TimeZone::timestamp_opt(&Utc, 1627580809, 0).unwrap(),
-                TimeZone::timestamp_opt(&Utc, 1627580810, 0).unwrap(),
-            ]),
-        ];
-
-        for ty in all_types {
-            let path = format!("/test/{}", ty.display_type());
-
-            let prop = StoredProp {
-                interface: "com.test",
-                path: &path,
-                value: &ty,
-                interface_major: 1,
-                ownership: Ownership::Server,
-            };
-            let prop_mapping = PropertyMapping::from(&prop);
-
-            store.store_prop(prop).await.unwrap();
-
-            let res = store.load_prop(&prop_mapping).await.unwrap();
-
-            assert_eq!(res, Some(ty));
-        }
-    }
-
     /// Test that the error is Send + Sync + 'static to be send across task boundaries.
     #[tokio::test]
     async fn error_should_compatible_with_tokio() {
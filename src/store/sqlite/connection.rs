@@ -16,10 +16,12 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::HashMap;
 use std::fmt::Display;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::ops::{Deref, DerefMut};
 use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
 use astarte_device_error::{Error, WrapError};
 use rusqlite::types::FromSql;
@@ -28,10 +30,16 @@ use tracing::{debug, error, instrument, trace, warn};
 
 use crate::builder::DEFAULT_STORE_CAPACITY;
 use crate::error::Report;
+use crate::retention::events::{RetentionEvent, RetentionEventSender};
+use crate::retention::order::ResendOrder;
+use crate::retention::quota::RetentionQuotas;
 
 use super::options::{SqliteOptions, SqlitePragmas};
 use super::{SQLITE_BUSY_TIMEOUT, SQLITE_CACHE_SIZE, SqliteError};
 
+/// Value returned by the `auto_vacuum` pragma for the incremental mode.
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
 #[cfg(feature = "sqlite-trace")]
 /// Logs the execution of SQLite statements
 #[tracing::instrument(name = "sqlite", skip_all)]
@@ -135,7 +143,10 @@ pub(crate) trait SqliteConnection: Sized + Deref<Target = Connection> {
         self.set_pragma("foreign_keys", &true)?;
         self.set_pragma("busy_timeout", &SQLITE_BUSY_TIMEOUT)?;
         self.set_pragma("synchronous", "NORMAL")?;
-        self.set_pragma("auto_vacuum", "INCREMENTAL")?;
+        // Once a VACUUM writes it in the header, setting it again fails on the read only connections
+        if self.get_pragma::<i64>("auto_vacuum")? != AUTO_VACUUM_INCREMENTAL {
+            self.set_pragma("auto_vacuum", "INCREMENTAL")?;
+        }
         self.set_pragma("temp_store", "MEMORY")?;
         self.set_pragma("cache_size", &SQLITE_CACHE_SIZE)?;
 
@@ -161,11 +172,205 @@ pub(crate) trait SqliteConnection: Sized + Deref<Target = Connection> {
     }
 }
 
+/// Retention configuration of each tenant.
+///
+/// It's kept in the pool and shared with the [`WriteConnection`], so it isn't lost when the
+/// connection is recreated.
+#[derive(Debug, Default)]
+pub(crate) struct TenantsConfig {
+    /// Maximum number of retention item to store for each tenant
+    retention_capacity: HashMap<String, NonZeroUsize>,
+    /// Maximum size in bytes of the retention payloads for each tenant
+    retention_max_bytes: HashMap<String, NonZeroU64>,
+    /// Retention quotas for each tenant
+    retention_quotas: HashMap<String, Arc<RetentionQuotas>>,
+    /// Sender of the removed retention publishes for each tenant
+    retention_events: HashMap<String, RetentionEventSender>,
+    /// Order of the unsent retention publishes for each tenant
+    resend_order: HashMap<String, ResendOrder>,
+}
+
+/// Events of the removed retention publishes, waiting for their changes to be committed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PendingEvents(Arc<Mutex<Vec<(RetentionEventSender, RetentionEvent)>>>);
+
+impl PendingEvents {
+    fn lock(&self) -> MutexGuard<'_, Vec<(RetentionEventSender, RetentionEvent)>> {
+        // The lock is only held to push or drain the events, which never panics
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the number of events waiting.
+    pub(crate) fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Discards the events queued after the first `len`, since their changes were rolled back.
+    pub(crate) fn truncate(&self, len: usize) {
+        let mut events = self.lock();
+
+        if events.len() > len {
+            trace!(
+                discarded = events.len() - len,
+                "discarding rolled back events"
+            );
+
+            events.truncate(len);
+        }
+    }
+
+    /// Sends the events, once their changes are committed.
+    pub(crate) fn flush(&self) {
+        let events = std::mem::take(&mut *self.lock());
+
+        for (sender, event) in events {
+            sender.send(event);
+        }
+    }
+}
+
+/// Sender of the retention events of a tenant, queued until the changes are committed.
+#[derive(Debug, Clone)]
+pub(crate) struct TenantEvents {
+    sender: RetentionEventSender,
+    pending: PendingEvents,
+}
+
+impl TenantEvents {
+    /// Queues the event, it's sent when the pending events are flushed.
+    pub(crate) fn send(&self, event: RetentionEvent) {
+        self.pending.lock().push((self.sender.clone(), event));
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct WriteConnection {
     connection: Connection,
-    /// Maximum number of retention item to store
-    pub(crate) retention_capacity: NonZeroUsize,
+    /// Configuration of the tenants, shared with the pool
+    tenants: Arc<Mutex<TenantsConfig>>,
+    /// Retention events of the changes not committed yet
+    pending_events: PendingEvents,
+}
+
+impl WriteConnection {
+    /// Returns the connection or creates a new one, using the configuration of the tenants.
+    pub(crate) fn lazy_with_tenants(
+        value: Option<Self>,
+        db_file: &Path,
+        options: &SqliteOptions,
+        tenants: &Arc<Mutex<TenantsConfig>>,
+    ) -> Result<Self, Error<SqliteError>> {
+        let mut writer = Self::lazy(value, db_file, options)?;
+
+        writer.tenants = Arc::clone(tenants);
+
+        Ok(writer)
+    }
+
+    fn tenants(&self) -> MutexGuard<'_, TenantsConfig> {
+        // The lock is only held to read or update the maps, which never panics
+        self.tenants.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the maximum number of retention items of the tenant.
+    pub(crate) fn retention_capacity(&self, tenant: &str) -> NonZeroUsize {
+        self.tenants()
+            .retention_capacity
+            .get(tenant)
+            .copied()
+            .unwrap_or(DEFAULT_STORE_CAPACITY)
+    }
+
+    /// Sets the maximum number of retention items of the tenant.
+    pub(crate) fn set_retention_capacity(&mut self, tenant: &str, capacity: NonZeroUsize) {
+        self.tenants()
+            .retention_capacity
+            .insert(tenant.to_string(), capacity);
+    }
+
+    /// Returns the maximum size in bytes of the retention payloads of the tenant, if any.
+    pub(crate) fn retention_max_bytes(&self, tenant: &str) -> Option<NonZeroU64> {
+        self.tenants().retention_max_bytes.get(tenant).copied()
+    }
+
+    /// Sets the maximum size in bytes of the retention payloads of the tenant.
+    pub(crate) fn set_retention_max_bytes(&mut self, tenant: &str, size: NonZeroU64) {
+        self.tenants()
+            .retention_max_bytes
+            .insert(tenant.to_string(), size);
+    }
+
+    /// Returns the retention quotas of the tenant, if any.
+    pub(crate) fn retention_quotas(&self, tenant: &str) -> Option<Arc<RetentionQuotas>> {
+        self.tenants().retention_quotas.get(tenant).cloned()
+    }
+
+    /// Sets the retention quotas of the tenant.
+    pub(crate) fn set_retention_quotas(&mut self, tenant: &str, quotas: RetentionQuotas) {
+        self.tenants()
+            .retention_quotas
+            .insert(tenant.to_string(), Arc::new(quotas));
+    }
+
+    /// Returns the sender of the removed retention publishes of the tenant, if any.
+    ///
+    /// The events are queued, they are sent by the pool after the changes are committed.
+    pub(crate) fn retention_events(&self, tenant: &str) -> Option<TenantEvents> {
+        let sender = self.tenants().retention_events.get(tenant).cloned()?;
+
+        Some(TenantEvents {
+            sender,
+            pending: self.pending_events.clone(),
+        })
+    }
+
+    /// Returns the retention events of the changes not committed yet.
+    pub(crate) fn pending_events(&self) -> &PendingEvents {
+        &self.pending_events
+    }
+
+    /// Sends the pending events if there is no open transaction.
+    ///
+    /// Without a transaction all the changes are already committed.
+    pub(crate) fn flush_committed_events(&self) {
+        if self.is_autocommit() {
+            self.pending_events.flush();
+        }
+    }
+
+    /// Sets the sender of the removed retention publishes of the tenant.
+    pub(crate) fn set_retention_events(&mut self, tenant: &str, events: RetentionEventSender) {
+        self.tenants()
+            .retention_events
+            .insert(tenant.to_string(), events);
+    }
+
+    /// Returns the order of the unsent retention publishes of the tenant.
+    pub(crate) fn resend_order(&self, tenant: &str) -> ResendOrder {
+        self.tenants()
+            .resend_order
+            .get(tenant)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets the order of the unsent retention publishes of the tenant.
+    pub(crate) fn set_resend_order(&mut self, tenant: &str, order: ResendOrder) {
+        self.tenants()
+            .resend_order
+            .insert(tenant.to_string(), order);
+    }
+
+    /// Removes the configuration of the tenant.
+    pub(crate) fn remove_tenant(&mut self, tenant: &str) {
+        let mut tenants = self.tenants();
+
+        tenants.retention_capacity.remove(tenant);
+        tenants.retention_max_bytes.remove(tenant);
+        tenants.retention_quotas.remove(tenant);
+        tenants.retention_events.remove(tenant);
+        tenants.resend_order.remove(tenant);
+    }
 }
 
 impl Deref for WriteConnection {
@@ -199,7 +404,8 @@ impl SqliteConnection for WriteConnection {
 
         let connection = Self {
             connection,
-            retention_capacity: DEFAULT_STORE_CAPACITY,
+            tenants: Arc::default(),
+            pending_events: PendingEvents::default(),
         };
 
         connection.apply_pragmas(options)?;
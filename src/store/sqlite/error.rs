@@ -48,6 +48,8 @@ pub enum SqliteError {
     Join,
     /// Couldn't convert passed input
     Conversion,
+    /// The retention quota doesn't allow storing the publish
+    QuotaExceeded,
 }
 
 impl Display for SqliteError {
@@ -65,6 +67,7 @@ impl Display for SqliteError {
             Self::Reader => write!(f, "couldn't acquire a reader permit"),
             Self::Join => write!(f, "couldn't join the connection task"),
             Self::Conversion => write!(f, "couldn't convert passed input"),
+            Self::QuotaExceeded => write!(f, "retention quota exceeded"),
         }
     }
 }
@@ -49,8 +49,11 @@ pub(crate) mod connection;
 pub mod error;
 pub mod options;
 pub(crate) mod pool;
+pub mod shared;
 pub(crate) mod statements;
 
+pub use self::shared::SqliteSharedStore;
+
 /// Milliseconds for the busy timeout
 ///
 /// <https://www.sqlite.org/c3ref/busy_timeout.html>
@@ -86,6 +89,11 @@ pub const SQLITE_WAL_AUTOCHECKPOINT: u32 = 1000;
 /// This is the default if we cannot access the available_parallelism
 pub const DEFAULT_MAX_READERS: NonZero<usize> = NonZero::<usize>::new(4).unwrap();
 
+/// Maximum number of writes committed in a single transaction.
+///
+/// This is the default if it's not configured in the [`SqliteOptions`].
+pub const DEFAULT_WRITE_BATCH_SIZE: NonZero<usize> = NonZero::<usize>::new(64).unwrap();
+
 /// Error when converting a u8 into the [`Ownership`] struct.
 #[derive(Debug, thiserror::Error)]
 #[error("invalid ownership value {value}")]
@@ -421,6 +429,10 @@ fn from_stored_type(value: u8) -> Result<MappingType, Error<ValueError>> {
 #[derive(Clone, Debug)]
 pub struct SqliteStore {
     pub(crate) pool: Arc<Connections>,
+    /// Namespace of the data in the database.
+    ///
+    /// It's empty for a store used by a single device, see [`SqliteSharedStore`].
+    pub(crate) tenant: Arc<str>,
 }
 
 impl SqliteStore {
@@ -433,6 +445,7 @@ impl SqliteStore {
     async fn new(db_file: PathBuf, options: SqliteOptions) -> Result<Self, Error<SqliteError>> {
         let sqlite_store = SqliteStore {
             pool: Arc::new(Connections::new(db_file, options)),
+            tenant: Arc::from(""),
         };
 
         sqlite_store.migrate().await?;
@@ -508,6 +521,9 @@ impl SqliteStore {
             include_query!("migrations/0002_unset_property.sql"),
             include_query!("migrations/0003_session.sql"),
             include_query!("migrations/0004_sent_properties.sql"),
+            include_query!("migrations/0005_tenant.sql"),
+            include_query!("migrations/0006_retention_codec.sql"),
+            include_query!("migrations/0007_credentials.sql"),
         ];
         const USER_VERSION: u32 = {
             assert!(MIGRATIONS.len() < (u32::MAX as usize));
@@ -589,8 +605,9 @@ impl PropertyStore for SqliteStore {
             .wrap_err(StoreError::Store)?;
 
         let prop = StoredProp::<String, AstarteData>::from(prop);
+        let tenant = Arc::clone(&self.tenant);
         self.pool
-            .acquire_writer(move |writer| writer.store_prop((&prop).into(), &buf))
+            .acquire_writer(move |writer| writer.store_prop(&tenant, (&prop).into(), &buf))
             .await
             .wrap_err(StoreError::Store)?;
 
@@ -603,13 +620,14 @@ impl PropertyStore for SqliteStore {
         state: PropertyState,
         expected: Option<AstarteData>,
     ) -> Result<bool, Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
         let interface_name = property.interface_name().to_string();
         let path = property.path().to_string();
 
         let updated = self
             .pool
             .acquire_writer(move |writer| {
-                writer.update_state(&interface_name, &path, expected.as_ref(), state)
+                writer.update_state(&tenant, &interface_name, &path, expected.as_ref(), state)
             })
             .await
             .wrap_err(StoreError::UpdateState)?;
@@ -621,12 +639,13 @@ impl PropertyStore for SqliteStore {
         &self,
         property: &PropertyMapping<'_>,
     ) -> Result<Option<AstarteData>, Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
         let interface_name = property.interface_name().to_string();
         let path = property.path().to_string();
 
         let opt_record = self
             .pool
-            .acquire_reader(move |reader| reader.load_prop(&interface_name, &path))
+            .acquire_reader(move |reader| reader.load_prop(&tenant, &interface_name, &path))
             .await
             .wrap_err(StoreError::Load)?;
 
@@ -663,21 +682,23 @@ impl PropertyStore for SqliteStore {
     }
 
     async fn unset_prop(&self, property: &PropertyMapping<'_>) -> Result<(), Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
         let interface_name = property.interface_name().to_string();
         let path = property.path().to_string();
 
         self.pool
-            .acquire_writer(move |writer| writer.unset_prop(&interface_name, &path))
+            .acquire_writer(move |writer| writer.unset_prop(&tenant, &interface_name, &path))
             .await
             .wrap_err(StoreError::Unset)
     }
 
     async fn delete_prop(&self, property: &PropertyMapping<'_>) -> Result<(), Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
         let interface_name = property.interface_name().to_string();
         let path = property.path().to_string();
 
         self.pool
-            .acquire_writer(move |writer| writer.delete_prop(&interface_name, &path))
+            .acquire_writer(move |writer| writer.delete_prop(&tenant, &interface_name, &path))
             .await
             .wrap_err(StoreError::Delete)
     }
@@ -687,13 +708,14 @@ impl PropertyStore for SqliteStore {
         property: &PropertyMapping<'_>,
         expected: Option<AstarteData>,
     ) -> Result<bool, Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
         let interface_name = property.interface_name().to_string();
         let path = property.path().to_string();
 
         let updated = self
             .pool
             .acquire_writer(move |writer| {
-                writer.delete_expected_prop(&interface_name, &path, expected.as_ref())
+                writer.delete_expected_prop(&tenant, &interface_name, &path, expected.as_ref())
             })
             .await
             .wrap_err(StoreError::DeleteInterface)?;
@@ -702,29 +724,37 @@ impl PropertyStore for SqliteStore {
     }
 
     async fn clear(&self) -> Result<(), Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
-            .acquire_writer(|writer| writer.clear_props())
+            .acquire_writer(move |writer| writer.clear_props(&tenant))
             .await
             .wrap_err(StoreError::Clear)
     }
 
     async fn load_all_props(&self) -> Result<Vec<StoredProp>, Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
-            .acquire_reader(|reader| reader.load_all_props())
+            .acquire_reader(move |reader| reader.load_all_props(&tenant))
             .await
             .wrap_err(StoreError::LoadAll)
     }
 
     async fn device_props(&self) -> Result<Vec<StoredProp>, Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
-            .acquire_reader(|reader| reader.props_with_ownership(Ownership::Device))
+            .acquire_reader(move |reader| reader.props_with_ownership(&tenant, Ownership::Device))
             .await
             .wrap_err(StoreError::DeviceProps)
     }
 
     async fn server_props(&self) -> Result<Vec<StoredProp>, Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
-            .acquire_reader(|reader| reader.props_with_ownership(Ownership::Server))
+            .acquire_reader(move |reader| reader.props_with_ownership(&tenant, Ownership::Server))
             .await
             .wrap_err(StoreError::ServerProps)
     }
@@ -733,19 +763,21 @@ impl PropertyStore for SqliteStore {
         &self,
         interface: &Properties,
     ) -> Result<Vec<StoredProp>, Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
         let interface_name = interface.name().to_string();
 
         self.pool
-            .acquire_reader(move |reader| reader.interface_props(&interface_name))
+            .acquire_reader(move |reader| reader.interface_props(&tenant, &interface_name))
             .await
             .wrap_err(StoreError::InterfaceProps)
     }
 
     async fn delete_interface(&self, interface: &Properties) -> Result<(), Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
         let interface_name = interface.name().to_string();
 
         self.pool
-            .acquire_writer(move |writer| writer.delete_interface_props(&interface_name))
+            .acquire_writer(move |writer| writer.delete_interface_props(&tenant, &interface_name))
             .await
             .wrap_err(StoreError::DeleteInterface)
     }
@@ -756,17 +788,21 @@ impl PropertyStore for SqliteStore {
         limit: usize,
         offset: usize,
     ) -> Result<Vec<OptStoredProp>, Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
             .acquire_reader(move |reader| {
-                reader.props_with_unset(Ownership::Device, state, limit, offset)
+                reader.props_with_unset(&tenant, Ownership::Device, state, limit, offset)
             })
             .await
             .wrap_err(StoreError::DeviceProps)
     }
 
     async fn reset_state(&self, ownership: Ownership) -> Result<(), Error<StoreError>> {
+        let tenant = Arc::clone(&self.tenant);
+
         self.pool
-            .acquire_writer(move |writer| writer.reset_state(ownership))
+            .acquire_writer(move |writer| writer.reset_state(&tenant, ownership))
             .await
             .wrap_err(StoreError::ResetState)
     }
@@ -787,7 +823,7 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
-    use crate::store::tests::test_property_store;
+    use crate::store::conformance;
 
     #[tokio::test]
     async fn test_sqlite_store() {
@@ -798,7 +834,9 @@ mod tests {
             .await
             .unwrap();
 
-        test_property_store(db).await;
+        conformance::property_store(&db).await;
+        conformance::stored_retention(&db).await;
+        conformance::stored_session(&db).await;
     }
 
     #[tokio::test]
@@ -23,6 +23,7 @@
 
 use std::num::NonZero;
 use std::path::Path;
+use std::time::Duration;
 
 use astarte_device_error::{Error, WrapError};
 use serde::{Deserialize, Serialize};
@@ -32,8 +33,8 @@ use crate::error::Report;
 
 use super::connection::SqliteConnection;
 use super::{
-    DEFAULT_MAX_READERS, SQLITE_DEFAULT_DB_MAX_SIZE, SQLITE_JOURNAL_SIZE_LIMIT, Size, SqliteError,
-    SqliteStore,
+    DEFAULT_MAX_READERS, DEFAULT_WRITE_BATCH_SIZE, SQLITE_DEFAULT_DB_MAX_SIZE,
+    SQLITE_JOURNAL_SIZE_LIMIT, Size, SqliteError, SqliteStore,
 };
 
 /// Choices of limit of the size of the sqlite database
@@ -48,6 +49,19 @@ pub enum SizeLimit {
     DbMaxSize(Size),
 }
 
+/// Compression of the payloads stored in the retention.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionCompression {
+    /// Store the payloads as they are.
+    #[default]
+    None,
+    /// Compress the payloads with zlib.
+    ///
+    /// A payload is stored compressed only if it's smaller than the original.
+    Zlib,
+}
+
 /// SQLite options that can be set externally to tweak the behaviour of the connections.
 #[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
 pub struct SqliteOptions {
@@ -65,6 +79,12 @@ pub struct SqliteOptions {
     /// limit only applies when the WAL journal is truncated. We set both options to correctly limit
     /// the size of the WAL file.
     journal_size_limit: Option<Size>,
+    /// Maximum number of concurrent writes committed in a single transaction.
+    write_batch_size: Option<NonZero<usize>>,
+    /// Maximum time a write waits for other writes before committing the batch.
+    write_batch_latency: Option<Duration>,
+    /// Compression of the stored retention payloads.
+    retention_compression: Option<RetentionCompression>,
 }
 
 impl SqliteOptions {
@@ -100,6 +120,24 @@ impl SqliteOptions {
         self.journal_size_limit.unwrap_or(SQLITE_JOURNAL_SIZE_LIMIT)
     }
 
+    /// Returns the write_batch_size or the default one
+    pub fn write_batch_size(&self) -> NonZero<usize> {
+        self.write_batch_size.unwrap_or(DEFAULT_WRITE_BATCH_SIZE)
+    }
+
+    /// Returns the write_batch_latency or the default one
+    ///
+    /// By default the writes don't wait, and only the ones queued while the writer is busy are
+    /// batched together.
+    pub fn write_batch_latency(&self) -> Duration {
+        self.write_batch_latency.unwrap_or(Duration::ZERO)
+    }
+
+    /// Returns the retention_compression or the default one
+    pub fn retention_compression(&self) -> RetentionCompression {
+        self.retention_compression.unwrap_or_default()
+    }
+
     /// Sets the database size limit
     #[must_use]
     pub fn set_db_max_size(mut self, db_size_limit: Size) -> Self {
@@ -124,6 +162,35 @@ impl SqliteOptions {
         self
     }
 
+    /// Sets the maximum number of writes committed in a single transaction
+    #[must_use]
+    pub fn set_write_batch_size(mut self, write_batch_size: NonZero<usize>) -> Self {
+        self.write_batch_size = Some(write_batch_size);
+
+        self
+    }
+
+    /// Sets the maximum time a write waits for other writes to be committed in the same transaction
+    ///
+    /// Higher values reduce the number of commits under load, but increase the latency of each
+    /// write.
+    #[must_use]
+    pub fn set_write_batch_latency(mut self, write_batch_latency: Duration) -> Self {
+        self.write_batch_latency = Some(write_batch_latency);
+
+        self
+    }
+
+    /// Sets the compression of the payloads stored in the retention
+    ///
+    /// Already stored payloads are kept with the compression they were stored with.
+    #[must_use]
+    pub fn set_retention_compression(mut self, compression: RetentionCompression) -> Self {
+        self.retention_compression = Some(compression);
+
+        self
+    }
+
     /// Connect to the SQLite database using the default db name in the writable path.
     pub async fn with_writable_dir(
         self,
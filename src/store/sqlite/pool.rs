@@ -17,27 +17,109 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::num::NonZero;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use astarte_device_error::{Error, WrapError};
-use tokio::sync::{Mutex, RwLock, Semaphore};
-use tracing::{error, instrument, trace, warn};
+use tokio::sync::{Mutex, MutexGuard, RwLock, Semaphore, oneshot};
+use tracing::{debug, error, instrument, trace, warn};
 
 use crate::error::Report;
 use crate::store::sqlite::connection::SqliteConnection;
 
 use super::SqliteError;
-use super::connection::{ReadConnection, WriteConnection};
+use super::connection::{ReadConnection, TenantsConfig, WriteConnection};
 use super::options::SqliteOptions;
 
 type HandleResult<C, O, E> = Result<(C, Result<O, E>), E>;
 
+type Batch = Vec<Box<dyn QueuedWrite>>;
+
+/// Write operation waiting in the [`WriteQueue`] to be executed in a batch.
+trait QueuedWrite: Send {
+    /// Executes the operation in its own savepoint of the batch transaction.
+    fn execute(&mut self, writer: &mut WriteConnection);
+
+    /// Sends the result of the operation to the caller.
+    ///
+    /// If the batch wasn't committed, the caller will receive an error even if the operation
+    /// itself succeeded.
+    fn complete(self: Box<Self>, committed: bool);
+}
+
+struct Queued<F, O> {
+    f: Option<F>,
+    out: Option<Result<O, Error<SqliteError>>>,
+    tx: oneshot::Sender<Result<O, Error<SqliteError>>>,
+}
+
+impl<F, O> QueuedWrite for Queued<F, O>
+where
+    F: FnOnce(&mut WriteConnection) -> Result<O, Error<SqliteError>> + Send,
+    O: Send,
+{
+    fn execute(&mut self, writer: &mut WriteConnection) {
+        let Some(f) = self.f.take() else {
+            return;
+        };
+
+        self.out = Some(with_savepoint(writer, f));
+    }
+
+    fn complete(self: Box<Self>, committed: bool) {
+        let out = match self.out {
+            Some(Ok(_)) if !committed => Err(Error::with(
+                SqliteError::Transaction,
+                "couldn't commit the write batch",
+            )),
+            Some(out) => out,
+            None => Err(Error::with(
+                SqliteError::Transaction,
+                "couldn't begin the write batch",
+            )),
+        };
+
+        // The caller could have been dropped, we don't care about the result
+        let _ = self.tx.send(out);
+    }
+}
+
+/// Queue of the writes to group in a single transaction.
+#[derive(Default)]
+struct WriteQueue {
+    pending: VecDeque<Box<dyn QueuedWrite>>,
+    /// A worker task is running and will drain the queue.
+    worker: bool,
+}
+
+impl WriteQueue {
+    fn take_batch(&mut self, max: NonZero<usize>) -> Batch {
+        let len = self.pending.len().min(max.get());
+
+        self.pending.drain(..len).collect()
+    }
+}
+
+impl Debug for WriteQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WriteQueue")
+            .field("pending", &self.pending.len())
+            .field("worker", &self.worker)
+            .finish()
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Connections {
     db_file: Arc<Path>,
     options: RwLock<SqliteOptions>,
     writer: Mutex<Option<WriteConnection>>,
+    /// Configuration of the tenants, kept when the writer is recreated.
+    tenants: Arc<std::sync::Mutex<TenantsConfig>>,
+    /// Writes waiting to be committed together by [`Connections::queue_writer`].
+    queue: std::sync::Mutex<WriteQueue>,
     reader_sem: Semaphore,
     /// Use a FIFO queue for the connections to cycle through them all.
     readers: Mutex<VecDeque<ReadConnection>>,
@@ -52,15 +134,25 @@ impl Connections {
             db_file: db_file.into(),
             options: RwLock::new(options),
             writer: Mutex::new(None),
+            tenants: Arc::default(),
+            queue: std::sync::Mutex::new(WriteQueue::default()),
             reader_sem: Semaphore::new(readers.get()),
             readers: Mutex::new(VecDeque::with_capacity(readers.get())),
         }
     }
 
+    /// Returns the options of the connections.
+    pub(crate) async fn options(&self) -> SqliteOptions {
+        *self.options.read().await
+    }
+
     /// Acquire the write connection to the database.
     ///
     /// It will call the closure in a [`tokio::task::spawn_blocking`] so all the SQLite operation
     /// will not block the runtime.
+    ///
+    /// The writes already in the queue are committed before calling the closure, to preserve the
+    /// order of the operations.
     #[instrument(skip_all, fields(db = %self.db_file.display()))]
     pub(crate) async fn acquire_writer<F, O>(&self, f: F) -> Result<O, Error<SqliteError>>
     where
@@ -73,17 +165,35 @@ impl Connections {
 
         trace!("writer connection acquired");
 
+        let options = { *self.options.read().await };
+
+        let mut pending = self.lock_queue().pending.len();
+        while pending > 0 {
+            let batch = self.lock_queue().take_batch(options.write_batch_size());
+            if batch.is_empty() {
+                break;
+            }
+
+            debug!(len = batch.len(), "flushing queued writes");
+            pending = pending.saturating_sub(batch.len());
+
+            self.commit_queued(&mut writer_g, batch).await;
+        }
+
         let writer = writer_g.take();
         let db_file = Arc::clone(&self.db_file);
-        let options = { *self.options.read().await };
+        let tenants = Arc::clone(&self.tenants);
 
         // this need to be a spawn blocking to both support single and multi threaded runtimes
         let (writer, out) = tokio::task::spawn_blocking(
             move || -> HandleResult<WriteConnection, O, Error<SqliteError>> {
-                let mut writer = WriteConnection::lazy(writer, &db_file, &options)?;
+                let mut writer =
+                    WriteConnection::lazy_with_tenants(writer, &db_file, &options, &tenants)?;
 
                 let out = (f)(&mut writer);
 
+                writer.flush_committed_events();
+
                 Ok((writer, out))
             },
         )
@@ -101,6 +211,137 @@ impl Connections {
         out
     }
 
+    /// Queue a write to be committed in a single transaction with other concurrent writes.
+    ///
+    /// The writes are executed in the order they are queued, each one in its own savepoint so an
+    /// error will only rollback the changes of the failed operation. The result is returned after
+    /// the whole batch is committed.
+    ///
+    /// The maximum number of writes in a batch and the time to wait for other writes are
+    /// configured in the [`SqliteOptions`].
+    #[instrument(skip_all, fields(db = %self.db_file.display()))]
+    pub(crate) async fn queue_writer<F, O>(self: &Arc<Self>, f: F) -> Result<O, Error<SqliteError>>
+    where
+        F: FnOnce(&mut WriteConnection) -> Result<O, Error<SqliteError>> + Send + 'static,
+        O: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+
+        let spawn_worker = {
+            let mut queue = self.lock_queue();
+
+            queue.pending.push_back(Box::new(Queued {
+                f: Some(f),
+                out: None,
+                tx,
+            }));
+
+            trace!(len = queue.pending.len(), "write queued");
+
+            !std::mem::replace(&mut queue.worker, true)
+        };
+
+        if spawn_worker {
+            trace!("spawning write worker");
+
+            tokio::spawn(Arc::clone(self).write_worker());
+        }
+
+        rx.await.wrap_err_with(|_err| {
+            error!("write dropped before completion");
+
+            Error::with(SqliteError::Join, "write dropped before completion")
+        })?
+    }
+
+    /// Drains the queue in batches until it's empty.
+    #[instrument(skip_all, fields(db = %self.db_file.display()))]
+    async fn write_worker(self: Arc<Self>) {
+        loop {
+            let options = { *self.options.read().await };
+            let max_batch = options.write_batch_size();
+            let latency = options.write_batch_latency();
+
+            if !latency.is_zero() && self.lock_queue().pending.len() < max_batch.get() {
+                trace!(?latency, "waiting for other writes");
+
+                tokio::time::sleep(latency).await;
+            }
+
+            let mut writer_g = self.writer.lock().await;
+
+            let batch = {
+                let mut queue = self.lock_queue();
+
+                if queue.pending.is_empty() {
+                    queue.worker = false;
+
+                    break;
+                }
+
+                queue.take_batch(max_batch)
+            };
+
+            self.commit_queued(&mut writer_g, batch).await;
+        }
+
+        trace!("write queue drained");
+    }
+
+    /// Executes the writes in a single transaction and sends the results to the callers.
+    #[instrument(skip_all, fields(len = batch.len()))]
+    async fn commit_queued(
+        &self,
+        writer_g: &mut MutexGuard<'_, Option<WriteConnection>>,
+        mut batch: Batch,
+    ) {
+        let writer = writer_g.take();
+        let db_file = Arc::clone(&self.db_file);
+        let tenants = Arc::clone(&self.tenants);
+        let options = { *self.options.read().await };
+
+        let res = tokio::task::spawn_blocking(
+            move || -> Result<(WriteConnection, Batch, bool), Error<SqliteError>> {
+                let mut writer =
+                    WriteConnection::lazy_with_tenants(writer, &db_file, &options, &tenants)?;
+
+                let committed = match commit_batch(&mut writer, &mut batch) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        error!(error = %Report::new(err), "couldn't commit write batch");
+
+                        false
+                    }
+                };
+
+                Ok((writer, batch, committed))
+            },
+        )
+        .await;
+
+        match res {
+            Ok(Ok((writer, batch, committed))) => {
+                writer_g.replace(writer);
+
+                batch.into_iter().for_each(|op| op.complete(committed));
+            }
+            Ok(Err(err)) => {
+                // the callers will receive an error since the batch was dropped
+                error!(error = %Report::new(err), "couldn't create writer connection for batch");
+            }
+            Err(err) => {
+                error!(error = %Report::new(err), "couldn't join sqlite batch task");
+            }
+        }
+    }
+
+    fn lock_queue(&self) -> std::sync::MutexGuard<'_, WriteQueue> {
+        // The queue is only modified while holding the lock and never panics
+        self.queue
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
     /// Acquire one of the reader connection to the database.
     ///
     /// It will call the closure in a [`tokio::task::spawn_blocking`] so all the SQLite operation
@@ -152,6 +393,64 @@ impl Connections {
     }
 }
 
+/// Run the closure in a savepoint, rolling back its changes on error.
+pub(crate) fn with_savepoint<F, O>(
+    writer: &mut WriteConnection,
+    f: F,
+) -> Result<O, Error<SqliteError>>
+where
+    F: FnOnce(&mut WriteConnection) -> Result<O, Error<SqliteError>>,
+{
+    writer
+        .execute_batch("SAVEPOINT queued_write")
+        .wrap_err_msg(SqliteError::Transaction, "while creating savepoint")?;
+
+    let queued_events = writer.pending_events().len();
+
+    let out = (f)(writer);
+
+    let end = if out.is_ok() {
+        "RELEASE queued_write"
+    } else {
+        writer.pending_events().truncate(queued_events);
+
+        "ROLLBACK TO queued_write; RELEASE queued_write"
+    };
+
+    writer
+        .execute_batch(end)
+        .wrap_err_msg(SqliteError::Transaction, "while releasing savepoint")?;
+
+    out
+}
+
+/// Executes all the writes in a single transaction.
+fn commit_batch(writer: &mut WriteConnection, batch: &mut Batch) -> Result<(), Error<SqliteError>> {
+    writer
+        .execute_batch("BEGIN IMMEDIATE")
+        .wrap_err_msg(SqliteError::Transaction, "while beginning the batch")?;
+
+    for op in batch.iter_mut() {
+        op.execute(writer);
+    }
+
+    if let Err(err) = writer.execute_batch("COMMIT") {
+        if let Err(err) = writer.execute_batch("ROLLBACK") {
+            warn!(error = %Report::new(err), "couldn't rollback the batch");
+        }
+
+        writer.pending_events().truncate(0);
+
+        return Err(
+            Error::with(SqliteError::Transaction, "while committing the batch").set_source(err),
+        );
+    }
+
+    writer.pending_events().flush();
+
+    Ok(())
+}
+
 // Drop to manually close all the readers before the writer.
 impl Drop for Connections {
     #[instrument(skip_all, fields(db = %self.db_file.display()))]
@@ -175,10 +474,63 @@ impl Drop for Connections {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use astarte_device_error::WrapError;
+    use futures::FutureExt;
     use tempfile::TempDir;
 
+    use crate::retention::events::{
+        DropReason, RetentionEvent, RetentionEventSender, RetentionEvents,
+    };
+    use crate::retention::inspect::RetentionKind;
+    use crate::retention::{Context, Id};
+
     use super::*;
 
+    async fn create_table(pool: &Connections) {
+        pool.acquire_writer(|writer| {
+            writer
+                .execute(
+                    "CREATE TABLE queued (id INTEGER PRIMARY KEY AUTOINCREMENT, value INTEGER)",
+                    [],
+                )
+                .wrap_err(SqliteError::Query)
+        })
+        .await
+        .unwrap();
+    }
+
+    async fn insert(pool: &Arc<Connections>, value: i64) -> Result<(), Error<SqliteError>> {
+        pool.queue_writer(move |writer| {
+            writer
+                .execute("INSERT INTO queued (value) VALUES (?)", [value])
+                .wrap_err(SqliteError::Query)?;
+
+            if value < 0 {
+                return Err(Error::new(SqliteError::Conversion));
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn values(pool: &Connections) -> Vec<i64> {
+        pool.acquire_reader(|reader| {
+            let mut statement = reader
+                .prepare("SELECT value FROM queued ORDER BY id")
+                .wrap_err(SqliteError::Prepare)?;
+
+            statement
+                .query_map([], |row| row.get(0))
+                .and_then(|rows| rows.collect())
+                .wrap_err(SqliteError::Query)
+        })
+        .await
+        .unwrap()
+    }
+
     #[tokio::test]
     async fn should_survive_panic() {
         let tpm = TempDir::new().unwrap();
@@ -201,4 +553,217 @@ mod tests {
 
         assert_eq!(*res.kind(), SqliteError::Join);
     }
+
+    #[tokio::test]
+    async fn should_keep_tenants_config_after_panic() {
+        let tpm = TempDir::new().unwrap();
+
+        let pool = Connections::new(tpm.path().join("sdk.db"), SqliteOptions::default());
+
+        pool.acquire_writer(|writer| {
+            writer.set_retention_capacity("tenant", NonZero::new(42).unwrap());
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+        // the writer is dropped and recreated
+        let res = pool
+            .acquire_writer::<_, ()>(|_writer| panic!())
+            .await
+            .unwrap_err();
+        assert_eq!(*res.kind(), SqliteError::Join);
+
+        let capacity = pool
+            .acquire_writer(|writer| Ok(writer.retention_capacity("tenant")))
+            .await
+            .unwrap();
+
+        assert_eq!(capacity.get(), 42);
+    }
+
+    #[tokio::test]
+    async fn should_batch_writes_in_order() {
+        let tpm = TempDir::new().unwrap();
+
+        let options = SqliteOptions::default()
+            .set_write_batch_size(NonZero::new(4).unwrap())
+            .set_write_batch_latency(Duration::from_millis(10));
+        let pool = Arc::new(Connections::new(tpm.path().join("sdk.db"), options));
+
+        create_table(&pool).await;
+
+        let res = futures::future::join_all((0..10).map(|i| insert(&pool, i))).await;
+
+        assert!(res.iter().all(Result::is_ok));
+        assert_eq!(values(&pool).await, (0..10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn should_rollback_only_failed_write() {
+        let tpm = TempDir::new().unwrap();
+
+        let options = SqliteOptions::default().set_write_batch_latency(Duration::from_millis(10));
+        let pool = Arc::new(Connections::new(tpm.path().join("sdk.db"), options));
+
+        create_table(&pool).await;
+
+        let (first, failed, last) =
+            tokio::join!(insert(&pool, 1), insert(&pool, -1), insert(&pool, 2));
+
+        first.unwrap();
+        assert_eq!(*failed.unwrap_err().kind(), SqliteError::Conversion);
+        last.unwrap();
+
+        assert_eq!(values(&pool).await, [1, 2]);
+    }
+
+    fn removed(id: Id) -> RetentionEvent {
+        RetentionEvent::new(
+            id,
+            RetentionKind::Stored,
+            "com.Foo",
+            "/path",
+            DropReason::Evicted,
+        )
+    }
+
+    async fn subscribe_events(pool: &Connections) -> RetentionEvents {
+        let sender = RetentionEventSender::new();
+        let events = sender.subscribe();
+
+        pool.acquire_writer(move |writer| {
+            writer.set_retention_events("tenant", sender);
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+        events
+    }
+
+    /// Queues the event and inserts the value, failing like [`insert`] for negative ones.
+    async fn insert_removing(
+        pool: &Arc<Connections>,
+        value: i64,
+        event: RetentionEvent,
+    ) -> Result<(), Error<SqliteError>> {
+        pool.queue_writer(move |writer| {
+            if let Some(events) = writer.retention_events("tenant") {
+                events.send(event);
+            }
+
+            writer
+                .execute("INSERT INTO queued (value) VALUES (?)", [value])
+                .wrap_err(SqliteError::Query)?;
+
+            if value < 0 {
+                return Err(Error::new(SqliteError::Conversion));
+            }
+
+            Ok(())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn should_send_events_of_committed_writes() {
+        let tpm = TempDir::new().unwrap();
+
+        let options = SqliteOptions::default().set_write_batch_latency(Duration::from_millis(10));
+        let pool = Arc::new(Connections::new(tpm.path().join("sdk.db"), options));
+
+        create_table(&pool).await;
+        let mut events = subscribe_events(&pool).await;
+
+        let ctx = Context::new();
+        let (first, failed) = (ctx.next(), ctx.next());
+
+        let (res_first, res_failed) = tokio::join!(
+            insert_removing(&pool, 1, removed(first)),
+            insert_removing(&pool, -1, removed(failed))
+        );
+        res_first.unwrap();
+        assert_eq!(*res_failed.unwrap_err().kind(), SqliteError::Conversion);
+
+        // the event of the rolled back write is discarded
+        assert_eq!(events.recv().await, Some(removed(first)));
+        assert_eq!(events.recv().now_or_never(), None);
+    }
+
+    #[tokio::test]
+    async fn should_discard_events_of_failed_commit() {
+        let tpm = TempDir::new().unwrap();
+
+        let pool = Arc::new(Connections::new(
+            tpm.path().join("sdk.db"),
+            SqliteOptions::default(),
+        ));
+
+        pool.acquire_writer(|writer| {
+            writer
+                .execute_batch(
+                    "CREATE TABLE parent (id INTEGER PRIMARY KEY);
+                    CREATE TABLE child (
+                        parent INTEGER REFERENCES parent(id) DEFERRABLE INITIALLY DEFERRED
+                    );",
+                )
+                .wrap_err(SqliteError::Query)
+        })
+        .await
+        .unwrap();
+        let mut events = subscribe_events(&pool).await;
+
+        // the foreign key is checked only on commit
+        let err = pool
+            .queue_writer(|writer| {
+                if let Some(events) = writer.retention_events("tenant") {
+                    events.send(removed(Context::new().next()));
+                }
+
+                writer
+                    .execute("INSERT INTO child (parent) VALUES (1)", [])
+                    .wrap_err(SqliteError::Query)
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(*err.kind(), SqliteError::Transaction);
+
+        assert_eq!(events.recv().now_or_never(), None);
+    }
+
+    #[tokio::test]
+    async fn writer_should_flush_queue() {
+        let tpm = TempDir::new().unwrap();
+
+        let options = SqliteOptions::default().set_write_batch_latency(Duration::from_secs(60));
+        let pool = Arc::new(Connections::new(tpm.path().join("sdk.db"), options));
+
+        create_table(&pool).await;
+
+        let queued = tokio::spawn({
+            let pool = Arc::clone(&pool);
+
+            async move { insert(&pool, 1).await }
+        });
+
+        // wait for the write to be queued
+        while pool.lock_queue().pending.is_empty() {
+            tokio::task::yield_now().await;
+        }
+
+        pool.acquire_writer(|writer| {
+            writer
+                .execute("INSERT INTO queued (value) VALUES (2)", [])
+                .wrap_err(SqliteError::Query)
+        })
+        .await
+        .unwrap();
+
+        queued.await.unwrap().unwrap();
+
+        assert_eq!(values(&pool).await, [1, 2]);
+    }
 }
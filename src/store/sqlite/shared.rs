@@ -0,0 +1,275 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Shared SQLite store
+//!
+//! A single database shared by multiple devices, for example in a gateway process.
+//!
+//! The properties, retention and introspection are namespaced by realm and device id.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use astarte_device_error::Error;
+use tracing::{debug, instrument};
+
+use super::SqliteStore;
+use super::error::SqliteError;
+use super::options::SqliteOptions;
+use super::pool::{Connections, with_savepoint};
+
+/// SQLite database shared between multiple devices.
+///
+/// Each device gets a [`SqliteStore`] handle with [`SqliteSharedStore::device`], that shares the
+/// connections with all the other devices.
+///
+/// # Example
+///
+/// ```no_run
+/// # use astarte_device_sdk::store::sqlite::{SqliteSharedStore, options::SqliteOptions};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let shared = SqliteSharedStore::with_writable_dir("/var/lib/astarte/", SqliteOptions::default())
+///         .await
+///         .expect("should connect");
+///
+///     let first = shared.device("realm", "2TBn-jNESuuHamE2Zo1anA");
+///     let second = shared.device("realm", "YyGTL4LtQ3C-d2aQgoEzsA");
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SqliteSharedStore {
+    pool: Arc<Connections>,
+}
+
+impl SqliteSharedStore {
+    /// Connect to the SQLite database using the default db name in the writable path.
+    ///
+    /// See [`SqliteStore::with_writable_dir`].
+    pub async fn with_writable_dir(
+        writable_path: impl AsRef<Path>,
+        options: SqliteOptions,
+    ) -> Result<Self, Error<SqliteError>> {
+        SqliteStore::with_writable_dir(writable_path, options)
+            .await
+            .map(Self::from_store)
+    }
+
+    /// Connect to the SQLite database give as a filename.
+    ///
+    /// See [`SqliteStore::with_db_file`].
+    pub async fn with_db_file(
+        database_file: impl AsRef<Path>,
+        options: SqliteOptions,
+    ) -> Result<Self, Error<SqliteError>> {
+        SqliteStore::with_db_file(database_file, options)
+            .await
+            .map(Self::from_store)
+    }
+
+    fn from_store(store: SqliteStore) -> Self {
+        Self { pool: store.pool }
+    }
+
+    /// Returns the store for the device with the given realm and id.
+    ///
+    /// The handle is cheap to create and to clone.
+    pub fn device(&self, realm: &str, device_id: &str) -> SqliteStore {
+        SqliteStore {
+            pool: Arc::clone(&self.pool),
+            tenant: tenant(realm, device_id),
+        }
+    }
+
+    /// Removes all the properties, retention, introspection and credentials of a device.
+    ///
+    /// The data of the other devices is not modified.
+    #[instrument(skip(self))]
+    pub async fn delete_device(
+        &self,
+        realm: &str,
+        device_id: &str,
+    ) -> Result<(), Error<SqliteError>> {
+        let tenant = tenant(realm, device_id);
+
+        self.pool
+            .acquire_writer(move |writer| {
+                with_savepoint(writer, |writer| {
+                    writer.clear_props(&tenant)?;
+                    writer.clear_retention(&tenant)?;
+                    writer.clear_introspection(&tenant)?;
+                    writer.clear_credentials(&tenant)
+                })?;
+
+                writer.remove_tenant(&tenant);
+
+                Ok(())
+            })
+            .await?;
+
+        debug!("device data deleted");
+
+        Ok(())
+    }
+}
+
+fn tenant(realm: &str, device_id: &str) -> Arc<str> {
+    Arc::from(format!("{realm}/{device_id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use astarte_interfaces::interface::Retention;
+    use astarte_interfaces::schema::{Ownership, Reliability};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::AstarteData;
+    use crate::credentials::{CredentialKind, CredentialStore};
+    use crate::retention::{Context, PublishInfo, StoredRetention};
+    use crate::session::{IntrospectionInterface, StoredSession};
+    use crate::store::{PropertyMapping, PropertyStore, StoredProp, conformance};
+
+    async fn store_device_data(store: &SqliteStore, ctx: &Context) {
+        let value = AstarteData::Integer(42);
+        store
+            .store_prop(StoredProp {
+                interface: "com.test",
+                path: "/test",
+                value: &value,
+                interface_major: 1,
+                ownership: Ownership::Device,
+            })
+            .await
+            .unwrap();
+
+        store
+            .store_publish(
+                &ctx.next(),
+                PublishInfo::from_ref(
+                    "com.Foo",
+                    "/bar",
+                    1,
+                    Reliability::Unique,
+                    Retention::Stored { expiry: None },
+                    false,
+                    &[],
+                ),
+            )
+            .await
+            .unwrap();
+
+        store
+            .add_interfaces(&[IntrospectionInterface::new("com.test", 1, 0)])
+            .await
+            .unwrap();
+
+        store
+            .store(CredentialKind::Secret, b"secret")
+            .await
+            .unwrap();
+    }
+
+    async fn assert_device_data(store: &SqliteStore, expected: bool) {
+        let value = AstarteData::Integer(42);
+        let prop = StoredProp {
+            interface: "com.test",
+            path: "/test",
+            value: &value,
+            interface_major: 1,
+            ownership: Ownership::Device,
+        };
+        let prop = store
+            .load_prop(&PropertyMapping::from(&prop))
+            .await
+            .unwrap();
+        assert_eq!(prop.is_some(), expected);
+
+        let mut buf = Vec::new();
+        let count = store.unsent_publishes(10, &mut buf).await.unwrap();
+        assert_eq!(count == 1, expected);
+
+        let introspection = store.load_introspection().await.unwrap();
+        assert_eq!(introspection.len() == 1, expected);
+
+        let secret = store.load(CredentialKind::Secret).await.unwrap();
+        assert_eq!(secret.is_some(), expected);
+    }
+
+    #[tokio::test]
+    async fn devices_should_pass_conformance() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let shared = SqliteSharedStore::with_writable_dir(dir.path(), SqliteOptions::default())
+            .await
+            .unwrap();
+
+        for device in ["first", "second"] {
+            let store = shared.device("realm", device);
+
+            conformance::property_store(&store).await;
+            conformance::stored_retention(&store).await;
+            conformance::stored_session(&store).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn devices_should_be_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let shared = SqliteSharedStore::with_writable_dir(dir.path(), SqliteOptions::default())
+            .await
+            .unwrap();
+
+        let ctx = Context::new();
+        let first = shared.device("realm", "first");
+        let second = shared.device("realm", "second");
+        let other_realm = shared.device("other", "first");
+
+        store_device_data(&first, &ctx).await;
+
+        assert_device_data(&first, true).await;
+        assert_device_data(&second, false).await;
+        assert_device_data(&other_realm, false).await;
+
+        store_device_data(&second, &ctx).await;
+        assert_device_data(&second, true).await;
+    }
+
+    #[tokio::test]
+    async fn delete_device_should_keep_other_devices() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let shared = SqliteSharedStore::with_writable_dir(dir.path(), SqliteOptions::default())
+            .await
+            .unwrap();
+
+        let ctx = Context::new();
+        let first = shared.device("realm", "first");
+        let second = shared.device("realm", "second");
+
+        store_device_data(&first, &ctx).await;
+        store_device_data(&second, &ctx).await;
+
+        shared.delete_device("realm", "first").await.unwrap();
+
+        assert_device_data(&first, false).await;
+        assert_device_data(&second, true).await;
+    }
+}
@@ -41,6 +41,7 @@ impl WriteConnection {
     #[instrument(skip_all)]
     pub(super) fn store_prop(
         &mut self,
+        tenant: &str,
         prop: StoredProp<&str, &AstarteData>,
         buf: &[u8],
     ) -> Result<(), Error<SqliteError>> {
@@ -54,6 +55,7 @@ impl WriteConnection {
 
         statement
             .execute((
+                tenant,
                 prop.interface,
                 prop.path,
                 buf,
@@ -74,6 +76,7 @@ impl WriteConnection {
     #[instrument(skip_all)]
     pub(super) fn update_state(
         &mut self,
+        tenant: &str,
         interface: &str,
         path: &str,
         expected: Option<&AstarteData>,
@@ -84,13 +87,14 @@ impl WriteConnection {
             .wrap_err_msg(SqliteError::Transaction, "while updating state")?;
 
         let result = {
-            let value = query_prop_row(&transaction, interface, path).and_then(|value| {
-                let Some(value) = value else {
-                    return Ok(None);
-                };
+            let value =
+                query_prop_row(&transaction, tenant, interface, path).and_then(|value| {
+                    let Some(value) = value else {
+                        return Ok(None);
+                    };
 
-                value.try_into_value().map_kind(SqliteError::Value)
-            })?;
+                    value.try_into_value().map_kind(SqliteError::Value)
+                })?;
 
             if expected != value.as_ref() {
                 // if the value is different from the expected one no records will be updated
@@ -102,7 +106,7 @@ impl WriteConnection {
                 .wrap_err_msg(SqliteError::Prepare, "while updating state")?;
 
             let result = statement
-                .execute((RecordPropertyState::from(state), interface, path))
+                .execute((tenant, RecordPropertyState::from(state), interface, path))
                 .wrap_err_msg(SqliteError::Query, "while updating state")?;
 
             debug_assert!(1 == result);
@@ -118,13 +122,18 @@ impl WriteConnection {
     }
 
     #[instrument(skip(self))]
-    pub(super) fn unset_prop(&self, interface: &str, path: &str) -> Result<(), Error<SqliteError>> {
+    pub(super) fn unset_prop(
+        &self,
+        tenant: &str,
+        interface: &str,
+        path: &str,
+    ) -> Result<(), Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!("queries/properties/write/unset_prop.sql"))
             .wrap_err(SqliteError::Prepare)?;
 
         let updated = statement
-            .execute((RecordPropertyState::Changed, interface, path))
+            .execute((tenant, RecordPropertyState::Changed, interface, path))
             .wrap_err(SqliteError::Query)?;
 
         debug_assert!((0..=1).contains(&updated));
@@ -135,6 +144,7 @@ impl WriteConnection {
     #[instrument(skip(self))]
     pub(super) fn delete_prop(
         &self,
+        tenant: &str,
         interface: &str,
         path: &str,
     ) -> Result<(), Error<SqliteError>> {
@@ -143,7 +153,7 @@ impl WriteConnection {
             .wrap_err(SqliteError::Prepare)?;
 
         let deleted = statement
-            .execute((interface, path))
+            .execute((tenant, interface, path))
             .wrap_err(SqliteError::Query)?;
 
         debug_assert!((0..=1).contains(&deleted));
@@ -154,6 +164,7 @@ impl WriteConnection {
     #[instrument(skip(self, expected))]
     pub(super) fn delete_expected_prop(
         &mut self,
+        tenant: &str,
         interface: &str,
         path: &str,
         expected: Option<&AstarteData>,
@@ -161,13 +172,14 @@ impl WriteConnection {
         let transaction = self.transaction().wrap_err(SqliteError::Transaction)?;
 
         let deleted = {
-            let value = query_prop_row(&transaction, interface, path).and_then(|value| {
-                let Some(value) = value else {
-                    return Ok(None);
-                };
+            let value =
+                query_prop_row(&transaction, tenant, interface, path).and_then(|value| {
+                    let Some(value) = value else {
+                        return Ok(None);
+                    };
 
-                value.try_into_value().map_kind(SqliteError::Value)
-            })?;
+                    value.try_into_value().map_kind(SqliteError::Value)
+                })?;
 
             if expected != value.as_ref() {
                 // if the value is different from the expected one no records will be updated
@@ -179,7 +191,7 @@ impl WriteConnection {
                 .wrap_err(SqliteError::Prepare)?;
 
             let deleted = statement
-                .execute((interface, path))
+                .execute((tenant, interface, path))
                 .wrap_err(SqliteError::Query)?;
 
             debug_assert!((0..=1).contains(&deleted));
@@ -193,18 +205,22 @@ impl WriteConnection {
     }
 
     #[instrument(skip(self))]
-    pub(super) fn clear_props(&self) -> Result<(), Error<SqliteError>> {
+    pub(crate) fn clear_props(&self, tenant: &str) -> Result<(), Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!("queries/properties/write/clear.sql"))
             .wrap_err(SqliteError::Prepare)?;
 
-        statement.execute(()).wrap_err(SqliteError::Query)?;
+        statement.execute([tenant]).wrap_err(SqliteError::Query)?;
 
         Ok(())
     }
 
     #[instrument(skip(self))]
-    pub(super) fn delete_interface_props(&self, interface: &str) -> Result<(), Error<SqliteError>> {
+    pub(super) fn delete_interface_props(
+        &self,
+        tenant: &str,
+        interface: &str,
+    ) -> Result<(), Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!(
                 "queries/properties/write/delete_interface.sql"
@@ -212,20 +228,25 @@ impl WriteConnection {
             .wrap_err(SqliteError::Prepare)?;
 
         statement
-            .execute([interface])
+            .execute([tenant, interface])
             .wrap_err(SqliteError::Query)?;
 
         Ok(())
     }
 
     #[instrument(skip_all)]
-    pub(super) fn reset_state(&self, ownership: Ownership) -> Result<(), Error<SqliteError>> {
+    pub(super) fn reset_state(
+        &self,
+        tenant: &str,
+        ownership: Ownership,
+    ) -> Result<(), Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!("queries/properties/write/reset_state.sql"))
             .wrap_err(SqliteError::Prepare)?;
 
         statement
             .execute((
+                tenant,
                 RecordPropertyState::Changed,
                 RecordOwnership::from(ownership),
             ))
@@ -237,6 +258,7 @@ impl WriteConnection {
 
 fn query_prop_row(
     connection: &rusqlite::Connection,
+    tenant: &str,
     interface: &str,
     path: &str,
 ) -> Result<Option<PropRecord>, Error<SqliteError>> {
@@ -245,7 +267,7 @@ fn query_prop_row(
         .wrap_err_msg(SqliteError::Prepare, "while querying property")?;
 
     statement
-        .query_row((interface, path), |row| {
+        .query_row((tenant, interface, path), |row| {
             Ok(PropRecord {
                 value: row.get(0)?,
                 stored_type: row.get(1)?,
@@ -260,20 +282,24 @@ impl ReadConnection {
     #[instrument(skip(self))]
     pub(super) fn load_prop(
         &self,
+        tenant: &str,
         interface: &str,
         path: &str,
     ) -> Result<Option<PropRecord>, Error<SqliteError>> {
-        query_prop_row(self, interface, path)
+        query_prop_row(self, tenant, interface, path)
     }
 
     #[instrument(skip(self))]
-    pub(super) fn load_all_props(&self) -> Result<Vec<StoredProp>, Error<SqliteError>> {
+    pub(super) fn load_all_props(
+        &self,
+        tenant: &str,
+    ) -> Result<Vec<StoredProp>, Error<SqliteError>> {
         let mut statement = self
             .prepare_cached(include_query!("queries/properties/read/load_all_props.sql"))
             .wrap_err(SqliteError::Prepare)?;
 
         let vec = statement
-            .query_map((), |row| {
+            .query_map([tenant], |row| {
                 Ok(StoredRecord {
                     interface: row.get(0)?,
                     path: row.get(1)?,
@@ -297,6 +323,7 @@ impl ReadConnection {
     #[instrument(skip(self))]
     pub(super) fn props_with_ownership(
         &self,
+        tenant: &str,
         ownership: Ownership,
     ) -> Result<Vec<StoredProp>, Error<SqliteError>> {
         let ownership_par = RecordOwnership::from(ownership);
@@ -308,7 +335,7 @@ impl ReadConnection {
             .wrap_err(SqliteError::Prepare)?;
 
         let v = statement
-            .query_map([ownership_par], |row| {
+            .query_map((tenant, ownership_par), |row| {
                 Ok(StoredRecord {
                     interface: row.get(0)?,
                     path: row.get(1)?,
@@ -342,6 +369,7 @@ impl ReadConnection {
 
     pub(super) fn props_with_unset(
         &self,
+        tenant: &str,
         ownership: Ownership,
         state: PropertyState,
         limit: usize,
@@ -363,6 +391,7 @@ impl ReadConnection {
         let v = statement
             .query_map(
                 (
+                    tenant,
                     ownership_par,
                     RecordPropertyState::from(state),
                     limit,
@@ -396,6 +425,7 @@ impl ReadConnection {
 
     pub(super) fn interface_props(
         &self,
+        tenant: &str,
         interface: &str,
     ) -> Result<Vec<StoredProp>, Error<SqliteError>> {
         let mut statement = self
@@ -405,7 +435,7 @@ impl ReadConnection {
             .wrap_err(SqliteError::Prepare)?;
 
         let v = statement
-            .query_map([interface], |row| {
+            .query_map([tenant, interface], |row| {
                 Ok(StoredRecord {
                     interface: row.get(0)?,
                     path: row.get(1)?,
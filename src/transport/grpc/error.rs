@@ -40,6 +40,8 @@ pub enum GrpcError {
     Server,
     /// Couldn't decode Protobuf message
     Decode,
+    /// Couldn't configure TLS
+    Tls,
 }
 
 impl Display for GrpcError {
@@ -52,6 +54,7 @@ impl Display for GrpcError {
             GrpcError::Conversion(error) => write!(f, "conversion error {error}"),
             GrpcError::Server => write!(f, "server error message"),
             GrpcError::Decode => write!(f, "couldn't decode protobuf message"),
+            GrpcError::Tls => write!(f, "couldn't configure TLS"),
         }
     }
 }
@@ -26,6 +26,8 @@ use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::ops::Deref;
+#[cfg(unix)]
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use astarte_device_error::{Error, ResultExt, WrapError};
@@ -51,6 +53,9 @@ use uuid::Uuid;
 use self::convert::{try_from_individual, try_from_object, try_from_property};
 use self::error::GrpcError;
 use self::store::GrpcStore;
+use self::tls::TlsConnector;
+#[cfg(unix)]
+use self::uds::UdsConnector;
 use super::{Connection, Disconnect, Publish, Receive, ReceivedEvent, Register, ValidatedProperty};
 use crate::Timestamp;
 use crate::aggregate::AstarteObject;
@@ -62,12 +67,21 @@ use crate::retention::{PublishInfo, RetentionId, StoredRetention};
 use crate::state::SharedState;
 use crate::store::{OptStoredProp, PropertyStore, StoreCapabilities};
 use crate::transport::AttemptStatus;
+use crate::transport::mqtt::TlsTrust;
 use crate::types::AstarteData;
 use crate::validate::{ValidatedIndividual, ValidatedObject, ValidatedUnset};
 
 pub mod convert;
 pub mod error;
 pub mod store;
+pub(crate) mod tls;
+#[cfg(unix)]
+pub(crate) mod uds;
+
+pub use self::tls::ClientCertificate;
+#[cfg(unix)]
+#[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(unix)))]
+pub use self::uds::PeerCredentials;
 
 #[cfg(feature = "message-hub")]
 #[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(feature = "message-hub")))]
@@ -669,31 +683,156 @@ impl GrpcPayload {
     }
 }
 
+/// Authority of the HTTP/2 requests sent over a Unix domain socket.
+#[cfg(unix)]
+const UNIX_SOCKET_AUTHORITY: &str = "http://localhost";
+
 /// Configuration for the mqtt connection
 #[derive(Debug, Clone)]
 pub struct GrpcConfig {
     uuid: Uuid,
     endpoint: Endpoint,
+    tls_trust: Option<TlsTrust>,
+    client_certificate: Option<ClientCertificate>,
+    #[cfg(unix)]
+    unix_socket: Option<PathBuf>,
+    #[cfg(unix)]
+    peer_credentials: PeerCredentials,
 }
 
 impl GrpcConfig {
     /// Create a new config.
     pub const fn new(uuid: Uuid, endpoint: Endpoint) -> Self {
-        Self { uuid, endpoint }
+        Self {
+            uuid,
+            endpoint,
+            tls_trust: None,
+            client_certificate: None,
+            #[cfg(unix)]
+            unix_socket: None,
+            #[cfg(unix)]
+            peer_credentials: PeerCredentials::new(),
+        }
     }
 
     /// Create a new config from node id and Message Hub endpoint.
+    ///
+    /// A `unix:/path/to/socket` or `unix:///path/to/socket` URL connects to the Message Hub on a
+    /// Unix domain socket, see [`GrpcConfig::unix`].
     pub fn from_url(uuid: Uuid, url: impl Into<Bytes>) -> Result<Self, Error<GrpcError>> {
+        let url = url.into();
+
+        if url.starts_with(b"unix:") {
+            return Self::from_unix_url(uuid, &url);
+        }
+
         let endpoint = Endpoint::from_shared(url)
             .wrap_err_msg(GrpcError::InvalidArgument, "for MessageHub URL")?;
 
         Ok(Self::new(uuid, endpoint))
     }
 
+    fn from_unix_url(uuid: Uuid, url: &[u8]) -> Result<Self, Error<GrpcError>> {
+        cfg_if::cfg_if! {
+            if #[cfg(unix)] {
+                let path = std::str::from_utf8(url)
+                    .ok()
+                    .and_then(uds::socket_path)
+                    .ok_or_else(|| {
+                        Error::with(GrpcError::InvalidArgument, "invalid unix socket URL")
+                    })?;
+
+                Ok(Self::unix(uuid, path))
+            } else {
+                let _ = (uuid, url);
+
+                Err(Error::with(
+                    GrpcError::InvalidArgument,
+                    "unix sockets are not supported on this platform",
+                ))
+            }
+        }
+    }
+
+    /// Create a new config connecting to the Message Hub on a Unix domain socket.
+    ///
+    /// The socket is local to the host, so the connection doesn't use TLS. Use
+    /// [`GrpcConfig::peer_credentials`] to check the process listening on the socket.
+    #[cfg(unix)]
+    #[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(unix)))]
+    pub fn unix(uuid: Uuid, path: impl Into<PathBuf>) -> Self {
+        let mut config = Self::new(uuid, Endpoint::from_static(UNIX_SOCKET_AUTHORITY));
+
+        config.unix_socket = Some(path.into());
+
+        config
+    }
+
     /// Returns a mutable reference to configure the endpoint.
     pub fn endpoint_mut(&mut self) -> &mut Endpoint {
         &mut self.endpoint
     }
+
+    /// Connect to the Message Hub over TLS.
+    ///
+    /// The server certificate is verified with the native roots and the additional CA
+    /// certificates and pins of the [`TlsTrust`].
+    pub fn tls(mut self, trust: TlsTrust) -> Self {
+        self.tls_trust = Some(trust);
+
+        self
+    }
+
+    /// Authenticate to the Message Hub with a client certificate.
+    ///
+    /// The connection uses TLS, with the trust configured by [`GrpcConfig::tls`] or the native
+    /// roots.
+    pub fn client_certificate(mut self, certificate: ClientCertificate) -> Self {
+        self.client_certificate = Some(certificate);
+
+        self
+    }
+
+    /// Checks the credentials of the Message Hub listening on the Unix domain socket.
+    #[cfg(unix)]
+    #[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(unix)))]
+    pub fn peer_credentials(mut self, peer: PeerCredentials) -> Self {
+        self.peer_credentials = peer;
+
+        self
+    }
+
+    fn is_tls(&self) -> bool {
+        self.tls_trust.is_some() || self.client_certificate.is_some()
+    }
+
+    /// Creates the channel, the connection is established lazily.
+    async fn channel(&self) -> Result<Channel, Error<GrpcError>> {
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_socket {
+            if self.is_tls() {
+                return Err(Error::with(
+                    GrpcError::InvalidArgument,
+                    "TLS is not supported over unix sockets",
+                ));
+            }
+
+            let connector = UdsConnector::new(path, self.peer_credentials);
+
+            return Ok(self.endpoint.connect_with_connector_lazy(connector));
+        }
+
+        if !self.is_tls() {
+            return Ok(self.endpoint.connect_lazy());
+        }
+
+        let default_trust = TlsTrust::new();
+        let trust = self.tls_trust.as_ref().unwrap_or(&default_trust);
+
+        let connector = TlsConnector::configure(trust, self.client_certificate.as_ref()).await?;
+
+        Ok(self.endpoint.connect_with_connector_lazy(connector))
+    }
 }
 
 impl<S> ConnectionConfig<S> for GrpcConfig
@@ -707,7 +846,7 @@ where
         self,
         config: BuildConfig<S>,
     ) -> Result<DeviceTransport<Self::Conn>, AstarteError> {
-        let channel = self.endpoint.connect_lazy();
+        let channel = self.channel().await.map_kind(ErrorKind::Grpc)?;
         let node_id_interceptor = NodeIdInterceptor::new(self.uuid);
         let client = MessageHubClient::with_interceptor(channel, node_id_interceptor);
         let store = GrpcStore::new(client.clone(), config.store);
@@ -1389,4 +1528,33 @@ mod test {
 
         assert_eq!(config.endpoint_mut().uri().host(), Some("hub.example.com"));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn create_unix_config() {
+        let uuid = Uuid::new_v4();
+
+        let mut config = GrpcConfig::from_url(uuid, "unix:///run/astarte/msghub.sock").unwrap();
+
+        assert_eq!(
+            config.unix_socket.as_deref(),
+            Some(std::path::Path::new("/run/astarte/msghub.sock"))
+        );
+        assert_eq!(config.endpoint_mut().uri().host(), Some("localhost"));
+
+        let err = GrpcConfig::from_url(uuid, "unix:").unwrap_err();
+
+        assert_eq!(*err.kind(), GrpcError::InvalidArgument);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn should_reject_tls_over_unix_socket() {
+        let config =
+            GrpcConfig::unix(Uuid::new_v4(), "/run/astarte/msghub.sock").tls(TlsTrust::new());
+
+        let err = config.channel().await.unwrap_err();
+
+        assert_eq!(*err.kind(), GrpcError::InvalidArgument);
+    }
 }
@@ -0,0 +1,291 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! TLS connection to the Message Hub.
+
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use astarte_device_error::{Error, ResultExt, WrapError};
+use astarte_message_hub_proto::tonic::codegen::Service;
+use http::Uri;
+use hyper_util::rt::TokioIo;
+use rustls::ClientConfig;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tracing::debug;
+
+use super::error::GrpcError;
+use crate::transport::mqtt::TlsTrust;
+
+/// Default port for the `https` scheme.
+const DEFAULT_TLS_PORT: u16 = 443;
+
+/// Source of a PEM encoded certificate or key.
+#[derive(Clone, PartialEq, Eq)]
+enum PemSource {
+    File(PathBuf),
+    Pem(String),
+}
+
+impl PemSource {
+    async fn read(&self, what: &'static str) -> Result<Vec<u8>, Error<GrpcError>> {
+        match self {
+            PemSource::File(path) => {
+                debug!(path = %path.display(), "reading the {what}");
+
+                tokio::fs::read(path).await.wrap_err_with(|_| {
+                    Error::with(GrpcError::Tls, "couldn't read the PEM file")
+                        .set_ctx(format!("{what} path {}", path.display()))
+                })
+            }
+            PemSource::Pem(pem) => Ok(pem.clone().into_bytes()),
+        }
+    }
+}
+
+/// Certificate and private key to authenticate the client to the Message Hub.
+///
+/// The certificate PEM can contain the full chain, the leaf certificate first.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    certificates: PemSource,
+    private_key: PemSource,
+}
+
+impl ClientCertificate {
+    /// Reads the certificate chain and the private key from PEM files.
+    ///
+    /// The files are read when connecting.
+    pub fn from_files(certificates: impl Into<PathBuf>, private_key: impl Into<PathBuf>) -> Self {
+        Self {
+            certificates: PemSource::File(certificates.into()),
+            private_key: PemSource::File(private_key.into()),
+        }
+    }
+
+    /// Uses the PEM encoded certificate chain and private key.
+    pub fn from_pem(certificates: impl Into<String>, private_key: impl Into<String>) -> Self {
+        Self {
+            certificates: PemSource::Pem(certificates.into()),
+            private_key: PemSource::Pem(private_key.into()),
+        }
+    }
+
+    /// Reads the certificate chain and the private key.
+    async fn load(
+        &self,
+    ) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), Error<GrpcError>> {
+        let pem = self.certificates.read("client certificate").await?;
+        let certificates = rustls_pemfile::certs(&mut pem.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .wrap_err_msg(GrpcError::Tls, "invalid client certificate PEM")?;
+
+        if certificates.is_empty() {
+            return Err(Error::with(GrpcError::Tls, "no client certificate in PEM"));
+        }
+
+        let pem = self.private_key.read("client private key").await?;
+        let private_key = rustls_pemfile::private_key(&mut pem.as_slice())
+            .wrap_err_msg(GrpcError::Tls, "invalid client private key PEM")?
+            .ok_or_else(|| Error::with(GrpcError::Tls, "no private key in PEM"))?;
+
+        Ok((certificates, private_key))
+    }
+}
+
+// The private key must not end up in the logs.
+impl std::fmt::Debug for ClientCertificate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = |pem: &PemSource| match pem {
+            PemSource::File(path) => path.display().to_string(),
+            PemSource::Pem(_) => "<pem>".to_string(),
+        };
+
+        f.debug_struct("ClientCertificate")
+            .field("certificates", &source(&self.certificates))
+            .field("private_key", &source(&self.private_key))
+            .finish()
+    }
+}
+
+/// Connects to the Message Hub over TLS.
+#[derive(Debug, Clone)]
+pub(crate) struct TlsConnector {
+    config: Arc<ClientConfig>,
+}
+
+impl TlsConnector {
+    /// Configures the connector, trusting the additional CA certificates and pins.
+    ///
+    /// With a client certificate the connection is mutually authenticated.
+    pub(crate) async fn configure(
+        trust: &TlsTrust,
+        client_certificate: Option<&ClientCertificate>,
+    ) -> Result<Self, Error<GrpcError>> {
+        let options = trust.load().await.map_kind(|_| GrpcError::Tls)?;
+
+        let builder = astarte_device_tls::builder_with_trust(&options).wrap_err(GrpcError::Tls)?;
+
+        let mut config = match client_certificate {
+            Some(client_certificate) => {
+                let (certificates, private_key) = client_certificate.load().await?;
+
+                builder
+                    .with_client_auth_cert(certificates, private_key)
+                    .wrap_err_msg(GrpcError::Tls, "invalid client certificate or private key")?
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        // gRPC requires HTTP/2
+        config.alpn_protocols = vec![b"h2".to_vec()];
+
+        Ok(Self {
+            config: Arc::new(config),
+        })
+    }
+}
+
+impl Service<Uri> for TlsConnector {
+    type Response = TokioIo<TlsStream<TcpStream>>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connector = tokio_rustls::TlsConnector::from(Arc::clone(&self.config));
+
+        Box::pin(async move {
+            let host = uri
+                .host()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "missing host in uri"))?
+                // IPv6 addresses are in brackets
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string();
+            let port = uri.port_u16().unwrap_or(DEFAULT_TLS_PORT);
+
+            let server_name = ServerName::try_from(host.clone())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+            debug!(%host, port, "connecting to the Message Hub over TLS");
+
+            let tcp = TcpStream::connect((host.as_str(), port)).await?;
+            let tls = connector.connect(server_name, tcp).await?;
+
+            Ok(TokioIo::new(tls))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use crate::transport::mqtt::config::tls::tests::TEST_CERTIFICATE;
+
+    use super::*;
+
+    fn client_certificate() -> (String, String) {
+        let rcgen::CertifiedKey { cert, signing_key } =
+            rcgen::generate_simple_self_signed(vec!["node".to_string()]).unwrap();
+
+        (cert.pem(), signing_key.serialize_pem())
+    }
+
+    #[tokio::test]
+    async fn should_configure_h2() {
+        let trust = TlsTrust::new().add_ca_pem(TEST_CERTIFICATE);
+
+        let connector = TlsConnector::configure(&trust, None).await.unwrap();
+
+        assert_eq!(connector.config.alpn_protocols, vec![b"h2".to_vec()]);
+        assert!(!connector.config.client_auth_cert_resolver.has_certs());
+    }
+
+    #[tokio::test]
+    async fn should_fail_invalid_trust() {
+        let trust = TlsTrust::new().add_ca_pem("not a certificate");
+
+        let err = TlsConnector::configure(&trust, None).await.unwrap_err();
+
+        assert_eq!(*err.kind(), GrpcError::Tls);
+    }
+
+    #[tokio::test]
+    async fn should_configure_client_certificate() {
+        let (cert, key) = client_certificate();
+
+        let dir = TempDir::new().unwrap();
+        let cert_path = dir.path().join("client.pem");
+        let key_path = dir.path().join("client.key");
+        tokio::fs::write(&cert_path, &cert).await.unwrap();
+        tokio::fs::write(&key_path, &key).await.unwrap();
+
+        let trust = TlsTrust::new().add_ca_pem(TEST_CERTIFICATE);
+
+        for client_certificate in [
+            ClientCertificate::from_pem(cert.clone(), key.clone()),
+            ClientCertificate::from_files(&cert_path, &key_path),
+        ] {
+            let connector = TlsConnector::configure(&trust, Some(&client_certificate))
+                .await
+                .unwrap();
+
+            assert!(connector.config.client_auth_cert_resolver.has_certs());
+        }
+    }
+
+    #[tokio::test]
+    async fn should_fail_invalid_client_certificate() {
+        let (cert, key) = client_certificate();
+        let dir = TempDir::new().unwrap();
+        let trust = TlsTrust::new();
+
+        let invalid = [
+            ClientCertificate::from_pem("not a certificate", key.clone()),
+            ClientCertificate::from_pem(cert.clone(), "not a key"),
+            ClientCertificate::from_files(dir.path().join("missing.pem"), dir.path().join("key")),
+        ];
+
+        for client_certificate in invalid {
+            let err = TlsConnector::configure(&trust, Some(&client_certificate))
+                .await
+                .unwrap_err();
+
+            assert_eq!(*err.kind(), GrpcError::Tls);
+        }
+    }
+
+    #[test]
+    fn should_not_debug_private_key() {
+        let (cert, key) = client_certificate();
+
+        let debug = format!("{:?}", ClientCertificate::from_pem(cert, key.clone()));
+
+        assert!(!debug.contains(&key));
+    }
+}
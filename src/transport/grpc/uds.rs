@@ -0,0 +1,226 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Unix domain socket connection to the Message Hub.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use astarte_message_hub_proto::tonic::codegen::Service;
+use http::Uri;
+use hyper_util::rt::TokioIo;
+use tokio::net::UnixStream;
+use tracing::{debug, error};
+
+/// Expected credentials of the Message Hub process listening on the socket.
+///
+/// The credentials are checked after connecting, the connection fails if the peer doesn't match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PeerCredentials {
+    uid: Option<u32>,
+    gid: Option<u32>,
+}
+
+impl PeerCredentials {
+    /// Accepts any peer.
+    pub const fn new() -> Self {
+        Self {
+            uid: None,
+            gid: None,
+        }
+    }
+
+    /// Requires the peer to run with the user id.
+    pub fn uid(mut self, uid: u32) -> Self {
+        self.uid = Some(uid);
+
+        self
+    }
+
+    /// Requires the peer to run with the group id.
+    pub fn gid(mut self, gid: u32) -> Self {
+        self.gid = Some(gid);
+
+        self
+    }
+
+    fn check(&self, uid: u32, gid: u32) -> bool {
+        self.uid.is_none_or(|expected| expected == uid)
+            && self.gid.is_none_or(|expected| expected == gid)
+    }
+}
+
+/// Connects to the Message Hub on a Unix domain socket.
+///
+/// The URI of the endpoint is ignored, it's only used for the HTTP/2 authority.
+#[derive(Debug, Clone)]
+pub(crate) struct UdsConnector {
+    path: Arc<Path>,
+    peer: PeerCredentials,
+}
+
+impl UdsConnector {
+    pub(crate) fn new(path: &Path, peer: PeerCredentials) -> Self {
+        Self {
+            path: Arc::from(path),
+            peer,
+        }
+    }
+}
+
+impl Service<Uri> for UdsConnector {
+    type Response = TokioIo<UnixStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let path = Arc::clone(&self.path);
+        let peer = self.peer;
+
+        Box::pin(async move {
+            debug!(path = %path.display(), "connecting to the Message Hub over a unix socket");
+
+            let stream = UnixStream::connect(&*path).await?;
+
+            let cred = stream.peer_cred()?;
+
+            if !peer.check(cred.uid(), cred.gid()) {
+                error!(
+                    uid = cred.uid(),
+                    gid = cred.gid(),
+                    pid = cred.pid(),
+                    "unexpected credentials of the Message Hub peer"
+                );
+
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "unexpected credentials of the Message Hub peer",
+                ));
+            }
+
+            debug!(
+                uid = cred.uid(),
+                gid = cred.gid(),
+                pid = cred.pid(),
+                "connected to the Message Hub"
+            );
+
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+/// Returns the socket path for a `unix:` URL.
+///
+/// Both the `unix:/path` and `unix:///path` forms are accepted.
+pub(crate) fn socket_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("unix:")?;
+    let path = path.strip_prefix("//").unwrap_or(path);
+
+    (!path.is_empty()).then(|| PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use tokio::net::UnixListener;
+
+    use super::*;
+
+    fn current_credentials() -> (u32, u32) {
+        let (a, b) = UnixStream::pair().unwrap();
+        let cred = a.peer_cred().unwrap();
+        drop(b);
+
+        (cred.uid(), cred.gid())
+    }
+
+    #[test]
+    fn should_parse_socket_path() {
+        let cases = [
+            (
+                "unix:/run/astarte/msghub.sock",
+                Some("/run/astarte/msghub.sock"),
+            ),
+            (
+                "unix:///run/astarte/msghub.sock",
+                Some("/run/astarte/msghub.sock"),
+            ),
+            ("unix:msghub.sock", Some("msghub.sock")),
+            ("unix:", None),
+            ("unix://", None),
+            ("http://localhost:50051", None),
+        ];
+
+        for (url, exp) in cases {
+            assert_eq!(socket_path(url), exp.map(PathBuf::from), "for {url}");
+        }
+    }
+
+    #[test]
+    fn should_check_credentials() {
+        assert!(PeerCredentials::new().check(1000, 1000));
+        assert!(PeerCredentials::new().uid(1000).check(1000, 42));
+        assert!(PeerCredentials::new().uid(1000).gid(42).check(1000, 42));
+        assert!(!PeerCredentials::new().uid(0).check(1000, 42));
+        assert!(!PeerCredentials::new().uid(1000).gid(0).check(1000, 42));
+    }
+
+    #[tokio::test]
+    async fn should_connect_with_matching_peer() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("msghub.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let (uid, gid) = current_credentials();
+        let mut connector = UdsConnector::new(&path, PeerCredentials::new().uid(uid).gid(gid));
+
+        let (res, accepted) = tokio::join!(
+            connector.call(Uri::from_static("http://localhost")),
+            listener.accept()
+        );
+
+        res.unwrap();
+        accepted.unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_reject_unexpected_peer() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("msghub.sock");
+        let _listener = UnixListener::bind(&path).unwrap();
+
+        let (uid, _gid) = current_credentials();
+        let mut connector =
+            UdsConnector::new(&path, PeerCredentials::new().uid(uid.wrapping_add(1)));
+
+        let err = connector
+            .call(Uri::from_static("http://localhost"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+}
@@ -16,10 +16,240 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use super::error::{MqttError, ReasonCode};
+
 #[cfg(test)]
 pub(crate) use self::mock::{MockAsyncClient as AsyncClient, MockEventLoop as EventLoop};
 #[cfg(not(test))]
-pub(crate) use rumqttc::{AsyncClient, EventLoop};
+pub(crate) use self::protocol::{AsyncClient, EventLoop};
+
+/// Error polling the connection, for the version of the protocol in use.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ConnectionError {
+    #[error(transparent)]
+    V311(#[from] rumqttc::ConnectionError),
+    #[error(transparent)]
+    V5(#[from] rumqttc::v5::ConnectionError),
+}
+
+impl ConnectionError {
+    /// Returns the kind of the error, with the reason code sent by the broker.
+    pub(crate) fn kind(&self) -> MqttError {
+        use rumqttc::v5::{ConnectionError as V5Error, StateError};
+
+        match self {
+            ConnectionError::V311(rumqttc::ConnectionError::ConnectionRefused(code)) => {
+                MqttError::ConnectionRefused(ReasonCode::from(*code))
+            }
+            ConnectionError::V5(V5Error::ConnectionRefused(code)) => {
+                MqttError::ConnectionRefused(ReasonCode::from(*code))
+            }
+            ConnectionError::V5(V5Error::MqttState(StateError::ServerDisconnect {
+                reason_code,
+                ..
+            })) => MqttError::ServerDisconnect(ReasonCode::from(*reason_code)),
+            ConnectionError::V311(_) | ConnectionError::V5(_) => MqttError::Connection,
+        }
+    }
+
+    /// Returns the TLS error of the connection.
+    pub(crate) fn tls_error(&self) -> Option<&rustls::Error> {
+        match self {
+            ConnectionError::V311(rumqttc::ConnectionError::Tls(rumqttc::TlsError::TLS(tls)))
+            | ConnectionError::V5(rumqttc::v5::ConnectionError::Tls(rumqttc::TlsError::TLS(tls))) => {
+                Some(tls)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Client and event loop for the configured version of the protocol.
+#[cfg(not(test))]
+mod protocol {
+    use std::fmt::Debug;
+
+    use rumqttc::{
+        AckOfPub, Event, MqttOptions, NetworkOptions, QoS, SubAck, Token, Transport, UnsubAck,
+    };
+
+    use crate::transport::mqtt::config::MqttProtocol;
+    use crate::transport::mqtt::v5::{self, Mqtt5Client, Mqtt5EventLoop};
+
+    use super::ConnectionError;
+
+    /// Error sending a request to the event loop.
+    #[derive(Debug, thiserror::Error)]
+    pub(crate) enum ClientError {
+        #[error(transparent)]
+        V311(#[from] rumqttc::ClientError),
+        #[error(transparent)]
+        V5(#[from] rumqttc::v5::ClientError),
+    }
+
+    #[derive(Debug, Clone)]
+    pub(crate) enum AsyncClient {
+        V311(rumqttc::AsyncClient),
+        V5(Mqtt5Client),
+    }
+
+    impl AsyncClient {
+        pub(crate) fn new(
+            options: MqttOptions,
+            cap: usize,
+            protocol: MqttProtocol,
+        ) -> (AsyncClient, EventLoop) {
+            match protocol {
+                MqttProtocol::V311 => {
+                    let (client, eventloop) = rumqttc::AsyncClient::new(options, cap);
+
+                    (AsyncClient::V311(client), EventLoop::V311(eventloop))
+                }
+                MqttProtocol::V5 {
+                    session_expiry,
+                    topic_alias_max,
+                } => {
+                    let (client, eventloop) =
+                        v5::new(&options, cap, session_expiry, topic_alias_max);
+
+                    (AsyncClient::V5(client), EventLoop::V5(eventloop))
+                }
+            }
+        }
+
+        pub(crate) async fn subscribe<S>(
+            &self,
+            topic: S,
+            qos: QoS,
+        ) -> Result<Token<SubAck>, ClientError>
+        where
+            S: Into<String>,
+        {
+            match self {
+                AsyncClient::V311(client) => client.subscribe(topic, qos).await.map_err(Into::into),
+                AsyncClient::V5(client) => client.subscribe(topic, qos).await.map_err(Into::into),
+            }
+        }
+
+        pub(crate) async fn publish<S, V>(
+            &self,
+            topic: S,
+            qos: QoS,
+            retain: bool,
+            payload: V,
+        ) -> Result<Token<AckOfPub>, ClientError>
+        where
+            S: Into<String>,
+            V: Into<Vec<u8>>,
+        {
+            match self {
+                AsyncClient::V311(client) => client
+                    .publish(topic, qos, retain, payload)
+                    .await
+                    .map_err(Into::into),
+                AsyncClient::V5(client) => client
+                    .publish(topic, qos, retain, payload)
+                    .await
+                    .map_err(Into::into),
+            }
+        }
+
+        pub(crate) async fn unsubscribe<S>(&self, topic: S) -> Result<Token<UnsubAck>, ClientError>
+        where
+            S: Into<String>,
+        {
+            match self {
+                AsyncClient::V311(client) => client.unsubscribe(topic).await.map_err(Into::into),
+                AsyncClient::V5(client) => client.unsubscribe(topic).await.map_err(Into::into),
+            }
+        }
+
+        pub(crate) async fn disconnect(&self) -> Result<Token<()>, ClientError> {
+            match self {
+                AsyncClient::V311(client) => client.disconnect().await.map_err(Into::into),
+                AsyncClient::V5(client) => client.disconnect().await.map_err(Into::into),
+            }
+        }
+    }
+
+    // There is a single event loop per connection, so boxing isn't worth it
+    #[allow(clippy::large_enum_variant)]
+    pub(crate) enum EventLoop {
+        V311(rumqttc::EventLoop),
+        V5(Mqtt5EventLoop),
+    }
+
+    impl EventLoop {
+        pub(crate) async fn poll(&mut self) -> Result<Event, ConnectionError> {
+            match self {
+                EventLoop::V311(eventloop) => eventloop.poll().await.map_err(Into::into),
+                EventLoop::V5(eventloop) => eventloop.poll().await,
+            }
+        }
+
+        pub(crate) fn set_network_options(&mut self, network_options: NetworkOptions) -> &mut Self {
+            match self {
+                EventLoop::V311(eventloop) => {
+                    eventloop.set_network_options(network_options);
+                }
+                EventLoop::V5(eventloop) => eventloop.set_network_options(network_options),
+            }
+
+            self
+        }
+
+        /// Replaces the options for the next connection, keeping the session.
+        pub(crate) fn set_options(&mut self, mut options: MqttOptions) {
+            options.set_clean_session(self.clean_session());
+
+            match self {
+                EventLoop::V311(eventloop) => eventloop.mqtt_options = options,
+                EventLoop::V5(eventloop) => eventloop.set_options(&options),
+            }
+        }
+
+        pub(crate) fn transport(&self) -> Transport {
+            match self {
+                EventLoop::V311(eventloop) => eventloop.mqtt_options.transport(),
+                EventLoop::V5(eventloop) => eventloop.transport(),
+            }
+        }
+
+        pub(crate) fn set_transport(&mut self, transport: Transport) {
+            match self {
+                EventLoop::V311(eventloop) => {
+                    eventloop.mqtt_options.set_transport(transport);
+                }
+                EventLoop::V5(eventloop) => eventloop.set_transport(transport),
+            }
+        }
+
+        pub(crate) fn clean_session(&self) -> bool {
+            match self {
+                EventLoop::V311(eventloop) => eventloop.mqtt_options.clean_session(),
+                EventLoop::V5(eventloop) => eventloop.clean_start(),
+            }
+        }
+
+        pub(crate) fn set_clean_session(&mut self, clean_session: bool) {
+            match self {
+                EventLoop::V311(eventloop) => {
+                    eventloop.mqtt_options.set_clean_session(clean_session);
+                }
+                EventLoop::V5(eventloop) => eventloop.set_clean_start(clean_session),
+            }
+        }
+    }
+
+    impl Debug for EventLoop {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                EventLoop::V311(_) => f.debug_tuple("V311").finish_non_exhaustive(),
+                EventLoop::V5(eventloop) => f.debug_tuple("V5").field(eventloop).finish(),
+            }
+        }
+    }
+}
 
 #[cfg(test)]
 pub(crate) mod mock {
@@ -27,13 +257,17 @@ pub(crate) mod mock {
 
     use mockall::mock;
     use rumqttc::{
-        AckOfPub, ClientError, ConnectionError, Event, MqttOptions, NetworkOptions, QoS, SubAck,
-        SubscribeFilter, Token, UnsubAck,
+        AckOfPub, ClientError, Event, MqttOptions, NetworkOptions, QoS, SubAck, SubscribeFilter,
+        Token, UnsubAck,
     };
 
+    use crate::transport::mqtt::config::MqttProtocol;
+
+    use super::ConnectionError;
+
     mock!(
         pub AsyncClient {
-            pub fn new(options: MqttOptions, cap: usize) -> (MockAsyncClient, MockEventLoop);
+            pub fn new(options: MqttOptions, cap: usize, protocol: MqttProtocol) -> (MockAsyncClient, MockEventLoop);
             pub async fn subscribe<S: Into<String> + 'static>(&self, topic: S, qos: QoS) -> Result<Token<SubAck>, ClientError>;
             pub async fn publish<S, V>(&self, topic: S, qos: QoS, retain: bool, payload: V,) -> Result<Token<AckOfPub>, ClientError> where S: Into<String> + 'static, V: Into<Vec<u8>> + 'static;
             pub async fn unsubscribe<S: Into<String> + 'static>(&self, topic: S) -> Result<Token<UnsubAck>, ClientError>;
@@ -0,0 +1,215 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Cache of the pairing API responses, to connect to the broker when the API can't be reached.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::{debug, error};
+use url::Url;
+
+use crate::error::Report;
+use crate::pairing::api::PAIRING_CACHE_FILE;
+
+/// Content of the cache file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheData {
+    broker_url: Option<Url>,
+    broker_url_at: Option<DateTime<Utc>>,
+    /// Last time the stored certificate was verified as valid.
+    verified_at: Option<DateTime<Utc>>,
+}
+
+/// Cache of the broker url and of the certificate verification, stored in the writable directory.
+#[derive(Debug, Clone)]
+pub(crate) struct PairingCache {
+    file: PathBuf,
+    ttl: Duration,
+}
+
+impl PairingCache {
+    pub(crate) fn new(dir: &Path, ttl: Duration) -> Self {
+        Self {
+            file: dir.join(PAIRING_CACHE_FILE),
+            ttl,
+        }
+    }
+
+    /// Returns the cached broker url, if not expired.
+    pub(crate) async fn broker_url(&self, now: DateTime<Utc>) -> Option<Url> {
+        let data = self.read().await;
+
+        data.broker_url
+            .filter(|_| self.is_fresh(data.broker_url_at, now))
+    }
+
+    /// Caches the broker url returned by the API.
+    pub(crate) async fn set_broker_url(&self, url: &Url, now: DateTime<Utc>) {
+        let mut data = self.read().await;
+
+        if data.broker_url.as_ref() == Some(url) && !self.needs_refresh(data.broker_url_at, now) {
+            return;
+        }
+
+        data.broker_url = Some(url.clone());
+        data.broker_url_at = Some(now);
+
+        self.write(&data).await;
+    }
+
+    /// Returns true if the stored certificate was verified, and the result is not expired.
+    pub(crate) async fn is_verified(&self, now: DateTime<Utc>) -> bool {
+        let data = self.read().await;
+
+        self.is_fresh(data.verified_at, now)
+    }
+
+    /// Caches the result of the certificate verification.
+    pub(crate) async fn set_verified(&self, valid: bool, now: DateTime<Utc>) {
+        let mut data = self.read().await;
+
+        if valid && !self.needs_refresh(data.verified_at, now) {
+            return;
+        }
+
+        if !valid && data.verified_at.is_none() {
+            return;
+        }
+
+        data.verified_at = valid.then_some(now);
+
+        self.write(&data).await;
+    }
+
+    fn is_fresh(&self, at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+        at.is_some_and(|at| (now - at).to_std().is_ok_and(|age| age < self.ttl))
+    }
+
+    /// Avoids writing the file at every connection, refreshing the value after half of the TTL.
+    fn needs_refresh(&self, at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+        !at.is_some_and(|at| (now - at).to_std().is_ok_and(|age| age < self.ttl / 2))
+    }
+
+    async fn read(&self) -> CacheData {
+        let content = match fs::read(&self.file).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("pairing cache missing");
+
+                return CacheData::default();
+            }
+            Err(err) => {
+                error!(error = %Report::new(err), file = %self.file.display(), "couldn't read the pairing cache");
+
+                return CacheData::default();
+            }
+        };
+
+        serde_json::from_slice(&content).unwrap_or_else(|err| {
+            error!(error = %Report::new(err), file = %self.file.display(), "invalid pairing cache");
+
+            CacheData::default()
+        })
+    }
+
+    /// Writes the cache, it doesn't fail since it's only used as a fallback.
+    async fn write(&self, data: &CacheData) {
+        let content = match serde_json::to_vec(data) {
+            Ok(content) => content,
+            Err(err) => {
+                error!(error = %Report::new(err), "couldn't serialize the pairing cache");
+
+                return;
+            }
+        };
+
+        if let Err(err) = fs::write(&self.file, content).await {
+            error!(error = %Report::new(err), file = %self.file.display(), "couldn't write the pairing cache");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[tokio::test]
+    async fn should_cache_broker_url() {
+        let dir = TempDir::new().unwrap();
+        let cache = PairingCache::new(dir.path(), HOUR);
+
+        let now = Utc::now();
+        let url: Url = "mqtts://broker.astarte.localhost:8883".parse().unwrap();
+
+        assert_eq!(cache.broker_url(now).await, None);
+
+        cache.set_broker_url(&url, now).await;
+        assert_eq!(cache.broker_url(now).await, Some(url.clone()));
+
+        // expired
+        assert_eq!(cache.broker_url(now + HOUR).await, None);
+
+        // refreshed
+        cache.set_broker_url(&url, now + HOUR).await;
+        assert_eq!(cache.broker_url(now + HOUR).await, Some(url));
+    }
+
+    #[tokio::test]
+    async fn should_cache_verification() {
+        let dir = TempDir::new().unwrap();
+        let cache = PairingCache::new(dir.path(), HOUR);
+
+        let now = Utc::now();
+
+        assert!(!cache.is_verified(now).await);
+
+        cache.set_verified(true, now).await;
+        assert!(cache.is_verified(now).await);
+        assert!(!cache.is_verified(now + HOUR).await);
+
+        cache.set_verified(false, now).await;
+        assert!(!cache.is_verified(now).await);
+    }
+
+    #[tokio::test]
+    async fn should_ignore_invalid_cache() {
+        let dir = TempDir::new().unwrap();
+        let cache = PairingCache::new(dir.path(), HOUR);
+
+        fs::write(dir.path().join(PAIRING_CACHE_FILE), b"not json")
+            .await
+            .unwrap();
+
+        let now = Utc::now();
+        assert_eq!(cache.broker_url(now).await, None);
+
+        // Overwrites the invalid file
+        cache.set_verified(true, now).await;
+        assert!(cache.is_verified(now).await);
+    }
+}
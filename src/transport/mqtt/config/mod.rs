@@ -18,28 +18,37 @@
 
 //! Configuration for the MQTT connection
 
-use astarte_device_error::ResultExt;
+use astarte_device_error::{Error, ResultExt};
+use futures::TryFutureExt;
 use serde::{Deserialize, Serialize};
-use std::fmt::{Debug, Display};
-use std::path::{Path, PathBuf};
+use std::fmt::Debug;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
 
 use crate::builder::{BuildConfig, ConnectionConfig, DEFAULT_REQUEST_TIMEOUT, DeviceTransport};
+use crate::credentials::{CredentialStore, DynCredentialStore, FileCredentialStore};
 use crate::error::{AstarteError, ErrorKind};
 use crate::store::StoreCapabilities;
 use crate::transport::mqtt::ClientId;
 use crate::transport::mqtt::config::transport::TransportProvider;
+use crate::transport::mqtt::crypto::{KeyAlgorithm, KeyProvider};
 use crate::transport::mqtt::error::MqttError;
 use crate::transport::mqtt::retention::MqttRetention;
 
 use super::connection::MqttState;
 use super::{Mqtt, MqttClient};
-use crate::pairing::api::{CERTIFICATE_FILE, PRIVATE_KEY_FILE, PairingApi};
+use crate::pairing::api::{PairingApi, PairingApiError};
 
+pub(crate) mod cache;
+pub(crate) mod proxy;
 pub(crate) mod tls;
 pub(crate) mod transport;
+pub(crate) mod trust;
+
+pub use self::proxy::ProxyConfig;
+pub use self::trust::{CaCertificate, SpkiPin, SpkiPinError, TlsTrust};
 
 /// Credentials for the [`Mqtt`] connection.
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -56,7 +65,8 @@ pub enum Credential {
     ///
     /// You need to set a writable directory on the builder to store the registered credential
     /// secret used for authentication. You can set it with the
-    /// [`crate::builder::DeviceBuilder::writable_dir`] methods.
+    /// [`crate::builder::DeviceBuilder::writable_dir`] methods, or configure a different store
+    /// with [`MqttConfig::credential_store`].
     ParingToken {
         /// The JWT secret to pair the device to astarte.
         pairing_token: String,
@@ -94,6 +104,134 @@ impl Debug for Credential {
     }
 }
 
+/// Transport used by the [`Mqtt`] connection to reach the broker.
+///
+/// All the transports authenticate the device with the client certificate.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MqttTransport {
+    /// MQTT over TLS, on the broker url returned by Astarte.
+    #[default]
+    Tls,
+    /// MQTT over secure WebSockets (`wss`).
+    ///
+    /// Useful on networks that only allow outbound HTTPS connections.
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(feature = "websocket")))]
+    WebSocket {
+        /// Url of the WebSocket endpoint of the broker.
+        ///
+        /// If missing, it's derived from the broker url returned by Astarte, using the `wss`
+        /// scheme on the port 443 and the [`DEFAULT_WEBSOCKET_PATH`] path.
+        url: Option<Url>,
+    },
+}
+
+/// Default path of the WebSocket endpoint of the broker.
+#[cfg(feature = "websocket")]
+pub const DEFAULT_WEBSOCKET_PATH: &str = "/mqtt";
+
+impl MqttTransport {
+    /// Returns the url set to override the one returned by Astarte.
+    pub(crate) fn url_override(&self) -> Option<&Url> {
+        match self {
+            MqttTransport::Tls => None,
+            #[cfg(feature = "websocket")]
+            MqttTransport::WebSocket { url } => url.as_ref(),
+        }
+    }
+
+    /// Adapts the broker url returned by Astarte to the transport.
+    pub(crate) fn broker_url(&self, broker_url: Url) -> Result<Url, Error<PairingApiError>> {
+        match self {
+            MqttTransport::Tls => Ok(broker_url),
+            #[cfg(feature = "websocket")]
+            MqttTransport::WebSocket { .. } => {
+                use astarte_device_error::WrapError;
+
+                let host = broker_url.host_str().ok_or_else(|| {
+                    Error::with(PairingApiError::InvalidArgument, "missing host in url")
+                        .set_ctx(format!("url {broker_url}"))
+                })?;
+
+                // The scheme cannot be changed from mqtts to wss, so we build a new url
+                format!("wss://{host}{DEFAULT_WEBSOCKET_PATH}")
+                    .parse()
+                    .wrap_err_with(|_| {
+                        Error::with(
+                            PairingApiError::InvalidArgument,
+                            "couldn't create the websocket url",
+                        )
+                        .set_ctx(format!("url {broker_url}"))
+                    })
+            }
+        }
+    }
+}
+
+/// Default time the MQTT 5 broker keeps the session after a disconnection.
+pub const DEFAULT_SESSION_EXPIRY: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+/// Default maximum number of MQTT 5 topic aliases.
+pub const DEFAULT_TOPIC_ALIAS_MAX: u16 = 64;
+
+/// Version of the MQTT protocol used by the [`Mqtt`] connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MqttProtocol {
+    /// MQTT 3.1.1, the session is kept by the broker until the device connects with a clean
+    /// session.
+    #[default]
+    V311,
+    /// MQTT 5, it requires a broker supporting it.
+    ///
+    /// The broker errors are returned with their reason code, and the keep alive requested by
+    /// the broker in the CONNACK is used instead of the configured one.
+    V5 {
+        /// Time the broker keeps the session after the device disconnects.
+        ///
+        /// The broker can reduce it in the CONNACK. A session expiry longer than [`u32::MAX`]
+        /// seconds never expires.
+        session_expiry: Duration,
+        /// Maximum number of topic aliases, in each direction.
+        ///
+        /// An alias replaces the topic of the publishes on an interface path after the first one
+        /// on a connection. The aliases are limited by the maximum sent by the broker in the
+        /// CONNACK, `0` disables them.
+        topic_alias_max: u16,
+    },
+}
+
+impl MqttProtocol {
+    /// MQTT 5 with the [`DEFAULT_SESSION_EXPIRY`] and the [`DEFAULT_TOPIC_ALIAS_MAX`].
+    pub fn v5() -> Self {
+        MqttProtocol::V5 {
+            session_expiry: DEFAULT_SESSION_EXPIRY,
+            topic_alias_max: DEFAULT_TOPIC_ALIAS_MAX,
+        }
+    }
+}
+
+/// [`KeyProvider`] shared between the clones of the configuration.
+#[derive(Clone, Debug)]
+pub(crate) struct SharedKeyProvider(Arc<dyn KeyProvider>);
+
+impl PartialEq for SharedKeyProvider {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(Arc::as_ptr(&self.0), Arc::as_ptr(&other.0))
+    }
+}
+
+/// [`CredentialStore`] shared between the clones of the configuration.
+#[derive(Clone, Debug)]
+pub(crate) struct SharedCredentialStore(Arc<dyn DynCredentialStore>);
+
+impl PartialEq for SharedCredentialStore {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(Arc::as_ptr(&self.0), Arc::as_ptr(&other.0))
+    }
+}
+
 /// Arguments to create the MQTT options.
 #[derive(Debug)]
 pub struct MqttArgs {
@@ -124,6 +262,28 @@ pub struct MqttConfig {
     pub(crate) pairing_url: Url,
     pub(crate) ignore_ssl_errors: bool,
     pub(crate) keepalive: Duration,
+    #[serde(default)]
+    pub(crate) transport: MqttTransport,
+    #[serde(default)]
+    pub(crate) protocol: MqttProtocol,
+    #[serde(default)]
+    pub(crate) proxy: ProxyConfig,
+    #[serde(default)]
+    pub(crate) tls_trust: TlsTrust,
+    #[serde(default)]
+    pub(crate) fallback_brokers: Vec<Url>,
+    #[serde(default)]
+    pub(crate) primary_broker_cooldown: Option<Duration>,
+    #[serde(default)]
+    pub(crate) cert_renewal_margin: Option<Duration>,
+    #[serde(default)]
+    pub(crate) offline_cache_ttl: Option<Duration>,
+    #[serde(default)]
+    pub(crate) key_algorithm: KeyAlgorithm,
+    #[serde(skip)]
+    pub(crate) key_provider: Option<SharedKeyProvider>,
+    #[serde(skip)]
+    pub(crate) credential_store: Option<SharedCredentialStore>,
 }
 
 impl MqttConfig {
@@ -159,6 +319,17 @@ impl MqttConfig {
             pairing_url,
             ignore_ssl_errors: false,
             keepalive: DEFAULT_REQUEST_TIMEOUT,
+            transport: MqttTransport::default(),
+            protocol: MqttProtocol::default(),
+            proxy: ProxyConfig::default(),
+            tls_trust: TlsTrust::default(),
+            fallback_brokers: Vec::new(),
+            primary_broker_cooldown: None,
+            cert_renewal_margin: None,
+            offline_cache_ttl: None,
+            key_algorithm: KeyAlgorithm::default(),
+            key_provider: None,
+            credential_store: None,
         }
     }
 
@@ -178,6 +349,154 @@ impl MqttConfig {
 
         self
     }
+
+    /// Trust additional CA certificates and pin the certificates of the pairing API and broker.
+    ///
+    /// See [`TlsTrust`] for the details, it's ignored with [`MqttConfig::ignore_ssl_errors`].
+    pub fn tls_trust(mut self, trust: TlsTrust) -> Self {
+        self.tls_trust = trust;
+
+        self
+    }
+
+    /// Configure the transport used to connect to the broker.
+    pub fn transport(mut self, transport: MqttTransport) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Configure the version of the MQTT protocol used to connect to the broker.
+    ///
+    /// The default is [`MqttProtocol::V311`], MQTT 5 should be enabled only if the broker
+    /// supports it.
+    pub fn protocol(mut self, protocol: MqttProtocol) -> Self {
+        self.protocol = protocol;
+
+        self
+    }
+
+    /// Connect to the broker with MQTT 5, with the default session expiry and topic aliases.
+    ///
+    /// See [`MqttProtocol::v5`].
+    pub fn mqtt5(self) -> Self {
+        self.protocol(MqttProtocol::v5())
+    }
+
+    /// Configure the proxy used for the pairing API and the broker connection.
+    ///
+    /// By default the proxy is read from the environment, see [`ProxyConfig`].
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = proxy;
+
+        self
+    }
+
+    /// Fall back to the given brokers, in order, when the connection to the broker fails.
+    ///
+    /// The primary broker is the one returned by the pairing API, or the url of the transport. On
+    /// a reconnection the brokers that failed recently are skipped, and the primary is preferred
+    /// again after the cooldown, see [`MqttConfig::primary_broker_cooldown`]. The urls are in the
+    /// same form as the one returned by Astarte, like `mqtts://broker.astarte.localhost:8883`.
+    pub fn fallback_brokers(mut self, urls: impl IntoIterator<Item = Url>) -> Self {
+        self.fallback_brokers = urls.into_iter().collect();
+
+        self
+    }
+
+    /// Configure the time after a failure before connecting to a broker again.
+    ///
+    /// It's used with the [`MqttConfig::fallback_brokers`], the default is 5 minutes.
+    pub fn primary_broker_cooldown(mut self, cooldown: Duration) -> Self {
+        self.primary_broker_cooldown = Some(cooldown);
+
+        self
+    }
+
+    /// Renew the client certificate the given time before it expires.
+    ///
+    /// The new certificate is requested to Astarte in background while connected, and it's used
+    /// from the next reconnection to the broker, keeping the current session. By default the
+    /// certificate is renewed only when the connection fails.
+    pub fn renew_certificate_before(mut self, margin: Duration) -> Self {
+        self.cert_renewal_margin = Some(margin);
+
+        self
+    }
+
+    /// Cache the broker url and the certificate verification for the given time.
+    ///
+    /// When the pairing API can't be reached, the device connects directly to the broker with the
+    /// cached data, if it's not older than the TTL and the stored certificate is not expired.
+    ///
+    /// ## Note
+    ///
+    /// The cache is stored in the writable directory set with
+    /// [`crate::builder::DeviceBuilder::writable_dir`], and it's disabled without one.
+    pub fn offline_cache(mut self, ttl: Duration) -> Self {
+        self.offline_cache_ttl = Some(ttl);
+
+        self
+    }
+
+    /// Configure the algorithm of the private key generated for the device certificate.
+    ///
+    /// A stored certificate with a different key algorithm is replaced by a new one on the next
+    /// connection. The default is [`KeyAlgorithm::EcdsaP256`].
+    pub fn key_algorithm(mut self, key_algorithm: KeyAlgorithm) -> Self {
+        self.key_algorithm = key_algorithm;
+
+        self
+    }
+
+    /// Configure the provider of the device private key.
+    ///
+    /// By default the key is stored with the other credentials, see
+    /// [`MqttConfig::credential_store`]. The provider is not serialized with the configuration.
+    pub fn key_provider(mut self, key_provider: impl KeyProvider) -> Self {
+        self.key_provider = Some(SharedKeyProvider(Arc::new(key_provider)));
+
+        self
+    }
+
+    /// Configure the store of the credential secret, the certificate and the private key.
+    ///
+    /// By default the credentials are stored in files in the writable directory, see
+    /// [`FileCredentialStore`]. The store is not serialized with the configuration.
+    pub fn credential_store(mut self, credential_store: impl CredentialStore) -> Self {
+        self.credential_store = Some(SharedCredentialStore(Arc::new(credential_store)));
+
+        self
+    }
+
+    /// Returns the configured credential store, or the default one in the writable directory.
+    pub(crate) fn resolve_credential_store(
+        &self,
+        writable_dir: Option<&Path>,
+    ) -> Option<Arc<dyn DynCredentialStore>> {
+        match (&self.credential_store, writable_dir) {
+            (Some(shared), _) => Some(Arc::clone(&shared.0)),
+            (None, Some(dir)) => Some(Arc::new(FileCredentialStore::new(dir))),
+            (None, None) => None,
+        }
+    }
+
+    /// Connect to the broker with MQTT over secure WebSockets.
+    ///
+    /// The WebSocket url is derived from the broker url returned by Astarte, see
+    /// [`MqttTransport::WebSocket`].
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(feature = "websocket")))]
+    pub fn websocket(self) -> Self {
+        self.transport(MqttTransport::WebSocket { url: None })
+    }
+
+    /// Connect to the broker with MQTT over secure WebSockets, on the given url.
+    #[cfg(feature = "websocket")]
+    #[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(feature = "websocket")))]
+    pub fn websocket_url(self, url: Url) -> Self {
+        self.transport(MqttTransport::WebSocket { url: Some(url) })
+    }
 }
 
 impl<S> ConnectionConfig<S> for MqttConfig
@@ -200,10 +519,29 @@ where
 
         let provider =
             TransportProvider::configure(state.config.writable_dir.clone(), self.ignore_ssl_errors)
+                .and_then(|provider| provider.with_trust(&self.tls_trust))
                 .await
-                .map_kind(|k| ErrorKind::Mqtt(MqttError::PairingApi(k)))?;
-
-        let mqtt_state = MqttState::new(PairingApi::new(self));
+                .and_then(|provider| provider.with_proxy(self.proxy.clone()))
+                .map_kind(|k| ErrorKind::Mqtt(MqttError::PairingApi(k)))?
+                .with_transport(self.transport.clone())
+                .with_protocol(self.protocol)
+                .with_offline_cache(self.offline_cache_ttl)
+                .with_key_algorithm(self.key_algorithm)
+                .with_key_provider(
+                    self.key_provider
+                        .as_ref()
+                        .map(|shared| Arc::clone(&shared.0)),
+                )
+                .with_credential_store(
+                    self.resolve_credential_store(state.config.writable_dir.as_deref()),
+                );
+
+        let cert_renewal_margin = self.cert_renewal_margin;
+        let fallback_brokers = self.fallback_brokers.clone();
+        let primary_broker_cooldown = self.primary_broker_cooldown;
+        let mqtt_state = MqttState::new(PairingApi::new(self))
+            .with_cert_renewal(cert_renewal_margin)
+            .with_broker_failover(fallback_brokers, primary_broker_cooldown);
 
         let connection = Mqtt {
             connection: mqtt_state,
@@ -212,6 +550,7 @@ where
             retention,
             store: store.clone(),
             state,
+            metrics: client.metrics.clone(),
         };
 
         Ok(DeviceTransport {
@@ -222,62 +561,6 @@ where
     }
 }
 
-/// Private keys file, to be a type safe when passed to functions.
-#[derive(Debug, Clone)]
-pub(crate) struct PrivateKeyFile(PathBuf);
-
-impl PrivateKeyFile {
-    /// Create a path to the key file.
-    pub(crate) fn new(dir: impl AsRef<Path>) -> Self {
-        Self(dir.as_ref().join(PRIVATE_KEY_FILE))
-    }
-
-    /// Gets the path to the file.
-    pub(crate) fn path(&self) -> &Path {
-        &self.0
-    }
-}
-
-impl Display for PrivateKeyFile {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0.display())
-    }
-}
-
-impl AsRef<Path> for PrivateKeyFile {
-    fn as_ref(&self) -> &Path {
-        self.path()
-    }
-}
-
-/// Certificate file, to be a type safe check.
-#[derive(Debug, Clone)]
-pub(crate) struct CertificateFile(PathBuf);
-
-impl CertificateFile {
-    /// Create a path to the certificate file.
-    pub(crate) fn new(dir: impl AsRef<Path>) -> Self {
-        Self(dir.as_ref().join(CERTIFICATE_FILE))
-    }
-
-    /// Gets the path to the file.
-    pub(crate) fn path(&self) -> &Path {
-        &self.0
-    }
-}
-
-impl Display for CertificateFile {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0.display())
-    }
-}
-
-impl AsRef<Path> for CertificateFile {
-    fn as_ref(&self) -> &Path {
-        self.path()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -300,6 +583,17 @@ mod tests {
             pairing_url: "http://api.astarte.localhost/pairing".parse().unwrap(),
             ignore_ssl_errors: false,
             keepalive: Duration::from_secs(15),
+            transport: MqttTransport::Tls,
+            protocol: MqttProtocol::V311,
+            proxy: ProxyConfig::Environment,
+            tls_trust: TlsTrust::default(),
+            fallback_brokers: Vec::new(),
+            primary_broker_cooldown: None,
+            cert_renewal_margin: None,
+            offline_cache_ttl: None,
+            key_algorithm: KeyAlgorithm::EcdsaP256,
+            key_provider: None,
+            credential_store: None,
         };
 
         assert_eq!(mqtt_config, exp)
@@ -316,7 +610,17 @@ mod tests {
 
         let mqtt_config = MqttConfig::new(args)
             .ignore_ssl_errors()
-            .keepalive(Duration::from_secs(60));
+            .keepalive(Duration::from_secs(60))
+            .mqtt5()
+            .proxy(ProxyConfig::Disabled)
+            .tls_trust(TlsTrust::new().add_ca_file("/etc/astarte/ca.pem"))
+            .fallback_brokers(["mqtts://broker-2.astarte.localhost:8883"
+                .parse::<Url>()
+                .unwrap()])
+            .primary_broker_cooldown(Duration::from_secs(600))
+            .renew_certificate_before(Duration::from_secs(3600))
+            .offline_cache(Duration::from_secs(86400))
+            .key_algorithm(KeyAlgorithm::Rsa3072);
 
         let exp = MqttConfig {
             realm: "realm".to_string(),
@@ -325,6 +629,20 @@ mod tests {
             pairing_url: "http://api.astarte.localhost/pairing".parse().unwrap(),
             ignore_ssl_errors: true,
             keepalive: Duration::from_secs(60),
+            transport: MqttTransport::Tls,
+            protocol: MqttProtocol::V5 {
+                session_expiry: DEFAULT_SESSION_EXPIRY,
+                topic_alias_max: DEFAULT_TOPIC_ALIAS_MAX,
+            },
+            proxy: ProxyConfig::Disabled,
+            tls_trust: TlsTrust::new().add_ca_file("/etc/astarte/ca.pem"),
+            fallback_brokers: vec!["mqtts://broker-2.astarte.localhost:8883".parse().unwrap()],
+            primary_broker_cooldown: Some(Duration::from_secs(600)),
+            cert_renewal_margin: Some(Duration::from_secs(3600)),
+            offline_cache_ttl: Some(Duration::from_secs(86400)),
+            key_algorithm: KeyAlgorithm::Rsa3072,
+            key_provider: None,
+            credential_store: None,
         };
 
         assert_eq!(mqtt_config, exp)
@@ -379,11 +697,59 @@ mod tests {
     }
 
     #[test]
-    fn check_key_and_cert_file() {
-        let key = PrivateKeyFile::new("/foo");
-        assert_eq!(key.path(), Path::new("/foo/priv-key.der"));
+    fn should_deserialize_config_without_transport() {
+        let config: MqttConfig = serde_json::from_str(
+            r#"{
+                "realm": "realm",
+                "device_id": "device_id",
+                "credentials_secret": "secret",
+                "pairing_url": "http://api.astarte.localhost/pairing",
+                "ignore_ssl_errors": false,
+                "keepalive": {"secs": 30, "nanos": 0}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.transport, MqttTransport::Tls);
+        assert_eq!(config.protocol, MqttProtocol::V311);
+        assert_eq!(config.proxy, ProxyConfig::Environment);
+    }
 
-        let cert = CertificateFile::new("/foo");
-        assert_eq!(cert.path(), Path::new("/foo/certificate.pem"));
+    #[test]
+    fn should_deserialize_mqtt5_protocol() {
+        let protocol: MqttProtocol = serde_json::from_str(
+            r#"{"v5": {"session_expiry": {"secs": 3600, "nanos": 0}, "topic_alias_max": 16}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            protocol,
+            MqttProtocol::V5 {
+                session_expiry: Duration::from_secs(3600),
+                topic_alias_max: 16,
+            }
+        );
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn should_adapt_broker_url_to_websocket() {
+        let broker_url: Url = "mqtts://broker.astarte.localhost:8883/".parse().unwrap();
+
+        let tls = MqttTransport::Tls;
+        assert_eq!(tls.broker_url(broker_url.clone()).unwrap(), broker_url);
+        assert_eq!(tls.url_override(), None);
+
+        let ws = MqttTransport::WebSocket { url: None };
+        let url = ws.broker_url(broker_url).unwrap();
+        assert_eq!(url.as_str(), "wss://broker.astarte.localhost/mqtt");
+        assert_eq!(url.port_or_known_default(), Some(443));
+        assert_eq!(ws.url_override(), None);
+
+        let custom: Url = "wss://gateway.example.com:8443/broker".parse().unwrap();
+        let ws = MqttTransport::WebSocket {
+            url: Some(custom.clone()),
+        };
+        assert_eq!(ws.url_override(), Some(&custom));
     }
 }
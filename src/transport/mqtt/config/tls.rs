@@ -21,21 +21,25 @@
 use std::{io, sync::Arc};
 
 use astarte_device_error::{Error, WrapError};
+use astarte_device_tls::pinning::{PinnedVerifier, SpkiPin};
 use chrono::{DateTime, Utc};
 use rustls::{
     ClientConfig, ConfigBuilder, RootCertStore,
-    client::WantsClientCert,
+    client::{WantsClientCert, WebPkiServerVerifier},
     crypto::CryptoProvider,
-    pki_types::{CertificateDer, PrivatePkcs8KeyDer},
+    pki_types::CertificateDer,
+    sign::{CertifiedKey, SigningKey, SingleCertAndKey},
 };
 use tracing::{debug, error, info, instrument, warn};
 use x509_parser::prelude::X509Certificate;
 
+use crate::credentials::{CredentialKind, DynCredentialStore};
 use crate::error::Report;
 use crate::logging::security::{SecurityEvent, notify_security_event};
 use crate::pairing::api::PairingApiError;
+use crate::transport::mqtt::crypto::KeyAlgorithm;
 
-use super::{CertificateFile, ClientId, PrivateKeyFile};
+use super::ClientId;
 
 pub(crate) fn is_env_ignore_ssl() -> bool {
     matches!(
@@ -47,53 +51,51 @@ pub(crate) fn is_env_ignore_ssl() -> bool {
 pub(crate) struct ClientAuth {
     pem: String,
     der: CertificateDer<'static>,
-    private_key: PrivatePkcs8KeyDer<'static>,
+    private_key: Arc<dyn SigningKey>,
 }
 
 impl ClientAuth {
     pub(crate) async fn try_read(
-        certificates: CertificateFile,
-        key: PrivateKeyFile,
+        store: &dyn DynCredentialStore,
+        private_key: Arc<dyn SigningKey>,
         client_id: ClientId<&str>,
     ) -> Option<Self> {
-        let res = Self::read_cert_and_key(certificates, key, client_id).await;
+        let pem = match store.load_boxed(CredentialKind::Certificate).await {
+            Ok(Some(pem)) => pem,
+            Ok(None) => {
+                debug!("certificate is missing");
 
-        match res {
-            Ok(auth) => auth,
-            Err(err) if err.kind() == io::ErrorKind::NotFound => {
-                debug!("credential files are missing");
+                return None;
+            }
+            Err(err) => {
+                error!(error = %Report::new(err), "couldn't read certificate");
 
-                None
+                return None;
             }
+        };
+
+        let pem = match String::from_utf8(pem) {
+            Ok(pem) => pem,
             Err(err) => {
-                error!("couldn't read certificates {err}");
+                warn!(error = %Report::new(err), "certificate is not valid PEM");
 
-                None
+                return None;
             }
-        }
-    }
+        };
 
-    /// Function to read the certificate and the key
-    async fn read_cert_and_key(
-        certificates: CertificateFile,
-        key: PrivateKeyFile,
-        client_id: ClientId<&str>,
-    ) -> Result<Option<Self>, io::Error> {
-        let pem = tokio::fs::read_to_string(certificates.path()).await?;
+        match Self::try_from_pem_cert(pem, private_key, client_id) {
+            Ok(auth) => auth,
+            Err(err) => {
+                error!(error = %Report::new(err), "couldn't read certificate");
 
-        let k_r = tokio::fs::read(key).await?;
-        if k_r.is_empty() {
-            debug!("no private key found");
-            return Ok(None);
+                None
+            }
         }
-        let private_key = PrivatePkcs8KeyDer::from(k_r);
-
-        Self::try_from_pem_cert(pem, private_key, client_id)
     }
 
     pub(crate) fn try_from_pem_cert(
         pem: String,
-        private_key: PrivatePkcs8KeyDer<'static>,
+        private_key: Arc<dyn SigningKey>,
         client_id: ClientId<&str>,
     ) -> Result<Option<Self>, io::Error> {
         let bytes = &mut pem.as_bytes();
@@ -142,7 +144,26 @@ impl ClientAuth {
             notify_security_event(SecurityEvent::CertificateValidationFailedExpired);
         }
 
-        self.verify_certificate_subject(&parsed, client_id)
+        self.verify_certificate_subject(&parsed, client_id) && self.verify_private_key()
+    }
+
+    /// Checks that the certificate was issued for the private key.
+    fn verify_private_key(&self) -> bool {
+        let certified = CertifiedKey::new(vec![self.der.clone()], Arc::clone(&self.private_key));
+
+        match certified.keys_match() {
+            Ok(()) => true,
+            Err(rustls::Error::InconsistentKeys(rustls::InconsistentKeys::Unknown)) => {
+                debug!("the private key doesn't provide the public key, assuming valid");
+
+                true
+            }
+            Err(err) => {
+                warn!(error = %Report::new(err), "certificate doesn't match the private key assuming invalid");
+
+                false
+            }
+        }
     }
 
     fn verify_certificate_subject(
@@ -164,10 +185,11 @@ impl ClientAuth {
     pub(crate) fn tls_config(
         self,
         roots: Arc<RootCertStore>,
+        pins: &[SpkiPin],
     ) -> Result<rustls::ClientConfig, Error<PairingApiError>> {
-        tls_config_builder(roots)?
-            .with_client_auth_cert(vec![self.der], self.private_key.into())
-            .wrap_err(PairingApiError::Tls)
+        let resolver = self.cert_resolver();
+
+        Ok(tls_config_builder(roots, pins)?.with_client_cert_resolver(resolver))
     }
 
     pub(crate) fn insecure_tls_config(
@@ -175,9 +197,17 @@ impl ClientAuth {
     ) -> Result<rustls::ClientConfig, Error<PairingApiError>> {
         warn!("INSECURE: ignore TLS certificates");
 
-        insecure_tls_config_builder()?
-            .with_client_auth_cert(vec![self.der], self.private_key.into())
-            .wrap_err(PairingApiError::Tls)
+        let resolver = self.cert_resolver();
+
+        Ok(insecure_tls_config_builder()?.with_client_cert_resolver(resolver))
+    }
+
+    /// Presents the certificate to the server, signing the handshake with the private key.
+    fn cert_resolver(self) -> Arc<SingleCertAndKey> {
+        Arc::new(SingleCertAndKey::from(CertifiedKey::new(
+            vec![self.der],
+            self.private_key,
+        )))
     }
 
     /// Parses the X.509 Not After field of a cert.
@@ -197,6 +227,22 @@ impl ClientAuth {
         DateTime::from_timestamp(parsed.validity.not_after.timestamp(), 0)
     }
 
+    /// Returns the algorithm of the certificate key.
+    ///
+    /// It's [`None`] if the certificate can't be read or the algorithm is not supported.
+    pub(crate) fn key_algorithm(&self) -> Option<KeyAlgorithm> {
+        let parsed = match x509_parser::parse_x509_certificate(&self.der) {
+            Ok((_remaining, cert)) => cert,
+            Err(e) => {
+                error!(error=%Report::new(e), "parsing certificate error, couldn't read the key algorithm");
+
+                return None;
+            }
+        };
+
+        KeyAlgorithm::from_public_key(parsed.public_key())
+    }
+
     pub(crate) fn pem(&self) -> &str {
         &self.pem
     }
@@ -237,28 +283,45 @@ pub(crate) async fn read_root_cert_store() -> Result<RootCertStore, Error<Pairin
     .wrap_err(PairingApiError::Join)
 }
 
+/// Returns the default crypto provider, or the aws-lc-rs one if not set.
+pub(crate) fn crypto_provider() -> Arc<CryptoProvider> {
+    CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()))
+}
+
+/// Builds the TLS configuration trusting the roots.
+///
+/// If there are pins, one of the certificates of the server must match them.
 pub(crate) fn tls_config_builder(
     roots: Arc<RootCertStore>,
+    pins: &[SpkiPin],
 ) -> Result<ConfigBuilder<ClientConfig, WantsClientCert>, Error<PairingApiError>> {
-    let provider = CryptoProvider::get_default()
-        .cloned()
-        .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()));
-
-    let builder = rustls::ClientConfig::builder_with_provider(provider)
+    let builder = rustls::ClientConfig::builder_with_provider(crypto_provider())
         .with_safe_default_protocol_versions()
-        .wrap_err(PairingApiError::Tls)?
-        .with_root_certificates(roots);
+        .wrap_err(PairingApiError::Tls)?;
 
-    Ok(builder)
+    if pins.is_empty() {
+        return Ok(builder.with_root_certificates(roots));
+    }
+
+    let verifier = WebPkiServerVerifier::builder_with_provider(roots, crypto_provider())
+        .build()
+        .wrap_err_msg(
+            PairingApiError::Tls,
+            "couldn't build the certificate verifier",
+        )?;
+
+    let verifier = PinnedVerifier::new(verifier, pins.to_vec());
+
+    Ok(builder
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier)))
 }
 
 pub(crate) fn insecure_tls_config_builder()
 -> Result<ConfigBuilder<ClientConfig, WantsClientCert>, Error<PairingApiError>> {
-    let provider = CryptoProvider::get_default()
-        .cloned()
-        .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()));
-
-    let builder = rustls::ClientConfig::builder_with_provider(provider)
+    let builder = rustls::ClientConfig::builder_with_provider(crypto_provider())
         .with_protocol_versions(rustls::ALL_VERSIONS)
         .wrap_err(PairingApiError::Tls)?
         .dangerous()
@@ -323,8 +386,9 @@ impl rustls::client::danger::ServerCertVerifier for NoVerifier {
 pub(crate) mod tests {
     use tempfile::TempDir;
 
+    use crate::credentials::{CredentialStore, FileCredentialStore, MemoryCredentialStore};
     use crate::pairing::api::client::tests::self_sign_csr_to_pem;
-    use crate::transport::mqtt::crypto::Bundle;
+    use crate::transport::mqtt::crypto::{Bundle, FileKeyProvider, KeyProvider, load_private_key};
 
     use super::*;
 
@@ -335,17 +399,49 @@ pub(crate) mod tests {
         device_id: "2TBn-jNESuuHamE2Zo1anA",
     };
 
+    /// Generates a key and stores the certificate signed for it.
+    async fn write_credentials(
+        client_id: ClientId<&str>,
+        algorithm: KeyAlgorithm,
+    ) -> (MemoryCredentialStore, Arc<dyn SigningKey>) {
+        let store = MemoryCredentialStore::new();
+        let private_key = FileKeyProvider::in_memory().generate(algorithm).unwrap();
+
+        let bundle = Bundle::new(client_id.realm, client_id.device_id, private_key).unwrap();
+
+        let cert = self_sign_csr_to_pem(&bundle.csr);
+
+        store
+            .store(CredentialKind::Certificate, cert.as_bytes())
+            .await
+            .unwrap();
+
+        (store, bundle.private_key)
+    }
+
     #[tokio::test]
     async fn should_read_keys() {
         let dir = TempDir::new().unwrap();
 
-        let cert = CertificateFile::new(dir.path());
-        tokio::fs::write(&cert, TEST_CERTIFICATE).await.unwrap();
+        let store = FileCredentialStore::new(dir.path());
+        store
+            .store(CredentialKind::Certificate, TEST_CERTIFICATE.as_bytes())
+            .await
+            .unwrap();
+        store
+            .store(CredentialKind::PrivateKey, TEST_PRIVATE_KEY)
+            .await
+            .unwrap();
 
-        let key = PrivateKeyFile::new(dir.path());
-        tokio::fs::write(&key, TEST_PRIVATE_KEY).await.unwrap();
+        let private_key = store
+            .load(CredentialKind::PrivateKey)
+            .await
+            .unwrap()
+            .map(load_private_key)
+            .expect("should read the key")
+            .unwrap();
 
-        let client = ClientAuth::try_read(cert.clone(), key.clone(), TEST_CLIENT_ID)
+        let client = ClientAuth::try_read(&store, Arc::clone(&private_key), TEST_CLIENT_ID)
             .await
             .unwrap();
 
@@ -362,13 +458,11 @@ pub(crate) mod tests {
 
         assert_eq!(*cert_der, exp);
 
-        assert_eq!(client.private_key.secret_pkcs8_der(), TEST_PRIVATE_KEY);
-
         let root_cert_store = Arc::new(rustls::RootCertStore::empty());
-        client.tls_config(root_cert_store).unwrap();
+        client.tls_config(root_cert_store, &[]).unwrap();
 
         // Reuse the file setup
-        let client = ClientAuth::try_read(cert, key, TEST_CLIENT_ID)
+        let client = ClientAuth::try_read(&store, private_key, TEST_CLIENT_ID)
             .await
             .unwrap();
 
@@ -381,25 +475,10 @@ pub(crate) mod tests {
             realm: "realm",
             device_id: "device_id",
         };
-        let bundle = Bundle::generate_key(client_id.realm, client_id.device_id).unwrap();
-
-        let dir = tempfile::tempdir().unwrap();
-        let certificate_file = CertificateFile::new(&dir);
-        let private_key_file = PrivateKeyFile::new(&dir);
 
-        let cert = self_sign_csr_to_pem(&bundle.csr);
+        let (store, private_key) = write_credentials(client_id, KeyAlgorithm::default()).await;
 
-        tokio::fs::write(certificate_file.path(), cert)
-            .await
-            .unwrap();
-        tokio::fs::write(
-            private_key_file.path(),
-            bundle.private_key.secret_pkcs8_der(),
-        )
-        .await
-        .unwrap();
-
-        let cert = ClientAuth::try_read(certificate_file, private_key_file, client_id)
+        let cert = ClientAuth::try_read(&store, private_key, client_id)
             .await
             .unwrap();
 
@@ -407,30 +486,50 @@ pub(crate) mod tests {
     }
 
     #[tokio::test]
-    async fn test_invalid_certificate_subject_cn() {
-        let bundle = Bundle::generate_key(TEST_CLIENT_ID.realm, TEST_CLIENT_ID.device_id).unwrap();
-
-        let dir = tempfile::tempdir().unwrap();
-        let certificate_file = CertificateFile::new(&dir);
-        let private_key_file = PrivateKeyFile::new(&dir);
+    async fn should_config_tls_with_key_algorithms() {
+        for algorithm in [
+            KeyAlgorithm::EcdsaP256,
+            KeyAlgorithm::EcdsaP384,
+            KeyAlgorithm::Ed25519,
+            KeyAlgorithm::Rsa3072,
+        ] {
+            let (store, private_key) = write_credentials(TEST_CLIENT_ID, algorithm).await;
+
+            let client = ClientAuth::try_read(&store, private_key, TEST_CLIENT_ID)
+                .await
+                .unwrap();
+
+            assert_eq!(client.key_algorithm(), Some(algorithm));
+
+            let root_cert_store = Arc::new(rustls::RootCertStore::empty());
+            client
+                .tls_config(root_cert_store, &[])
+                .unwrap_or_else(|err| panic!("couldn't configure TLS for {algorithm}: {err}"));
+        }
+    }
 
-        let cert = self_sign_csr_to_pem(&bundle.csr);
+    #[tokio::test]
+    async fn should_reject_certificate_for_other_key() {
+        let (store, _) = write_credentials(TEST_CLIENT_ID, KeyAlgorithm::default()).await;
 
-        tokio::fs::write(certificate_file.path(), cert)
-            .await
+        let other_key = FileKeyProvider::in_memory()
+            .generate(KeyAlgorithm::default())
             .unwrap();
-        tokio::fs::write(
-            private_key_file.path(),
-            bundle.private_key.secret_pkcs8_der(),
-        )
-        .await
-        .unwrap();
+
+        let cert = ClientAuth::try_read(&store, other_key, TEST_CLIENT_ID).await;
+
+        assert!(cert.is_none())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_certificate_subject_cn() {
+        let (store, private_key) = write_credentials(TEST_CLIENT_ID, KeyAlgorithm::default()).await;
 
         let diff_client_id = ClientId {
             realm: "realm",
             device_id: "different_device_id",
         };
-        let cert = ClientAuth::try_read(certificate_file, private_key_file, diff_client_id).await;
+        let cert = ClientAuth::try_read(&store, private_key, diff_client_id).await;
 
         assert!(cert.is_none())
     }
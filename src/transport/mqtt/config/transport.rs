@@ -19,16 +19,23 @@
 use core::str;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use astarte_device_error::{Error, ResultExt, WrapError};
-use rumqttc::Transport;
+use astarte_device_tls::pinning::SpkiPin;
+use chrono::Utc;
+use rumqttc::{MqttOptions, Transport};
 use rustls::RootCertStore;
-use rustls::pki_types::PrivatePkcs8KeyDer;
-use tokio::fs;
-use tracing::{debug, error, instrument};
-
-use super::ClientId;
-use super::{CertificateFile, PrivateKeyFile, tls::ClientAuth};
+use rustls::sign::SigningKey;
+use tracing::{debug, error, info, instrument, warn};
+use url::Url;
+
+use super::cache::PairingCache;
+use super::proxy::ProxyConfig;
+use super::tls::ClientAuth;
+use super::trust::TlsTrust;
+use super::{ClientId, MqttProtocol, MqttTransport};
+use crate::credentials::{CredentialKind, DynCredentialStore, FileCredentialStore};
 use crate::error::Report;
 use crate::logging::security::{SecurityEvent, notify_security_event};
 use crate::pairing::api::PairingApiError;
@@ -36,7 +43,9 @@ use crate::pairing::api::client::ApiClient;
 use crate::transport::mqtt::config::tls::{
     insecure_tls_config_builder, is_env_ignore_ssl, read_root_cert_store, tls_config_builder,
 };
-use crate::transport::mqtt::crypto::Bundle;
+use crate::transport::mqtt::crypto::{
+    Bundle, CryptoError, KeyAlgorithm, KeyProvider, load_private_key,
+};
 
 /// Structure to create an authenticated [`Transport`]
 #[derive(Debug)]
@@ -44,6 +53,17 @@ pub(crate) struct TransportProvider {
     store_dir: Option<PathBuf>,
     insecure_ssl: bool,
     root_cert_store: Arc<RootCertStore>,
+    /// Public keys the server certificates must match, if not empty.
+    pins: Vec<SpkiPin>,
+    transport: MqttTransport,
+    protocol: MqttProtocol,
+    proxy: ProxyConfig,
+    mqtt_proxy: Option<rumqttc::Proxy>,
+    cache: Option<PairingCache>,
+    key_algorithm: KeyAlgorithm,
+    /// Custom provider of the private key, otherwise it's stored in the credential store.
+    key_provider: Option<Arc<dyn KeyProvider>>,
+    credential_store: Option<Arc<dyn DynCredentialStore>>,
 }
 
 impl TransportProvider {
@@ -61,11 +81,183 @@ impl TransportProvider {
         debug!("reading root cert store from native certs");
         let root_certs = read_root_cert_store().await?;
 
-        Ok(Self {
+        let credential_store = store_dir
+            .as_ref()
+            .map(|dir| Arc::new(FileCredentialStore::new(dir)) as Arc<dyn DynCredentialStore>);
+
+        let provider = Self {
             insecure_ssl,
             root_cert_store: Arc::new(root_certs),
+            pins: Vec::new(),
             store_dir,
-        })
+            transport: MqttTransport::default(),
+            protocol: MqttProtocol::default(),
+            proxy: ProxyConfig::default(),
+            mqtt_proxy: None,
+            cache: None,
+            key_algorithm: KeyAlgorithm::default(),
+            key_provider: None,
+            credential_store,
+        };
+
+        provider.with_proxy(ProxyConfig::default())
+    }
+
+    /// Trusts the additional CA certificates and checks the pins.
+    ///
+    /// The proxy is configured again, since it uses the TLS configuration of the pairing API.
+    pub(crate) async fn with_trust(
+        mut self,
+        trust: &TlsTrust,
+    ) -> Result<Self, Error<PairingApiError>> {
+        if trust.is_empty() {
+            return Ok(self);
+        }
+
+        let trust = trust.load().await?;
+
+        let (added, ignored) = Arc::make_mut(&mut self.root_cert_store)
+            .add_parsable_certificates(trust.ca_certificates);
+
+        info!(
+            added,
+            ignored,
+            pins = trust.pins.len(),
+            "custom TLS trust configured"
+        );
+
+        self.pins = trust.pins;
+
+        let proxy = self.proxy.clone();
+
+        self.with_proxy(proxy)
+    }
+
+    /// Sets the transport used to connect to the broker.
+    pub(crate) fn with_transport(mut self, transport: MqttTransport) -> Self {
+        self.transport = transport;
+
+        self
+    }
+
+    /// Sets the version of the MQTT protocol used to connect to the broker.
+    pub(crate) fn with_protocol(mut self, protocol: MqttProtocol) -> Self {
+        self.protocol = protocol;
+
+        self
+    }
+
+    /// Sets the proxy used for the pairing API and the broker connection.
+    pub(crate) fn with_proxy(mut self, proxy: ProxyConfig) -> Result<Self, Error<PairingApiError>> {
+        self.mqtt_proxy = proxy.mqtt_proxy(|| self.api_tls_config())?;
+        self.proxy = proxy;
+
+        Ok(self)
+    }
+
+    /// Caches the broker url and the certificate verification for the given time.
+    ///
+    /// The cache is used when the pairing API can't be reached. It requires the store directory.
+    pub(crate) fn with_offline_cache(mut self, ttl: Option<Duration>) -> Self {
+        self.cache = match (ttl, &self.store_dir) {
+            (Some(ttl), Some(store_dir)) => Some(PairingCache::new(store_dir, ttl)),
+            (Some(_), None) => {
+                warn!("the offline cache requires a writable directory, it will be disabled");
+
+                None
+            }
+            (None, _) => None,
+        };
+
+        self
+    }
+
+    /// Sets the algorithm of the private key generated for new certificates.
+    pub(crate) fn with_key_algorithm(mut self, key_algorithm: KeyAlgorithm) -> Self {
+        self.key_algorithm = key_algorithm;
+
+        self
+    }
+
+    /// Sets the provider of the private key, instead of storing it in the writable directory.
+    pub(crate) fn with_key_provider(mut self, key_provider: Option<Arc<dyn KeyProvider>>) -> Self {
+        self.key_provider = key_provider;
+
+        self
+    }
+
+    /// Sets the store of the credentials, instead of the files in the writable directory.
+    pub(crate) fn with_credential_store(
+        mut self,
+        credential_store: Option<Arc<dyn DynCredentialStore>>,
+    ) -> Self {
+        if let Some(credential_store) = credential_store {
+            self.credential_store = Some(credential_store);
+        }
+
+        self
+    }
+
+    /// Store of the credentials, missing if there is no writable directory.
+    pub(crate) fn credential_store(&self) -> Option<&dyn DynCredentialStore> {
+        self.credential_store.as_deref()
+    }
+
+    /// Proxy for the pairing API.
+    pub(crate) fn proxy(&self) -> &ProxyConfig {
+        &self.proxy
+    }
+
+    /// Version of the MQTT protocol used to connect to the broker.
+    pub(crate) fn protocol(&self) -> MqttProtocol {
+        self.protocol
+    }
+
+    /// Sets the proxy on the MQTT options.
+    pub(crate) fn config_proxy(&self, mqtt_opts: &mut MqttOptions) {
+        if let Some(proxy) = &self.mqtt_proxy {
+            mqtt_opts.set_proxy(proxy.clone());
+        }
+    }
+
+    /// Returns the url of the broker for the configured transport.
+    ///
+    /// The url is requested to Astarte, unless the transport overrides it.
+    pub(crate) async fn broker_url(
+        &self,
+        client: &ApiClient<'_>,
+    ) -> Result<Url, Error<PairingApiError>> {
+        if let Some(url) = self.transport.url_override() {
+            debug!(%url, "using the broker url override");
+
+            return Ok(url.clone());
+        }
+
+        let broker_url = match (client.get_broker_url().await, &self.cache) {
+            (Ok(url), Some(cache)) => {
+                cache.set_broker_url(&url, Utc::now()).await;
+
+                url
+            }
+            (Ok(url), None) => url,
+            (Err(err), Some(cache)) if is_unreachable(&err) => {
+                let Some(url) = cache.broker_url(Utc::now()).await else {
+                    return Err(err);
+                };
+
+                warn!(error = %Report::new(&err), %url, "pairing API unreachable, using the cached broker url");
+
+                url
+            }
+            (Err(err), _) => return Err(err),
+        };
+
+        self.transport.broker_url(broker_url)
+    }
+
+    /// Returns the url of a fallback broker for the configured transport.
+    pub(crate) fn fallback_broker_url(&self, url: &Url) -> Result<Url, Error<PairingApiError>> {
+        self.transport.broker_url(url.clone())
     }
 
     pub(crate) fn api_tls_config(&self) -> Result<rustls::ClientConfig, Error<PairingApiError>> {
@@ -74,7 +266,7 @@ impl TransportProvider {
         } else {
             let roots = Arc::clone(&self.root_cert_store);
 
-            tls_config_builder(roots)?.with_no_client_auth()
+            tls_config_builder(roots, &self.pins)?.with_no_client_auth()
         };
 
         debug!("TLS client config read");
@@ -96,12 +288,16 @@ impl TransportProvider {
         } else {
             let roots = Arc::clone(&self.root_cert_store);
 
-            client_auth.tls_config(roots)?
+            client_auth.tls_config(roots, &self.pins)?
         };
 
-        Ok(Transport::tls_with_config(
-            rumqttc::TlsConfiguration::Rustls(Arc::new(config)),
-        ))
+        let config = rumqttc::TlsConfiguration::Rustls(Arc::new(config));
+
+        match self.transport {
+            MqttTransport::Tls => Ok(Transport::tls_with_config(config)),
+            #[cfg(feature = "websocket")]
+            MqttTransport::WebSocket { .. } => Ok(Transport::wss_with_config(config)),
+        }
     }
 
     /// Retrieves an already stored certificate or creates a new one
@@ -124,7 +320,38 @@ impl TransportProvider {
 
         debug!("existing certificate found");
 
-        let is_valid = client.verify_certificate(auth.pem()).await?;
+        let key_algorithm = auth.key_algorithm();
+        if key_algorithm != Some(self.key_algorithm) {
+            info!(
+                stored = ?key_algorithm,
+                configured = %self.key_algorithm,
+                "stored certificate has a different key algorithm, creating new credentials"
+            );
+
+            return Ok(None);
+        }
+
+        let is_valid = match (client.verify_certificate(auth.pem()).await, &self.cache) {
+            (Ok(is_valid), Some(cache)) => {
+                cache.set_verified(is_valid, Utc::now()).await;
+
+                is_valid
+            }
+            (Ok(is_valid), None) => is_valid,
+            (Err(err), Some(cache)) if is_unreachable(&err) => {
+                let now = Utc::now();
+                let not_expired = auth.validity_not_after().is_some_and(|expiry| now < expiry);
+
+                if !not_expired || !cache.is_verified(now).await {
+                    return Err(err);
+                }
+
+                warn!(error = %Report::new(&err), "pairing API unreachable, using the cached certificate verification");
+
+                true
+            }
+            (Err(err), _) => return Err(err),
+        };
 
         if is_valid {
             notify_security_event(SecurityEvent::CertificateValidationSucceeded);
@@ -133,11 +360,13 @@ impl TransportProvider {
         } else {
             notify_security_event(SecurityEvent::CertificateValidationFailed);
 
+            self.delete_certificate().await;
+
             Ok(None)
         }
     }
 
-    /// Creates new credentials and if a store directory is set, it stores it
+    /// Creates new credentials and if a credential store is set, it stores them
     pub(crate) async fn create_credentials(
         &self,
         client: &ApiClient<'_>,
@@ -147,20 +376,16 @@ impl TransportProvider {
 
         let (bundle, certificate) = self.create_certificate(client).await?;
 
-        // If no store dir is set we just create a new certificate
-        if let Some(store_dir) = &self.store_dir {
+        // If no store is set we just create a new certificate
+        if let Some(store) = &self.credential_store {
             debug!("storing credentials");
 
-            let certificate_file = CertificateFile::new(store_dir);
-            let private_key_file = PrivateKeyFile::new(store_dir);
+            Self::store_certificate(store.as_ref(), &certificate).await;
 
-            self.store_credentials(
-                &private_key_file,
-                &bundle.private_key,
-                &certificate_file,
-                &certificate,
-            )
-            .await
+            // The certificate was just created by Astarte
+            if let Some(cache) = &self.cache {
+                cache.set_verified(true, Utc::now()).await;
+            }
         }
 
         ClientAuth::try_from_pem_cert(certificate, bundle.private_key, client_id)
@@ -185,7 +410,9 @@ impl TransportProvider {
         &self,
         client: &ApiClient<'_>,
     ) -> Result<(Bundle, String), Error<PairingApiError>> {
-        let bundle = Bundle::generate_key(client.realm, client.device_id)
+        let private_key = self.generate_key().await?;
+
+        let bundle = Bundle::new(client.realm, client.device_id, private_key)
             .map_kind(PairingApiError::Crypto)?;
         notify_security_event(SecurityEvent::CsrPendingApproval);
 
@@ -204,49 +431,130 @@ impl TransportProvider {
         Ok((bundle, certificate))
     }
 
-    /// Store the credentials to files.
-    async fn store_credentials(
+    /// Generates a new private key with the [`KeyProvider`], or stores it with the credentials.
+    async fn generate_key(&self) -> Result<Arc<dyn SigningKey>, Error<PairingApiError>> {
+        let key_algorithm = self.key_algorithm;
+
+        if let Some(key_provider) = &self.key_provider {
+            return key_provider_blocking(key_provider, move |provider| {
+                provider.generate(key_algorithm)
+            })
+            .await;
+        }
+
+        let der = tokio::task::spawn_blocking(move || key_algorithm.generate_der())
+            .await
+            .wrap_err(PairingApiError::Join)?
+            .map_kind(PairingApiError::Crypto)?;
+
+        // Don't fail here since the key can be used from memory and regenerated
+        if let Some(store) = &self.credential_store
+            && let Err(err) = store.store_boxed(CredentialKind::PrivateKey, &der).await
+        {
+            error!(error = %Report::new(err), "couldn't store the private key");
+        }
+
+        load_private_key(der).map_kind(PairingApiError::Crypto)
+    }
+
+    /// Loads the private key from the [`KeyProvider`], or from the credential store.
+    async fn load_key(
         &self,
-        private_key_file: &PrivateKeyFile,
-        private_key: &PrivatePkcs8KeyDer<'_>,
-        certificate_file: &CertificateFile,
-        certificate: &str,
-    ) {
-        let store_cert = fs::write(&certificate_file, &certificate).await;
-        let store_key = fs::write(&private_key_file, &private_key.secret_pkcs8_der()).await;
-
-        if store_cert.is_ok() && store_key.is_ok() {
-            notify_security_event(SecurityEvent::CertificateStoredSuccessfully);
-        } else {
-            notify_security_event(SecurityEvent::CertificateWriteFailed);
+        store: &dyn DynCredentialStore,
+    ) -> Result<Option<Arc<dyn SigningKey>>, Error<PairingApiError>> {
+        if let Some(key_provider) = &self.key_provider {
+            return key_provider_blocking(key_provider, |provider| provider.load()).await;
         }
 
+        store
+            .load_boxed(CredentialKind::PrivateKey)
+            .await
+            .map_kind(PairingApiError::CredentialStore)?
+            .map(load_private_key)
+            .transpose()
+            .map_kind(PairingApiError::Crypto)
+    }
+
+    /// Store the certificate, the private key is stored when it's generated.
+    async fn store_certificate(store: &dyn DynCredentialStore, certificate: &str) {
+        let store_cert = store
+            .store_boxed(CredentialKind::Certificate, certificate.as_bytes())
+            .await;
+
         // Don't fail here since the SDK can always regenerate the certificate,
-        if let Err(err) = store_cert {
-            error!(error = %Report::new(&err), file = %certificate_file, "couldn't write certificate file");
+        match store_cert {
+            Ok(()) => {
+                notify_security_event(SecurityEvent::CertificateStoredSuccessfully);
+            }
+            Err(err) => {
+                notify_security_event(SecurityEvent::CertificateWriteFailed);
+
+                error!(error = %Report::new(&err), "couldn't store certificate");
+            }
         }
-        if let Err(err) = store_key {
-            error!(error = %Report::new(err), file = %private_key_file, "couldn't write private key file");
+    }
+
+    /// Deletes the stored certificate, after it was rejected by Astarte.
+    async fn delete_certificate(&self) {
+        let Some(store) = &self.credential_store else {
+            return;
+        };
+
+        if let Err(err) = store.delete_boxed(CredentialKind::Certificate).await {
+            error!(error = %Report::new(err), "couldn't delete the invalid certificate");
         }
     }
 
-    /// Read credentials from the filesystem.
+    /// Read credentials from the credential store.
     async fn read_credentials(&self, client_id: ClientId<&str>) -> Option<ClientAuth> {
-        let Some(store_dir) = &self.store_dir else {
-            debug!("no store directory");
+        let Some(store) = &self.credential_store else {
+            debug!("no credential store");
 
             return None;
         };
 
-        debug!("reading existing credentials from {}", store_dir.display());
+        debug!("reading existing credentials");
 
-        let certificate_file = CertificateFile::new(store_dir);
-        let private_key_file = PrivateKeyFile::new(store_dir);
+        let private_key = match self.load_key(store.as_ref()).await {
+            Ok(Some(private_key)) => private_key,
+            Ok(None) => {
+                debug!("private key is missing");
 
-        ClientAuth::try_read(certificate_file, private_key_file, client_id).await
+                return None;
+            }
+            Err(err) => {
+                error!(error = %Report::new(err), "couldn't load the private key");
+
+                return None;
+            }
+        };
+
+        ClientAuth::try_read(store.as_ref(), private_key, client_id).await
     }
 }
 
+/// Calls the [`KeyProvider`] on a blocking thread.
+async fn key_provider_blocking<F, T>(
+    key_provider: &Arc<dyn KeyProvider>,
+    f: F,
+) -> Result<T, Error<PairingApiError>>
+where
+    F: FnOnce(&dyn KeyProvider) -> Result<T, Error<CryptoError>> + Send + 'static,
+    T: Send + 'static,
+{
+    let key_provider = Arc::clone(key_provider);
+
+    tokio::task::spawn_blocking(move || f(key_provider.as_ref()))
+        .await
+        .wrap_err(PairingApiError::Join)?
+        .map_kind(PairingApiError::Crypto)
+}
+
+/// Returns true if the error is caused by the pairing API not being reachable.
+fn is_unreachable(err: &Error<PairingApiError>) -> bool {
+    *err.kind() == PairingApiError::Request
+}
+
 // TODO: test the certificate validation fail
 #[cfg(test)]
 mod tests {
@@ -259,8 +567,10 @@ mod tests {
     use url::Url;
 
     use crate::builder::Config;
+    use crate::credentials::{CredentialStore, MemoryCredentialStore};
     use crate::pairing::api::client::ClientArgs;
-    use crate::pairing::api::client::tests::mock_create_certificate;
+    use crate::pairing::api::client::tests::{mock_create_certificate, mock_get_broker_url};
+    use crate::pairing::api::{CERTIFICATE_FILE, PRIVATE_KEY_FILE};
 
     use super::*;
 
@@ -281,17 +591,16 @@ mod tests {
     }
 
     async fn check_stored_keys(dir: &Path) {
-        let certificate_file = CertificateFile::new(dir);
-        let private_key_file = PrivateKeyFile::new(dir);
-
-        let cert = tokio::fs::read_to_string(&certificate_file).await.unwrap();
+        let cert = tokio::fs::read_to_string(dir.join(CERTIFICATE_FILE))
+            .await
+            .unwrap();
 
         rustls_pemfile::certs(&mut cert.as_bytes())
             .next()
             .unwrap()
             .unwrap();
 
-        let key = tokio::fs::read(&private_key_file).await.unwrap();
+        let key = tokio::fs::read(dir.join(PRIVATE_KEY_FILE)).await.unwrap();
 
         assert!(!key.is_empty());
     }
@@ -430,6 +739,138 @@ mod tests {
         mock.assert_async().await;
     }
 
+    #[cfg(feature = "websocket")]
+    #[tokio::test]
+    async fn should_create_websocket_transport() {
+        let mut server = Server::new_async().await;
+
+        let mock = mock_create_certificate(&mut server)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let provider = TransportProvider::configure(None, false)
+            .await
+            .expect("failed to configure transport provider")
+            .with_transport(MqttTransport::WebSocket { url: None });
+
+        let url = server.url().parse().unwrap();
+        let (client_id, args) = mock_args(&url);
+
+        let api = ApiClient::from_transport(&Config::default(), &provider, args)
+            .expect("failed to create api client");
+
+        let auth = provider.create_credentials(&api, client_id).await.unwrap();
+        let transport = provider.config_transport(auth).unwrap();
+
+        assert!(matches!(
+            transport,
+            Transport::Wss(TlsConfiguration::Rustls(..))
+        ));
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn should_create_credentials_with_key_algorithm() {
+        let dir = TempDir::new().unwrap();
+
+        let mut server = Server::new_async().await;
+
+        let mock = mock_create_certificate(&mut server)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let provider = TransportProvider::configure(Some(dir.path().to_owned()), false)
+            .await
+            .expect("failed to configure transport provider")
+            .with_key_algorithm(KeyAlgorithm::EcdsaP384);
+
+        let url = server.url().parse().unwrap();
+        let (client_id, args) = mock_args(&url);
+        let api = ApiClient::from_transport(&Config::default(), &provider, args).unwrap();
+
+        let auth = provider.create_credentials(&api, client_id).await.unwrap();
+        assert_eq!(auth.key_algorithm(), Some(KeyAlgorithm::EcdsaP384));
+
+        let transport = provider.config_transport(auth).unwrap();
+        assert!(matches!(
+            transport,
+            Transport::Tls(TlsConfiguration::Rustls(..))
+        ));
+
+        mock.assert_async().await;
+
+        // The stored key doesn't match the configured algorithm
+        let provider = TransportProvider::configure(Some(dir.path().to_owned()), false)
+            .await
+            .expect("failed to configure transport provider");
+        let (client_id, args) = mock_args(&url);
+        let api = ApiClient::from_transport(&Config::default(), &provider, args).unwrap();
+
+        let auth = provider
+            .retrieve_credentials(&api, client_id)
+            .await
+            .unwrap();
+        assert!(auth.is_none());
+    }
+
+    #[tokio::test]
+    async fn should_use_offline_cache() {
+        let dir = TempDir::new().unwrap();
+
+        let mut server = Server::new_async().await;
+
+        let create = mock_create_certificate(&mut server)
+            .expect(1)
+            .create_async()
+            .await;
+        let broker = mock_get_broker_url(&mut server)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let provider = TransportProvider::configure(Some(dir.path().to_owned()), false)
+            .await
+            .expect("failed to configure transport provider")
+            .with_offline_cache(Some(Duration::from_secs(3600)));
+
+        let url = server.url().parse().unwrap();
+        let (client_id, args) = mock_args(&url);
+        let api = ApiClient::from_transport(&Config::default(), &provider, args).unwrap();
+
+        provider.create_credentials(&api, client_id).await.unwrap();
+        let broker_url = provider.broker_url(&api).await.unwrap();
+
+        create.assert_async().await;
+        broker.assert_async().await;
+
+        // Nothing is listening on the port
+        let unreachable = "http://127.0.0.1:1".parse().unwrap();
+        let (client_id, args) = mock_args(&unreachable);
+        let api = ApiClient::from_transport(&Config::default(), &provider, args).unwrap();
+
+        let auth = provider
+            .retrieve_credentials(&api, client_id)
+            .await
+            .unwrap();
+        assert!(auth.is_some());
+        assert_eq!(provider.broker_url(&api).await.unwrap(), broker_url);
+
+        // Without the cache it fails
+        let provider = TransportProvider::configure(Some(dir.path().to_owned()), false)
+            .await
+            .expect("failed to configure transport provider");
+        let (client_id, args) = mock_args(&unreachable);
+        let api = ApiClient::from_transport(&Config::default(), &provider, args).unwrap();
+
+        let err = provider.broker_url(&api).await.unwrap_err();
+        assert_eq!(*err.kind(), PairingApiError::Request);
+        let res = provider.retrieve_credentials(&api, client_id).await;
+        assert!(res.is_err_and(|err| *err.kind() == PairingApiError::Request));
+    }
+
     #[tokio::test]
     async fn should_succeed_if_fs_error() {
         let dir = TempDir::new().unwrap();
@@ -461,4 +902,38 @@ mod tests {
 
         mock.assert_async().await;
     }
+
+    #[tokio::test]
+    async fn should_use_credential_store() {
+        let mut server = Server::new_async().await;
+
+        let mock = mock_create_certificate(&mut server)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let store = MemoryCredentialStore::new();
+
+        // Without the writable directory
+        let provider = TransportProvider::configure(None, false)
+            .await
+            .expect("failed to configure transport provider")
+            .with_credential_store(Some(Arc::new(store.clone())));
+
+        let url = server.url().parse().unwrap();
+        let (client_id, args) = mock_args(&url);
+        let api = ApiClient::from_transport(&Config::default(), &provider, args).unwrap();
+
+        provider.create_credentials(&api, client_id).await.unwrap();
+
+        mock.assert_async().await;
+
+        let cert = store.load(CredentialKind::Certificate).await.unwrap();
+        assert!(cert.is_some());
+        let key = store.load(CredentialKind::PrivateKey).await.unwrap();
+        assert!(key.is_some());
+
+        let auth = provider.read_credentials(client_id).await;
+        assert!(auth.is_some());
+    }
 }
@@ -0,0 +1,240 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Custom CA certificates and pinning for the TLS connections.
+
+use std::path::PathBuf;
+
+use astarte_device_error::{Error, WrapError};
+use astarte_device_tls::TrustOptions;
+use rustls::pki_types::CertificateDer;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+pub use astarte_device_tls::pinning::{SpkiPin, SpkiPinError};
+
+use crate::pairing::api::PairingApiError;
+
+/// Source of a trusted CA certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaCertificate {
+    /// Path to a PEM file, it can contain multiple certificates.
+    File(PathBuf),
+    /// PEM encoded certificates.
+    Pem(String),
+}
+
+/// Additional trust for the TLS connections to Astarte.
+///
+/// The CA certificates are trusted together with the native or `webpki` roots, this is useful for
+/// an on-premise Astarte with a private CA.
+///
+/// The pins are checked after the certificate chain is verified: one of the certificates presented
+/// by the server must have a public key matching one of the pins. The pins are checked for both
+/// the pairing API and the broker, so they should include the keys of both.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsTrust {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ca_certificates: Vec<CaCertificate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "pins")]
+    pins: Vec<SpkiPin>,
+}
+
+impl TlsTrust {
+    /// Creates an empty trust configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts the CA certificates in the PEM file.
+    ///
+    /// The file is read when connecting.
+    pub fn add_ca_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_certificates.push(CaCertificate::File(path.into()));
+
+        self
+    }
+
+    /// Trusts the PEM encoded CA certificates.
+    pub fn add_ca_pem(mut self, pem: impl Into<String>) -> Self {
+        self.ca_certificates.push(CaCertificate::Pem(pem.into()));
+
+        self
+    }
+
+    /// Pins the public key of a server certificate.
+    pub fn pin(mut self, pin: SpkiPin) -> Self {
+        self.pins.push(pin);
+
+        self
+    }
+
+    /// Returns true if no CA certificates or pins are configured.
+    pub fn is_empty(&self) -> bool {
+        self.ca_certificates.is_empty() && self.pins.is_empty()
+    }
+
+    /// Returns the configured CA certificates.
+    pub fn ca_certificates(&self) -> &[CaCertificate] {
+        &self.ca_certificates
+    }
+
+    /// Returns the configured pins.
+    pub fn pins(&self) -> &[SpkiPin] {
+        &self.pins
+    }
+
+    /// Reads the CA certificates.
+    pub(crate) async fn load(&self) -> Result<TrustOptions, Error<PairingApiError>> {
+        let mut ca_certificates = Vec::new();
+
+        for ca in &self.ca_certificates {
+            let certs = match ca {
+                CaCertificate::File(path) => {
+                    debug!(path = %path.display(), "reading CA certificates");
+
+                    let pem = tokio::fs::read(path).await.wrap_err_with(|err| {
+                        Error::with(
+                            PairingApiError::Io(err.kind()),
+                            "couldn't read the CA certificates",
+                        )
+                        .set_ctx(format!("path {}", path.display()))
+                    })?;
+
+                    parse_pem(&pem)
+                        .map_err(|err| err.set_ctx(format!("path {}", path.display())))?
+                }
+                CaCertificate::Pem(pem) => parse_pem(pem.as_bytes())?,
+            };
+
+            ca_certificates.extend(certs);
+        }
+
+        Ok(TrustOptions {
+            ca_certificates,
+            pins: self.pins.clone(),
+        })
+    }
+}
+
+/// Parses all the certificates in the PEM.
+fn parse_pem(mut pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, Error<PairingApiError>> {
+    let certs = rustls_pemfile::certs(&mut pem)
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err_msg(
+            PairingApiError::InvalidArgument,
+            "invalid CA certificates PEM",
+        )?;
+
+    if certs.is_empty() {
+        return Err(Error::with(
+            PairingApiError::InvalidArgument,
+            "no CA certificate in PEM",
+        ));
+    }
+
+    Ok(certs)
+}
+
+/// Serializes the pins as strings.
+mod pins {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::SpkiPin;
+
+    pub(super) fn serialize<S>(pins: &[SpkiPin], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(pins.iter().map(SpkiPin::to_string))
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<SpkiPin>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|pin| pin.parse().map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use crate::transport::mqtt::config::tls::tests::TEST_CERTIFICATE;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn should_load_ca_certificates() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("ca.pem");
+        tokio::fs::write(&path, TEST_CERTIFICATE).await.unwrap();
+
+        let trust = TlsTrust::new()
+            .add_ca_file(&path)
+            .add_ca_pem(TEST_CERTIFICATE);
+
+        let options = trust.load().await.unwrap();
+
+        assert_eq!(options.ca_certificates.len(), 2);
+        assert!(options.pins.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_fail_invalid_ca_certificates() {
+        let dir = TempDir::new().unwrap();
+
+        let err = TlsTrust::new()
+            .add_ca_file(dir.path().join("missing.pem"))
+            .load()
+            .await
+            .unwrap_err();
+        assert_eq!(
+            *err.kind(),
+            PairingApiError::Io(std::io::ErrorKind::NotFound)
+        );
+
+        let err = TlsTrust::new()
+            .add_ca_pem("not a certificate")
+            .load()
+            .await
+            .unwrap_err();
+        assert_eq!(*err.kind(), PairingApiError::InvalidArgument);
+    }
+
+    #[test]
+    fn should_serialize_pins() {
+        let pin = SpkiPin::from_sha256([42; 32]);
+        let trust = TlsTrust::new().pin(pin);
+
+        let json = serde_json::to_string(&trust).unwrap();
+        assert_eq!(json, format!(r#"{{"pins":["{pin}"]}}"#));
+
+        let res: TlsTrust = serde_json::from_str(&json).unwrap();
+        assert_eq!(res, trust);
+
+        let empty: TlsTrust = serde_json::from_str("{}").unwrap();
+        assert!(empty.is_empty());
+    }
+}
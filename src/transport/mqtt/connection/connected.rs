@@ -33,10 +33,9 @@ impl Connected {
         loop {
             let publish = self
                 .connection
-                .eventloop_mut()
                 .poll()
                 .await
-                .wrap_err_msg(MqttError::Connection, "polling next publish")
+                .wrap_err_with(|err| Error::with(err.kind(), "polling next publish"))
                 .and_then(handle_event)?;
 
             if let Some(publish) = publish {
@@ -55,6 +54,7 @@ pub(crate) mod tests {
 
     use crate::transport::mqtt::client::{AsyncClient, EventLoop};
     use crate::transport::mqtt::connection::tests::publish_pkt;
+    use crate::transport::mqtt::metrics::MqttMetrics;
 
     use super::*;
 
@@ -63,6 +63,7 @@ pub(crate) mod tests {
             connection: Connection {
                 client,
                 eventloop: SyncWrapper::new(eventloop),
+                metrics: MqttMetrics::default(),
             },
         }
     }
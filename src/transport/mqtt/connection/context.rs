@@ -18,16 +18,22 @@
 
 use std::fmt::Debug;
 use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 
-use rumqttc::{MqttOptions, NetworkOptions};
+use astarte_device_error::Error;
+use rumqttc::{Event, MqttOptions, NetworkOptions, Outgoing, Packet};
 use sync_wrapper::SyncWrapper;
+use url::Url;
 
-use crate::builder::Config;
 use crate::interfaces::Interfaces;
+use crate::pairing::PairingConfig;
+use crate::pairing::api::PairingApiError;
 use crate::state::SharedState;
 use crate::transport::mqtt::ClientSender;
-use crate::transport::mqtt::client::{AsyncClient, EventLoop};
+use crate::transport::mqtt::client::{AsyncClient, ConnectionError, EventLoop};
+use crate::transport::mqtt::config::MqttProtocol;
 use crate::transport::mqtt::config::transport::TransportProvider;
+use crate::transport::mqtt::metrics::MqttMetrics;
 
 /// Context for the connection
 #[derive(Debug)]
@@ -37,6 +43,8 @@ pub struct ConnCtx<'a, S> {
     pub(crate) provider: &'a TransportProvider,
     pub(crate) store: &'a S,
     pub(crate) interfaces: &'a Interfaces,
+    /// Quality metrics of the connection
+    pub(crate) metrics: &'a MqttMetrics,
     /// Whether the stored introspection matches the current one
     pub(crate) session_synced: bool,
 }
@@ -52,33 +60,79 @@ pub(super) struct Connection {
     //       is stabilized or the EventLoop becomes Sync
     //       https://doc.rust-lang.org/std/sync/struct.Exclusive.html
     pub(super) eventloop: SyncWrapper<EventLoop>,
+    pub(super) metrics: MqttMetrics,
 }
 
 impl Connection {
-    pub(crate) fn new(config: &Config, opt: MqttOptions, net: NetworkOptions) -> Connection {
-        let (client, mut eventloop) = AsyncClient::new(opt, config.channel_size.get());
+    pub(crate) fn new(
+        state: &SharedState,
+        metrics: &MqttMetrics,
+        opt: MqttOptions,
+        net: NetworkOptions,
+        protocol: MqttProtocol,
+    ) -> Connection {
+        let (client, mut eventloop) =
+            AsyncClient::new(opt, state.config.channel_size.get(), protocol);
 
         eventloop.set_network_options(net);
 
         Connection {
             client,
             eventloop: SyncWrapper::new(eventloop),
+            metrics: metrics.clone(),
         }
     }
 
+    /// Polls the next event, measuring the round-trip time of the pings.
+    pub(crate) async fn poll(&mut self) -> Result<Event, ConnectionError> {
+        let event = self.eventloop.get_mut().poll().await?;
+
+        match &event {
+            Event::Outgoing(Outgoing::PingReq) => self.metrics.ping_sent(Instant::now()),
+            Event::Incoming(Packet::PingResp) => self.metrics.ping_received(Instant::now()),
+            Event::Incoming(_) | Event::Outgoing(_) => {}
+        }
+
+        Ok(event)
+    }
+
     /// Set the transport for the rumqttc
     pub(crate) fn set_transport(&mut self, transport: rumqttc::Transport) {
         cfg_if::cfg_if! {
             if #[cfg(test)] {
                 let _ = transport;
             } else {
-                self.eventloop.get_mut().mqtt_options.set_transport(transport);
+                self.eventloop.get_mut().set_transport(transport);
             }
         }
     }
 
-    pub(crate) fn eventloop_mut(&mut self) -> &mut EventLoop {
-        self.eventloop.get_mut()
+    /// Set the broker for the next connection, keeping the transport and the session.
+    pub(crate) fn set_broker<S>(
+        &mut self,
+        ctx: &ConnCtx<'_, S>,
+        cfg: &PairingConfig,
+        broker_url: &Url,
+    ) -> Result<(), Error<PairingApiError>> {
+        cfg_if::cfg_if! {
+            if #[cfg(test)] {
+                let _ = (ctx, cfg, broker_url);
+            } else {
+                let eventloop = self.eventloop.get_mut();
+
+                let (mut mqtt_opts, _) = cfg.build_mqtt_opts(
+                    eventloop.transport(),
+                    broker_url,
+                    ctx.state.config.connection_timeout,
+                )?;
+
+                ctx.provider.config_proxy(&mut mqtt_opts);
+
+                eventloop.set_options(mqtt_opts);
+            }
+        }
+
+        Ok(())
     }
 
     pub(crate) fn set_clean_session(&mut self, clean_session: bool) {
@@ -86,7 +140,7 @@ impl Connection {
             if #[cfg(test)] {
                 let _ = clean_session;
             } else {
-                self.eventloop.get_mut().mqtt_options.set_clean_session(clean_session);
+                self.eventloop.get_mut().set_clean_session(clean_session);
             }
         }
     }
@@ -97,6 +151,7 @@ impl Debug for Connection {
         let Self {
             client: _,
             eventloop: _,
+            metrics: _,
         } = self;
 
         f.debug_struct("Connection").finish_non_exhaustive()
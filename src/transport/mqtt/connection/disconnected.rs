@@ -17,7 +17,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use astarte_device_error::Error;
+use chrono::Utc;
 use tracing::{debug, error};
+use url::Url;
 
 use crate::error::Report;
 use crate::pairing::PairingConfig;
@@ -25,6 +27,7 @@ use crate::pairing::api::client::{ApiClient, ClientArgs};
 use crate::transport::mqtt::{ClientSender, PairingApiError};
 
 use super::context::{ConnCtx, Connection};
+use super::failover::{BrokerFailover, Endpoint};
 
 /// Disconnected state.
 ///
@@ -41,15 +44,10 @@ impl Disconnected {
         &mut self,
         ctx: &mut ConnCtx<'_, S>,
         cfg: &PairingConfig,
+        failover: &mut BrokerFailover,
     ) -> Result<&mut Connection, Error<PairingApiError>> {
-        let args = ClientArgs {
-            realm: &cfg.client_id.realm,
-            device_id: &cfg.client_id.device_id,
-            pairing_url: &cfg.pairing_url,
-            token: &cfg.secret,
-        };
-
-        let api = ApiClient::from_transport(&ctx.state.config, ctx.provider, args)?;
+        let api =
+            ApiClient::from_transport(&ctx.state.config, ctx.provider, ClientArgs::from(cfg))?;
 
         let client_auth = ctx
             .provider
@@ -63,6 +61,8 @@ impl Disconnected {
             (Some(connection), Some(client_auth)) => {
                 debug!("connection and auth present and valid");
 
+                Self::switch_broker(connection, ctx, cfg, &api, failover).await?;
+
                 (connection, client_auth.validity_not_after())
             }
             (Some(connection), None) => {
@@ -79,6 +79,8 @@ impl Disconnected {
 
                 connection.set_transport(transport);
 
+                Self::switch_broker(connection, ctx, cfg, &api, failover).await?;
+
                 (connection, expiry)
             }
             (conn @ None, Some(client_auth)) => {
@@ -89,7 +91,7 @@ impl Disconnected {
                 let transport = ctx.provider.config_transport(client_auth)?;
 
                 (
-                    Self::create_connection(conn, ctx, cfg, &api, transport).await?,
+                    Self::create_connection(conn, ctx, cfg, &api, transport, failover).await?,
                     expiry,
                 )
             }
@@ -106,7 +108,7 @@ impl Disconnected {
                 let transport = ctx.provider.config_transport(client_auth)?;
 
                 (
-                    Self::create_connection(conn, ctx, cfg, &api, transport).await?,
+                    Self::create_connection(conn, ctx, cfg, &api, transport, failover).await?,
                     expiry,
                 )
             }
@@ -125,13 +127,22 @@ impl Disconnected {
         cfg: &PairingConfig,
         api: &ApiClient<'_>,
         transport: rumqttc::Transport,
+        failover: &mut BrokerFailover,
     ) -> Result<&'a mut Connection, Error<PairingApiError>> {
-        let broker_url = api.get_broker_url().await?;
+        let broker_url = Self::broker_url(ctx, api, failover).await?;
 
-        let (mqtt_opts, net_opts) =
+        let (mut mqtt_opts, net_opts) =
             cfg.build_mqtt_opts(transport, &broker_url, ctx.state.config.connection_timeout)?;
 
-        let new = Connection::new(&ctx.state.config, mqtt_opts, net_opts);
+        ctx.provider.config_proxy(&mut mqtt_opts);
+
+        let new = Connection::new(
+            ctx.state,
+            ctx.metrics,
+            mqtt_opts,
+            net_opts,
+            ctx.provider.protocol(),
+        );
 
         let client_sender = ClientSender {
             id: cfg.client_id.clone(),
@@ -144,4 +155,41 @@ impl Disconnected {
 
         Ok(connection.insert(new))
     }
+
+    /// Sets the broker selected by the failover on an existing connection.
+    async fn switch_broker<S>(
+        connection: &mut Connection,
+        ctx: &ConnCtx<'_, S>,
+        cfg: &PairingConfig,
+        api: &ApiClient<'_>,
+        failover: &mut BrokerFailover,
+    ) -> Result<(), Error<PairingApiError>> {
+        // Keep the broker of the connection when there is nothing to fail over to
+        if !failover.is_enabled() {
+            return Ok(());
+        }
+
+        let broker_url = Self::broker_url(ctx, api, failover).await?;
+
+        connection.set_broker(ctx, cfg, &broker_url)
+    }
+
+    /// Returns the url of the broker selected by the failover.
+    async fn broker_url<S>(
+        ctx: &ConnCtx<'_, S>,
+        api: &ApiClient<'_>,
+        failover: &mut BrokerFailover,
+    ) -> Result<Url, Error<PairingApiError>> {
+        let broker_url = match failover.select(Utc::now()) {
+            Endpoint::Primary => ctx.provider.broker_url(api).await,
+            Endpoint::Fallback(url) => {
+                debug!(%url, "using the fallback broker");
+
+                ctx.provider.fallback_broker_url(url)
+            }
+        };
+
+        // The primary is unknown if the pairing API can't be reached
+        broker_url.inspect_err(|_| failover.failed(Utc::now()))
+    }
 }
@@ -0,0 +1,228 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Selects the broker to connect to, between the primary and the fallback ones.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tracing::{debug, warn};
+use url::Url;
+
+/// Default time after a failure before connecting to a broker again.
+pub(crate) const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60 * 5);
+
+/// Broker to connect to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Endpoint<'a> {
+    /// The broker returned by the pairing API, or the configured override.
+    Primary,
+    /// One of the configured fallback brokers.
+    Fallback(&'a Url),
+}
+
+/// Health of a broker.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Health {
+    /// Number of consecutive failures.
+    failures: u32,
+    /// Time of the last failure.
+    last_failure: Option<DateTime<Utc>>,
+}
+
+impl Health {
+    fn is_healthy(&self, cooldown: Duration, now: DateTime<Utc>) -> bool {
+        self.last_failure.is_none_or(|last| {
+            now.signed_duration_since(last).to_std().unwrap_or_default() >= cooldown
+        })
+    }
+}
+
+/// Failover between the primary and the fallback brokers.
+///
+/// The brokers are tried in order, skipping the ones that failed in the last cooldown. So the
+/// primary is preferred again on the first reconnection after its cooldown elapsed. If all the
+/// brokers failed recently, the one that failed first is tried.
+#[derive(Debug)]
+pub(crate) struct BrokerFailover {
+    fallbacks: Vec<Url>,
+    cooldown: Duration,
+    /// Index of the selected broker, the primary is `0`.
+    current: usize,
+    /// Health of the primary followed by the fallbacks.
+    health: Vec<Health>,
+}
+
+impl BrokerFailover {
+    pub(crate) fn new(fallbacks: Vec<Url>, cooldown: Option<Duration>) -> Self {
+        let health = vec![Health::default(); fallbacks.len() + 1];
+
+        Self {
+            fallbacks,
+            cooldown: cooldown.unwrap_or(DEFAULT_COOLDOWN),
+            current: 0,
+            health,
+        }
+    }
+
+    /// Returns true if there are fallback brokers.
+    pub(crate) fn is_enabled(&self) -> bool {
+        !self.fallbacks.is_empty()
+    }
+
+    /// Selects the broker for the next connection.
+    pub(crate) fn select(&mut self, now: DateTime<Utc>) -> Endpoint<'_> {
+        let healthy = self
+            .health
+            .iter()
+            .position(|health| health.is_healthy(self.cooldown, now));
+
+        let next = healthy.unwrap_or_else(|| {
+            // All failed recently, retry the one that failed first
+            self.health
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, health)| health.last_failure)
+                .map_or(0, |(idx, _)| idx)
+        });
+
+        if next != self.current {
+            debug!(from = self.current, to = next, "switching broker");
+        }
+
+        self.current = next;
+
+        self.current_endpoint()
+    }
+
+    fn current_endpoint(&self) -> Endpoint<'_> {
+        match self.current.checked_sub(1) {
+            None => Endpoint::Primary,
+            Some(idx) => Endpoint::Fallback(&self.fallbacks[idx]),
+        }
+    }
+
+    /// The connection to the selected broker failed.
+    pub(crate) fn failed(&mut self, now: DateTime<Utc>) {
+        let health = &mut self.health[self.current];
+
+        health.failures = health.failures.saturating_add(1);
+        health.last_failure = Some(now);
+
+        warn!(
+            broker = self.current,
+            failures = health.failures,
+            "connection to the broker failed"
+        );
+    }
+
+    /// The connection to the selected broker succeeded.
+    pub(crate) fn connected(&mut self) {
+        self.health[self.current] = Health::default();
+    }
+}
+
+impl Default for BrokerFailover {
+    fn default() -> Self {
+        Self::new(Vec::new(), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    fn failover() -> (BrokerFailover, Url, Url) {
+        let first: Url = "mqtts://broker-1.astarte.localhost:8883".parse().unwrap();
+        let second: Url = "mqtts://broker-2.astarte.localhost:8883".parse().unwrap();
+
+        let failover = BrokerFailover::new(vec![first.clone(), second.clone()], Some(COOLDOWN));
+
+        (failover, first, second)
+    }
+
+    #[test]
+    fn should_use_primary_without_fallbacks() {
+        let now = Utc::now();
+
+        let mut failover = BrokerFailover::default();
+        assert!(!failover.is_enabled());
+        assert_eq!(failover.select(now), Endpoint::Primary);
+
+        failover.failed(now);
+        assert_eq!(failover.select(now), Endpoint::Primary);
+    }
+
+    #[test]
+    fn should_rotate_on_failures() {
+        let now = Utc::now();
+        let (mut failover, first, second) = failover();
+
+        assert!(failover.is_enabled());
+        assert_eq!(failover.select(now), Endpoint::Primary);
+
+        failover.failed(now);
+        assert_eq!(failover.select(now), Endpoint::Fallback(&first));
+
+        failover.failed(now + Duration::from_secs(1));
+        assert_eq!(failover.select(now), Endpoint::Fallback(&second));
+
+        // All failed, retry the one that failed first
+        failover.failed(now + Duration::from_secs(2));
+        assert_eq!(failover.select(now), Endpoint::Primary);
+    }
+
+    #[test]
+    fn should_prefer_primary_after_cooldown() {
+        let now = Utc::now();
+        let (mut failover, first, _) = failover();
+
+        assert_eq!(failover.select(now), Endpoint::Primary);
+        failover.failed(now);
+
+        assert_eq!(failover.select(now), Endpoint::Fallback(&first));
+        failover.connected();
+
+        // Still connected to the fallback before the cooldown
+        assert_eq!(
+            failover.select(now + COOLDOWN / 2),
+            Endpoint::Fallback(&first)
+        );
+
+        assert_eq!(failover.select(now + COOLDOWN), Endpoint::Primary);
+    }
+
+    #[test]
+    fn should_reset_health_on_connection() {
+        let now = Utc::now();
+        let (mut failover, _, _) = failover();
+
+        assert_eq!(failover.select(now), Endpoint::Primary);
+        failover.failed(now);
+        assert_eq!(failover.health[0].failures, 1);
+
+        // Select the primary again after the cooldown
+        assert_eq!(failover.select(now + COOLDOWN), Endpoint::Primary);
+        failover.connected();
+        assert_eq!(failover.health[0], Health::default());
+    }
+}
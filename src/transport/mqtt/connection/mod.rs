@@ -35,17 +35,29 @@
 
 use std::fmt::Debug;
 use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
 
 use astarte_device_error::{Error, ResultExt, WrapError};
+use chrono::{DateTime, Utc};
 use rumqttc::{Event, Packet, Publish};
-use tracing::{debug, error, trace};
+use tracing::{debug, error, info, trace};
+use url::Url;
 
+use crate::error::Report;
 use crate::logging::security::{SecurityEvent, notify_security_event, notify_tls_error};
+use crate::pairing::PairingConfig;
+use crate::pairing::api::PairingApiError;
+use crate::pairing::api::client::{ApiClient, ClientArgs};
+use crate::state::SharedState;
 use crate::store::StoreCapabilities;
+use crate::transport::mqtt::client::ConnectionError;
+use crate::transport::mqtt::config::transport::TransportProvider;
 
 use self::context::ConnCtx;
 use self::disconnected::Disconnected;
+use self::failover::BrokerFailover;
 use self::handshake::Handshake;
+use self::renewal::CertRenewal;
 use self::state::State;
 use self::wait_connack::Connack;
 use self::wait_sends::TaskHandle;
@@ -56,16 +68,17 @@ use crate::pairing::Pairing;
 mod connected;
 pub(crate) mod context;
 mod disconnected;
+mod failover;
 mod handshake;
+mod renewal;
 mod state;
 mod wait_connack;
 mod wait_sends;
 
 fn is_tls_error(error: &Error<MqttError>) -> Option<&rustls::Error> {
-    std::error::Error::source(error).and_then(|s| match s.downcast_ref() {
-        Some(rumqttc::ConnectionError::Tls(rumqttc::TlsError::TLS(tls))) => Some(tls),
-        _ => None,
-    })
+    std::error::Error::source(error)
+        .and_then(|s| s.downcast_ref::<ConnectionError>())
+        .and_then(ConnectionError::tls_error)
 }
 
 #[derive(Debug)]
@@ -73,6 +86,10 @@ pub(crate) struct MqttState<P> {
     /// The device is disconnected from Astarte, it will need to recreate the connection.
     pub(crate) pairing: P,
     state: State,
+    /// Configuration of the last connection, used to renew the certificate.
+    config: Option<PairingConfig>,
+    renewal: CertRenewal,
+    failover: BrokerFailover,
 }
 
 impl<P> MqttState<P> {
@@ -80,9 +97,98 @@ impl<P> MqttState<P> {
         Self {
             pairing,
             state: State::Disconnected(Disconnected { connection: None }),
+            config: None,
+            renewal: CertRenewal::default(),
+            failover: BrokerFailover::default(),
         }
     }
 
+    /// Renews the client certificate the given time before it expires.
+    pub(crate) fn with_cert_renewal(mut self, margin: Option<Duration>) -> Self {
+        self.renewal = CertRenewal::new(margin);
+
+        self
+    }
+
+    /// Fails over to the fallback brokers when the connection to the primary fails.
+    ///
+    /// The primary is preferred again on a reconnection after the cooldown.
+    pub(crate) fn with_broker_failover(
+        mut self,
+        fallbacks: Vec<Url>,
+        cooldown: Option<Duration>,
+    ) -> Self {
+        self.failover = BrokerFailover::new(fallbacks, cooldown);
+
+        self
+    }
+
+    /// Returns the time to wait before renewing the client certificate.
+    pub(crate) async fn cert_renewal_delay(&self, state: &SharedState) -> Option<Duration> {
+        // The device never connected
+        self.config.as_ref()?;
+
+        let expiry = *state.cert_expiry.read().await;
+
+        self.renewal.delay(expiry, Utc::now())
+    }
+
+    /// Renews the client certificate before it expires.
+    ///
+    /// The transport with the new certificate is used from the next reconnection, so the current
+    /// connection and session are kept.
+    pub(crate) async fn renew_certificate(
+        &mut self,
+        provider: &TransportProvider,
+        state: &SharedState,
+    ) {
+        notify_security_event(SecurityEvent::CertificateAboutToExpire);
+
+        match self.create_certificate(provider, state).await {
+            Ok(expiry) => {
+                info!(?expiry, "client certificate renewed");
+
+                self.renewal.renewed(expiry, Utc::now());
+            }
+            Err(err) => {
+                error!(error = %Report::new(&err), "couldn't renew the client certificate");
+
+                self.renewal.failed(Utc::now());
+            }
+        }
+    }
+
+    async fn create_certificate(
+        &mut self,
+        provider: &TransportProvider,
+        state: &SharedState,
+    ) -> Result<Option<DateTime<Utc>>, Error<PairingApiError>> {
+        let cfg = self.config.as_ref().ok_or_else(|| {
+            Error::with(
+                PairingApiError::InvalidArgument,
+                "missing pairing configuration",
+            )
+        })?;
+
+        let api = ApiClient::from_transport(&state.config, provider, ClientArgs::from(cfg))?;
+
+        let client_auth = provider
+            .create_credentials(&api, cfg.client_id.as_ref())
+            .await?;
+
+        let expiry = client_auth.validity_not_after();
+
+        let transport = provider.config_transport(client_auth)?;
+
+        if let Some(connection) = self.state.connection_mut() {
+            connection.set_transport(transport);
+        }
+
+        *state.cert_expiry.write().await = expiry;
+
+        Ok(expiry)
+    }
+
     pub(crate) async fn next_publish(&mut self) -> Option<Publish> {
         match &mut self.state {
             State::Connected(connected) => match connected.next_publish().await {
@@ -121,8 +227,14 @@ impl<P> MqttState<P> {
         match &mut self.state {
             State::Connected(_) => Ok(ControlFlow::Break(true)),
             State::Disconnected(disconnected) => {
-                let (session_present, join_handle) =
-                    Self::handle_reconnect(disconnected, &mut self.pairing, ctx).await?;
+                let (session_present, join_handle) = Self::handle_reconnect(
+                    disconnected,
+                    &mut self.pairing,
+                    &mut self.config,
+                    &mut self.failover,
+                    ctx,
+                )
+                .await?;
 
                 let Some(join_handle) = join_handle else {
                     self.state.set_connected()?;
@@ -154,6 +266,8 @@ impl<P> MqttState<P> {
     async fn handle_reconnect<S>(
         disconnected: &mut Disconnected,
         pairing: &mut P,
+        config: &mut Option<PairingConfig>,
+        failover: &mut BrokerFailover,
         ctx: &mut ConnCtx<'_, S>,
     ) -> Result<(bool, Option<TaskHandle>), Error<MqttError>>
     where
@@ -169,14 +283,31 @@ impl<P> MqttState<P> {
         // FIXME: Not sure if this is the best place to do it
         ctx.state.set_device_status(true);
 
+        let cfg = config.insert(cfg);
+
+        let started = Instant::now();
+
         let connection = disconnected
-            .connect(ctx, &cfg)
+            .connect(ctx, cfg, failover)
             .await
             .map_kind(MqttError::PairingApi)?;
 
         // NOTE: the next packet will always be a CONNACK, see Disconnected for more information
         let mut connack = Connack { connection };
-        let session_present = connack.wait(ctx).await?;
+        let session_present = connack.wait(ctx).await.inspect_err(|err| {
+            failover.failed(Utc::now());
+
+            let reason = match err.kind() {
+                MqttError::ConnectionRefused(reason) => Some(*reason),
+                _ => None,
+            };
+
+            ctx.metrics.connect_failed(started, reason, Instant::now());
+        })?;
+
+        failover.connected();
+        ctx.metrics
+            .connected(started, session_present, Instant::now());
 
         let mut handshake = Handshake {
             connection: connack.connection,
@@ -263,8 +394,11 @@ pub(crate) mod tests {
     use rumqttc::{ConnAck, Outgoing, Pkid};
 
     use crate::pairing::api::PairingApi;
+    use crate::pairing::api::client::tests::mock_create_certificate;
     use crate::transport::mqtt::MqttConfig;
     use crate::transport::mqtt::client::{AsyncClient, EventLoop};
+    use crate::transport::mqtt::components::ClientId;
+    use crate::transport::mqtt::test::mock_state;
 
     use super::connected::tests::mock_connected;
     use super::*;
@@ -277,6 +411,9 @@ pub(crate) mod tests {
         MqttState {
             pairing: PairingApi::new(mqtt_config),
             state: State::Connected(mock_connected(client, eventloop)),
+            config: None,
+            renewal: CertRenewal::default(),
+            failover: BrokerFailover::default(),
         }
     }
 
@@ -303,6 +440,44 @@ pub(crate) mod tests {
         assert_eq!(res, exp);
     }
 
+    #[tokio::test]
+    async fn should_renew_certificate() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = mock_create_certificate(&mut server)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let provider = TransportProvider::configure(None, true).await.unwrap();
+        let state = mock_state(&[]);
+
+        let mut mqtt_state =
+            MqttState::new(()).with_cert_renewal(Some(Duration::from_secs(60 * 60 * 24)));
+
+        // Not connected yet
+        assert_eq!(mqtt_state.cert_renewal_delay(&state).await, None);
+
+        mqtt_state.config = Some(PairingConfig {
+            client_id: ClientId {
+                realm: "realm".to_string(),
+                device_id: "device_id".to_string(),
+            },
+            secret: "secret".to_string(),
+            pairing_url: server.url().parse().unwrap(),
+            keepalive: Duration::from_secs(30),
+        });
+
+        mqtt_state.renew_certificate(&provider, &state).await;
+
+        mock.assert_async().await;
+
+        assert!(state.cert_expiry.read().await.is_some());
+
+        // Scheduled with the new certificate
+        let delay = mqtt_state.cert_renewal_delay(&state).await;
+        assert!(delay.is_some_and(|delay| !delay.is_zero()));
+    }
+
     #[rstest]
     #[case(Event::Incoming(Packet::Disconnect))]
     #[case(Event::Incoming(Packet::ConnAck(ConnAck{session_present:false, code: rumqttc::ConnectReturnCode::Success})))]
@@ -0,0 +1,141 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Schedules the renewal of the client certificate before it expires.
+
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use tracing::warn;
+
+/// Interval between the attempts after a renewal failed.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60 * 5);
+
+/// Renewal of the client certificate.
+#[derive(Debug, Default)]
+pub(crate) struct CertRenewal {
+    /// Time before the expiry of the certificate to renew it.
+    margin: Option<Duration>,
+    /// Don't try to renew the certificate before this time.
+    retry_after: Option<DateTime<Utc>>,
+}
+
+impl CertRenewal {
+    pub(crate) fn new(margin: Option<Duration>) -> Self {
+        Self {
+            margin,
+            retry_after: None,
+        }
+    }
+
+    /// Returns the time to wait before renewing a certificate with the given expiry.
+    ///
+    /// It's [`None`] when the renewal is disabled or there is no certificate.
+    pub(crate) fn delay(
+        &self,
+        expiry: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Option<Duration> {
+        let margin = TimeDelta::from_std(self.margin?).unwrap_or(TimeDelta::MAX);
+
+        let deadline = expiry?
+            .checked_sub_signed(margin)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let deadline = self
+            .retry_after
+            .map_or(deadline, |retry| retry.max(deadline));
+
+        Some((deadline - now).to_std().unwrap_or(Duration::ZERO))
+    }
+
+    /// The certificate was renewed, with the new expiry.
+    pub(crate) fn renewed(&mut self, expiry: Option<DateTime<Utc>>, now: DateTime<Utc>) {
+        self.retry_after = None;
+
+        if self.delay(expiry, now).is_some_and(|delay| delay.is_zero()) {
+            warn!(
+                ?expiry,
+                "the renewed certificate is already in the renewal margin, renewing it at the expiry"
+            );
+
+            self.retry_after = expiry;
+        }
+    }
+
+    /// The renewal failed, it will be retried after an interval.
+    pub(crate) fn failed(&mut self, now: DateTime<Utc>) {
+        self.retry_after = Some(now + RETRY_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(60 * 60 * 24);
+
+    #[test]
+    fn should_schedule_before_expiry() {
+        let now = Utc::now();
+        let expiry = now + DAY * 10;
+
+        let renewal = CertRenewal::new(Some(DAY * 3));
+        assert_eq!(renewal.delay(Some(expiry), now), Some(DAY * 7));
+        assert_eq!(renewal.delay(None, now), None);
+
+        let disabled = CertRenewal::new(None);
+        assert_eq!(disabled.delay(Some(expiry), now), None);
+    }
+
+    #[test]
+    fn should_renew_immediately_in_margin() {
+        let now = Utc::now();
+
+        let renewal = CertRenewal::new(Some(DAY * 3));
+        assert_eq!(renewal.delay(Some(now + DAY), now), Some(Duration::ZERO));
+
+        // expired certificate
+        assert_eq!(renewal.delay(Some(now - DAY), now), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn should_retry_after_failure() {
+        let now = Utc::now();
+        let expiry = now + DAY;
+
+        let mut renewal = CertRenewal::new(Some(DAY * 3));
+        renewal.failed(now);
+        assert_eq!(renewal.delay(Some(expiry), now), Some(RETRY_INTERVAL));
+
+        renewal.renewed(Some(now + DAY * 10), now);
+        assert_eq!(renewal.delay(Some(now + DAY * 10), now), Some(DAY * 7));
+    }
+
+    #[test]
+    fn should_not_loop_on_short_certificates() {
+        let now = Utc::now();
+        let expiry = now + DAY;
+
+        let mut renewal = CertRenewal::new(Some(DAY * 3));
+        renewal.renewed(Some(expiry), now);
+
+        assert_eq!(renewal.delay(Some(expiry), now), Some(DAY));
+    }
+}
@@ -16,12 +16,15 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use std::time::Instant;
+
 use astarte_device_error::Error;
 
 use crate::transport::mqtt::error::MqttError;
 
 use super::Disconnected;
 use super::connected::Connected;
+use super::context::Connection;
 use super::wait_sends::{TaskHandle, WaitTask};
 
 /// This cannot be a type state machine, because any additional data cannot be moved out of the enum
@@ -40,16 +43,34 @@ pub(super) enum State {
 }
 
 impl State {
+    /// Returns the connection, if it was created.
+    pub(super) fn connection_mut(&mut self) -> Option<&mut Connection> {
+        match self {
+            State::Transition => None,
+            State::Disconnected(disconnected) => disconnected.connection.as_mut(),
+            State::WaitAcks(wait_task) => Some(&mut wait_task.connection),
+            State::Connected(connected) => Some(&mut connected.connection),
+        }
+    }
+
     pub(super) fn set_disconnected(&mut self) {
         let disconnected = match std::mem::replace(self, State::Transition) {
             State::Transition => Disconnected { connection: None },
             State::Disconnected(disconnected) => disconnected,
-            State::WaitAcks(wait_task) => Disconnected {
-                connection: Some(wait_task.connection),
-            },
-            State::Connected(connected) => Disconnected {
-                connection: Some(connected.connection),
-            },
+            State::WaitAcks(wait_task) => {
+                wait_task.connection.metrics.disconnected(Instant::now());
+
+                Disconnected {
+                    connection: Some(wait_task.connection),
+                }
+            }
+            State::Connected(connected) => {
+                connected.connection.metrics.disconnected(Instant::now());
+
+                Disconnected {
+                    connection: Some(connected.connection),
+                }
+            }
         };
 
         *self = State::Disconnected(disconnected);
@@ -45,7 +45,7 @@ impl<'a> Connack<'a> {
         let timeout = ctx.state.config.connection_timeout.saturating_mul(2);
 
         while instant.elapsed() < timeout {
-            let event = match self.connection.eventloop_mut().poll().await {
+            let event = match self.connection.poll().await {
                 Ok(event) => {
                     trace!("event received");
 
@@ -54,9 +54,7 @@ impl<'a> Connack<'a> {
                 Err(err) => {
                     error!(error = %Report::new(&err),"error received from mqtt connection");
 
-                    return Err(
-                        Error::with(MqttError::Connection, "waiting for CONNACK").set_source(err)
-                    );
+                    return Err(Error::with(err.kind(), "waiting for CONNACK").set_source(err));
                 }
             };
 
@@ -19,12 +19,13 @@
 use std::ops::ControlFlow;
 
 use astarte_device_error::{Error, WrapError};
-use rumqttc::{ConnectionError, Event, Publish};
+use rumqttc::{Event, Publish};
 use tokio::task::JoinError;
 use tokio_util::task::AbortOnDropHandle;
 use tracing::{debug, error, info};
 
 use crate::error::Report;
+use crate::transport::mqtt::client::ConnectionError;
 use crate::transport::mqtt::connection::handle_event;
 use crate::transport::mqtt::error::MqttError;
 
@@ -59,7 +60,7 @@ impl WaitTask {
                     return self.handle_join(ctx, res);
                 }
                 // I hope this is cancel safe
-                res = self.connection.eventloop_mut().poll() => {
+                res = self.connection.poll() => {
                     Self::handle_poll(res)?
                 }
             };
@@ -108,7 +109,7 @@ impl WaitTask {
     ) -> Result<Option<Publish>, Error<MqttError>> {
         debug!("next event polled");
 
-        res.wrap_err_msg(MqttError::Connection, "waiting for send task")
+        res.wrap_err_with(|err| Error::with(err.kind(), "waiting for send task"))
             .and_then(handle_event)
     }
 }
@@ -118,7 +119,6 @@ pub(crate) mod tests {
     use std::sync::{Arc, OnceLock};
 
     use futures::FutureExt;
-    use mockall::Sequence;
     use sync_wrapper::SyncWrapper;
 
     use crate::interfaces::Interfaces;
@@ -127,6 +127,7 @@ pub(crate) mod tests {
     use crate::transport::mqtt::ClientSender;
     use crate::transport::mqtt::client::{AsyncClient, EventLoop};
     use crate::transport::mqtt::config::transport::TransportProvider;
+    use crate::transport::mqtt::metrics::MqttMetrics;
     use crate::transport::mqtt::test::mock_state;
 
     use super::*;
@@ -140,6 +141,7 @@ pub(crate) mod tests {
             connection: Connection {
                 client,
                 eventloop: SyncWrapper::new(eventloop),
+                metrics: MqttMetrics::default(),
             },
             handle: AbortOnDropHandle::new(tokio::spawn(futures::future::ok(()))),
             session_present,
@@ -153,6 +155,7 @@ pub(crate) mod tests {
         pub(crate) provider: TransportProvider,
         pub(crate) store: MockStore,
         pub(crate) interfaces: Interfaces,
+        pub(crate) metrics: MqttMetrics,
         /// Whether the stored introspection matches the current one
         pub(crate) session_synced: bool,
     }
@@ -165,6 +168,7 @@ pub(crate) mod tests {
                 provider: TransportProvider::configure(None, false).await.unwrap(),
                 store: MockStore::default(),
                 interfaces: Interfaces::default(),
+                metrics: MqttMetrics::default(),
                 session_synced: true,
             }
         }
@@ -176,6 +180,7 @@ pub(crate) mod tests {
                 provider: &self.provider,
                 store: &self.store,
                 interfaces: &self.interfaces,
+                metrics: &self.metrics,
                 session_synced: self.session_synced,
             }
         }
@@ -186,11 +191,10 @@ pub(crate) mod tests {
         let client = AsyncClient::default();
         let mut eventloop = EventLoop::default();
 
-        let mut seq = Sequence::new();
+        // the select can complete with the join handle before polling the event loop
         eventloop
             .expect_poll()
-            .once()
-            .in_sequence(&mut seq)
+            .times(..=1)
             .with()
             .returning(|| futures::future::pending().boxed());
 
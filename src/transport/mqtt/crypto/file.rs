@@ -0,0 +1,148 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Private key stored in a file.
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use astarte_device_error::Error;
+use rustls::sign::SigningKey;
+use tracing::{debug, error};
+
+use crate::error::Report;
+
+use super::{CryptoError, KeyAlgorithm, KeyProvider, load_private_key};
+
+/// Stores the private key in a file, in the PKCS#8 DER format.
+///
+/// Useful to keep the key in a different location than the other credentials, by default the key
+/// is stored by the [`CredentialStore`](crate::credentials::CredentialStore).
+#[derive(Debug, Clone)]
+pub struct FileKeyProvider {
+    file: Option<PathBuf>,
+}
+
+impl FileKeyProvider {
+    /// Stores the key in the given file.
+    pub fn new(file: impl Into<PathBuf>) -> Self {
+        Self {
+            file: Some(file.into()),
+        }
+    }
+
+    /// Keeps the generated key only in memory.
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Self {
+        Self { file: None }
+    }
+}
+
+impl KeyProvider for FileKeyProvider {
+    fn generate(&self, algorithm: KeyAlgorithm) -> Result<Arc<dyn SigningKey>, Error<CryptoError>> {
+        let der = algorithm.generate_der()?;
+
+        // Don't fail here since the key can be used from memory and regenerated
+        if let Some(file) = &self.file
+            && let Err(err) = std::fs::write(file, &der)
+        {
+            error!(error = %Report::new(err), file = %file.display(), "couldn't write private key file");
+        }
+
+        load_private_key(der)
+    }
+
+    fn load(&self) -> Result<Option<Arc<dyn SigningKey>>, Error<CryptoError>> {
+        let Some(file) = &self.file else {
+            debug!("private key stored only in memory");
+
+            return Ok(None);
+        };
+
+        let der = match std::fs::read(file) {
+            Ok(der) => der,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("private key file is missing");
+
+                return Ok(None);
+            }
+            Err(err) => {
+                return Err(
+                    Error::with(CryptoError::Storage, "couldn't read private key file")
+                        .set_source(err)
+                        .set_ctx(format!("file {}", file.display())),
+                );
+            }
+        };
+
+        if der.is_empty() {
+            debug!("no private key found");
+
+            return Ok(None);
+        }
+
+        load_private_key(der).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use crate::pairing::api::PRIVATE_KEY_FILE;
+
+    use super::*;
+
+    #[test]
+    fn should_store_generated_key() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join(PRIVATE_KEY_FILE);
+
+        let provider = FileKeyProvider::new(&file);
+        assert!(provider.load().unwrap().is_none());
+
+        let key = provider.generate(KeyAlgorithm::EcdsaP384).unwrap();
+
+        let loaded = provider.load().unwrap().expect("should load the key");
+        assert_eq!(loaded.public_key(), key.public_key());
+
+        std::fs::write(&file, b"").unwrap();
+        assert!(provider.load().unwrap().is_none());
+    }
+
+    #[test]
+    fn should_reject_invalid_key() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join(PRIVATE_KEY_FILE);
+        std::fs::write(&file, b"not a key").unwrap();
+
+        let err = FileKeyProvider::new(&file).load().unwrap_err();
+
+        assert_eq!(*err.kind(), CryptoError::PrivateKey);
+    }
+
+    #[test]
+    fn should_not_load_in_memory_key() {
+        let provider = FileKeyProvider::in_memory();
+
+        provider.generate(KeyAlgorithm::default()).unwrap();
+
+        assert!(provider.load().unwrap().is_none());
+    }
+}
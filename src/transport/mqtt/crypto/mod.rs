@@ -0,0 +1,385 @@
+// This file is part of Astarte.
+//
+// Copyright 2021-2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Crypto module to generate the CSR to authenticate the device to the Astarte.
+//!
+//! The private key of the device is managed by a [`KeyProvider`], by default it's stored with the
+//! other credentials in the [`CredentialStore`](crate::credentials::CredentialStore). A custom
+//! provider can keep the key in a PKCS#11 token, a TPM or a secure element, signing the CSR and
+//! the TLS handshakes through the rustls [`SigningKey`].
+
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+use astarte_device_error::{Error, WrapError};
+use rcgen::{
+    CertificateParams, DistinguishedName, DnType, KeyPair, PKCS_ECDSA_P256_SHA256,
+    PKCS_ECDSA_P384_SHA384, PKCS_ED25519, PKCS_RSA_SHA256, PublicKeyData, RsaKeySize,
+    SignatureAlgorithm,
+};
+use rustls::SignatureScheme;
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::sign::SigningKey;
+use serde::{Deserialize, Serialize};
+use x509_parser::oid_registry::{
+    OID_EC_P256, OID_KEY_TYPE_EC_PUBLIC_KEY, OID_NIST_EC_P384, OID_PKCS1_RSAENCRYPTION,
+    OID_SIG_ED25519,
+};
+use x509_parser::prelude::FromDer;
+use x509_parser::public_key::PublicKey;
+use x509_parser::x509::SubjectPublicKeyInfo;
+
+use crate::transport::mqtt::config::tls::crypto_provider;
+
+pub use self::file::FileKeyProvider;
+#[cfg(feature = "pkcs11")]
+#[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(feature = "pkcs11")))]
+pub use self::pkcs11::Pkcs11KeyProvider;
+// Re-export for the types used to create the PKCS#11 provider
+#[cfg(feature = "pkcs11")]
+#[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(feature = "pkcs11")))]
+pub use cryptoki;
+
+mod file;
+#[cfg(feature = "pkcs11")]
+mod pkcs11;
+
+/// Errors that can occur while generating the Certificate and CSR.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptoError {
+    /// Failed to generate the PrivateKey.
+    PrivateKey,
+    /// Failed to generate the CSR.
+    Certificate,
+    /// Failed to access the storage of the private key.
+    Storage,
+}
+
+impl Display for CryptoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CryptoError::PrivateKey => write!(f, "couldn't create private keys"),
+            CryptoError::Certificate => write!(f, "couldn't create certificate signing request"),
+            CryptoError::Storage => write!(f, "couldn't access the private key storage"),
+        }
+    }
+}
+
+/// Provides the private key of the device.
+///
+/// The key is used to sign the CSR sent to Astarte and the TLS handshakes with the broker, so it
+/// never needs to leave the provider.
+///
+/// The methods are called on a blocking thread, so they can perform blocking operations.
+pub trait KeyProvider: Debug + Send + Sync + 'static {
+    /// Generates a new private key with the given algorithm, replacing the stored one.
+    ///
+    /// The returned key must return its public key from [`SigningKey::public_key`].
+    fn generate(&self, algorithm: KeyAlgorithm) -> Result<Arc<dyn SigningKey>, Error<CryptoError>>;
+
+    /// Loads the stored private key, returns [`None`] if the key is missing.
+    fn load(&self) -> Result<Option<Arc<dyn SigningKey>>, Error<CryptoError>>;
+}
+
+/// Algorithm of the private key generated for the device certificate.
+///
+/// The key is stored in PKCS#8 format, and the CSR is signed with SHA-256 for RSA keys.
+#[non_exhaustive]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAlgorithm {
+    /// ECDSA on the P-256 curve.
+    #[default]
+    EcdsaP256,
+    /// ECDSA on the P-384 curve.
+    EcdsaP384,
+    /// Ed25519 signature scheme.
+    Ed25519,
+    /// RSA with a 2048 bit modulus.
+    Rsa2048,
+    /// RSA with a 3072 bit modulus.
+    Rsa3072,
+    /// RSA with a 4096 bit modulus.
+    Rsa4096,
+}
+
+impl KeyAlgorithm {
+    /// Generates a random key pair.
+    fn generate(&self) -> Result<KeyPair, rcgen::Error> {
+        match self {
+            KeyAlgorithm::EcdsaP256 => KeyPair::generate_for(&PKCS_ECDSA_P256_SHA256),
+            KeyAlgorithm::EcdsaP384 => KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384),
+            KeyAlgorithm::Ed25519 => KeyPair::generate_for(&PKCS_ED25519),
+            KeyAlgorithm::Rsa2048 => KeyPair::generate_rsa_for(&PKCS_RSA_SHA256, RsaKeySize::_2048),
+            KeyAlgorithm::Rsa3072 => KeyPair::generate_rsa_for(&PKCS_RSA_SHA256, RsaKeySize::_3072),
+            KeyAlgorithm::Rsa4096 => KeyPair::generate_rsa_for(&PKCS_RSA_SHA256, RsaKeySize::_4096),
+        }
+    }
+
+    /// Generates a random private key in the PKCS#8 DER format.
+    pub(crate) fn generate_der(&self) -> Result<Vec<u8>, Error<CryptoError>> {
+        let key_pair = self.generate().wrap_err_with(|_| {
+            Error::new(CryptoError::PrivateKey).set_ctx(format!("algorithm {self}"))
+        })?;
+
+        Ok(key_pair.serialize_der())
+    }
+
+    /// Algorithm used to sign the CSR.
+    fn csr_algorithm(&self) -> &'static SignatureAlgorithm {
+        match self {
+            KeyAlgorithm::EcdsaP256 => &PKCS_ECDSA_P256_SHA256,
+            KeyAlgorithm::EcdsaP384 => &PKCS_ECDSA_P384_SHA384,
+            KeyAlgorithm::Ed25519 => &PKCS_ED25519,
+            KeyAlgorithm::Rsa2048 | KeyAlgorithm::Rsa3072 | KeyAlgorithm::Rsa4096 => {
+                &PKCS_RSA_SHA256
+            }
+        }
+    }
+
+    /// Signature scheme matching the [`csr_algorithm`](Self::csr_algorithm).
+    fn csr_scheme(&self) -> SignatureScheme {
+        match self {
+            KeyAlgorithm::EcdsaP256 => SignatureScheme::ECDSA_NISTP256_SHA256,
+            KeyAlgorithm::EcdsaP384 => SignatureScheme::ECDSA_NISTP384_SHA384,
+            KeyAlgorithm::Ed25519 => SignatureScheme::ED25519,
+            KeyAlgorithm::Rsa2048 | KeyAlgorithm::Rsa3072 | KeyAlgorithm::Rsa4096 => {
+                SignatureScheme::RSA_PKCS1_SHA256
+            }
+        }
+    }
+
+    /// Returns the algorithm of a certificate public key, if supported.
+    pub(crate) fn from_public_key(spki: &SubjectPublicKeyInfo<'_>) -> Option<Self> {
+        let algorithm = &spki.algorithm.algorithm;
+
+        if *algorithm == OID_KEY_TYPE_EC_PUBLIC_KEY {
+            let curve = spki.algorithm.parameters.as_ref()?.as_oid().ok()?;
+
+            if curve == OID_EC_P256 {
+                Some(KeyAlgorithm::EcdsaP256)
+            } else if curve == OID_NIST_EC_P384 {
+                Some(KeyAlgorithm::EcdsaP384)
+            } else {
+                None
+            }
+        } else if *algorithm == OID_SIG_ED25519 {
+            Some(KeyAlgorithm::Ed25519)
+        } else if *algorithm == OID_PKCS1_RSAENCRYPTION {
+            let PublicKey::RSA(rsa) = spki.parsed().ok()? else {
+                return None;
+            };
+
+            match rsa.key_size() {
+                2048 => Some(KeyAlgorithm::Rsa2048),
+                3072 => Some(KeyAlgorithm::Rsa3072),
+                4096 => Some(KeyAlgorithm::Rsa4096),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyAlgorithm::EcdsaP256 => write!(f, "ECDSA P-256"),
+            KeyAlgorithm::EcdsaP384 => write!(f, "ECDSA P-384"),
+            KeyAlgorithm::Ed25519 => write!(f, "Ed25519"),
+            KeyAlgorithm::Rsa2048 => write!(f, "RSA-2048"),
+            KeyAlgorithm::Rsa3072 => write!(f, "RSA-3072"),
+            KeyAlgorithm::Rsa4096 => write!(f, "RSA-4096"),
+        }
+    }
+}
+
+/// Loads a private key in the PKCS#8 DER format.
+pub(crate) fn load_private_key(der: Vec<u8>) -> Result<Arc<dyn SigningKey>, Error<CryptoError>> {
+    let der = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(der));
+
+    crypto_provider()
+        .key_provider
+        .load_private_key(der)
+        .wrap_err_msg(CryptoError::PrivateKey, "loading the private key")
+}
+
+/// Signs the CSR with a rustls [`SigningKey`].
+struct CsrSigner<'a> {
+    key: &'a dyn SigningKey,
+    algorithm: KeyAlgorithm,
+    /// Content of the subject public key bit string.
+    public_key: Vec<u8>,
+}
+
+impl<'a> CsrSigner<'a> {
+    fn new(key: &'a dyn SigningKey) -> Result<Self, Error<CryptoError>> {
+        let spki = key.public_key().ok_or_else(|| {
+            Error::with(
+                CryptoError::Certificate,
+                "the private key doesn't provide the public key",
+            )
+        })?;
+
+        let (_, parsed) = SubjectPublicKeyInfo::from_der(&spki)
+            .wrap_err_msg(CryptoError::Certificate, "parsing the public key")?;
+
+        let algorithm = KeyAlgorithm::from_public_key(&parsed).ok_or_else(|| {
+            Error::with(CryptoError::Certificate, "unsupported public key algorithm")
+                .set_ctx(format!("algorithm {}", parsed.algorithm.algorithm))
+        })?;
+
+        Ok(Self {
+            key,
+            algorithm,
+            public_key: parsed.subject_public_key.data.to_vec(),
+        })
+    }
+}
+
+impl PublicKeyData for CsrSigner<'_> {
+    fn der_bytes(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    fn algorithm(&self) -> &'static SignatureAlgorithm {
+        self.algorithm.csr_algorithm()
+    }
+}
+
+impl rcgen::SigningKey for CsrSigner<'_> {
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, rcgen::Error> {
+        let signer = self
+            .key
+            .choose_scheme(&[self.algorithm.csr_scheme()])
+            .ok_or(rcgen::Error::RemoteKeyError)?;
+
+        signer.sign(msg).map_err(|_| rcgen::Error::RemoteKeyError)
+    }
+}
+
+/// Private key and the CSR signed with it.
+#[derive(Debug)]
+pub(crate) struct Bundle {
+    pub private_key: Arc<dyn SigningKey>,
+    /// PEM encoded CSR
+    pub csr: String,
+}
+
+impl Bundle {
+    pub(crate) fn new(
+        realm: &str,
+        device_id: &str,
+        private_key: Arc<dyn SigningKey>,
+    ) -> Result<Bundle, Error<CryptoError>> {
+        // The realm/device_id for the certificate
+        let mut dn = DistinguishedName::new();
+        dn.push(DnType::CommonName, format!("{realm}/{device_id}"));
+
+        let signer = CsrSigner::new(private_key.as_ref())?;
+
+        let mut csr_param = CertificateParams::new([]).wrap_err(CryptoError::Certificate)?;
+        csr_param.distinguished_name = dn;
+
+        // Singed CSR
+        let csr = csr_param
+            .serialize_request(&signer)
+            .wrap_err_msg(CryptoError::Certificate, "serializing CSR")?
+            .pem()
+            .wrap_err_msg(CryptoError::Certificate, "encoding to PEM")?;
+
+        Ok(Bundle { private_key, csr })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use x509_parser::certification_request::X509CertificationRequest;
+
+    use super::*;
+
+    fn generate_bundle(algorithm: KeyAlgorithm) -> Bundle {
+        let key = FileKeyProvider::in_memory().generate(algorithm).unwrap();
+
+        Bundle::new("realm", "device_id", key).unwrap()
+    }
+
+    #[test]
+    fn test_new_cert() {
+        let key = FileKeyProvider::in_memory()
+            .generate(KeyAlgorithm::default())
+            .unwrap();
+        let bundle = Bundle::new("realm", "device_id", key);
+
+        assert!(
+            bundle.is_ok(),
+            "Failed to generate certificate: {}",
+            bundle.unwrap_err()
+        );
+
+        let bundle = bundle.unwrap();
+
+        assert!(bundle.private_key.public_key().is_some());
+        assert!(!bundle.csr.is_empty());
+
+        rustls_pemfile::csr(&mut bundle.csr.clone().as_bytes())
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_bundle() {
+        let Bundle { private_key, csr } = generate_bundle(KeyAlgorithm::default());
+        assert!(private_key.public_key().is_some());
+        assert!(!csr.is_empty());
+
+        rustls_pemfile::csr(&mut csr.clone().as_bytes())
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn should_generate_key_algorithms() {
+        for algorithm in [
+            KeyAlgorithm::EcdsaP256,
+            KeyAlgorithm::EcdsaP384,
+            KeyAlgorithm::Ed25519,
+            KeyAlgorithm::Rsa2048,
+            KeyAlgorithm::Rsa3072,
+            KeyAlgorithm::Rsa4096,
+        ] {
+            let Bundle { private_key, csr } = generate_bundle(algorithm);
+
+            let der = rustls_pemfile::csr(&mut csr.as_bytes()).unwrap().unwrap();
+            let (_, csr) = X509CertificationRequest::from_der(&der).unwrap();
+
+            csr.verify_signature()
+                .unwrap_or_else(|err| panic!("invalid CSR signature for {algorithm}: {err}"));
+            assert_eq!(
+                KeyAlgorithm::from_public_key(&csr.certification_request_info.subject_pki),
+                Some(algorithm)
+            );
+            assert_eq!(
+                private_key.public_key().unwrap().as_ref(),
+                csr.certification_request_info.subject_pki.raw
+            );
+        }
+    }
+}
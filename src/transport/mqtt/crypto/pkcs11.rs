@@ -0,0 +1,715 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Private key stored in a PKCS#11 token.
+
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use astarte_device_error::{Error, WrapError};
+use cryptoki::context::{CInitializeArgs, CInitializeFlags, Pkcs11};
+use cryptoki::error::RvError;
+use cryptoki::mechanism::eddsa::{EddsaParams, EddsaSignatureScheme};
+use cryptoki::mechanism::rsa::{PkcsMgfType, PkcsPssParams};
+use cryptoki::mechanism::{Mechanism, MechanismType};
+use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::slot::Slot;
+use cryptoki::types::AuthPin;
+use rustls::pki_types::{SubjectPublicKeyInfoDer, alg_id};
+use rustls::sign::{Signer, SigningKey, public_key_to_spki};
+use rustls::{SignatureAlgorithm, SignatureScheme};
+use tracing::{debug, info};
+use x509_parser::prelude::FromDer;
+use x509_parser::x509::SubjectPublicKeyInfo;
+
+use super::{CryptoError, KeyAlgorithm, KeyProvider};
+
+/// DER encoded OID of the P-256 curve.
+const EC_PARAMS_P256: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+/// DER encoded OID of the P-384 curve.
+const EC_PARAMS_P384: &[u8] = &[0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x22];
+/// DER encoded OID of the Ed25519 curve.
+const EC_PARAMS_ED25519: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+
+/// RSA public exponent 65537.
+const RSA_PUBLIC_EXPONENT: &[u8] = &[0x01, 0x00, 0x01];
+
+/// Schemes supported for RSA keys, in order of preference.
+const RSA_SCHEMES: [SignatureScheme; 6] = [
+    SignatureScheme::RSA_PSS_SHA256,
+    SignatureScheme::RSA_PSS_SHA384,
+    SignatureScheme::RSA_PSS_SHA512,
+    SignatureScheme::RSA_PKCS1_SHA256,
+    SignatureScheme::RSA_PKCS1_SHA384,
+    SignatureScheme::RSA_PKCS1_SHA512,
+];
+
+/// Stores the private key in a PKCS#11 token, like a HSM, a TPM or a secure element.
+///
+/// The key pair is generated in the token as not extractable, and it's identified by its label.
+/// The CSR and the TLS handshakes are signed by the token.
+pub struct Pkcs11KeyProvider {
+    session: Arc<Mutex<Session>>,
+    label: String,
+}
+
+impl Pkcs11KeyProvider {
+    /// Opens the token with the given label, loading the PKCS#11 module from the path.
+    ///
+    /// It logs in with the user PIN, and stores the key with the `key_label`.
+    pub fn open(
+        module: impl AsRef<Path>,
+        token_label: &str,
+        pin: &str,
+        key_label: impl Into<String>,
+    ) -> Result<Self, Error<CryptoError>> {
+        let module = module.as_ref();
+
+        let pkcs11 = Pkcs11::new(module).wrap_err_with(|_| {
+            Error::with(CryptoError::Storage, "couldn't load the PKCS#11 module")
+                .set_ctx(format!("module {}", module.display()))
+        })?;
+
+        match pkcs11.initialize(CInitializeArgs::new(CInitializeFlags::OS_LOCKING_OK)) {
+            Ok(()) => {}
+            Err(cryptoki::error::Error::Pkcs11(RvError::CryptokiAlreadyInitialized, _)) => {
+                debug!("PKCS#11 module already initialized");
+            }
+            Err(err) => {
+                return Err(Error::with(
+                    CryptoError::Storage,
+                    "couldn't initialize the PKCS#11 module",
+                )
+                .set_source(err));
+            }
+        }
+
+        let slot = pkcs11
+            .get_slots_with_token()
+            .wrap_err_msg(CryptoError::Storage, "couldn't list the PKCS#11 slots")?
+            .into_iter()
+            .find(|slot| {
+                pkcs11
+                    .get_token_info(*slot)
+                    .is_ok_and(|info| info.label().trim_end() == token_label)
+            })
+            .ok_or_else(|| {
+                Error::with(CryptoError::Storage, "PKCS#11 token not found")
+                    .set_ctx(format!("token {token_label}"))
+            })?;
+
+        Self::new(&pkcs11, slot, pin, key_label)
+    }
+
+    /// Uses the token in the slot of an initialized PKCS#11 context.
+    ///
+    /// It logs in with the user PIN, and stores the key with the `key_label`.
+    pub fn new(
+        pkcs11: &Pkcs11,
+        slot: Slot,
+        pin: &str,
+        key_label: impl Into<String>,
+    ) -> Result<Self, Error<CryptoError>> {
+        let session = pkcs11
+            .open_rw_session(slot)
+            .wrap_err_msg(CryptoError::Storage, "couldn't open the PKCS#11 session")?;
+
+        match session.login(UserType::User, Some(&AuthPin::from(pin.to_string()))) {
+            Ok(()) => {}
+            Err(cryptoki::error::Error::Pkcs11(RvError::UserAlreadyLoggedIn, _)) => {
+                debug!("already logged in the PKCS#11 token");
+            }
+            Err(err) => {
+                return Err(Error::with(
+                    CryptoError::Storage,
+                    "couldn't login to the PKCS#11 token",
+                )
+                .set_source(err));
+            }
+        }
+
+        Ok(Self {
+            session: Arc::new(Mutex::new(session)),
+            label: key_label.into(),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Session> {
+        lock(&self.session)
+    }
+
+    fn find(
+        session: &Session,
+        class: ObjectClass,
+        label: &str,
+    ) -> Result<Option<ObjectHandle>, Error<CryptoError>> {
+        let objects = session
+            .find_objects(&[
+                Attribute::Class(class),
+                Attribute::Label(label.as_bytes().to_vec()),
+            ])
+            .wrap_err_with(|_| {
+                Error::with(CryptoError::Storage, "couldn't find the key in the token")
+                    .set_ctx(format!("label {label}"))
+            })?;
+
+        Ok(objects.into_iter().next())
+    }
+
+    /// Creates the key from the handles of the key pair.
+    fn key(
+        &self,
+        session: &Session,
+        public: ObjectHandle,
+        private: ObjectHandle,
+    ) -> Result<Pkcs11Key, Error<CryptoError>> {
+        let spki = public_key_info(session, public)?;
+
+        let (_, parsed) = SubjectPublicKeyInfo::from_der(&spki)
+            .wrap_err_msg(CryptoError::PrivateKey, "invalid public key in the token")?;
+        let algorithm = KeyAlgorithm::from_public_key(&parsed).ok_or_else(|| {
+            Error::with(
+                CryptoError::PrivateKey,
+                "unsupported key algorithm in the token",
+            )
+            .set_ctx(format!("label {}", self.label))
+        })?;
+
+        Ok(Pkcs11Key {
+            session: Arc::clone(&self.session),
+            handle: private,
+            algorithm,
+            spki,
+        })
+    }
+}
+
+impl Debug for Pkcs11KeyProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pkcs11KeyProvider")
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KeyProvider for Pkcs11KeyProvider {
+    fn generate(&self, algorithm: KeyAlgorithm) -> Result<Arc<dyn SigningKey>, Error<CryptoError>> {
+        let session = self.lock();
+
+        // Replace the previous key pair
+        let previous = session
+            .find_objects(&[Attribute::Label(self.label.as_bytes().to_vec())])
+            .wrap_err_msg(CryptoError::Storage, "couldn't find the previous key")?;
+        for handle in previous {
+            session
+                .destroy_object(handle)
+                .wrap_err_msg(CryptoError::Storage, "couldn't remove the previous key")?;
+        }
+
+        let (mechanism, params) = match algorithm {
+            KeyAlgorithm::EcdsaP256 => (
+                Mechanism::EccKeyPairGen,
+                vec![Attribute::EcParams(EC_PARAMS_P256.to_vec())],
+            ),
+            KeyAlgorithm::EcdsaP384 => (
+                Mechanism::EccKeyPairGen,
+                vec![Attribute::EcParams(EC_PARAMS_P384.to_vec())],
+            ),
+            KeyAlgorithm::Ed25519 => (
+                Mechanism::EccEdwardsKeyPairGen,
+                vec![Attribute::EcParams(EC_PARAMS_ED25519.to_vec())],
+            ),
+            KeyAlgorithm::Rsa2048 | KeyAlgorithm::Rsa3072 | KeyAlgorithm::Rsa4096 => {
+                let bits = match algorithm {
+                    KeyAlgorithm::Rsa2048 => 2048,
+                    KeyAlgorithm::Rsa3072 => 3072,
+                    _ => 4096,
+                };
+
+                (
+                    Mechanism::RsaPkcsKeyPairGen,
+                    vec![
+                        Attribute::ModulusBits(bits.into()),
+                        Attribute::PublicExponent(RSA_PUBLIC_EXPONENT.to_vec()),
+                    ],
+                )
+            }
+        };
+
+        let label = self.label.as_bytes().to_vec();
+
+        let mut public_template = vec![
+            Attribute::Token(true),
+            Attribute::Private(false),
+            Attribute::Verify(true),
+            Attribute::Label(label.clone()),
+            Attribute::Id(label.clone()),
+        ];
+        public_template.extend(params);
+
+        let private_template = [
+            Attribute::Token(true),
+            Attribute::Private(true),
+            Attribute::Sensitive(true),
+            Attribute::Extractable(false),
+            Attribute::Sign(true),
+            Attribute::Label(label.clone()),
+            Attribute::Id(label),
+        ];
+
+        let (public, private) = session
+            .generate_key_pair(&mechanism, &public_template, &private_template)
+            .wrap_err_with(|_| {
+                Error::with(
+                    CryptoError::PrivateKey,
+                    "couldn't generate the key in the token",
+                )
+                .set_ctx(format!("algorithm {algorithm}"))
+            })?;
+
+        info!(label = self.label, %algorithm, "generated private key in the PKCS#11 token");
+
+        self.key(&session, public, private)
+            .map(|key| Arc::new(key) as Arc<dyn SigningKey>)
+    }
+
+    fn load(&self) -> Result<Option<Arc<dyn SigningKey>>, Error<CryptoError>> {
+        let session = self.lock();
+
+        let Some(private) = Self::find(&session, ObjectClass::PRIVATE_KEY, &self.label)? else {
+            debug!(
+                label = self.label,
+                "private key missing in the PKCS#11 token"
+            );
+
+            return Ok(None);
+        };
+
+        let public =
+            Self::find(&session, ObjectClass::PUBLIC_KEY, &self.label)?.ok_or_else(|| {
+                Error::with(CryptoError::Storage, "public key missing in the token")
+                    .set_ctx(format!("label {}", self.label))
+            })?;
+
+        self.key(&session, public, private)
+            .map(|key| Some(Arc::new(key) as Arc<dyn SigningKey>))
+    }
+}
+
+/// Private key in the token.
+#[derive(Clone)]
+struct Pkcs11Key {
+    session: Arc<Mutex<Session>>,
+    handle: ObjectHandle,
+    algorithm: KeyAlgorithm,
+    spki: SubjectPublicKeyInfoDer<'static>,
+}
+
+impl Pkcs11Key {
+    fn schemes(&self) -> &'static [SignatureScheme] {
+        match self.algorithm {
+            KeyAlgorithm::EcdsaP256 => &[SignatureScheme::ECDSA_NISTP256_SHA256],
+            KeyAlgorithm::EcdsaP384 => &[SignatureScheme::ECDSA_NISTP384_SHA384],
+            KeyAlgorithm::Ed25519 => &[SignatureScheme::ED25519],
+            KeyAlgorithm::Rsa2048 | KeyAlgorithm::Rsa3072 | KeyAlgorithm::Rsa4096 => &RSA_SCHEMES,
+        }
+    }
+
+    fn sign(&self, scheme: SignatureScheme, message: &[u8]) -> cryptoki::error::Result<Vec<u8>> {
+        let session = lock(&self.session);
+
+        match scheme {
+            SignatureScheme::ECDSA_NISTP256_SHA256 | SignatureScheme::ECDSA_NISTP384_SHA384 => {
+                let hash = if scheme == SignatureScheme::ECDSA_NISTP256_SHA256 {
+                    Mechanism::Sha256
+                } else {
+                    Mechanism::Sha384
+                };
+
+                let digest = session.digest(&hash, message)?;
+                let signature = session.sign(&Mechanism::Ecdsa, self.handle, &digest)?;
+
+                Ok(ecdsa_signature_to_der(&signature))
+            }
+            SignatureScheme::ED25519 => session.sign(
+                &Mechanism::Eddsa(EddsaParams::new(EddsaSignatureScheme::Pure)),
+                self.handle,
+                message,
+            ),
+            SignatureScheme::RSA_PSS_SHA256 => session.sign(
+                &Mechanism::Sha256RsaPkcsPss(pss_params(MechanismType::SHA256)),
+                self.handle,
+                message,
+            ),
+            SignatureScheme::RSA_PSS_SHA384 => session.sign(
+                &Mechanism::Sha384RsaPkcsPss(pss_params(MechanismType::SHA384)),
+                self.handle,
+                message,
+            ),
+            SignatureScheme::RSA_PSS_SHA512 => session.sign(
+                &Mechanism::Sha512RsaPkcsPss(pss_params(MechanismType::SHA512)),
+                self.handle,
+                message,
+            ),
+            SignatureScheme::RSA_PKCS1_SHA256 => {
+                session.sign(&Mechanism::Sha256RsaPkcs, self.handle, message)
+            }
+            SignatureScheme::RSA_PKCS1_SHA384 => {
+                session.sign(&Mechanism::Sha384RsaPkcs, self.handle, message)
+            }
+            _ => session.sign(&Mechanism::Sha512RsaPkcs, self.handle, message),
+        }
+    }
+}
+
+impl Debug for Pkcs11Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pkcs11Key")
+            .field("handle", &self.handle)
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SigningKey for Pkcs11Key {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        let scheme = self
+            .schemes()
+            .iter()
+            .find(|scheme| offered.contains(scheme))?;
+
+        Some(Box::new(Pkcs11Signer {
+            key: self.clone(),
+            scheme: *scheme,
+        }))
+    }
+
+    fn public_key(&self) -> Option<SubjectPublicKeyInfoDer<'_>> {
+        Some(self.spki.clone())
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        match self.algorithm {
+            KeyAlgorithm::EcdsaP256 | KeyAlgorithm::EcdsaP384 => SignatureAlgorithm::ECDSA,
+            KeyAlgorithm::Ed25519 => SignatureAlgorithm::ED25519,
+            KeyAlgorithm::Rsa2048 | KeyAlgorithm::Rsa3072 | KeyAlgorithm::Rsa4096 => {
+                SignatureAlgorithm::RSA
+            }
+        }
+    }
+}
+
+/// Signs with the key in the token, for the chosen scheme.
+#[derive(Debug)]
+struct Pkcs11Signer {
+    key: Pkcs11Key,
+    scheme: SignatureScheme,
+}
+
+impl Signer for Pkcs11Signer {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, rustls::Error> {
+        self.key
+            .sign(self.scheme, message)
+            .map_err(|err| rustls::Error::General(format!("PKCS#11 signature failed: {err}")))
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
+
+/// Locks the session, the state is still valid if another thread panicked while signing.
+fn lock(session: &Mutex<Session>) -> MutexGuard<'_, Session> {
+    session
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn pss_params(hash_alg: MechanismType) -> PkcsPssParams {
+    let (mgf, s_len) = if hash_alg == MechanismType::SHA256 {
+        (PkcsMgfType::MGF1_SHA256, 32)
+    } else if hash_alg == MechanismType::SHA384 {
+        (PkcsMgfType::MGF1_SHA384, 48)
+    } else {
+        (PkcsMgfType::MGF1_SHA512, 64)
+    };
+
+    PkcsPssParams {
+        hash_alg,
+        mgf,
+        s_len: s_len.into(),
+    }
+}
+
+/// Reads the public key of the key pair as a DER SubjectPublicKeyInfo.
+fn public_key_info(
+    session: &Session,
+    public: ObjectHandle,
+) -> Result<SubjectPublicKeyInfoDer<'static>, Error<CryptoError>> {
+    let attributes = session
+        .get_attributes(
+            public,
+            &[
+                AttributeType::KeyType,
+                AttributeType::EcParams,
+                AttributeType::EcPoint,
+                AttributeType::Modulus,
+                AttributeType::PublicExponent,
+            ],
+        )
+        .wrap_err_msg(CryptoError::Storage, "couldn't read the public key")?;
+
+    let mut key_type = None;
+    let mut ec_params = None;
+    let mut ec_point = None;
+    let mut modulus = None;
+    let mut exponent = None;
+
+    for attribute in attributes {
+        match attribute {
+            Attribute::KeyType(value) => key_type = Some(value),
+            Attribute::EcParams(value) => ec_params = Some(value),
+            Attribute::EcPoint(value) => ec_point = Some(value),
+            Attribute::Modulus(value) => modulus = Some(value),
+            Attribute::PublicExponent(value) => exponent = Some(value),
+            _ => {}
+        }
+    }
+
+    let unsupported = || {
+        Error::with(
+            CryptoError::PrivateKey,
+            "unsupported public key in the token",
+        )
+    };
+
+    match key_type.ok_or_else(unsupported)? {
+        KeyType::EC | KeyType::EC_EDWARDS => {
+            let (alg_id, len) = match ec_params.as_deref().ok_or_else(unsupported)? {
+                EC_PARAMS_P256 => (alg_id::ECDSA_P256, 65),
+                EC_PARAMS_P384 => (alg_id::ECDSA_P384, 97),
+                EC_PARAMS_ED25519 => (alg_id::ED25519, 32),
+                _ => return Err(unsupported()),
+            };
+
+            let point = ec_point.ok_or_else(unsupported)?;
+            // The point should be DER encoded as an OCTET STRING, but some tokens return it raw
+            let point = if point.len() == len {
+                point.as_slice()
+            } else {
+                der_octet_string(&point).ok_or_else(unsupported)?
+            };
+
+            Ok(public_key_to_spki(&alg_id, point))
+        }
+        KeyType::RSA => {
+            let modulus = modulus.ok_or_else(unsupported)?;
+            let exponent = exponent.ok_or_else(unsupported)?;
+
+            // RSAPublicKey ::= SEQUENCE { modulus INTEGER, publicExponent INTEGER }
+            let mut content = der_integer(&modulus);
+            content.extend(der_integer(&exponent));
+
+            Ok(public_key_to_spki(
+                &alg_id::RSA_ENCRYPTION,
+                der_tlv(0x30, &content),
+            ))
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+/// Converts the `r || s` ECDSA signature of PKCS#11 to the DER format used by TLS.
+fn ecdsa_signature_to_der(signature: &[u8]) -> Vec<u8> {
+    let (r, s) = signature.split_at(signature.len() / 2);
+
+    let mut content = der_integer(r);
+    content.extend(der_integer(s));
+
+    der_tlv(0x30, &content)
+}
+
+/// Encodes a big endian unsigned integer.
+fn der_integer(value: &[u8]) -> Vec<u8> {
+    let start = value
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(value.len().saturating_sub(1));
+    let value = &value[start..];
+
+    if value.first().is_some_and(|byte| byte & 0x80 != 0) {
+        let mut padded = Vec::with_capacity(value.len() + 1);
+        padded.push(0);
+        padded.extend_from_slice(value);
+
+        der_tlv(0x02, &padded)
+    } else {
+        der_tlv(0x02, value)
+    }
+}
+
+fn der_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(0);
+
+        out.push(0x80 | (bytes.len() - start) as u8);
+        out.extend_from_slice(&bytes[start..]);
+    }
+
+    out.extend_from_slice(content);
+
+    out
+}
+
+/// Returns the content of a DER OCTET STRING.
+fn der_octet_string(value: &[u8]) -> Option<&[u8]> {
+    let (&tag, rest) = value.split_first()?;
+    if tag != 0x04 {
+        return None;
+    }
+
+    let (&len, rest) = rest.split_first()?;
+    let (len, rest) = if len & 0x80 == 0 {
+        (usize::from(len), rest)
+    } else {
+        let count = usize::from(len & 0x7f);
+        if count > std::mem::size_of::<usize>() || rest.len() < count {
+            return None;
+        }
+
+        let (bytes, rest) = rest.split_at(count);
+        let len = bytes
+            .iter()
+            .fold(0usize, |acc, byte| (acc << 8) | usize::from(*byte));
+
+        (len, rest)
+    };
+
+    (rest.len() == len).then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::transport::mqtt::crypto::Bundle;
+
+    use super::*;
+
+    #[test]
+    fn should_encode_der() {
+        assert_eq!(der_integer(&[0x00, 0x00, 0x01]), [0x02, 0x01, 0x01]);
+        assert_eq!(der_integer(&[0x80]), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(der_integer(&[0x00]), [0x02, 0x01, 0x00]);
+
+        let long = [0x01; 200];
+        let encoded = der_tlv(0x04, &long);
+        assert_eq!(encoded[..3], [0x04, 0x81, 200]);
+        assert_eq!(der_octet_string(&encoded), Some(long.as_slice()));
+
+        assert_eq!(der_octet_string(&[0x04, 0x02, 0xaa]), None);
+    }
+
+    #[test]
+    fn should_convert_ecdsa_signature() {
+        let mut raw = [0u8; 64];
+        raw[31] = 0x01;
+        raw[32] = 0x80;
+
+        let der = ecdsa_signature_to_der(&raw);
+
+        let mut exp = vec![0x30, 0x26, 0x02, 0x01, 0x01, 0x02, 0x21, 0x00, 0x80];
+        exp.extend([0; 31]);
+        assert_eq!(der, exp);
+    }
+
+    /// Runs against SoftHSM, the `SOFTHSM2_MODULE` must be set to the path of the module.
+    ///
+    /// The `SOFTHSM2_CONF` must point to a configuration with a writable token directory, a new
+    /// token is initialized in the first free slot.
+    #[test]
+    #[ignore = "requires SoftHSM, run by the softhsm CI job"]
+    fn should_sign_with_softhsm() {
+        let module = std::env::var("SOFTHSM2_MODULE").expect("SOFTHSM2_MODULE not set");
+
+        const SO_PIN: &str = "1234";
+        const PIN: &str = "123456";
+        const TOKEN: &str = "astarte-test";
+
+        let pkcs11 = Pkcs11::new(module).unwrap();
+        pkcs11
+            .initialize(CInitializeArgs::new(CInitializeFlags::OS_LOCKING_OK))
+            .unwrap();
+
+        let slot = pkcs11
+            .get_all_slots()
+            .unwrap()
+            .into_iter()
+            .find(|slot| {
+                pkcs11
+                    .get_token_info(*slot)
+                    .is_ok_and(|info| !info.token_initialized())
+            })
+            .expect("no free slot");
+
+        pkcs11
+            .init_token(slot, &AuthPin::from(SO_PIN.to_string()), TOKEN)
+            .unwrap();
+        {
+            let session = pkcs11.open_rw_session(slot).unwrap();
+            session
+                .login(UserType::So, Some(&AuthPin::from(SO_PIN.to_string())))
+                .unwrap();
+            session.init_pin(&AuthPin::from(PIN.to_string())).unwrap();
+        }
+
+        let provider = Pkcs11KeyProvider::new(&pkcs11, slot, PIN, "device").unwrap();
+        assert!(provider.load().unwrap().is_none());
+
+        for algorithm in [
+            KeyAlgorithm::EcdsaP256,
+            KeyAlgorithm::EcdsaP384,
+            KeyAlgorithm::Ed25519,
+            KeyAlgorithm::Rsa2048,
+        ] {
+            let key = provider.generate(algorithm).unwrap();
+
+            let loaded = provider.load().unwrap().expect("should load the key");
+            assert_eq!(loaded.public_key(), key.public_key());
+
+            // The CSR is signed by the token
+            let bundle = Bundle::new("realm", "device_id", key).unwrap();
+            let der = rustls_pemfile::csr(&mut bundle.csr.as_bytes())
+                .unwrap()
+                .unwrap();
+            let (_, csr) =
+                x509_parser::certification_request::X509CertificationRequest::from_der(&der)
+                    .unwrap();
+            csr.verify_signature()
+                .unwrap_or_else(|err| panic!("invalid signature for {algorithm}: {err}"));
+        }
+    }
+}
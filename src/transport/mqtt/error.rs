@@ -58,6 +58,10 @@ pub enum MqttError {
     Timeout,
     /// Couldn't join task
     Task,
+    /// The broker refused the connection
+    ConnectionRefused(ReasonCode),
+    /// The MQTT 5 broker closed the connection
+    ServerDisconnect(ReasonCode),
 }
 
 impl Display for MqttError {
@@ -80,6 +84,143 @@ impl Display for MqttError {
             ),
             MqttError::Timeout => write!(f, "the configured timeout was reached"),
             MqttError::Task => write!(f, "couldn't join task"),
+            MqttError::ConnectionRefused(reason) => {
+                write!(f, "the broker refused the connection with reason {reason}")
+            }
+            MqttError::ServerDisconnect(reason) => {
+                write!(f, "the broker closed the connection with reason {reason}")
+            }
         }
     }
 }
+
+/// Reason code sent by an MQTT 5 broker.
+///
+/// The codes are defined in the
+/// [MQTT 5 specification](https://docs.oasis-open.org/mqtt/mqtt/v5.0/os/mqtt-v5.0-os.html#_Toc3901031),
+/// the ones greater or equal to `0x80` are errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReasonCode(u8);
+
+impl ReasonCode {
+    /// Returns the numeric value of the reason code.
+    pub fn code(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns true if the reason code is an error.
+    pub fn is_error(&self) -> bool {
+        self.0 >= 0x80
+    }
+
+    /// Returns the name of the reason code in the specification.
+    pub fn name(&self) -> Option<&'static str> {
+        let name = match self.0 {
+            0x00 => "Success",
+            0x04 => "Disconnect with Will Message",
+            0x80 => "Unspecified error",
+            0x81 => "Malformed Packet",
+            0x82 => "Protocol Error",
+            0x83 => "Implementation specific error",
+            0x84 => "Unsupported Protocol Version",
+            0x85 => "Client Identifier not valid",
+            0x86 => "Bad User Name or Password",
+            0x87 => "Not authorized",
+            0x88 => "Server unavailable",
+            0x89 => "Server busy",
+            0x8A => "Banned",
+            0x8B => "Server shutting down",
+            0x8C => "Bad authentication method",
+            0x8D => "Keep Alive timeout",
+            0x8E => "Session taken over",
+            0x8F => "Topic Filter invalid",
+            0x90 => "Topic Name invalid",
+            0x93 => "Receive Maximum exceeded",
+            0x94 => "Topic Alias invalid",
+            0x95 => "Packet too large",
+            0x96 => "Message rate too high",
+            0x97 => "Quota exceeded",
+            0x98 => "Administrative action",
+            0x99 => "Payload format invalid",
+            0x9A => "Retain not supported",
+            0x9B => "QoS not supported",
+            0x9C => "Use another server",
+            0x9D => "Server moved",
+            0x9E => "Shared Subscriptions not supported",
+            0x9F => "Connection rate exceeded",
+            0xA0 => "Maximum connect time",
+            0xA1 => "Subscription Identifiers not supported",
+            0xA2 => "Wildcard Subscriptions not supported",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+}
+
+impl Display for ReasonCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{name} (0x{:02X})", self.0),
+            None => write!(f, "0x{:02X}", self.0),
+        }
+    }
+}
+
+/// Maps the MQTT 3.1.1 return codes to the equivalent MQTT 5 ones.
+impl From<rumqttc::ConnectReturnCode> for ReasonCode {
+    fn from(value: rumqttc::ConnectReturnCode) -> Self {
+        use rumqttc::ConnectReturnCode;
+
+        let code = match value {
+            ConnectReturnCode::Success => 0x00,
+            ConnectReturnCode::RefusedProtocolVersion => 0x84,
+            ConnectReturnCode::BadClientId => 0x85,
+            ConnectReturnCode::ServiceUnavailable => 0x88,
+            ConnectReturnCode::BadUserNamePassword => 0x86,
+            ConnectReturnCode::NotAuthorized => 0x87,
+        };
+
+        Self(code)
+    }
+}
+
+impl From<rumqttc::v5::mqttbytes::v5::ConnectReturnCode> for ReasonCode {
+    fn from(value: rumqttc::v5::mqttbytes::v5::ConnectReturnCode) -> Self {
+        use rumqttc::v5::mqttbytes::v5::ConnectReturnCode;
+
+        let code = match value {
+            ConnectReturnCode::Success => 0x00,
+            ConnectReturnCode::UnspecifiedError => 0x80,
+            ConnectReturnCode::MalformedPacket => 0x81,
+            ConnectReturnCode::ProtocolError => 0x82,
+            ConnectReturnCode::ImplementationSpecificError => 0x83,
+            ConnectReturnCode::RefusedProtocolVersion
+            | ConnectReturnCode::UnsupportedProtocolVersion => 0x84,
+            ConnectReturnCode::BadClientId | ConnectReturnCode::ClientIdentifierNotValid => 0x85,
+            ConnectReturnCode::BadUserNamePassword => 0x86,
+            ConnectReturnCode::NotAuthorized => 0x87,
+            ConnectReturnCode::ServiceUnavailable | ConnectReturnCode::ServerUnavailable => 0x88,
+            ConnectReturnCode::ServerBusy => 0x89,
+            ConnectReturnCode::Banned => 0x8A,
+            ConnectReturnCode::BadAuthenticationMethod => 0x8C,
+            ConnectReturnCode::TopicNameInvalid => 0x90,
+            ConnectReturnCode::PacketTooLarge => 0x95,
+            ConnectReturnCode::QuotaExceeded => 0x97,
+            ConnectReturnCode::PayloadFormatInvalid => 0x99,
+            ConnectReturnCode::RetainNotSupported => 0x9A,
+            ConnectReturnCode::QoSNotSupported => 0x9B,
+            ConnectReturnCode::UseAnotherServer => 0x9C,
+            ConnectReturnCode::ServerMoved => 0x9D,
+            ConnectReturnCode::ConnectionRateExceeded => 0x9F,
+        };
+
+        Self(code)
+    }
+}
+
+impl From<rumqttc::v5::mqttbytes::v5::DisconnectReasonCode> for ReasonCode {
+    fn from(value: rumqttc::v5::mqttbytes::v5::DisconnectReasonCode) -> Self {
+        Self(value as u8)
+    }
+}
@@ -0,0 +1,552 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Quality metrics of the MQTT connection.
+//!
+//! The metrics are collected by the connection and read through a [`MqttMetrics`] handle,
+//! returned by [`DeviceClient::metrics`](crate::DeviceClient::metrics).
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tracing::trace;
+
+use super::error::ReasonCode;
+
+/// Statistics of a measured duration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DurationStats {
+    count: u64,
+    last: Option<Duration>,
+    min: Option<Duration>,
+    max: Option<Duration>,
+    sum: Duration,
+}
+
+impl DurationStats {
+    fn record(&mut self, value: Duration) {
+        self.count = self.count.saturating_add(1);
+        self.last = Some(value);
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+        self.sum = self.sum.saturating_add(value);
+    }
+
+    /// Returns the number of measurements.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the last measurement.
+    pub fn last(&self) -> Option<Duration> {
+        self.last
+    }
+
+    /// Returns the minimum measurement.
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    /// Returns the maximum measurement.
+    pub fn max(&self) -> Option<Duration> {
+        self.max
+    }
+
+    /// Returns the sum of the measurements.
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// Returns the mean of the measurements.
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let count = u32::try_from(self.count).ok()?;
+
+        Some(self.sum / count)
+    }
+}
+
+/// Metrics of the MQTT connection at a point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    ping_rtt: DurationStats,
+    connect_duration: DurationStats,
+    connections: u64,
+    connect_failures: u64,
+    connack_failures: BTreeMap<ReasonCode, u64>,
+    session_present: u64,
+    uptime: DurationStats,
+    current_uptime: Option<Duration>,
+}
+
+impl MetricsSnapshot {
+    /// Returns the round-trip time between a PINGREQ and the PINGRESP of the broker.
+    pub fn ping_rtt(&self) -> &DurationStats {
+        &self.ping_rtt
+    }
+
+    /// Returns the duration of the connection attempts, both the successful and the failed ones.
+    ///
+    /// An attempt lasts from the start of the connection until the CONNACK is received, including
+    /// the requests to the pairing API.
+    pub fn connect_duration(&self) -> &DurationStats {
+        &self.connect_duration
+    }
+
+    /// Returns the number of successful connections.
+    pub fn connections(&self) -> u64 {
+        self.connections
+    }
+
+    /// Returns the number of successful connections after the first one.
+    pub fn reconnections(&self) -> u64 {
+        self.connections.saturating_sub(1)
+    }
+
+    /// Returns the number of failed connection attempts.
+    pub fn connect_failures(&self) -> u64 {
+        self.connect_failures
+    }
+
+    /// Returns the number of connections refused by the broker, by the reason code of the
+    /// CONNACK.
+    pub fn connack_failures(&self) -> &BTreeMap<ReasonCode, u64> {
+        &self.connack_failures
+    }
+
+    /// Returns the number of connections where the broker had the session of the device.
+    pub fn session_present(&self) -> u64 {
+        self.session_present
+    }
+
+    /// Returns the ratio of the connections where the broker had the session of the device.
+    pub fn session_present_ratio(&self) -> Option<f64> {
+        if self.connections == 0 {
+            return None;
+        }
+
+        let ratio = self.session_present as f64 / self.connections as f64;
+
+        Some(ratio)
+    }
+
+    /// Returns the uptime of the closed connections.
+    pub fn uptime(&self) -> &DurationStats {
+        &self.uptime
+    }
+
+    /// Returns the uptime of the current connection, or [`None`] if disconnected.
+    pub fn current_uptime(&self) -> Option<Duration> {
+        self.current_uptime
+    }
+
+    /// Encodes the metrics in the Prometheus text exposition format.
+    ///
+    /// ```
+    /// use astarte_device_sdk::transport::mqtt::metrics::MqttMetrics;
+    ///
+    /// let metrics = MqttMetrics::default();
+    ///
+    /// let text = metrics.snapshot().encode_prometheus();
+    ///
+    /// assert!(text.contains("astarte_mqtt_connections_total 0"));
+    /// ```
+    #[cfg(feature = "prometheus")]
+    #[cfg_attr(astarte_device_sdk_docsrs, doc(cfg(feature = "prometheus")))]
+    pub fn encode_prometheus(&self) -> String {
+        use std::fmt::{Display, Write};
+
+        fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+        }
+
+        fn summary(out: &mut String, name: &str, help: &str, stats: &DurationStats) {
+            header(out, name, "summary", help);
+            let _ = writeln!(out, "{name}_sum {}", stats.sum.as_secs_f64());
+            let _ = writeln!(out, "{name}_count {}", stats.count);
+        }
+
+        fn value(out: &mut String, name: &str, kind: &str, help: &str, value: impl Display) {
+            header(out, name, kind, help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        let mut out = String::new();
+
+        summary(
+            &mut out,
+            "astarte_mqtt_ping_rtt_seconds",
+            "Round-trip time between a PINGREQ and the PINGRESP.",
+            &self.ping_rtt,
+        );
+        value(
+            &mut out,
+            "astarte_mqtt_ping_rtt_last_seconds",
+            "gauge",
+            "Last round-trip time between a PINGREQ and the PINGRESP.",
+            self.ping_rtt.last.unwrap_or_default().as_secs_f64(),
+        );
+        summary(
+            &mut out,
+            "astarte_mqtt_connect_duration_seconds",
+            "Duration of the connection attempts.",
+            &self.connect_duration,
+        );
+        value(
+            &mut out,
+            "astarte_mqtt_connections_total",
+            "counter",
+            "Successful connections to the broker.",
+            self.connections,
+        );
+        value(
+            &mut out,
+            "astarte_mqtt_connect_failures_total",
+            "counter",
+            "Failed connection attempts.",
+            self.connect_failures,
+        );
+
+        header(
+            &mut out,
+            "astarte_mqtt_connack_failures_total",
+            "counter",
+            "Connections refused by the broker, by reason code.",
+        );
+        for (reason, count) in &self.connack_failures {
+            let _ = writeln!(
+                out,
+                "astarte_mqtt_connack_failures_total{{reason=\"0x{:02X}\"}} {count}",
+                reason.code()
+            );
+        }
+
+        value(
+            &mut out,
+            "astarte_mqtt_session_present_total",
+            "counter",
+            "Connections where the broker had the session of the device.",
+            self.session_present,
+        );
+        summary(
+            &mut out,
+            "astarte_mqtt_connection_uptime_seconds",
+            "Uptime of the closed connections.",
+            &self.uptime,
+        );
+        value(
+            &mut out,
+            "astarte_mqtt_current_uptime_seconds",
+            "gauge",
+            "Uptime of the current connection, zero if disconnected.",
+            self.current_uptime.unwrap_or_default().as_secs_f64(),
+        );
+        value(
+            &mut out,
+            "astarte_mqtt_connected",
+            "gauge",
+            "Whether the device is connected to the broker.",
+            u8::from(self.current_uptime.is_some()),
+        );
+
+        out
+    }
+}
+
+#[derive(Debug, Default)]
+struct Metrics {
+    snapshot: MetricsSnapshot,
+    /// Time the last PINGREQ was sent.
+    ping_sent: Option<Instant>,
+    /// Time the current connection was established.
+    connected_at: Option<Instant>,
+}
+
+impl Metrics {
+    fn close_connection(&mut self, now: Instant) {
+        if let Some(connected_at) = self.connected_at.take() {
+            let uptime = now.saturating_duration_since(connected_at);
+
+            trace!(?uptime, "connection closed");
+
+            self.snapshot.uptime.record(uptime);
+        }
+
+        self.ping_sent = None;
+    }
+}
+
+/// Handle to the quality metrics of the MQTT connection.
+///
+/// The handle is cheap to clone and the metrics are shared between the clones.
+///
+/// ```no_run
+/// use astarte_device_sdk::builder::DeviceBuilder;
+/// use astarte_device_sdk::store::memory::MemoryStore;
+/// use astarte_device_sdk::transport::mqtt::{Credential, MqttArgs, MqttConfig};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let args = MqttArgs {
+///         realm: "realm_id".to_string(),
+///         device_id: "device_id".to_string(),
+///         credential: Credential::secret("credential_secret"),
+///         pairing_url: "http://api.astarte.localhost/pairing".parse().expect("a valid URL"),
+///     };
+///
+///     let (client, _connection) = DeviceBuilder::new()
+///         .store(MemoryStore::new())
+///         .connection(MqttConfig::new(args))
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let snapshot = client.metrics().snapshot();
+///
+///     println!("ping rtt: {:?}", snapshot.ping_rtt().mean());
+///     println!("reconnections: {}", snapshot.reconnections());
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MqttMetrics {
+    inner: Arc<Mutex<Metrics>>,
+}
+
+impl MqttMetrics {
+    fn lock(&self) -> MutexGuard<'_, Metrics> {
+        // The metrics are always left in a consistent state
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns the current value of the metrics.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.snapshot_at(Instant::now())
+    }
+
+    fn snapshot_at(&self, now: Instant) -> MetricsSnapshot {
+        let metrics = self.lock();
+
+        let mut snapshot = metrics.snapshot.clone();
+        snapshot.current_uptime = metrics
+            .connected_at
+            .map(|connected_at| now.saturating_duration_since(connected_at));
+
+        snapshot
+    }
+
+    /// A PINGREQ was sent to the broker.
+    pub(crate) fn ping_sent(&self, now: Instant) {
+        self.lock().ping_sent = Some(now);
+    }
+
+    /// A PINGRESP was received from the broker.
+    pub(crate) fn ping_received(&self, now: Instant) {
+        let mut metrics = self.lock();
+
+        let Some(sent) = metrics.ping_sent.take() else {
+            trace!("PINGRESP without a PINGREQ");
+
+            return;
+        };
+
+        let rtt = now.saturating_duration_since(sent);
+
+        trace!(?rtt, "ping round-trip");
+
+        metrics.snapshot.ping_rtt.record(rtt);
+    }
+
+    /// The connection attempt started at the given time succeeded.
+    pub(crate) fn connected(&self, started: Instant, session_present: bool, now: Instant) {
+        let mut metrics = self.lock();
+
+        // The previous connection wasn't closed
+        metrics.close_connection(now);
+
+        metrics
+            .snapshot
+            .connect_duration
+            .record(now.saturating_duration_since(started));
+        metrics.snapshot.connections = metrics.snapshot.connections.saturating_add(1);
+        if session_present {
+            metrics.snapshot.session_present = metrics.snapshot.session_present.saturating_add(1);
+        }
+
+        metrics.connected_at = Some(now);
+    }
+
+    /// The connection attempt started at the given time failed, with the reason code of the
+    /// CONNACK if the broker refused it.
+    pub(crate) fn connect_failed(
+        &self,
+        started: Instant,
+        reason: Option<ReasonCode>,
+        now: Instant,
+    ) {
+        let mut metrics = self.lock();
+
+        metrics
+            .snapshot
+            .connect_duration
+            .record(now.saturating_duration_since(started));
+        metrics.snapshot.connect_failures = metrics.snapshot.connect_failures.saturating_add(1);
+
+        if let Some(reason) = reason {
+            let count = metrics.snapshot.connack_failures.entry(reason).or_default();
+
+            *count = count.saturating_add(1);
+        }
+    }
+
+    /// The connection was closed.
+    pub(crate) fn disconnected(&self, now: Instant) {
+        self.lock().close_connection(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rumqttc::ConnectReturnCode;
+
+    use super::*;
+
+    #[test]
+    fn should_measure_ping_rtt() {
+        let metrics = MqttMetrics::default();
+        let now = Instant::now();
+
+        // Ignored without a PINGREQ
+        metrics.ping_received(now);
+
+        metrics.ping_sent(now);
+        metrics.ping_received(now + Duration::from_millis(30));
+        metrics.ping_sent(now + Duration::from_secs(1));
+        metrics.ping_received(now + Duration::from_millis(1010));
+
+        let rtt = *metrics.snapshot_at(now).ping_rtt();
+
+        assert_eq!(rtt.count(), 2);
+        assert_eq!(rtt.last(), Some(Duration::from_millis(10)));
+        assert_eq!(rtt.min(), Some(Duration::from_millis(10)));
+        assert_eq!(rtt.max(), Some(Duration::from_millis(30)));
+        assert_eq!(rtt.mean(), Some(Duration::from_millis(20)));
+    }
+
+    #[test]
+    fn should_count_connections() {
+        let metrics = MqttMetrics::default();
+        let now = Instant::now();
+
+        let refused = ReasonCode::from(ConnectReturnCode::NotAuthorized);
+        metrics.connect_failed(now, Some(refused), now + Duration::from_secs(2));
+        metrics.connect_failed(now, None, now + Duration::from_secs(4));
+        metrics.connected(now, false, now + Duration::from_secs(6));
+
+        let snapshot = metrics.snapshot_at(now + Duration::from_secs(16));
+        assert_eq!(snapshot.connections(), 1);
+        assert_eq!(snapshot.reconnections(), 0);
+        assert_eq!(snapshot.connect_failures(), 2);
+        assert_eq!(snapshot.connack_failures(), &BTreeMap::from([(refused, 1)]));
+        assert_eq!(snapshot.connect_duration().count(), 3);
+        assert_eq!(
+            snapshot.connect_duration().mean(),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(snapshot.current_uptime(), Some(Duration::from_secs(10)));
+
+        metrics.disconnected(now + Duration::from_secs(26));
+        metrics.connected(
+            now + Duration::from_secs(30),
+            true,
+            now + Duration::from_secs(31),
+        );
+
+        let snapshot = metrics.snapshot_at(now + Duration::from_secs(31));
+        assert_eq!(snapshot.reconnections(), 1);
+        assert_eq!(snapshot.session_present(), 1);
+        assert_eq!(snapshot.session_present_ratio(), Some(0.5));
+        assert_eq!(snapshot.uptime().last(), Some(Duration::from_secs(20)));
+        assert_eq!(snapshot.current_uptime(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn should_close_previous_connection() {
+        let metrics = MqttMetrics::default();
+        let now = Instant::now();
+
+        metrics.connected(now, true, now);
+        metrics.connected(now, true, now + Duration::from_secs(5));
+
+        let snapshot = metrics.snapshot_at(now + Duration::from_secs(5));
+        assert_eq!(snapshot.uptime().count(), 1);
+        assert_eq!(snapshot.uptime().sum(), Duration::from_secs(5));
+
+        metrics.disconnected(now + Duration::from_secs(6));
+        metrics.disconnected(now + Duration::from_secs(7));
+
+        let snapshot = metrics.snapshot_at(now + Duration::from_secs(8));
+        assert_eq!(snapshot.uptime().count(), 2);
+        assert_eq!(snapshot.current_uptime(), None);
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn should_encode_prometheus() {
+        let metrics = MqttMetrics::default();
+        let now = Instant::now();
+
+        metrics.connect_failed(
+            now,
+            Some(ReasonCode::from(ConnectReturnCode::BadUserNamePassword)),
+            now + Duration::from_millis(500),
+        );
+        metrics.connected(now, true, now + Duration::from_secs(1));
+        metrics.ping_sent(now);
+        metrics.ping_received(now + Duration::from_millis(250));
+
+        let text = metrics
+            .snapshot_at(now + Duration::from_secs(3))
+            .encode_prometheus();
+
+        for line in [
+            "# TYPE astarte_mqtt_ping_rtt_seconds summary",
+            "astarte_mqtt_ping_rtt_seconds_sum 0.25",
+            "astarte_mqtt_ping_rtt_seconds_count 1",
+            "astarte_mqtt_connect_duration_seconds_sum 1.5",
+            "astarte_mqtt_connections_total 1",
+            "astarte_mqtt_connect_failures_total 1",
+            "astarte_mqtt_connack_failures_total{reason=\"0x86\"} 1",
+            "astarte_mqtt_session_present_total 1",
+            "astarte_mqtt_current_uptime_seconds 2",
+            "astarte_mqtt_connected 1",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing {line} in:\n{text}"
+            );
+        }
+    }
+}
@@ -28,9 +28,11 @@ pub(crate) mod config;
 pub(crate) mod connection;
 pub mod crypto;
 pub mod error;
+pub mod metrics;
 pub(crate) mod payload;
 pub(crate) mod retention;
 pub mod topic;
+pub(crate) mod v5;
 
 use std::collections::HashMap;
 use std::fmt::Debug;
@@ -81,8 +83,17 @@ use self::{
 };
 
 pub use self::config::Credential;
+#[cfg(feature = "websocket")]
+pub use self::config::DEFAULT_WEBSOCKET_PATH;
 pub use self::config::MqttArgs;
 pub use self::config::MqttConfig;
+pub use self::config::MqttProtocol;
+pub use self::config::MqttTransport;
+pub use self::config::ProxyConfig;
+pub use self::config::{CaCertificate, SpkiPin, SpkiPinError, TlsTrust};
+pub use self::config::{DEFAULT_SESSION_EXPIRY, DEFAULT_TOPIC_ALIAS_MAX};
+pub use self::crypto::{KeyAlgorithm, KeyProvider};
+pub use self::metrics::MqttMetrics;
 
 /// Default keep alive interval in seconds for the MQTT connection.
 pub const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(15);
@@ -103,6 +114,8 @@ pub struct MqttClient<S> {
     retention: RetSender,
     store: S,
     state: Arc<SharedState>,
+    /// Quality metrics shared with the connection
+    pub(crate) metrics: MqttMetrics,
 }
 
 impl<S> MqttClient<S> {
@@ -113,6 +126,7 @@ impl<S> MqttClient<S> {
             retention,
             store,
             state,
+            metrics: MqttMetrics::default(),
         }
     }
 
@@ -666,6 +680,7 @@ pub struct Mqtt<S, P> {
     pub(crate) retention: MqttRetention,
     pub(crate) store: S,
     pub(crate) state: Arc<SharedState>,
+    pub(crate) metrics: MqttMetrics,
 }
 
 impl<S, P> Mqtt<S, P> {
@@ -709,24 +724,36 @@ impl<S, P> Mqtt<S, P> {
     where
         S: StoreCapabilities,
     {
-        if self.retention.is_empty() {
-            return Ok(self.connection.next_publish().await);
-        }
-
         loop {
-            let mut conn_future = std::pin::pin!(self.connection.next_publish());
+            let renewal = self.connection.cert_renewal_delay(&self.state).await;
+
+            if self.retention.is_empty() && renewal.is_none() {
+                return Ok(self.connection.next_publish().await);
+            }
+
+            let renewal = match renewal {
+                Some(delay) => Either::Left(tokio::time::sleep(delay)),
+                None => Either::Right(std::future::pending()),
+            };
 
-            match futures::future::select(self.retention.into_future(), &mut conn_future).await {
-                Either::Left((res, _)) => {
+            // The retention and the timer can be dropped safely
+            tokio::select! {
+                res = self.retention.into_future(), if !self.retention.is_empty() => {
                     Self::mark_packet_received(&self.state.volatile_store, &self.store, res)
                         .await
                         .map_kind(ErrorKind::Retention)?;
                 }
-                // the retention future can be dropped safely
-                Either::Right((publish, _)) => {
+                () = renewal => {
+                    debug!("renewing the client certificate");
+
+                    self.connection
+                        .renew_certificate(&self.provider, &self.state)
+                        .await;
+                }
+                publish = self.connection.next_publish() => {
                     return Ok(publish);
                 }
-            };
+            }
         }
     }
 
@@ -832,6 +859,7 @@ where
             provider: &self.provider,
             store: &self.store,
             interfaces,
+            metrics: &self.metrics,
             session_synced: false,
         };
 
@@ -1122,6 +1150,17 @@ pub(crate) mod test {
             pairing_url: "http://api.astarte.localhost/pairing".parse().unwrap(),
             ignore_ssl_errors: true,
             keepalive: DEFAULT_KEEP_ALIVE,
+            transport: MqttTransport::Tls,
+            protocol: MqttProtocol::V311,
+            proxy: ProxyConfig::Environment,
+            tls_trust: TlsTrust::default(),
+            fallback_brokers: Vec::new(),
+            primary_broker_cooldown: None,
+            cert_renewal_margin: None,
+            offline_cache_ttl: None,
+            key_algorithm: KeyAlgorithm::EcdsaP256,
+            key_provider: None,
+            credential_store: None,
         };
 
         let mqtt_state = mock_mqtt_state_connected(client.clone(), eventloop, mqtt_config);
@@ -1138,6 +1177,7 @@ pub(crate) mod test {
             retention: MqttRetention::new(ret_rx),
             store: store.clone(),
             state: Arc::clone(&state),
+            metrics: MqttMetrics::default(),
         };
 
         let mqtt_client = MqttClient {
@@ -1145,6 +1185,7 @@ pub(crate) mod test {
             store,
             state,
             retention: ret_tx,
+            metrics: mqtt.metrics.clone(),
         };
 
         (mqtt_client, mqtt)
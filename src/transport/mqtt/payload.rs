@@ -222,7 +222,6 @@ pub(super) fn deserialize_object(
 #[cfg(test)]
 mod test {
     use astarte_interfaces::Interface;
-    use astarte_interfaces::schema::MappingType;
     use astarte_test_utils::Hexdump;
     use astarte_test_utils::with_insta;
     use chrono::{DateTime, Utc};
@@ -240,25 +239,6 @@ mod test {
     use crate::test::E2E_DEVICE_AGGREGATE;
     use crate::test::E2E_DEVICE_DATASTREAM;
 
-    fn mapping_type(value: &AstarteData) -> MappingType {
-        match value {
-            AstarteData::Double(_) => MappingType::Double,
-            AstarteData::Integer(_) => MappingType::Integer,
-            AstarteData::Boolean(_) => MappingType::Boolean,
-            AstarteData::LongInteger(_) => MappingType::LongInteger,
-            AstarteData::String(_) => MappingType::String,
-            AstarteData::BinaryBlob(_) => MappingType::BinaryBlob,
-            AstarteData::DateTime(_) => MappingType::DateTime,
-            AstarteData::DoubleArray(_) => MappingType::DoubleArray,
-            AstarteData::IntegerArray(_) => MappingType::IntegerArray,
-            AstarteData::BooleanArray(_) => MappingType::BooleanArray,
-            AstarteData::LongIntegerArray(_) => MappingType::LongIntegerArray,
-            AstarteData::StringArray(_) => MappingType::StringArray,
-            AstarteData::BinaryBlobArray(_) => MappingType::BinaryBlobArray,
-            AstarteData::DateTimeArray(_) => MappingType::DateTimeArray,
-        }
-    }
-
     #[test]
     fn test_individual_serialization() {
         let interface = Interface::from_str(E2E_DEVICE_DATASTREAM).unwrap();
@@ -290,7 +270,7 @@ mod test {
         ];
 
         for ty in alltypes {
-            let mapping_type = mapping_type(&ty);
+            let mapping_type = ty.mapping_type();
             let endpoint = format!("/{mapping_type}_endpoint");
 
             let path = MappingPath::try_from(endpoint.as_str()).unwrap();
@@ -340,7 +320,7 @@ mod test {
         let data: AstarteObject = alltypes
             .into_iter()
             .map(|ty| {
-                let mapping_type = mapping_type(&ty);
+                let mapping_type = ty.mapping_type();
 
                 let endpoint = format!("{mapping_type}_endpoint");
 
@@ -0,0 +1,869 @@
+// This file is part of Astarte.
+//
+// Copyright 2026 SECO Mind Srl
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! MQTT 5 client, adapted to the MQTT 3.1.1 types used by the connection.
+//!
+//! The requests and the events are converted from and to the [`rumqttc`] v3.1.1 ones, so the rest
+//! of the connection handles both the protocols in the same way. The MQTT 5 only features are
+//! handled here: the session expiry, the reason codes returned in the errors and the topic
+//! aliases of the publishes.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use rumqttc::v5::mqttbytes::v5::{
+    ConnAck, Packet, PubAckReason, PubCompReason, PublishProperties, SubscribeReasonCode,
+};
+use rumqttc::v5::{self as mqtt5, StateError};
+use rumqttc::{
+    AckOfPub, Event, MqttOptions, NetworkOptions, QoS, Resolver, SubAck, Token, Transport, UnsubAck,
+};
+use tracing::{debug, trace, warn};
+
+use super::client::ConnectionError;
+
+/// The MQTT 5 keep alive must be at least 5 seconds.
+const MIN_KEEP_ALIVE: Duration = Duration::from_secs(5);
+
+/// Creates the MQTT 5 client and event loop from the MQTT 3.1.1 options.
+pub(crate) fn new(
+    options: &MqttOptions,
+    cap: usize,
+    session_expiry: Duration,
+    topic_alias_max: u16,
+) -> (Mqtt5Client, Mqtt5EventLoop) {
+    let options = mqtt5_options(options, session_expiry, topic_alias_max);
+
+    let (client, eventloop) = mqtt5::AsyncClient::new(options, cap);
+
+    let aliases = Arc::new(Mutex::new(TopicAliases::new(topic_alias_max)));
+
+    let client = Mqtt5Client {
+        client,
+        aliases: Arc::clone(&aliases),
+        send: Arc::new(tokio::sync::Mutex::new(())),
+    };
+
+    let eventloop = Mqtt5EventLoop {
+        eventloop,
+        aliases,
+        session_expiry,
+        topic_alias_max,
+    };
+
+    (client, eventloop)
+}
+
+fn mqtt5_options(
+    options: &MqttOptions,
+    session_expiry: Duration,
+    topic_alias_max: u16,
+) -> mqtt5::MqttOptions {
+    let (host, port) = options.broker_address();
+    let session_expiry = u32::try_from(session_expiry.as_secs()).unwrap_or(u32::MAX);
+
+    let mut mqtt5 = mqtt5::MqttOptions::new(options.client_id(), host, port);
+
+    mqtt5
+        .set_keep_alive(options.keep_alive().max(MIN_KEEP_ALIVE))
+        .set_clean_start(options.clean_session())
+        .set_transport(options.transport())
+        .set_request_channel_capacity(options.request_channel_capacity())
+        .set_pending_throttle(options.pending_throttle())
+        .set_outgoing_inflight_upper_limit(options.inflight())
+        .set_max_packet_size(u32::try_from(options.max_packet_size()).ok())
+        .set_session_expiry_interval(Some(session_expiry))
+        .set_topic_alias_max(Some(topic_alias_max));
+
+    if let Some(proxy) = options.proxy() {
+        mqtt5.set_proxy(proxy);
+    }
+
+    mqtt5
+}
+
+fn qos_to_mqtt5(qos: QoS) -> mqtt5::mqttbytes::QoS {
+    match qos {
+        QoS::AtMostOnce => mqtt5::mqttbytes::QoS::AtMostOnce,
+        QoS::AtLeastOnce => mqtt5::mqttbytes::QoS::AtLeastOnce,
+        QoS::ExactlyOnce => mqtt5::mqttbytes::QoS::ExactlyOnce,
+    }
+}
+
+fn qos_from_mqtt5(qos: mqtt5::mqttbytes::QoS) -> QoS {
+    match qos {
+        mqtt5::mqttbytes::QoS::AtMostOnce => QoS::AtMostOnce,
+        mqtt5::mqttbytes::QoS::AtLeastOnce => QoS::AtLeastOnce,
+        mqtt5::mqttbytes::QoS::ExactlyOnce => QoS::ExactlyOnce,
+    }
+}
+
+/// Resolves the MQTT 3.1.1 token with the acknowledgment of the MQTT 5 one.
+///
+/// The token is dropped, returning an error to the caller, if the map returns [`None`].
+fn forward<T, U, F>(token: Token<T>, map: F) -> Token<U>
+where
+    T: Send + 'static,
+    U: Send + 'static,
+    F: FnOnce(T) -> Option<U> + Send + 'static,
+{
+    let (resolver, forwarded) = Resolver::new();
+
+    tokio::spawn(async move {
+        match token.await {
+            Ok(ack) => {
+                if let Some(ack) = map(ack) {
+                    resolver.resolve(ack);
+                }
+            }
+            Err(err) => trace!(error = %err, "request dropped by the event loop"),
+        }
+    });
+
+    forwarded
+}
+
+fn map_ack_of_pub(ack: mqtt5::AckOfPub) -> Option<AckOfPub> {
+    match ack {
+        mqtt5::AckOfPub::PubAck(puback) => match puback.reason {
+            PubAckReason::Success | PubAckReason::NoMatchingSubscribers => {
+                Some(AckOfPub::PubAck(rumqttc::PubAck::new(puback.pkid)))
+            }
+            reason => {
+                warn!(pkid = puback.pkid, ?reason, "publish refused by the broker");
+
+                None
+            }
+        },
+        mqtt5::AckOfPub::PubComp(pubcomp) => match pubcomp.reason {
+            PubCompReason::Success => Some(AckOfPub::PubComp(rumqttc::PubComp::new(pubcomp.pkid))),
+            reason => {
+                warn!(
+                    pkid = pubcomp.pkid,
+                    ?reason,
+                    "publish refused by the broker"
+                );
+
+                None
+            }
+        },
+        mqtt5::AckOfPub::None => Some(AckOfPub::None),
+    }
+}
+
+fn map_suback(suback: mqtt5::mqttbytes::v5::SubAck) -> rumqttc::SubAck {
+    let codes = suback
+        .return_codes
+        .into_iter()
+        .map(|code| match code {
+            SubscribeReasonCode::Success(qos) => {
+                rumqttc::SubscribeReasonCode::Success(qos_from_mqtt5(qos))
+            }
+            reason => {
+                warn!(
+                    pkid = suback.pkid,
+                    ?reason,
+                    "subscription refused by the broker"
+                );
+
+                rumqttc::SubscribeReasonCode::Failure
+            }
+        })
+        .collect();
+
+    rumqttc::SubAck::new(suback.pkid, codes)
+}
+
+/// Converts an incoming packet, returns [`None`] for the ones a broker doesn't send.
+fn map_incoming(packet: Packet) -> Option<rumqttc::Packet> {
+    let packet = match packet {
+        Packet::ConnAck(connack) => rumqttc::Packet::ConnAck(rumqttc::ConnAck::new(
+            rumqttc::ConnectReturnCode::Success,
+            connack.session_present,
+        )),
+        Packet::Publish(publish) => {
+            let topic = String::from_utf8_lossy(&publish.topic).into_owned();
+
+            rumqttc::Packet::Publish(rumqttc::Publish {
+                dup: publish.dup,
+                qos: qos_from_mqtt5(publish.qos),
+                retain: publish.retain,
+                topic,
+                pkid: publish.pkid,
+                payload: publish.payload,
+            })
+        }
+        Packet::PubAck(puback) => rumqttc::Packet::PubAck(rumqttc::PubAck::new(puback.pkid)),
+        Packet::PubRec(pubrec) => rumqttc::Packet::PubRec(rumqttc::PubRec::new(pubrec.pkid)),
+        Packet::PubRel(pubrel) => rumqttc::Packet::PubRel(rumqttc::PubRel::new(pubrel.pkid)),
+        Packet::PubComp(pubcomp) => rumqttc::Packet::PubComp(rumqttc::PubComp::new(pubcomp.pkid)),
+        Packet::SubAck(suback) => rumqttc::Packet::SubAck(map_suback(suback)),
+        Packet::UnsubAck(unsuback) => {
+            rumqttc::Packet::UnsubAck(rumqttc::UnsubAck::new(unsuback.pkid))
+        }
+        Packet::PingResp(_) => rumqttc::Packet::PingResp,
+        Packet::Disconnect(_) => rumqttc::Packet::Disconnect,
+        Packet::Auth(_)
+        | Packet::Connect(..)
+        | Packet::PingReq(_)
+        | Packet::Subscribe(_)
+        | Packet::Unsubscribe(_) => {
+            warn!(?packet, "unexpected packet received from the broker");
+
+            return None;
+        }
+    };
+
+    Some(packet)
+}
+
+/// Topic alias to use for a publish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alias {
+    /// Send the full topic without an alias.
+    None,
+    /// Send the full topic to register the alias on the broker.
+    Register { alias: u16, generation: u64 },
+    /// Send only the alias, already registered in the current connection.
+    Registered(u16),
+}
+
+/// Topic aliases of the outgoing publishes.
+///
+/// An alias is assigned to a topic the first time it's published and never reassigned, so a
+/// publish can be retransmitted with the full topic after a reconnection. The aliases are used
+/// only up to the maximum sent by the broker in the CONNACK, and are registered again on each
+/// connection.
+#[derive(Debug, Default)]
+struct TopicAliases {
+    /// Maximum number of aliases to assign.
+    max: u16,
+    /// Topic alias maximum of the broker, zero while disconnected.
+    broker_max: u16,
+    /// Connection the registered aliases refer to.
+    generation: u64,
+    /// Topics by alias, the alias `1` is at index `0`.
+    topics: Vec<String>,
+    aliases: HashMap<String, u16>,
+    /// Aliases registered in the current connection.
+    registered: HashSet<u16>,
+}
+
+impl TopicAliases {
+    fn new(max: u16) -> Self {
+        Self {
+            max,
+            ..Default::default()
+        }
+    }
+
+    fn alias(&mut self, topic: &str) -> Alias {
+        let limit = self.max.min(self.broker_max);
+
+        let alias = match self.aliases.get(topic) {
+            Some(alias) => *alias,
+            None => {
+                let Some(next) = u16::try_from(self.topics.len())
+                    .ok()
+                    .and_then(|len| len.checked_add(1))
+                    .filter(|next| *next <= limit)
+                else {
+                    return Alias::None;
+                };
+
+                self.topics.push(topic.to_string());
+                self.aliases.insert(topic.to_string(), next);
+
+                next
+            }
+        };
+
+        if alias > limit {
+            Alias::None
+        } else if self.registered.contains(&alias) {
+            Alias::Registered(alias)
+        } else {
+            Alias::Register {
+                alias,
+                generation: self.generation,
+            }
+        }
+    }
+
+    /// The publish registering the alias was sent.
+    fn register(&mut self, alias: u16, generation: u64) {
+        // Skip if disconnected in the meantime
+        if generation == self.generation {
+            self.registered.insert(alias);
+        }
+    }
+
+    fn topic(&self, alias: u16) -> Option<&str> {
+        let idx = usize::from(alias).checked_sub(1)?;
+
+        self.topics.get(idx).map(String::as_str)
+    }
+
+    fn connected(&mut self, broker_max: Option<u16>) {
+        self.broker_max = broker_max.unwrap_or_default();
+        self.generation = self.generation.wrapping_add(1);
+        self.registered.clear();
+    }
+
+    fn disconnected(&mut self) {
+        self.broker_max = 0;
+        self.generation = self.generation.wrapping_add(1);
+        self.registered.clear();
+    }
+}
+
+fn lock_aliases(aliases: &Mutex<TopicAliases>) -> MutexGuard<'_, TopicAliases> {
+    // The aliases are always left in a consistent state
+    aliases
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// MQTT 5 client, returning the MQTT 3.1.1 acknowledgments.
+#[derive(Debug, Clone)]
+pub(crate) struct Mqtt5Client {
+    client: mqtt5::AsyncClient,
+    aliases: Arc<Mutex<TopicAliases>>,
+    /// Sends the publishes in order, so an alias is registered before it's used.
+    send: Arc<tokio::sync::Mutex<()>>,
+}
+
+// The protocol wrapper is mocked in the tests
+#[cfg_attr(test, allow(dead_code))]
+impl Mqtt5Client {
+    pub(crate) async fn publish<S, V>(
+        &self,
+        topic: S,
+        qos: QoS,
+        retain: bool,
+        payload: V,
+    ) -> Result<Token<AckOfPub>, mqtt5::ClientError>
+    where
+        S: Into<String>,
+        V: Into<Vec<u8>>,
+    {
+        let topic = topic.into();
+        let payload = payload.into();
+        let qos = qos_to_mqtt5(qos);
+
+        let _send = self.send.lock().await;
+
+        let alias = lock_aliases(&self.aliases).alias(&topic);
+
+        let token = match alias {
+            Alias::None => self.client.publish(topic, qos, retain, payload).await?,
+            Alias::Register { alias, generation } => {
+                let properties = PublishProperties {
+                    topic_alias: Some(alias),
+                    ..Default::default()
+                };
+
+                let token = self
+                    .client
+                    .publish_with_properties(topic, qos, retain, payload, properties)
+                    .await?;
+
+                lock_aliases(&self.aliases).register(alias, generation);
+
+                token
+            }
+            Alias::Registered(alias) => {
+                let properties = PublishProperties {
+                    topic_alias: Some(alias),
+                    ..Default::default()
+                };
+
+                self.client
+                    .publish_with_properties("", qos, retain, payload, properties)
+                    .await?
+            }
+        };
+
+        Ok(forward(token, map_ack_of_pub))
+    }
+
+    pub(crate) async fn subscribe<S>(
+        &self,
+        topic: S,
+        qos: QoS,
+    ) -> Result<Token<SubAck>, mqtt5::ClientError>
+    where
+        S: Into<String>,
+    {
+        let token = self.client.subscribe(topic, qos_to_mqtt5(qos)).await?;
+
+        Ok(forward(token, |suback| Some(map_suback(suback))))
+    }
+
+    pub(crate) async fn unsubscribe<S>(
+        &self,
+        topic: S,
+    ) -> Result<Token<UnsubAck>, mqtt5::ClientError>
+    where
+        S: Into<String>,
+    {
+        let token = self.client.unsubscribe(topic).await?;
+
+        Ok(forward(token, |unsuback| {
+            Some(rumqttc::UnsubAck::new(unsuback.pkid))
+        }))
+    }
+
+    pub(crate) async fn disconnect(&self) -> Result<Token<()>, mqtt5::ClientError> {
+        self.client.disconnect().await
+    }
+}
+
+/// MQTT 5 event loop, returning the MQTT 3.1.1 events.
+pub(crate) struct Mqtt5EventLoop {
+    eventloop: mqtt5::EventLoop,
+    aliases: Arc<Mutex<TopicAliases>>,
+    session_expiry: Duration,
+    topic_alias_max: u16,
+}
+
+// The protocol wrapper is mocked in the tests
+#[cfg_attr(test, allow(dead_code))]
+impl Mqtt5EventLoop {
+    pub(crate) async fn poll(&mut self) -> Result<Event, ConnectionError> {
+        loop {
+            let event = match self.eventloop.poll().await {
+                Ok(event) => event,
+                // The connection is still up, the state wasn't cleaned
+                Err(err @ mqtt5::ConnectionError::MqttState(StateError::Unsolicited(_))) => {
+                    return Err(err.into());
+                }
+                Err(err) => {
+                    lock_aliases(&self.aliases).disconnected();
+
+                    return Err(err.into());
+                }
+            };
+
+            let incoming = match event {
+                mqtt5::Event::Outgoing(outgoing) => return Ok(Event::Outgoing(outgoing)),
+                mqtt5::Event::Incoming(incoming) => incoming,
+            };
+
+            if let Packet::ConnAck(connack) = &incoming {
+                self.connected(connack);
+            }
+
+            if let Some(packet) = map_incoming(incoming) {
+                return Ok(Event::Incoming(packet));
+            }
+        }
+    }
+
+    /// Enables the aliases for the new connection.
+    ///
+    /// The CONNACK is returned before the pending requests are sent, so the publishes of the
+    /// previous connection are restored with the full topic, since the aliases are not valid
+    /// anymore.
+    fn connected(&mut self, connack: &ConnAck) {
+        let broker_max = connack
+            .properties
+            .as_ref()
+            .and_then(|props| props.topic_alias_max);
+
+        debug!(?broker_max, "connected with MQTT 5");
+
+        let mut aliases = lock_aliases(&self.aliases);
+
+        aliases.connected(broker_max);
+
+        for request in &mut self.eventloop.pending {
+            let mqtt5::Request::Publish(publish, _) = request else {
+                continue;
+            };
+
+            let Some(alias) = publish
+                .properties
+                .as_mut()
+                .and_then(|props| props.topic_alias.take())
+            else {
+                continue;
+            };
+
+            if publish.topic.is_empty() {
+                match aliases.topic(alias) {
+                    Some(topic) => publish.topic = topic.to_string().into(),
+                    None => warn!(alias, "couldn't restore the topic of the alias"),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn set_network_options(&mut self, network_options: NetworkOptions) {
+        self.eventloop
+            .options
+            .set_connection_timeout(network_options.connection_timeout())
+            .set_network_options(network_options);
+    }
+
+    /// Replaces the options for the next connection, keeping the network ones.
+    pub(crate) fn set_options(&mut self, options: &MqttOptions) {
+        let mut mqtt5 = mqtt5_options(options, self.session_expiry, self.topic_alias_max);
+
+        mqtt5
+            .set_connection_timeout(self.eventloop.options.connection_timeout())
+            .set_network_options(self.eventloop.options.network_options());
+
+        self.eventloop.options = mqtt5;
+    }
+
+    pub(crate) fn transport(&self) -> Transport {
+        self.eventloop.options.transport()
+    }
+
+    pub(crate) fn set_transport(&mut self, transport: Transport) {
+        self.eventloop.options.set_transport(transport);
+    }
+
+    pub(crate) fn clean_start(&self) -> bool {
+        self.eventloop.options.clean_start()
+    }
+
+    pub(crate) fn set_clean_start(&mut self, clean_start: bool) {
+        self.eventloop.options.set_clean_start(clean_start);
+    }
+}
+
+impl Debug for Mqtt5EventLoop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mqtt5EventLoop")
+            .field("options", &self.eventloop.options)
+            .field("session_expiry", &self.session_expiry)
+            .field("topic_alias_max", &self.topic_alias_max)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use pretty_assertions::assert_eq;
+    use rumqttc::v5::mqttbytes::v5::{
+        ConnAckProperties, ConnectReturnCode, Disconnect, DisconnectProperties,
+        DisconnectReasonCode, PubAck,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use crate::transport::mqtt::error::{MqttError, ReasonCode};
+
+    use super::*;
+
+    /// Broker stand-in, reading and writing the MQTT 5 packets.
+    struct Broker {
+        stream: TcpStream,
+        buf: BytesMut,
+    }
+
+    impl Broker {
+        async fn read(&mut self) -> Packet {
+            loop {
+                match Packet::read(&mut self.buf, None) {
+                    Ok(packet) => return packet,
+                    Err(mqtt5::mqttbytes::Error::InsufficientBytes(_)) => {
+                        let read = self.stream.read_buf(&mut self.buf).await.unwrap();
+                        assert_ne!(read, 0, "connection closed");
+                    }
+                    Err(err) => panic!("invalid packet: {err}"),
+                }
+            }
+        }
+
+        async fn write(&mut self, packet: Packet) {
+            let mut buf = BytesMut::new();
+            packet.write(&mut buf, None).unwrap();
+
+            self.stream.write_all(&buf).await.unwrap();
+        }
+
+        /// Reads the CONNECT and sends the CONNACK.
+        async fn accept(&mut self, code: ConnectReturnCode, properties: Option<ConnAckProperties>) {
+            let Packet::Connect(connect, _, _) = self.read().await else {
+                panic!("expected CONNECT");
+            };
+
+            let props = connect.properties.unwrap();
+            assert_eq!(props.session_expiry_interval, Some(60));
+            assert_eq!(props.topic_alias_max, Some(10));
+
+            self.write(Packet::ConnAck(ConnAck {
+                session_present: false,
+                code,
+                properties,
+            }))
+            .await;
+        }
+    }
+
+    fn connack_props(topic_alias_max: u16, server_keep_alive: u16) -> ConnAckProperties {
+        ConnAckProperties {
+            session_expiry_interval: None,
+            receive_max: None,
+            max_qos: None,
+            retain_available: None,
+            max_packet_size: None,
+            assigned_client_identifier: None,
+            topic_alias_max: Some(topic_alias_max),
+            reason_string: None,
+            user_properties: Vec::new(),
+            wildcard_subscription_available: None,
+            subscription_identifiers_available: None,
+            shared_subscription_available: None,
+            server_keep_alive: Some(server_keep_alive),
+            response_information: None,
+            server_reference: None,
+            authentication_method: None,
+            authentication_data: None,
+        }
+    }
+
+    async fn connect() -> (Mqtt5Client, Mqtt5EventLoop, TcpListener) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut options = MqttOptions::new("realm/device_id", "127.0.0.1", port);
+        options.set_keep_alive(Duration::from_secs(30));
+
+        let (client, eventloop) = new(&options, 10, Duration::from_secs(60), 10);
+
+        (client, eventloop, listener)
+    }
+
+    async fn accept(listener: &TcpListener) -> Broker {
+        let (stream, _) = listener.accept().await.unwrap();
+
+        Broker {
+            stream,
+            buf: BytesMut::new(),
+        }
+    }
+
+    #[test]
+    fn should_assign_stable_aliases() {
+        let mut aliases = TopicAliases::new(2);
+
+        // Disabled until connected
+        assert_eq!(aliases.alias("realm/device/a"), Alias::None);
+
+        aliases.connected(Some(5));
+        let generation = aliases.generation;
+
+        assert_eq!(
+            aliases.alias("realm/device/a"),
+            Alias::Register {
+                alias: 1,
+                generation
+            }
+        );
+        aliases.register(1, generation);
+        assert_eq!(aliases.alias("realm/device/a"), Alias::Registered(1));
+        assert_eq!(
+            aliases.alias("realm/device/b"),
+            Alias::Register {
+                alias: 2,
+                generation
+            }
+        );
+        // Over the configured maximum
+        assert_eq!(aliases.alias("realm/device/c"), Alias::None);
+
+        aliases.disconnected();
+        aliases.connected(Some(1));
+        let generation = aliases.generation;
+
+        // Registered again, the alias over the broker maximum is not used
+        assert_eq!(
+            aliases.alias("realm/device/a"),
+            Alias::Register {
+                alias: 1,
+                generation
+            }
+        );
+        assert_eq!(aliases.alias("realm/device/b"), Alias::None);
+        assert_eq!(aliases.topic(2), Some("realm/device/b"));
+        assert_eq!(aliases.topic(3), None);
+    }
+
+    #[test]
+    fn should_not_register_alias_after_disconnection() {
+        let mut aliases = TopicAliases::new(2);
+
+        aliases.connected(Some(2));
+        let Alias::Register { alias, generation } = aliases.alias("realm/device/a") else {
+            panic!("expected a new alias");
+        };
+
+        aliases.disconnected();
+        aliases.connected(Some(2));
+        aliases.register(alias, generation);
+
+        assert!(matches!(
+            aliases.alias("realm/device/a"),
+            Alias::Register { alias: 1, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_return_connack_reason_code() {
+        let (_client, mut eventloop, listener) = connect().await;
+
+        let broker = tokio::spawn(async move {
+            let mut broker = accept(&listener).await;
+
+            broker.accept(ConnectReturnCode::Banned, None).await;
+        });
+
+        let err = eventloop.poll().await.unwrap_err();
+
+        broker.await.unwrap();
+
+        let reason = ReasonCode::from(ConnectReturnCode::Banned);
+        assert_eq!(err.kind(), MqttError::ConnectionRefused(reason));
+        assert_eq!(reason.code(), 0x8a);
+    }
+
+    #[tokio::test]
+    async fn should_return_server_disconnect_reason() {
+        let (_client, mut eventloop, listener) = connect().await;
+
+        let broker = tokio::spawn(async move {
+            let mut broker = accept(&listener).await;
+
+            broker
+                .accept(ConnectReturnCode::Success, Some(connack_props(10, 30)))
+                .await;
+            // Without properties the packet is written with the wrong remaining length
+            broker
+                .write(Packet::Disconnect(Disconnect {
+                    reason_code: DisconnectReasonCode::SessionTakenOver,
+                    properties: Some(DisconnectProperties {
+                        session_expiry_interval: None,
+                        reason_string: Some("session taken over".to_string()),
+                        user_properties: Vec::new(),
+                        server_reference: None,
+                    }),
+                }))
+                .await;
+
+            broker
+        });
+
+        let event = eventloop.poll().await.unwrap();
+        assert!(matches!(
+            event,
+            Event::Incoming(rumqttc::Packet::ConnAck(_))
+        ));
+
+        let err = loop {
+            match eventloop.poll().await {
+                Ok(event) => trace!(?event, "event"),
+                Err(err) => break err,
+            }
+        };
+
+        drop(broker.await.unwrap());
+
+        assert_eq!(
+            err.kind(),
+            MqttError::ServerDisconnect(ReasonCode::from(DisconnectReasonCode::SessionTakenOver))
+        );
+    }
+
+    #[tokio::test]
+    async fn should_use_topic_alias_and_server_keep_alive() {
+        let (client, mut eventloop, listener) = connect().await;
+
+        let broker = tokio::spawn(async move {
+            let mut broker = accept(&listener).await;
+
+            broker
+                .accept(ConnectReturnCode::Success, Some(connack_props(5, 20)))
+                .await;
+
+            let mut publishes = Vec::new();
+            for _ in 0..2 {
+                let Packet::Publish(publish) = broker.read().await else {
+                    panic!("expected PUBLISH");
+                };
+
+                broker
+                    .write(Packet::PubAck(PubAck::new(publish.pkid, None)))
+                    .await;
+
+                publishes.push(publish);
+            }
+
+            publishes
+        });
+
+        let event = eventloop.poll().await.unwrap();
+        assert!(matches!(
+            event,
+            Event::Incoming(rumqttc::Packet::ConnAck(_))
+        ));
+        assert_eq!(
+            eventloop.eventloop.options.keep_alive(),
+            Duration::from_secs(20)
+        );
+
+        let poll = tokio::spawn(async move {
+            loop {
+                eventloop.poll().await.unwrap();
+            }
+        });
+
+        for payload in [b"first", b"other"] {
+            let token = client
+                .publish(
+                    "realm/device/interface/path",
+                    QoS::AtLeastOnce,
+                    false,
+                    payload.to_vec(),
+                )
+                .await
+                .unwrap();
+
+            let ack = token.await.unwrap();
+            assert!(matches!(ack, AckOfPub::PubAck(_)));
+        }
+
+        let publishes = broker.await.unwrap();
+        poll.abort();
+
+        let alias = |publish: &mqtt5::mqttbytes::v5::Publish| {
+            publish.properties.as_ref().and_then(|p| p.topic_alias)
+        };
+
+        assert_eq!(publishes[0].topic, "realm/device/interface/path");
+        assert_eq!(alias(&publishes[0]), Some(1));
+        assert!(publishes[1].topic.is_empty());
+        assert_eq!(alias(&publishes[1]), Some(1));
+    }
+}
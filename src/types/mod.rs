@@ -200,6 +200,29 @@ pub enum AstarteData {
 }
 
 impl AstarteData {
+    /// Returns the mapping type of the value.
+    ///
+    /// An [`AstarteData::Integer`] is always an integer, see [`AstarteData::eq_mapping_type`] for
+    /// the compatible types.
+    pub(crate) fn mapping_type(&self) -> MappingType {
+        match self {
+            AstarteData::Double(_) => MappingType::Double,
+            AstarteData::Integer(_) => MappingType::Integer,
+            AstarteData::Boolean(_) => MappingType::Boolean,
+            AstarteData::LongInteger(_) => MappingType::LongInteger,
+            AstarteData::String(_) => MappingType::String,
+            AstarteData::BinaryBlob(_) => MappingType::BinaryBlob,
+            AstarteData::DateTime(_) => MappingType::DateTime,
+            AstarteData::DoubleArray(_) => MappingType::DoubleArray,
+            AstarteData::IntegerArray(_) => MappingType::IntegerArray,
+            AstarteData::BooleanArray(_) => MappingType::BooleanArray,
+            AstarteData::LongIntegerArray(_) => MappingType::LongIntegerArray,
+            AstarteData::StringArray(_) => MappingType::StringArray,
+            AstarteData::BinaryBlobArray(_) => MappingType::BinaryBlobArray,
+            AstarteData::DateTimeArray(_) => MappingType::DateTimeArray,
+        }
+    }
+
     pub(crate) fn eq_mapping_type(&self, other: MappingType) -> bool {
         if (other == MappingType::LongInteger || other == MappingType::Double)
             && let AstarteData::Integer(_) = self